@@ -0,0 +1,144 @@
+use crate::engine::reader::ReadStrategy;
+use crate::model::{Config, Target};
+use async_trait::async_trait;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::clients::session::ClientSession;
+use crate::clients::{Client, ClientStream};
+
+const PROTOCOL_VERSION: i32 = 70016;
+const USER_AGENT: &[u8] = b"/banner-grabber:0.1.0/";
+const HEADER_LEN: usize = 24;
+/// `magic(4) command(12) length(4, little-endian) checksum(4)`, then the
+/// payload.
+const MESSAGE: ReadStrategy = ReadStrategy::LengthPrefixed {
+    header_len: HEADER_LEN,
+    header_offset: 16,
+    size: 4,
+    little_endian: true,
+    counted: 0,
+};
+
+pub(crate) struct BitcoinClient;
+
+#[async_trait]
+impl Client for BitcoinClient {
+    fn name(&self) -> &'static str {
+        "bitcoin"
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        matches!(target.resolved.port(), 8333 | 18333 | 38333 | 48333 | 18444)
+    }
+
+    async fn execute(
+        &self,
+        stream: &mut dyn ClientStream,
+        cfg: &Config,
+        target: &Target,
+    ) -> anyhow::Result<crate::engine::reader::ReadResult> {
+        // The target, not the socket's peer, which is the proxy under --proxy.
+        let peer = target.resolved;
+        let mut session = ClientSession::new(cfg);
+        session
+            .send(stream, &version_message(network_magic(peer.port()), peer))
+            .await?;
+        // The peer's first message is its own `version`.
+        session.read_using(stream, MESSAGE).await?;
+        Ok(session.finish())
+    }
+}
+
+/// Nodes drop messages whose magic does not match their chain, so pick it
+/// from the chain's default port.
+fn network_magic(port: u16) -> [u8; 4] {
+    match port {
+        18333 => [0x0b, 0x11, 0x09, 0x07],
+        48333 => [0x1c, 0x16, 0x3f, 0x28],
+        38333 => [0x0a, 0x03, 0xcf, 0x40],
+        18444 => [0xfa, 0xbf, 0xb5, 0xda],
+        _ => [0xf9, 0xbe, 0xb4, 0xd9],
+    }
+}
+
+fn version_message(magic: [u8; 4], peer: SocketAddr) -> Vec<u8> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(&PROTOCOL_VERSION.to_le_bytes());
+    payload.extend_from_slice(&0u64.to_le_bytes());
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default();
+    payload.extend_from_slice(&now.to_le_bytes());
+    push_net_addr(&mut payload, peer);
+    push_net_addr(&mut payload, SocketAddr::from(([0, 0, 0, 0], 0)));
+    payload.extend_from_slice(&rand::random::<u64>().to_le_bytes());
+    payload.push(USER_AGENT.len() as u8);
+    payload.extend_from_slice(USER_AGENT);
+    payload.extend_from_slice(&0i32.to_le_bytes());
+    // relay: no transactions, we disconnect right after the handshake.
+    payload.push(0);
+
+    let mut message = magic.to_vec();
+    let mut command = [0u8; 12];
+    command[..7].copy_from_slice(b"version");
+    message.extend_from_slice(&command);
+    message.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    let checksum = openssl::sha::sha256(&openssl::sha::sha256(&payload));
+    message.extend_from_slice(&checksum[..4]);
+    message.extend_from_slice(&payload);
+    message
+}
+
+/// `services(8) ipv6(16) port(2, big-endian)`, IPv4 mapped into IPv6.
+fn push_net_addr(buf: &mut Vec<u8>, addr: SocketAddr) {
+    buf.extend_from_slice(&0u64.to_le_bytes());
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    };
+    buf.extend_from_slice(&ip.octets());
+    buf.extend_from_slice(&addr.port().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock::{config, serve_with, target};
+    use crate::model::Protocol;
+    use tokio::io::AsyncReadExt;
+
+    #[test]
+    fn frames_version_message() {
+        let peer: SocketAddr = "192.0.2.1:8333".parse().unwrap();
+        let message = version_message(network_magic(peer.port()), peer);
+        assert_eq!(&message[..4], &[0xf9, 0xbe, 0xb4, 0xd9]);
+        assert_eq!(&message[4..11], b"version");
+        let len = u32::from_le_bytes([message[16], message[17], message[18], message[19]]);
+        assert_eq!(len as usize, message.len() - HEADER_LEN);
+        let checksum = openssl::sha::sha256(&openssl::sha::sha256(&message[HEADER_LEN..]));
+        assert_eq!(&message[20..24], &checksum[..4]);
+        // addr_recv (after version, services, timestamp and its own services
+        // field) carries the peer as an IPv4-mapped address.
+        let ip = HEADER_LEN + 28;
+        assert_eq!(&message[ip + 10..ip + 16], &[0xff, 0xff, 192, 0, 2, 1]);
+        assert_eq!(&message[ip + 16..ip + 18], &8333u16.to_be_bytes());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn picks_the_magic_from_the_target_port() {
+        let (mut stream, server) = serve_with(|mut server| async move {
+            let mut magic = [0u8; 4];
+            server.read_exact(&mut magic).await.unwrap();
+            assert_eq!(magic, [0x0b, 0x11, 0x09, 0x07]);
+        });
+        let mut target = target();
+        target.original.port = 18333;
+        target.resolved.set_port(18333);
+        BitcoinClient
+            .execute(&mut stream, &config(Protocol::Bitcoin), &target)
+            .await
+            .unwrap();
+        server.await.unwrap();
+    }
+}
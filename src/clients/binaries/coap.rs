@@ -0,0 +1,174 @@
+use crate::clients::UdpClient;
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, ReadStopReason, Target};
+use async_trait::async_trait;
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Instant};
+
+const TYPE_CON: u8 = 0;
+const TYPE_ACK: u8 = 2;
+const TYPE_RST: u8 = 3;
+const CODE_GET: u8 = 0x01;
+const CODE_EMPTY: u8 = 0x00;
+const OPTION_URI_PATH: u8 = 11;
+const TOKEN_LEN: usize = 4;
+
+/// Asks a CoAP server for `/.well-known/core`. The request is sent once as
+/// confirmable; scans hit too many hosts for the RFC 7252 retransmit schedule.
+pub struct CoapClient;
+
+#[async_trait]
+impl UdpClient for CoapClient {
+    fn name(&self) -> &'static str {
+        "coap"
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        target.resolved.port() == 5683
+    }
+
+    async fn execute(&self, target: &Target, cfg: &Config) -> anyhow::Result<ReadResult> {
+        let bind = if target.resolved.is_ipv6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(target.resolved).await?;
+
+        let message_id: u16 = rand::random();
+        let token: [u8; TOKEN_LEN] = rand::random();
+        let request = well_known_core_request(message_id, &token);
+        tokio::time::timeout(cfg.connect_timeout, socket.send(&request)).await??;
+
+        let deadline = Instant::now() + cfg.read_timeout;
+        let mut buf = vec![0u8; cfg.max_bytes.clamp(64, 1500)];
+        loop {
+            let n = match timeout_at(deadline, socket.recv(&mut buf)).await {
+                Ok(Ok(n)) => n,
+                Ok(Err(err)) => return Err(err.into()),
+                Err(_) => {
+                    return Ok(ReadResult {
+                        bytes: Vec::new(),
+                        reason: ReadStopReason::Timeout,
+                        truncated: false,
+                        tls_info: None,
+                        fields: Default::default(),
+                    })
+                }
+            };
+            let datagram = &buf[..n];
+            match classify(datagram, message_id, &token) {
+                Reply::Final => {
+                    if message_type(datagram) == Some(TYPE_CON) {
+                        // Separate responses are confirmable; acknowledge so the
+                        // server does not keep retransmitting to us.
+                        let _ = socket.send(&empty_ack(datagram)).await;
+                    }
+                    let truncated = n >= cfg.max_bytes;
+                    let mut bytes = datagram.to_vec();
+                    bytes.truncate(cfg.max_bytes);
+                    return Ok(ReadResult {
+                        bytes,
+                        reason: ReadStopReason::ConnectionClosed,
+                        truncated,
+                        tls_info: None,
+                        fields: Default::default(),
+                    });
+                }
+                // An empty ACK means the answer follows as a separate message.
+                Reply::Pending | Reply::Unrelated => continue,
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Reply {
+    /// Response carrying our token, or a reset of our request.
+    Final,
+    /// Empty ACK for our request; the response comes later.
+    Pending,
+    Unrelated,
+}
+
+fn well_known_core_request(message_id: u16, token: &[u8; TOKEN_LEN]) -> Vec<u8> {
+    let mut message = vec![0x40 | (TYPE_CON << 4) | TOKEN_LEN as u8, CODE_GET];
+    message.extend_from_slice(&message_id.to_be_bytes());
+    message.extend_from_slice(token);
+    let mut previous = 0;
+    for segment in [".well-known", "core"] {
+        let delta = OPTION_URI_PATH - previous;
+        message.push((delta << 4) | segment.len() as u8);
+        message.extend_from_slice(segment.as_bytes());
+        previous = OPTION_URI_PATH;
+    }
+    message
+}
+
+fn message_type(datagram: &[u8]) -> Option<u8> {
+    datagram.first().map(|first| (first >> 4) & 0x03)
+}
+
+fn classify(datagram: &[u8], message_id: u16, token: &[u8]) -> Reply {
+    let Some(header) = datagram.get(..4) else {
+        return Reply::Unrelated;
+    };
+    if header[0] >> 6 != 1 {
+        return Reply::Unrelated;
+    }
+    let kind = (header[0] >> 4) & 0x03;
+    let code = header[1];
+    let id = u16::from_be_bytes([header[2], header[3]]);
+    let token_len = (header[0] & 0x0f) as usize;
+    let same_exchange = id == message_id;
+    if code == CODE_EMPTY {
+        return match (kind, same_exchange) {
+            (TYPE_ACK, true) => Reply::Pending,
+            // The server refused our request outright.
+            (TYPE_RST, true) => Reply::Final,
+            _ => Reply::Unrelated,
+        };
+    }
+    // Non-confirmable servers answer with a NON of their own message id, so
+    // the token is what ties the response to our request.
+    if datagram.get(4..4 + token_len) == Some(token) {
+        Reply::Final
+    } else {
+        Reply::Unrelated
+    }
+}
+
+fn empty_ack(datagram: &[u8]) -> [u8; 4] {
+    [0x40 | (TYPE_ACK << 4), CODE_EMPTY, datagram[2], datagram[3]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_well_known_core_get() {
+        let request = well_known_core_request(0x1234, &[1, 2, 3, 4]);
+        assert_eq!(&request[..8], &[0x44, 0x01, 0x12, 0x34, 1, 2, 3, 4]);
+        assert_eq!(request[8], 0xbb);
+        assert_eq!(&request[9..20], b".well-known");
+        assert_eq!(request[20], 0x04);
+        assert_eq!(&request[21..], b"core");
+    }
+
+    #[test]
+    fn matches_piggybacked_separate_and_non_responses() {
+        let token = [1, 2, 3, 4];
+        let ack = [0x64, 0x45, 0x12, 0x34, 1, 2, 3, 4, 0xff, b'<'];
+        assert_eq!(classify(&ack, 0x1234, &token), Reply::Final);
+        assert_eq!(
+            classify(&[0x60, 0x00, 0x12, 0x34], 0x1234, &token),
+            Reply::Pending
+        );
+        let non = [0x54, 0x45, 0x99, 0x99, 1, 2, 3, 4];
+        assert_eq!(classify(&non, 0x1234, &token), Reply::Final);
+        let other = [0x54, 0x45, 0x99, 0x99, 9, 9, 9, 9];
+        assert_eq!(classify(&other, 0x1234, &token), Reply::Unrelated);
+    }
+}
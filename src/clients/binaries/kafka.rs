@@ -0,0 +1,97 @@
+use crate::engine::reader::ReadStrategy;
+use crate::model::{Config, Target};
+use async_trait::async_trait;
+
+use crate::clients::session::ClientSession;
+use crate::clients::{Client, ClientStream};
+
+const API_VERSIONS_KEY: i16 = 18;
+const CORRELATION_ID: i32 = 0x6267;
+const CLIENT_ID: &[u8] = b"banner-grabber";
+/// Responses start with their 4-byte big-endian length.
+const RESPONSE: ReadStrategy = ReadStrategy::LengthPrefixed {
+    header_len: 4,
+    header_offset: 0,
+    size: 4,
+    little_endian: false,
+    counted: 0,
+};
+
+pub(crate) struct KafkaClient;
+
+#[async_trait]
+impl Client for KafkaClient {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        matches!(target.resolved.port(), 9092 | 9093)
+    }
+
+    async fn execute(
+        &self,
+        stream: &mut dyn ClientStream,
+        cfg: &Config,
+        _target: &Target,
+    ) -> anyhow::Result<crate::engine::reader::ReadResult> {
+        let mut session = ClientSession::new(cfg);
+        session.send(stream, &api_versions_request()).await?;
+        // A broker that hangs up without answering (SSL or SASL-only
+        // listeners) leaves an empty banner for the formatter to report.
+        session.read_using(stream, RESPONSE).await?;
+        Ok(session.finish())
+    }
+}
+
+/// ApiVersions v0: every broker since 0.10 answers it, and it is allowed
+/// before SASL authentication.
+fn api_versions_request() -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&API_VERSIONS_KEY.to_be_bytes());
+    body.extend_from_slice(&0i16.to_be_bytes());
+    body.extend_from_slice(&CORRELATION_ID.to_be_bytes());
+    body.extend_from_slice(&(CLIENT_ID.len() as i16).to_be_bytes());
+    body.extend_from_slice(CLIENT_ID);
+
+    let mut packet = (body.len() as i32).to_be_bytes().to_vec();
+    packet.extend_from_slice(&body);
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock::{config, serve_with, target};
+    use crate::model::{Protocol, ReadStopReason};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn frames_api_versions_request() {
+        let packet = api_versions_request();
+        let len = i32::from_be_bytes([packet[0], packet[1], packet[2], packet[3]]) as usize;
+        assert_eq!(len, packet.len() - 4);
+        assert_eq!(&packet[4..8], &[0, 18, 0, 0]);
+        assert_eq!(&packet[8..12], &CORRELATION_ID.to_be_bytes());
+        assert_eq!(&packet[14..], CLIENT_ID);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reads_exactly_one_response() {
+        let (mut stream, server) = serve_with(|mut server| async move {
+            let mut request = vec![0u8; api_versions_request().len()];
+            server.read_exact(&mut request).await.unwrap();
+            server
+                .write_all(b"\0\0\0\x0a\0\0\x62\x67\0\0\0\0\0\0NEXT")
+                .await
+                .unwrap();
+        });
+        let result = KafkaClient
+            .execute(&mut stream, &config(Protocol::Kafka), &target())
+            .await
+            .unwrap();
+        server.await.unwrap();
+        assert_eq!(result.bytes, b"\0\0\0\x0a\0\0\x62\x67\0\0\0\0\0\0");
+        assert_eq!(result.reason, ReadStopReason::Delimiter);
+    }
+}
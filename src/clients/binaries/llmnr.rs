@@ -0,0 +1,83 @@
+use crate::clients::UdpClient;
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, ReadStopReason, Target};
+use crate::util::dns;
+use async_trait::async_trait;
+use tokio::net::UdpSocket;
+use tokio::time::{timeout, timeout_at, Instant};
+
+/// Asks an LLMNR responder (RFC 4795) for the A record of a random name.
+/// Nobody owns the name, so any answer comes from a host that claims names it
+/// does not have, which is how LLMNR poisoning tools behave.
+pub struct LlmnrClient;
+
+#[async_trait]
+impl UdpClient for LlmnrClient {
+    fn name(&self) -> &'static str {
+        "llmnr"
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        target.resolved.port() == 5355
+    }
+
+    async fn execute(&self, target: &Target, cfg: &Config) -> anyhow::Result<ReadResult> {
+        let bind = if target.resolved.is_ipv6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(target.resolved).await?;
+
+        let id: u16 = rand::random();
+        let name = random_name(rand::random());
+        let query = dns::encode_query(id, &[(&name, dns::TYPE_A)], dns::CLASS_IN);
+        timeout(cfg.connect_timeout, socket.send(&query)).await??;
+
+        let deadline = Instant::now() + cfg.read_timeout;
+        let mut buf = vec![0u8; 1500];
+        loop {
+            let n = match timeout_at(deadline, socket.recv(&mut buf)).await {
+                Ok(Ok(n)) => n,
+                Ok(Err(err)) => return Err(err.into()),
+                Err(_) => {
+                    return Ok(ReadResult {
+                        bytes: Vec::new(),
+                        reason: ReadStopReason::Timeout,
+                        truncated: false,
+                        tls_info: None,
+                        fields: Default::default(),
+                    })
+                }
+            };
+            if buf[..n].starts_with(&id.to_be_bytes()) {
+                buf.truncate(n.min(cfg.max_bytes));
+                return Ok(ReadResult {
+                    bytes: buf,
+                    reason: ReadStopReason::ConnectionClosed,
+                    truncated: n > cfg.max_bytes,
+                    tls_info: None,
+                    fields: Default::default(),
+                });
+            }
+        }
+    }
+}
+
+/// A single-label name such as `bg-3f9a1c2e`; LLMNR only resolves those.
+fn random_name(suffix: u32) -> String {
+    format!("bg-{suffix:08x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queries_a_single_label_name() {
+        let query = dns::encode_query(1, &[(&random_name(0xabc), dns::TYPE_A)], dns::CLASS_IN);
+        assert_eq!(&query[12..24], b"\x0bbg-00000abc");
+        assert_eq!(&query[24..], &[0, 0, 1, 0, 1]);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::clients::UdpClient;
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, ReadStopReason, Target};
+use crate::util::dns::{self, RData};
+use async_trait::async_trait;
+use tokio::net::UdpSocket;
+use tokio::time::{timeout, timeout_at, Instant};
+
+const SERVICES: &str = "_services._dns-sd._udp.local";
+/// `IN` with the unicast-response bit set (RFC 6762 section 5.4).
+const CLASS_IN_UNICAST: u16 = 0x8001;
+/// Service types asked about in the second query; responders list few.
+const MAX_SERVICE_TYPES: usize = 16;
+
+/// Enumerates DNS-SD services over unicast mDNS. The first query lists the
+/// service types, the second asks for their instances, whose SRV/TXT records
+/// responders send along as additional records.
+///
+/// The banner holds every response datagram, each prefixed by its two-byte
+/// length as in DNS over TCP.
+pub struct MdnsClient;
+
+#[async_trait]
+impl UdpClient for MdnsClient {
+    fn name(&self) -> &'static str {
+        "mdns"
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        target.resolved.port() == 5353
+    }
+
+    async fn execute(&self, target: &Target, cfg: &Config) -> anyhow::Result<ReadResult> {
+        let bind = if target.resolved.is_ipv6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(target.resolved).await?;
+
+        let mut bytes = Vec::new();
+        let Some(services) = exchange(&socket, &[(SERVICES, dns::TYPE_PTR)], cfg).await? else {
+            return Ok(ReadResult {
+                bytes,
+                reason: ReadStopReason::Timeout,
+                truncated: false,
+                tls_info: None,
+                fields: Default::default(),
+            });
+        };
+        append_frame(&mut bytes, &services);
+
+        let types = service_types(&services);
+        if !types.is_empty() {
+            let questions: Vec<(&str, u16)> = types
+                .iter()
+                .map(|service| (service.as_str(), dns::TYPE_PTR))
+                .collect();
+            if let Some(instances) = exchange(&socket, &questions, cfg).await? {
+                append_frame(&mut bytes, &instances);
+            }
+        }
+        let truncated = bytes.len() > cfg.max_bytes;
+        bytes.truncate(cfg.max_bytes);
+        Ok(ReadResult {
+            bytes,
+            reason: ReadStopReason::ConnectionClosed,
+            truncated,
+            tls_info: None,
+            fields: Default::default(),
+        })
+    }
+}
+
+/// Sends one query and waits for the response carrying its id. Responders
+/// echo the id to legacy unicast queries, which ours are since they do not
+/// come from port 5353.
+async fn exchange(
+    socket: &UdpSocket,
+    questions: &[(&str, u16)],
+    cfg: &Config,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let id: u16 = rand::random();
+    let query = dns::encode_query(id, questions, CLASS_IN_UNICAST);
+    timeout(cfg.connect_timeout, socket.send(&query)).await??;
+
+    let deadline = Instant::now() + cfg.read_timeout;
+    let mut buf = vec![0u8; 9000];
+    loop {
+        let n = match timeout_at(deadline, socket.recv(&mut buf)).await {
+            Ok(Ok(n)) => n,
+            Ok(Err(err)) => return Err(err.into()),
+            Err(_) => return Ok(None),
+        };
+        if buf[..n].starts_with(&id.to_be_bytes()) {
+            return Ok(Some(buf[..n].to_vec()));
+        }
+    }
+}
+
+fn append_frame(bytes: &mut Vec<u8>, datagram: &[u8]) {
+    bytes.extend_from_slice(&(datagram.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(datagram);
+}
+
+fn service_types(response: &[u8]) -> Vec<String> {
+    let Some(message) = dns::parse_message(response) else {
+        return Vec::new();
+    };
+    let mut types: Vec<String> = Vec::new();
+    for record in message.records {
+        if let RData::Ptr(service) = record.data {
+            if record.name.eq_ignore_ascii_case(SERVICES) && !types.contains(&service) {
+                types.push(service);
+            }
+        }
+    }
+    types.truncate(MAX_SERVICE_TYPES);
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_service_types_from_the_meta_query() {
+        let mut response = dns::encode_query(1, &[(SERVICES, dns::TYPE_PTR)], CLASS_IN_UNICAST);
+        response[2] = 0x84;
+        response[7] = 1;
+        // PTR `_ipp._tcp.<local>`, pointing at `local` (offset 35) in the question.
+        response.extend_from_slice(&[0xc0, 12, 0, 12, 0, 1, 0, 0, 0, 10, 0, 12]);
+        response.extend_from_slice(b"\x04_ipp\x04_tcp\xc0\x23");
+        assert_eq!(service_types(&response), ["_ipp._tcp.local"]);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::clients::UdpClient;
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, ReadStopReason, Target};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::{timeout_at, Instant};
+
+/// Request id, sequence number, datagram count, reserved.
+const FRAME_HEADER_LEN: usize = 8;
+const STATS: &[u8] = b"stats\r\n";
+/// Reflectors answer at once; waiting the full `--read-timeout` for every
+/// closed port would only slow UDP sweeps down.
+const MAX_WAIT: Duration = Duration::from_secs(2);
+
+/// Sends `stats` to memcached's UDP port, the amplification vector: a
+/// 15-byte request draws several kilobytes of statistics.
+pub struct MemcachedUdpClient;
+
+#[async_trait]
+impl UdpClient for MemcachedUdpClient {
+    fn name(&self) -> &'static str {
+        "memcached-udp"
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        target.resolved.port() == 11211
+    }
+
+    async fn execute(&self, target: &Target, cfg: &Config) -> anyhow::Result<ReadResult> {
+        let bind = if target.resolved.is_ipv6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(target.resolved).await?;
+
+        let request_id: u16 = rand::random();
+        let request = stats_request(request_id);
+        tokio::time::timeout(cfg.connect_timeout, socket.send(&request)).await??;
+
+        let deadline = Instant::now() + cfg.read_timeout.min(MAX_WAIT);
+        let mut reply = Reassembly::default();
+        let mut buf = vec![0u8; u16::MAX as usize];
+        while !reply.complete() {
+            let n = match timeout_at(deadline, socket.recv(&mut buf)).await {
+                Ok(Ok(n)) => n,
+                Ok(Err(err)) => return Err(err.into()),
+                Err(_) => break,
+            };
+            reply.push(request_id, &buf[..n]);
+        }
+
+        let mut fields = BTreeMap::new();
+        fields.insert("udp_enabled".into(), (reply.received() > 0).to_string());
+        if reply.received() == 0 {
+            return Ok(ReadResult {
+                bytes: Vec::new(),
+                reason: ReadStopReason::Timeout,
+                truncated: false,
+                tls_info: None,
+                fields,
+            });
+        }
+        fields.insert("request_bytes".into(), request.len().to_string());
+        fields.insert("response_bytes".into(), reply.wire_bytes.to_string());
+        fields.insert(
+            "amplification_factor".into(),
+            format!("{:.1}", reply.wire_bytes as f64 / request.len() as f64),
+        );
+        fields.insert("datagrams_received".into(), reply.received().to_string());
+        fields.insert("datagrams_expected".into(), reply.total.to_string());
+
+        let mut bytes = reply.payload();
+        let truncated = bytes.len() > cfg.max_bytes;
+        bytes.truncate(cfg.max_bytes);
+        Ok(ReadResult {
+            bytes,
+            reason: ReadStopReason::ConnectionClosed,
+            truncated,
+            tls_info: None,
+            fields,
+        })
+    }
+}
+
+fn stats_request(request_id: u16) -> Vec<u8> {
+    let mut request = Vec::with_capacity(FRAME_HEADER_LEN + STATS.len());
+    request.extend_from_slice(&request_id.to_be_bytes());
+    // Sequence 0 of 1 datagram, reserved.
+    request.extend_from_slice(&[0, 0, 0, 1, 0, 0]);
+    request.extend_from_slice(STATS);
+    request
+}
+
+/// Puts a multi-datagram reply back together: each datagram repeats the
+/// request id and carries its sequence number and the total count, and may
+/// arrive in any order.
+#[derive(Default)]
+struct Reassembly {
+    parts: BTreeMap<u16, Vec<u8>>,
+    total: u16,
+    /// Every datagram of the reply as it crossed the wire, headers included.
+    wire_bytes: usize,
+}
+
+impl Reassembly {
+    fn push(&mut self, request_id: u16, datagram: &[u8]) {
+        let Some(header) = datagram.get(..FRAME_HEADER_LEN) else {
+            return;
+        };
+        let field = |at: usize| u16::from_be_bytes([header[at], header[at + 1]]);
+        if field(0) != request_id || field(4) == 0 || field(2) >= field(4) {
+            return;
+        }
+        if self.parts.contains_key(&field(2)) {
+            return;
+        }
+        self.total = field(4);
+        self.wire_bytes += datagram.len();
+        self.parts
+            .insert(field(2), datagram[FRAME_HEADER_LEN..].to_vec());
+    }
+
+    fn received(&self) -> usize {
+        self.parts.len()
+    }
+
+    fn complete(&self) -> bool {
+        self.total > 0 && self.parts.len() == self.total as usize
+    }
+
+    /// The reply text in sequence order; missing datagrams leave gaps.
+    fn payload(&self) -> Vec<u8> {
+        self.parts.values().flatten().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datagram(request_id: u16, sequence: u16, total: u16, payload: &[u8]) -> Vec<u8> {
+        let mut datagram = Vec::new();
+        for field in [request_id, sequence, total, 0] {
+            datagram.extend_from_slice(&field.to_be_bytes());
+        }
+        datagram.extend_from_slice(payload);
+        datagram
+    }
+
+    #[test]
+    fn frames_stats_request() {
+        assert_eq!(
+            stats_request(0x1234),
+            b"\x12\x34\0\0\0\x01\0\0stats\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn reassembles_datagrams_in_sequence_order() {
+        let mut reply = Reassembly::default();
+        reply.push(7, &datagram(7, 1, 2, b"STAT pid 2\r\nEND\r\n"));
+        assert!(!reply.complete());
+        reply.push(7, &datagram(9, 0, 2, b"unrelated"));
+        reply.push(7, &datagram(7, 1, 2, b"duplicate"));
+        reply.push(7, &datagram(7, 0, 2, b"STAT pid 1\r\n"));
+        assert!(reply.complete());
+        assert_eq!(reply.payload(), b"STAT pid 1\r\nSTAT pid 2\r\nEND\r\n");
+        assert_eq!(reply.wire_bytes, 2 * FRAME_HEADER_LEN + 29);
+    }
+}
@@ -1,4 +1,14 @@
+pub mod bitcoin;
+pub mod coap;
+pub mod kafka;
+pub mod llmnr;
+pub mod mdns;
+pub mod memcached_udp;
+pub mod mongodb;
 pub mod mssql;
+pub mod mssql_browser;
 pub mod mysql;
 pub mod ntp;
+pub mod postgres;
 pub mod rpcbind;
+pub mod udp_raw;
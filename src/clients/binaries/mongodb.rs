@@ -0,0 +1,130 @@
+use crate::engine::reader::ReadStrategy;
+use crate::model::{Config, ReadStopReason, Target};
+use crate::util::bson::{self, Bson};
+use async_trait::async_trait;
+
+use crate::clients::session::ClientSession;
+use crate::clients::{Client, ClientStream};
+
+/// Little-endian messageLength at the start of the header, counting it.
+const MESSAGE: ReadStrategy = ReadStrategy::LengthPrefixed {
+    header_len: bson::HEADER_LEN,
+    header_offset: 0,
+    size: 4,
+    little_endian: true,
+    counted: bson::HEADER_LEN,
+};
+
+/// requestIDs of the commands, echoed as responseTo so the formatter can
+/// tell the replies apart.
+pub(crate) const HELLO_REQUEST: i32 = 1;
+pub(crate) const IS_MASTER_REQUEST: i32 = 2;
+pub(crate) const BUILD_INFO_REQUEST: i32 = 3;
+
+pub(crate) struct MongodbClient;
+
+#[async_trait]
+impl Client for MongodbClient {
+    fn name(&self) -> &'static str {
+        "mongodb"
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        matches!(target.resolved.port(), 27017..=27019)
+    }
+
+    async fn execute(
+        &self,
+        stream: &mut dyn ClientStream,
+        cfg: &Config,
+        _target: &Target,
+    ) -> anyhow::Result<crate::engine::reader::ReadResult> {
+        let mut session = ClientSession::new(cfg);
+        session
+            .send(stream, &bson::command_message(HELLO_REQUEST, "hello"))
+            .await?;
+        let hello = session.read_using(stream, MESSAGE).await?;
+        if hello.reason != ReadStopReason::Delimiter {
+            return Ok(session.finish());
+        }
+        // Servers before 4.4.2 answer `hello` with "no such command".
+        if !command_ok(&hello.bytes) {
+            session
+                .send(
+                    stream,
+                    &bson::command_message(IS_MASTER_REQUEST, "isMaster"),
+                )
+                .await?;
+            let is_master = session.read_using(stream, MESSAGE).await?;
+            if is_master.reason != ReadStopReason::Delimiter {
+                return Ok(session.finish());
+            }
+        }
+        // Allowed without authentication on most deployments; the formatter
+        // reports `ok: 0` replies as refused.
+        session
+            .send(
+                stream,
+                &bson::command_message(BUILD_INFO_REQUEST, "buildInfo"),
+            )
+            .await?;
+        session.read_using(stream, MESSAGE).await?;
+        Ok(session.finish())
+    }
+}
+
+fn command_ok(reply: &[u8]) -> bool {
+    bson::parse_replies(reply)
+        .first()
+        .is_some_and(|(_, body)| bson::get(body, "ok").and_then(Bson::as_i64) == Some(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock::{config, serve, target, Step};
+    use crate::model::Protocol;
+
+    /// An OP_MSG reply to `request_id` with the body `{ok: <ok>}`.
+    fn reply(request_id: i32, ok: f64) -> &'static [u8] {
+        let mut message = 38i32.to_le_bytes().to_vec();
+        message.extend_from_slice(&9i32.to_le_bytes());
+        message.extend_from_slice(&request_id.to_le_bytes());
+        message.extend_from_slice(&2013i32.to_le_bytes());
+        message.extend_from_slice(&[0, 0, 0, 0, 0]);
+        message.extend_from_slice(&17i32.to_le_bytes());
+        message.extend_from_slice(b"\x01ok\0");
+        message.extend_from_slice(&ok.to_le_bytes());
+        message.push(0);
+        message.leak()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn falls_back_to_is_master_then_asks_for_build_info() {
+        let hello = bson::command_message(HELLO_REQUEST, "hello").leak();
+        let is_master = bson::command_message(IS_MASTER_REQUEST, "isMaster").leak();
+        let build_info = bson::command_message(BUILD_INFO_REQUEST, "buildInfo").leak();
+        let (mut stream, server) = serve(vec![
+            Step::Expect(hello),
+            Step::Send(reply(HELLO_REQUEST, 0.0)),
+            Step::Expect(is_master),
+            Step::Send(reply(IS_MASTER_REQUEST, 1.0)),
+            Step::Expect(build_info),
+            Step::Send(reply(BUILD_INFO_REQUEST, 1.0)),
+        ]);
+        let result = MongodbClient
+            .execute(&mut stream, &config(Protocol::Mongodb), &target())
+            .await
+            .unwrap();
+        server.await.unwrap();
+
+        let replies: Vec<i32> = bson::parse_replies(&result.bytes)
+            .into_iter()
+            .map(|(response_to, _)| response_to)
+            .collect();
+        assert_eq!(
+            replies,
+            [HELLO_REQUEST, IS_MASTER_REQUEST, BUILD_INFO_REQUEST]
+        );
+    }
+}
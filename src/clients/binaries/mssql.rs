@@ -1,9 +1,19 @@
+use crate::engine::reader::ReadStrategy;
 use crate::model::{Config, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
 
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
+
+/// TDS packet header: type, status, then a big-endian length covering the
+/// whole 8-byte header.
+const PACKET: ReadStrategy = ReadStrategy::LengthPrefixed {
+    header_len: 8,
+    header_offset: 2,
+    size: 2,
+    little_endian: false,
+    counted: 8,
+};
 
 pub(crate) struct MssqlClient;
 
@@ -19,8 +29,9 @@ impl Client for MssqlClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
         let mut session = ClientSession::new(cfg);
 
@@ -47,7 +58,7 @@ impl Client for MssqlClient {
         packet.extend_from_slice(&payload);
 
         session.send(stream, &packet).await?;
-        session.read_with_result(stream, None).await?;
+        session.read_using(stream, PACKET).await?;
         Ok(session.finish())
     }
 }
@@ -0,0 +1,108 @@
+use crate::clients::UdpClient;
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, ReadStopReason, Target};
+use async_trait::async_trait;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+/// `CLNT_UCAST_EX`: ask the SQL Server Browser for every instance on the host.
+const CLNT_UCAST_EX: u8 = 0x02;
+/// `CLNT_UCAST_INST`: ask about one instance, named after the byte.
+const CLNT_UCAST_INST: u8 = 0x03;
+
+/// SQL Server Browser (SSRP) client; lists named instances and the dynamic
+/// TCP ports they listen on.
+pub struct MssqlBrowserClient;
+
+#[async_trait]
+impl UdpClient for MssqlBrowserClient {
+    fn name(&self) -> &'static str {
+        "mssql-browser"
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        target.resolved.port() == 1434
+    }
+
+    async fn execute(&self, target: &Target, cfg: &Config) -> anyhow::Result<ReadResult> {
+        let bind = if target.resolved.is_ipv6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(target.resolved).await?;
+
+        timeout(cfg.connect_timeout, socket.send(&request(cfg))).await??;
+
+        // SVR_RESP is a single datagram of at most 3 + 65535 bytes.
+        let mut buf = vec![0u8; 3 + u16::MAX as usize];
+        match timeout(cfg.read_timeout, socket.recv(&mut buf)).await {
+            Ok(Ok(n)) => {
+                buf.truncate(n.min(cfg.max_bytes));
+                Ok(ReadResult {
+                    bytes: buf,
+                    reason: ReadStopReason::ConnectionClosed,
+                    truncated: n > cfg.max_bytes,
+                    tls_info: None,
+                    fields: Default::default(),
+                })
+            }
+            Ok(Err(err)) => Err(err.into()),
+            Err(_) => Ok(ReadResult {
+                bytes: Vec::new(),
+                reason: ReadStopReason::Timeout,
+                truncated: false,
+                tls_info: None,
+                fields: Default::default(),
+            }),
+        }
+    }
+}
+
+/// `CLNT_UCAST_INST` with the NUL-terminated `--mssql-instance` name, else
+/// `CLNT_UCAST_EX`.
+fn request(cfg: &Config) -> Vec<u8> {
+    match &cfg.mssql_instance {
+        Some(name) => {
+            let mut request = vec![CLNT_UCAST_INST];
+            request.extend_from_slice(name.as_bytes());
+            request.push(0);
+            request
+        }
+        None => vec![CLNT_UCAST_EX],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock;
+    use crate::model::{Protocol, TargetSpec};
+
+    #[tokio::test]
+    async fn asks_about_one_named_instance() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let target = Target {
+            original: TargetSpec {
+                host: addr.ip().to_string(),
+                port: addr.port(),
+            },
+            resolved: addr,
+        };
+        let mut cfg = mock::config(Protocol::MssqlBrowser);
+        cfg.mssql_instance = Some("SQLEXPRESS".into());
+
+        let reply = b"\x05\x1e\x00InstanceName;SQLEXPRESS;tcp;49733;;";
+        let answer = async {
+            let mut buf = [0u8; 64];
+            let (n, client) = server.recv_from(&mut buf).await.unwrap();
+            server.send_to(reply, client).await.unwrap();
+            buf[..n].to_vec()
+        };
+        let (result, request) = tokio::join!(MssqlBrowserClient.execute(&target, &cfg), answer);
+        assert_eq!(request, b"\x03SQLEXPRESS\0");
+        assert_eq!(result.unwrap().bytes, reply);
+    }
+}
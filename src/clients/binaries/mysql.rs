@@ -1,9 +1,20 @@
-use crate::model::{Config, Target};
+use crate::engine::reader::ReadStrategy;
+use crate::model::{Config, ReadStopReason, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
 
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
+
+const HEADER_LEN: usize = 4;
+/// 3-byte little-endian payload length, then the sequence number.
+const PACKET: ReadStrategy = ReadStrategy::LengthPrefixed {
+    header_len: HEADER_LEN,
+    header_offset: 0,
+    size: 3,
+    little_endian: true,
+    counted: 0,
+};
+const PROBE_USER: &[u8] = b"banner-grabber";
 
 pub(crate) struct MysqlClient;
 
@@ -19,11 +30,67 @@ impl Client for MysqlClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
         let mut session = ClientSession::new(cfg);
-        session.read(stream, None).await?;
+        let greeting = session.read_using(stream, PACKET).await?;
+        // A saturated or blocking server may still greet and only refuse the
+        // login, so answer the greeting with an empty-password login for a
+        // user that should not exist and keep the server's verdict.
+        if greeting.reason == ReadStopReason::Delimiter
+            && greeting.bytes.get(HEADER_LEN) == Some(&10)
+        {
+            session
+                .send(
+                    stream,
+                    &handshake_response(greeting.bytes[3].wrapping_add(1)),
+                )
+                .await?;
+            session.read_using(stream, PACKET).await?;
+        }
         Ok(session.finish())
     }
 }
+
+/// `HandshakeResponse41` with an empty auth response for `mysql_native_password`.
+fn handshake_response(sequence: u8) -> Vec<u8> {
+    const CLIENT_LONG_PASSWORD: u32 = 0x0000_0001;
+    const CLIENT_PROTOCOL_41: u32 = 0x0000_0200;
+    const CLIENT_SECURE_CONNECTION: u32 = 0x0000_8000;
+    const CLIENT_PLUGIN_AUTH: u32 = 0x0008_0000;
+    let capabilities =
+        CLIENT_LONG_PASSWORD | CLIENT_PROTOCOL_41 | CLIENT_SECURE_CONNECTION | CLIENT_PLUGIN_AUTH;
+
+    let mut payload = Vec::new();
+    payload.extend_from_slice(&capabilities.to_le_bytes());
+    payload.extend_from_slice(&(16u32 << 20).to_le_bytes());
+    // utf8_general_ci, then 23 reserved bytes.
+    payload.push(33);
+    payload.extend_from_slice(&[0; 23]);
+    payload.extend_from_slice(PROBE_USER);
+    payload.push(0);
+    payload.push(0);
+    payload.extend_from_slice(b"mysql_native_password\0");
+
+    let len = payload.len() as u32;
+    let mut packet = len.to_le_bytes()[..3].to_vec();
+    packet.push(sequence);
+    packet.extend_from_slice(&payload);
+    packet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_login_after_greeting() {
+        let packet = handshake_response(1);
+        assert_eq!(PACKET.body_len(&packet), packet.len() - HEADER_LEN);
+        assert_eq!(packet[3], 1);
+        let user = HEADER_LEN + 32;
+        assert_eq!(&packet[user..user + PROBE_USER.len()], PROBE_USER);
+    }
+}
@@ -39,6 +39,7 @@ impl UdpClient for NtpClient {
                     reason: ReadStopReason::ConnectionClosed,
                     truncated: n >= cfg.max_bytes,
                     tls_info: None,
+                    fields: Default::default(),
                 })
             }
             Ok(Err(err)) => Err(err.into()),
@@ -47,6 +48,7 @@ impl UdpClient for NtpClient {
                 reason: ReadStopReason::Timeout,
                 truncated: false,
                 tls_info: None,
+                fields: Default::default(),
             }),
         }
     }
@@ -0,0 +1,102 @@
+use crate::engine::reader::ReadStrategy;
+use crate::model::{Config, Target};
+use async_trait::async_trait;
+
+use crate::clients::session::ClientSession;
+use crate::clients::{Client, ClientStream};
+
+/// Protocol 3.2, the newest minor version (PostgreSQL 18). Servers that
+/// only speak 3.0 answer with NegotiateProtocolVersion before the
+/// authentication request, revealing the newest minor they support.
+const PROTOCOL_VERSION: u32 = (3 << 16) | 2;
+
+/// Type byte, then a big-endian length that counts itself.
+const MESSAGE: ReadStrategy = ReadStrategy::LengthPrefixed {
+    header_len: 5,
+    header_offset: 1,
+    size: 4,
+    little_endian: false,
+    counted: 4,
+};
+
+pub(crate) struct PostgresClient;
+
+#[async_trait]
+impl Client for PostgresClient {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        target.resolved.port() == 5432
+    }
+
+    async fn execute(
+        &self,
+        stream: &mut dyn ClientStream,
+        cfg: &Config,
+        _target: &Target,
+    ) -> anyhow::Result<crate::engine::reader::ReadResult> {
+        let mut session = ClientSession::new(cfg);
+        session.send(stream, &startup_message()).await?;
+        session.insert_field("requested_protocol_version", "3.2");
+        // The server answers with either an authentication request or an
+        // ErrorResponse explaining why it will not take the connection,
+        // possibly preceded by NegotiateProtocolVersion.
+        let first = session.read_using(stream, MESSAGE).await?;
+        if first.bytes.first() == Some(&b'v') {
+            session.read_using(stream, MESSAGE).await?;
+        }
+        Ok(session.finish())
+    }
+}
+
+fn startup_message() -> Vec<u8> {
+    let mut body = PROTOCOL_VERSION.to_be_bytes().to_vec();
+    for (key, value) in [
+        ("user", "banner-grabber"),
+        ("database", "postgres"),
+        ("application_name", "banner-grabber"),
+    ] {
+        body.extend_from_slice(key.as_bytes());
+        body.push(0);
+        body.extend_from_slice(value.as_bytes());
+        body.push(0);
+    }
+    body.push(0);
+    let mut message = ((body.len() + 4) as u32).to_be_bytes().to_vec();
+    message.extend_from_slice(&body);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock::{config, serve, target, Step};
+    use crate::model::Protocol;
+
+    #[test]
+    fn frames_startup_message() {
+        let message = startup_message();
+        let len = u32::from_be_bytes([message[0], message[1], message[2], message[3]]);
+        assert_eq!(len as usize, message.len());
+        assert_eq!(&message[4..8], &[0, 3, 0, 2]);
+        assert!(message.ends_with(b"banner-grabber\0\0"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reads_the_authentication_request_after_negotiation() {
+        let (mut stream, server) = serve(vec![
+            Step::Expect(b"\0\0\0\x4f\0\x03\0\x02"),
+            Step::Send(b"v\0\0\0\x0c\0\0\0\0\0\0\0\0R\0\0\0\x08\0\0\0\x05"),
+        ]);
+        let result = PostgresClient
+            .execute(&mut stream, &config(Protocol::Postgres), &target())
+            .await
+            .unwrap();
+        server.await.unwrap();
+        assert_eq!(result.bytes.len(), 22);
+        assert_eq!(result.bytes[13], b'R');
+        assert_eq!(result.fields["requested_protocol_version"], "3.2");
+    }
+}
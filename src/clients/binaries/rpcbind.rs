@@ -1,10 +1,9 @@
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
 use crate::engine::reader::ReadResult;
 use crate::model::{Config, ReadStopReason, Target};
 use async_trait::async_trait;
 use rand::Rng;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
 use tokio::time::timeout;
 
 pub(crate) struct RpcbindClient;
@@ -27,7 +26,12 @@ impl Client for RpcbindClient {
         target.resolved.port() == 111
     }
 
-    async fn execute(&self, stream: &mut TcpStream, cfg: &Config) -> anyhow::Result<ReadResult> {
+    async fn execute(
+        &self,
+        stream: &mut dyn ClientStream,
+        cfg: &Config,
+        _target: &Target,
+    ) -> anyhow::Result<ReadResult> {
         let versions = [4u32, 3u32, 2u32];
         for version in versions {
             let request = build_dump_request(version);
@@ -41,6 +45,7 @@ impl Client for RpcbindClient {
                         reason: ReadStopReason::Timeout,
                         truncated: false,
                         tls_info: None,
+                        fields: Default::default(),
                     })
                 }
                 Err(ReadError::Io(err)) => return Err(err),
@@ -55,6 +60,7 @@ impl Client for RpcbindClient {
                         reason: ReadStopReason::ConnectionClosed,
                         truncated,
                         tls_info: None,
+                        fields: Default::default(),
                     });
                 }
                 Some(false) => continue,
@@ -134,7 +140,10 @@ enum ReadError {
     Io(anyhow::Error),
 }
 
-async fn read_rpc_message(stream: &mut TcpStream, cfg: &Config) -> Result<Vec<u8>, ReadError> {
+async fn read_rpc_message(
+    stream: &mut dyn ClientStream,
+    cfg: &Config,
+) -> Result<Vec<u8>, ReadError> {
     let mut message = Vec::new();
     loop {
         let mut marker = [0u8; 4];
@@ -0,0 +1,57 @@
+use crate::clients::UdpClient;
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, ReadStopReason, Target};
+use async_trait::async_trait;
+use tokio::net::UdpSocket;
+use tokio::time::timeout;
+
+/// Sends the `--send` payload (an empty datagram without one) and keeps the
+/// first datagram that comes back, for services without a dedicated client.
+pub struct UdpRawClient;
+
+#[async_trait]
+impl UdpClient for UdpRawClient {
+    fn name(&self) -> &'static str {
+        "udp-raw"
+    }
+
+    fn matches(&self, _target: &Target) -> bool {
+        true
+    }
+
+    async fn execute(&self, target: &Target, cfg: &Config) -> anyhow::Result<ReadResult> {
+        let bind = if target.resolved.is_ipv6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        // Connected, so an ICMP port unreachable surfaces as ConnectionRefused.
+        socket.connect(target.resolved).await?;
+
+        let payload = cfg.send_payload.as_deref().unwrap_or_default();
+        timeout(cfg.connect_timeout, socket.send(payload)).await??;
+
+        let mut buf = vec![0u8; 65_535];
+        match timeout(cfg.read_timeout, socket.recv(&mut buf)).await {
+            Ok(Ok(n)) => {
+                buf.truncate(n.min(cfg.max_bytes));
+                Ok(ReadResult {
+                    bytes: buf,
+                    reason: ReadStopReason::ConnectionClosed,
+                    truncated: n > cfg.max_bytes,
+                    tls_info: None,
+                    fields: Default::default(),
+                })
+            }
+            Ok(Err(err)) => Err(err.into()),
+            Err(_) => Ok(ReadResult {
+                bytes: Vec::new(),
+                reason: ReadStopReason::Timeout,
+                truncated: false,
+                tls_info: None,
+                fields: Default::default(),
+            }),
+        }
+    }
+}
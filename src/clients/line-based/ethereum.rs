@@ -0,0 +1,90 @@
+use crate::model::{Config, Target};
+use async_trait::async_trait;
+
+use crate::clients::session::ClientSession;
+use crate::clients::{Client, ClientStream};
+
+const CLIENT_VERSION_CALL: &str =
+    r#"{"jsonrpc":"2.0","method":"eth_clientVersion","params":[],"id":1}"#;
+
+pub(crate) struct EthereumClient;
+
+#[async_trait]
+impl Client for EthereumClient {
+    fn name(&self) -> &'static str {
+        "ethereum"
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        matches!(target.resolved.port(), 8545 | 8546)
+    }
+
+    async fn execute(
+        &self,
+        stream: &mut dyn ClientStream,
+        cfg: &Config,
+        target: &Target,
+    ) -> anyhow::Result<crate::engine::reader::ReadResult> {
+        let mut session = ClientSession::new(cfg);
+        session
+            .send(
+                stream,
+                client_version_request(&host_header(target)).as_bytes(),
+            )
+            .await?;
+        // The first read stops after the headers; the body follows until the
+        // server closes the connection.
+        session.read(stream, None).await?;
+        session.read(stream, None).await?;
+        Ok(session.finish())
+    }
+}
+
+/// The name the target was given, as a browser would send it; nodes behind
+/// a reverse proxy route on it. The address stands in for bare IPs.
+fn host_header(target: &Target) -> String {
+    let host = &target.original.host;
+    let host = if host.is_empty() {
+        target.resolved.ip().to_string()
+    } else {
+        host.clone()
+    };
+    let port = target.resolved.port();
+    if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    }
+}
+
+fn client_version_request(host: &str) -> String {
+    format!(
+        "POST / HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{CLIENT_VERSION_CALL}",
+        CLIENT_VERSION_CALL.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock;
+
+    #[test]
+    fn sets_content_length_of_rpc_call() {
+        let request = client_version_request("192.0.2.1:8545");
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        assert!(head.contains(&format!("Content-Length: {}", body.len())));
+        assert!(body.contains("eth_clientVersion"));
+    }
+
+    #[test]
+    fn sends_the_target_name_as_host() {
+        let mut target = mock::target();
+        target.resolved.set_port(8545);
+        assert_eq!(host_header(&target), "server.test:8545");
+        target.original.host = String::new();
+        assert_eq!(host_header(&target), "127.0.0.1:8545");
+        target.original.host = "2001:db8::1".into();
+        assert_eq!(host_header(&target), "[2001:db8::1]:8545");
+    }
+}
@@ -1,9 +1,8 @@
 use crate::model::{Config, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
 
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
 
 pub(crate) struct FtpClient;
 
@@ -19,15 +18,21 @@ impl Client for FtpClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
         let mut session = ClientSession::new(cfg);
         let greeting = session.read_with_result(stream, None).await?;
 
+        // Most servers answer both before login; the rest are asked again
+        // once logged in.
+        let mut system_type = query(&mut session, stream, "SYST\r\n", parse_system_type).await?;
+        let mut features = query(&mut session, stream, "FEAT\r\n", parse_features).await?;
+
         let mut logged_in = false;
         let attempts = [
-            ("anonymous", "anonymous"),
+            ("anonymous", "anonymous@"),
             ("anonymous", ""),
             ("ftp", "ftp"),
             ("ftp", "anonymous"),
@@ -59,14 +64,13 @@ impl Client for FtpClient {
         }
 
         if logged_in {
-            for command in [
-                "SYST\r\n",
-                "FEAT\r\n",
-                "STAT\r\n",
-                "PWD\r\n",
-                "HELP SITE\r\n",
-                "HELP\r\n",
-            ] {
+            if system_type.is_none() {
+                system_type = query(&mut session, stream, "SYST\r\n", parse_system_type).await?;
+            }
+            if features.is_none() {
+                features = query(&mut session, stream, "FEAT\r\n", parse_features).await?;
+            }
+            for command in ["STAT\r\n", "PWD\r\n", "HELP SITE\r\n", "HELP\r\n"] {
                 session.send(stream, command.as_bytes()).await?;
                 session.read(stream, None).await?;
             }
@@ -77,10 +81,55 @@ impl Client for FtpClient {
             }
         }
 
+        session.insert_field("anonymous_login", logged_in.to_string());
+        if let Some(system_type) = system_type {
+            session.insert_field("system_type", system_type);
+        }
+        if let Some(features) = features {
+            session.insert_field("features", features.join("\n"));
+        }
         Ok(session.finish())
     }
 }
 
+/// Sends `command` and parses its reply, `None` when it was refused.
+async fn query<T>(
+    session: &mut ClientSession,
+    stream: &mut dyn ClientStream,
+    command: &str,
+    parse: fn(&[u8]) -> Option<T>,
+) -> anyhow::Result<Option<T>> {
+    session.send(stream, command.as_bytes()).await?;
+    let reply = session.read_with_result(stream, None).await?;
+    Ok(parse(&reply.bytes))
+}
+
+/// `215 UNIX Type: L8` -> `UNIX Type: L8`.
+fn parse_system_type(bytes: &[u8]) -> Option<String> {
+    if status_code(bytes)? != 215 {
+        return None;
+    }
+    let text = String::from_utf8_lossy(bytes);
+    let line = text.lines().next()?.trim();
+    Some(line.get(4..).unwrap_or_default().trim().to_string())
+}
+
+/// The features of a `211-Features:` listing, one per indented line
+/// (RFC 2389); a plain `211 No features` gives an empty list.
+fn parse_features(bytes: &[u8]) -> Option<Vec<String>> {
+    if status_code(bytes)? != 211 {
+        return None;
+    }
+    let text = String::from_utf8_lossy(bytes);
+    Some(
+        text.lines()
+            .filter(|line| line.starts_with(' '))
+            .map(|line| line.trim().to_string())
+            .filter(|feature| !feature.is_empty())
+            .collect(),
+    )
+}
+
 fn status_code(bytes: &[u8]) -> Option<u16> {
     let first_line = bytes.split(|b| *b == b'\n').next()?;
     let trimmed_start = first_line
@@ -112,6 +161,8 @@ fn requires_password(bytes: &[u8]) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clients::mock::{self, Step};
+    use crate::model::Protocol;
 
     #[test]
     fn parses_status_code_from_first_line() {
@@ -126,4 +177,74 @@ mod tests {
         assert!(requires_password(b"331 Please specify the password."));
         assert!(!requires_password(b"530 Permission denied"));
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn logs_in_anonymously_and_collects_server_details() {
+        let (mut stream, server) = mock::serve(vec![
+            Step::Send(b"220 ProFTPD Server ready\r\n"),
+            Step::Expect(b"SYST\r\n"),
+            Step::Send(b"215 UNIX Type: L8\r\n"),
+            Step::Expect(b"FEAT\r\n"),
+            Step::Send(b"211-Features:\r\n MDTM\r\n SIZE\r\n AUTH TLS\r\n UTF8\r\n211 End\r\n"),
+            Step::Expect(b"USER anonymous\r\n"),
+            Step::Send(b"331 Anonymous login ok, send your email as password\r\n"),
+            Step::Expect(b"PASS anonymous@\r\n"),
+            Step::Send(b"230 Anonymous access granted\r\n"),
+            Step::Expect(b"STAT\r\n"),
+            Step::Send(b"211 Status OK\r\n"),
+            Step::Expect(b"PWD\r\n"),
+            Step::Send(b"257 \"/\" is the current directory\r\n"),
+            Step::Expect(b"HELP SITE\r\n"),
+            Step::Send(b"214 CHMOD UMASK\r\n"),
+            Step::Expect(b"HELP\r\n"),
+            Step::Send(b"214 Direct comments to root\r\n"),
+        ]);
+
+        let result = FtpClient
+            .execute(&mut stream, &mock::config(Protocol::Ftp), &mock::target())
+            .await
+            .unwrap();
+        server.await.unwrap();
+
+        let text = String::from_utf8_lossy(&result.bytes);
+        assert!(text.starts_with("220 ProFTPD Server ready\r\n"));
+        assert!(text.contains("215 UNIX Type: L8"));
+        assert!(text.ends_with("214 Direct comments to root\r\n"));
+        assert_eq!(result.fields["system_type"], "UNIX Type: L8");
+        assert_eq!(result.fields["features"], "MDTM\nSIZE\nAUTH TLS\nUTF8");
+        assert_eq!(result.fields["anonymous_login"], "true");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_every_login_is_refused() {
+        let mut script = vec![
+            Step::Send(b"220 Service ready\r\n"),
+            Step::Expect(b"SYST\r\n"),
+            Step::Send(b"530 Please login with USER and PASS\r\n"),
+            Step::Expect(b"FEAT\r\n"),
+            Step::Send(b"530 Please login with USER and PASS\r\n"),
+        ];
+        for user in [
+            &b"USER anonymous\r\n"[..],
+            b"USER anonymous\r\n",
+            b"USER ftp\r\n",
+            b"USER ftp\r\n",
+        ] {
+            script.push(Step::Expect(user));
+            script.push(Step::Send(b"530 Login incorrect\r\n"));
+        }
+        let (mut stream, server) = mock::serve(script);
+
+        let result = FtpClient
+            .execute(&mut stream, &mock::config(Protocol::Ftp), &mock::target())
+            .await
+            .unwrap();
+        server.await.unwrap();
+
+        let text = String::from_utf8_lossy(&result.bytes);
+        assert_eq!(text.matches("530 Login incorrect").count(), 4);
+        assert!(!text.contains("215"));
+        assert_eq!(result.fields["anonymous_login"], "false");
+        assert!(!result.fields.contains_key("system_type"));
+    }
 }
@@ -1,8 +1,7 @@
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
 use crate::model::{Config, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
 
 pub(crate) struct ImqBrokerClient;
 
@@ -21,8 +20,9 @@ impl Client for ImqBrokerClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
         let mut session = ClientSession::new(cfg);
         session.send(stream, b"101 imqbroker probe\n").await?;
@@ -1,9 +1,8 @@
 use crate::model::{Config, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
 
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
 
 pub(crate) struct MemcachedClient;
 
@@ -19,8 +18,9 @@ impl Client for MemcachedClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
         let mut session = ClientSession::new(cfg);
         session.send(stream, b"version\r\n").await?;
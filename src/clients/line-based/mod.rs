@@ -1,3 +1,4 @@
+pub mod ethereum;
 pub mod ftp;
 pub mod imap;
 pub mod imqbroker;
@@ -1,9 +1,13 @@
 use crate::model::{Config, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
 
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
+
+/// Commands that turn an exposed server into a foothold or a wipe: `CONFIG
+/// SET dir` lets a client drop files (SSH keys, cron jobs), `FLUSHALL`
+/// deletes every key. Only looked up, never run.
+const DANGEROUS_COMMANDS: &[&str] = &["CONFIG", "FLUSHALL"];
 
 pub(crate) struct RedisClient;
 
@@ -19,14 +23,141 @@ impl Client for RedisClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
         let mut session = ClientSession::new(cfg);
         session.send(stream, b"PING\r\n").await?;
-        session.read(stream, None).await?;
+        let pong = session.read_with_result(stream, Some(b"\r\n")).await?;
+        // `-NOAUTH` when a password is set, `-DENIED` in protected mode.
+        let unauthenticated = pong.bytes.starts_with(b"+PONG");
+        session.insert_field("unauthenticated", unauthenticated.to_string());
+        if pong.bytes.starts_with(b"-DENIED") {
+            session.insert_field("protected_mode", "true");
+        }
+        if !unauthenticated {
+            return Ok(session.finish());
+        }
+
         session.send(stream, b"INFO\r\n").await?;
         session.read(stream, None).await?;
+
+        // Everything below is read-only: CONFIG GET changes nothing and
+        // COMMAND INFO only looks a command up.
+        session.send(stream, b"CONFIG GET dir\r\n").await?;
+        let dir = session.read_unrecorded(stream, Some(b"\r\n")).await?;
+        let dir = config_value(&dir.bytes);
+        let mut reachable = Vec::new();
+        if let Some(dir) = dir {
+            session.insert_field("config_dir", dir);
+            reachable.push("CONFIG");
+
+            session.send(stream, b"CONFIG GET requirepass\r\n").await?;
+            let password = session.read_unrecorded(stream, Some(b"\r\n")).await?;
+            if let Some(password) = config_value(&password.bytes) {
+                session.insert_field("requirepass_empty", password.is_empty().to_string());
+            }
+        }
+        for &command in DANGEROUS_COMMANDS {
+            if reachable.contains(&command) {
+                continue;
+            }
+            session
+                .send(stream, format!("COMMAND INFO {command}\r\n").as_bytes())
+                .await?;
+            let reply = session.read_unrecorded(stream, Some(b"\r\n")).await?;
+            if command_known(&reply.bytes) {
+                reachable.push(command);
+            }
+        }
+        session.insert_field("dangerous_commands", reachable.join(","));
         Ok(session.finish())
     }
 }
+
+/// The value of a `CONFIG GET <name>` reply: a name/value pair as a RESP2
+/// array or a RESP3 map. `None` for an error or an unknown parameter.
+fn config_value(reply: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(reply).ok()?;
+    let mut lines = text.split("\r\n");
+    if !matches!(lines.next()?, "*2" | "%1") {
+        return None;
+    }
+    // `$len`, name, `$len`, value.
+    lines.nth(3).map(str::to_string)
+}
+
+/// `COMMAND INFO` answers one entry per name, a null one when the command
+/// does not exist or was renamed away.
+fn command_known(reply: &[u8]) -> bool {
+    reply.starts_with(b"*1\r\n*") && !reply.starts_with(b"*1\r\n*-1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock::{self, Step};
+    use crate::model::Protocol;
+
+    #[test]
+    fn reads_config_values() {
+        assert_eq!(
+            config_value(b"*2\r\n$3\r\ndir\r\n$5\r\n/data\r\n").as_deref(),
+            Some("/data")
+        );
+        assert_eq!(
+            config_value(b"*2\r\n$11\r\nrequirepass\r\n$0\r\n\r\n").as_deref(),
+            Some("")
+        );
+        assert_eq!(config_value(b"*0\r\n"), None);
+        assert_eq!(config_value(b"-ERR unknown command 'CONFIG'\r\n"), None);
+        assert!(command_known(b"*1\r\n*10\r\n$8\r\nflushall\r\n"));
+        assert!(!command_known(b"*1\r\n*-1\r\n"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn flags_an_open_server_without_running_commands() {
+        let (mut stream, server) = mock::serve(vec![
+            Step::Expect(b"PING\r\n"),
+            Step::Send(b"+PONG\r\n"),
+            Step::Expect(b"INFO\r\n"),
+            Step::Send(b"$31\r\n# Server\r\nredis_version:7.2.4\r\n\r\n"),
+            Step::Expect(b"CONFIG GET dir\r\n"),
+            Step::Send(b"*2\r\n$3\r\ndir\r\n$5\r\n/data\r\n"),
+            Step::Expect(b"CONFIG GET requirepass\r\n"),
+            Step::Send(b"*2\r\n$11\r\nrequirepass\r\n$0\r\n\r\n"),
+            Step::Expect(b"COMMAND INFO FLUSHALL\r\n"),
+            Step::Send(b"*1\r\n*10\r\n$8\r\nflushall\r\n:-1\r\n"),
+        ]);
+
+        let result = RedisClient
+            .execute(&mut stream, &mock::config(Protocol::Redis), &mock::target())
+            .await
+            .unwrap();
+        server.await.unwrap();
+
+        assert_eq!(result.fields["unauthenticated"], "true");
+        assert_eq!(result.fields["config_dir"], "/data");
+        assert_eq!(result.fields["requirepass_empty"], "true");
+        assert_eq!(result.fields["dangerous_commands"], "CONFIG,FLUSHALL");
+        assert!(!String::from_utf8_lossy(&result.bytes).contains("requirepass"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stops_when_a_password_is_required() {
+        let (mut stream, server) = mock::serve(vec![
+            Step::Expect(b"PING\r\n"),
+            Step::Send(b"-NOAUTH Authentication required.\r\n"),
+        ]);
+
+        let result = RedisClient
+            .execute(&mut stream, &mock::config(Protocol::Redis), &mock::target())
+            .await
+            .unwrap();
+        server.await.unwrap();
+
+        assert_eq!(result.fields["unauthenticated"], "false");
+        assert!(!result.fields.contains_key("dangerous_commands"));
+    }
+}
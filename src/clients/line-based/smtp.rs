@@ -1,9 +1,9 @@
 use crate::model::{Config, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
+use tokio::time::Instant;
 
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
 
 pub(crate) struct SmtpClient;
 
@@ -19,15 +19,129 @@ impl Client for SmtpClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
+        // The connect already used part of the overall timeout.
+        let deadline = Instant::now() + cfg.overall_timeout.saturating_sub(cfg.connect_timeout);
         let mut session = ClientSession::new(cfg);
         session.read(stream, None).await?;
         session.send(stream, b"EHLO banner-grabber\r\n").await?;
         session.read(stream, None).await?;
+        if !cfg.smtp_users.is_empty() {
+            enumerate_users(stream, cfg, &mut session, deadline).await?;
+        }
         session.send(stream, b"QUIT\r\n").await?;
         session.read(stream, None).await?;
         Ok(session.finish())
     }
 }
+
+/// Checks each `--smtp-users` name with VRFY, switching to MAIL FROM/RCPT TO
+/// once the server says VRFY is not available. Each result is recorded as
+/// the field `user_enum.<name>` = `<method> <code>`. Stops early, leaving
+/// `user_enum_complete` false, when another command might not finish before
+/// the overall timeout cancels the whole target.
+async fn enumerate_users(
+    stream: &mut dyn ClientStream,
+    cfg: &Config,
+    session: &mut ClientSession,
+    deadline: Instant,
+) -> anyhow::Result<()> {
+    let mut use_rcpt = false;
+    let mut mail_started = false;
+    let mut complete = true;
+    for user in &cfg.smtp_users {
+        if Instant::now() + cfg.read_timeout * 2 > deadline {
+            complete = false;
+            break;
+        }
+        if !use_rcpt {
+            session
+                .send(stream, format!("VRFY {user}\r\n").as_bytes())
+                .await?;
+            match read_reply(stream, session).await? {
+                // Not implemented, disabled, or not permitted.
+                Some(500 | 502 | 504) => use_rcpt = true,
+                Some(code) => {
+                    session.insert_field(&format!("user_enum.{user}"), format!("vrfy {code}"));
+                    continue;
+                }
+                None => {
+                    complete = false;
+                    break;
+                }
+            }
+        }
+        if !mail_started {
+            session.send(stream, b"MAIL FROM:<>\r\n").await?;
+            if read_reply(stream, session).await? != Some(250) {
+                complete = false;
+                break;
+            }
+            mail_started = true;
+        }
+        session
+            .send(stream, format!("RCPT TO:<{user}>\r\n").as_bytes())
+            .await?;
+        match read_reply(stream, session).await? {
+            Some(code) => {
+                session.insert_field(&format!("user_enum.{user}"), format!("rcpt {code}"))
+            }
+            None => {
+                complete = false;
+                break;
+            }
+        }
+    }
+    if mail_started {
+        session.send(stream, b"RSET\r\n").await?;
+        read_reply(stream, session).await?;
+    }
+    session.insert_field("user_enum_complete", complete.to_string());
+    Ok(())
+}
+
+/// Reads one complete reply, multi-line ones included, and returns its code;
+/// `None` when the server went quiet or hung up first.
+async fn read_reply(
+    stream: &mut dyn ClientStream,
+    session: &mut ClientSession,
+) -> anyhow::Result<Option<u16>> {
+    let mut reply = Vec::new();
+    loop {
+        let part = session.read_with_result(stream, Some(b"\r\n")).await?;
+        if part.bytes.is_empty() {
+            return Ok(None);
+        }
+        reply.extend_from_slice(&part.bytes);
+        if let Some(code) = final_reply_code(&reply) {
+            return Ok(Some(code));
+        }
+    }
+}
+
+/// The code of the reply's last line, once that line (`250 ok`, as opposed
+/// to `250-more`) has arrived in full.
+fn final_reply_code(reply: &[u8]) -> Option<u16> {
+    let text = std::str::from_utf8(reply).ok()?;
+    let last = text.strip_suffix("\r\n")?.rsplit("\r\n").next()?;
+    if last.as_bytes().get(3).is_some_and(|b| *b != b' ') {
+        return None;
+    }
+    last.get(..3)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_for_the_last_line_of_a_reply() {
+        assert_eq!(final_reply_code(b"252 2.0.0 root\r\n"), Some(252));
+        assert_eq!(final_reply_code(b"250-first\r\n"), None);
+        assert_eq!(final_reply_code(b"250-first\r\n250 last\r\n"), Some(250));
+        assert_eq!(final_reply_code(b"550 5.1.1 no such"), None);
+    }
+}
@@ -1,17 +1,16 @@
 use crate::model::{Config, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
 
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
 
-const IAC: u8 = 255; // Interpret as Command
+pub(crate) const IAC: u8 = 255; // Interpret as Command
 const DO: u8 = 253;
-const DONT: u8 = 254;
-const WILL: u8 = 251;
+pub(crate) const DONT: u8 = 254;
+pub(crate) const WILL: u8 = 251;
 const WONT: u8 = 252;
-const SB: u8 = 250;
-const SE: u8 = 240;
+pub(crate) const SB: u8 = 250;
+pub(crate) const SE: u8 = 240;
 
 pub(crate) struct TelnetClient;
 
@@ -27,8 +26,9 @@ impl Client for TelnetClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
         let mut session = ClientSession::new(cfg);
 
@@ -51,7 +51,7 @@ impl Client for TelnetClient {
 impl TelnetClient {
     async fn handle_negotiation(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         session: &mut ClientSession,
         payload: &[u8],
     ) -> anyhow::Result<()> {
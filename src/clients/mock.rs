@@ -0,0 +1,143 @@
+//! In-memory transport for client tests: a scripted server on one half of a
+//! `tokio::io::duplex` pipe and the client under test on the other, so no
+//! listener or port is involved. Run the tests with `start_paused = true`:
+//! clients often read until the idle timeout, which paused time skips.
+
+use crate::model::{
+    Config, InputFormat, OutputConfig, OutputFormat, Protocol, ScanMode, Target, TargetSpec,
+};
+use std::future::Future;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio::task::JoinHandle;
+
+pub(crate) enum Step {
+    /// Bytes the server writes.
+    Send(&'static [u8]),
+    /// Bytes the server must receive next; anything else fails the test.
+    Expect(&'static [u8]),
+}
+
+/// Runs `script` against the returned stream. The server half closes once
+/// the script ends; await the handle to surface a failed `Expect`.
+pub(crate) fn serve(script: Vec<Step>) -> (DuplexStream, JoinHandle<()>) {
+    serve_with(|mut server| async move {
+        for step in script {
+            match step {
+                Step::Send(bytes) => server.write_all(bytes).await.unwrap(),
+                Step::Expect(expected) => {
+                    let mut received = vec![0u8; expected.len()];
+                    server.read_exact(&mut received).await.unwrap();
+                    assert_eq!(
+                        String::from_utf8_lossy(&received),
+                        String::from_utf8_lossy(expected)
+                    );
+                }
+            }
+        }
+    })
+}
+
+/// Like [`serve`], for servers whose replies depend on what the client sent
+/// (a key exchange, say): `server` drives its half of the pipe directly.
+pub(crate) fn serve_with<F, Fut>(server: F) -> (DuplexStream, JoinHandle<()>)
+where
+    F: FnOnce(DuplexStream) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (client, half) = tokio::io::duplex(64 * 1024);
+    (client, tokio::spawn(server(half)))
+}
+
+/// A target named `server.test` at the address the pipe reports as its
+/// peer.
+pub(crate) fn target() -> Target {
+    Target {
+        original: TargetSpec {
+            host: "server.test".into(),
+            port: 0,
+        },
+        resolved: std::net::SocketAddr::from(([127, 0, 0, 1], 0)),
+    }
+}
+
+pub(crate) fn config(protocol: Protocol) -> Config {
+    Config {
+        target: None,
+        input: Vec::new(),
+        input_format: InputFormat::Lines,
+        port_filter: None,
+        concurrency: 1,
+        rate: 1,
+        jitter: 0.0,
+        probe_timeout_jitter: 0.0,
+        ramp: None,
+        connect_timeout: Duration::from_millis(500),
+        read_timeout: Duration::from_millis(500),
+        overall_timeout: Duration::from_secs(5),
+        deadline: None,
+        max_bytes: 4096,
+        max_bytes_for: Default::default(),
+        max_read_rounds: 1000,
+        udp_retries: 1,
+        protocols: Vec::new(),
+        protocol_by_port: Default::default(),
+        repeat: None,
+        repeat_count: 0,
+        active_ports: None,
+        mode: ScanMode::Active,
+        protocol,
+        webdriver: false,
+        tech: false,
+        fail_fast: false,
+        exit_code_on_open: false,
+        max_body: None,
+        peek_bytes: None,
+        test_proxy_protocol: None,
+        resolvers: Vec::new(),
+        proxy: None,
+        replay: None,
+        bench_targets: None,
+        tech_rules: None,
+        tls_resumption: false,
+        tls_reneg: false,
+        tls_groups: false,
+        mssql_instance: None,
+        cert_warn_days: 30,
+        client_cert: None,
+        record_probe: false,
+        ssh_auth_methods: false,
+        http_accept_status: None,
+        smtp_users: Vec::new(),
+        send_payload: None,
+        nudge: false,
+        starttls: false,
+        implicit_tls_ports: Vec::new(),
+        test_smuggling: false,
+        test_open_proxy: false,
+        http_paths: Vec::new(),
+        strict_resolution: false,
+        dedupe: false,
+        dedupe_resolved: false,
+        host_discovery: None,
+        output: OutputConfig {
+            format: OutputFormat::Jsonl,
+            reverse_dns: false,
+            count_only: false,
+            banner_encoding: None,
+            raw_dir: None,
+            cert_dir: None,
+            cert_chain: false,
+            stream_to: None,
+            stream_policy: Default::default(),
+            tags: Default::default(),
+            scan_id: None,
+            timestamp_format: Default::default(),
+            color: Default::default(),
+            dedupe_http_bodies: false,
+            group_by_host: false,
+            hexdump: None,
+            emit_config: false,
+        },
+    }
+}
@@ -1,20 +1,39 @@
 mod binaries;
 #[path = "line-based/mod.rs"]
 mod line_based;
+#[cfg(test)]
+pub(crate) mod mock;
+mod recorder;
 mod registry;
 mod session;
 mod stateful;
 
+pub use binaries::coap::CoapClient;
+pub use binaries::llmnr::LlmnrClient;
+pub use binaries::mdns::MdnsClient;
+pub use binaries::memcached_udp::MemcachedUdpClient;
+pub use binaries::mssql_browser::MssqlBrowserClient;
 pub use binaries::ntp::NtpClient;
-pub use binaries::{mssql, mysql, rpcbind};
-pub use line_based::{ftp, imap, imqbroker, memcached, mqtt, pop3, redis, smtp, telnet, upnp};
+pub use binaries::udp_raw::UdpRawClient;
+pub use binaries::{bitcoin, kafka, mongodb, mssql, mysql, postgres, rpcbind};
+pub use line_based::{
+    ethereum, ftp, imap, imqbroker, memcached, mqtt, pop3, redis, smtp, telnet, upnp,
+};
+pub use recorder::RecordingStream;
 pub use registry::{client_for_target, udp_client_for_target, ClientRequest};
 pub use stateful::{smb, ssh, vnc};
 
 use crate::engine::reader::ReadResult;
 use crate::model::{Config, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// The connection a [`Client`] talks over: the target's `TcpStream` in a
+/// scan (or the TLS session on it, on an implicit-TLS port), an in-memory
+/// `tokio::io::duplex` pipe in tests.
+pub trait ClientStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> ClientStream for S {}
 
 #[async_trait]
 pub trait Client: Send + Sync {
@@ -22,7 +41,12 @@ pub trait Client: Send + Sync {
     #[allow(dead_code)]
     fn matches(&self, target: &Target) -> bool;
 
-    async fn execute(&self, stream: &mut TcpStream, cfg: &Config) -> anyhow::Result<ReadResult>;
+    async fn execute(
+        &self,
+        stream: &mut dyn ClientStream,
+        cfg: &Config,
+        target: &Target,
+    ) -> anyhow::Result<ReadResult>;
 }
 
 #[async_trait]
@@ -0,0 +1,83 @@
+//! Pass-through stream for `--record-probe`: keeps a copy of every byte a
+//! client writes to the target.
+
+use super::ClientStream;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+pub struct RecordingStream<'a> {
+    inner: &'a mut dyn ClientStream,
+    sent: Vec<u8>,
+}
+
+impl<'a> RecordingStream<'a> {
+    pub fn new(inner: &'a mut dyn ClientStream) -> Self {
+        Self {
+            inner,
+            sent: Vec::new(),
+        }
+    }
+
+    /// Everything written so far, in order.
+    pub fn into_sent(self) -> Vec<u8> {
+        self.sent
+    }
+}
+
+impl AsyncRead for RecordingStream<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for RecordingStream<'_> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut *self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.sent.extend_from_slice(&buf[..written]);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock::{serve, Step};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn keeps_written_bytes_and_passes_reads_through() {
+        let (mut stream, server) = serve(vec![
+            Step::Expect(b"PING\r\n"),
+            Step::Send(b"+PONG\r\n"),
+            Step::Expect(b"QUIT\r\n"),
+        ]);
+        let mut recorder = RecordingStream::new(&mut stream);
+        recorder.write_all(b"PING\r\n").await.unwrap();
+        let mut reply = [0u8; 7];
+        recorder.read_exact(&mut reply).await.unwrap();
+        recorder.write_all(b"QUIT\r\n").await.unwrap();
+        assert_eq!(&reply, b"+PONG\r\n");
+        assert_eq!(recorder.into_sent(), b"PING\r\nQUIT\r\n");
+        server.await.unwrap();
+    }
+}
@@ -1,13 +1,18 @@
 use crate::model::{Protocol, ScanMode, Target};
 
+use super::bitcoin::BitcoinClient;
+use super::ethereum::EthereumClient;
 use super::ftp::FtpClient;
 use super::imap::ImapClient;
 use super::imqbroker::ImqBrokerClient;
+use super::kafka::KafkaClient;
 use super::memcached::MemcachedClient;
+use super::mongodb::MongodbClient;
 use super::mqtt::MqttClient;
 use super::mssql::MssqlClient;
 use super::mysql::MysqlClient;
 use super::pop3::Pop3Client;
+use super::postgres::PostgresClient;
 use super::redis::RedisClient;
 use super::rpcbind::RpcbindClient;
 use super::smb::SmbClient;
@@ -16,8 +21,11 @@ use super::ssh::SshClient;
 use super::telnet::TelnetClient;
 use super::upnp::UpnpClient;
 use super::vnc::VncClient;
-use crate::clients::NtpClient;
 use crate::clients::{Client, UdpClient};
+use crate::clients::{
+    CoapClient, LlmnrClient, MdnsClient, MemcachedUdpClient, MssqlBrowserClient, NtpClient,
+    UdpRawClient,
+};
 
 pub struct ClientRequest {
     #[allow(dead_code)]
@@ -26,17 +34,28 @@ pub struct ClientRequest {
     pub protocol: Protocol,
 }
 
+static COAP_CLIENT: CoapClient = CoapClient;
+static LLMNR_CLIENT: LlmnrClient = LlmnrClient;
+static MDNS_CLIENT: MdnsClient = MdnsClient;
+static MEMCACHED_UDP_CLIENT: MemcachedUdpClient = MemcachedUdpClient;
+static MSSQL_BROWSER_CLIENT: MssqlBrowserClient = MssqlBrowserClient;
 static NTP_CLIENT: NtpClient = NtpClient;
+static UDP_RAW_CLIENT: UdpRawClient = UdpRawClient;
 static UPNP_CLIENT: UpnpClient = UpnpClient;
 
+static BITCOIN_CLIENT: BitcoinClient = BitcoinClient;
+static ETHEREUM_CLIENT: EthereumClient = EthereumClient;
 static FTP_CLIENT: FtpClient = FtpClient;
 static IMAP_CLIENT: ImapClient = ImapClient;
 static IMQBROKER_CLIENT: ImqBrokerClient = ImqBrokerClient;
+static KAFKA_CLIENT: KafkaClient = KafkaClient;
 static MEMCACHED_CLIENT: MemcachedClient = MemcachedClient;
+static MONGODB_CLIENT: MongodbClient = MongodbClient;
 static MQTT_CLIENT: MqttClient = MqttClient;
 static MSSQL_CLIENT: MssqlClient = MssqlClient;
 static MYSQL_CLIENT: MysqlClient = MysqlClient;
 static POP3_CLIENT: Pop3Client = Pop3Client;
+static POSTGRES_CLIENT: PostgresClient = PostgresClient;
 static REDIS_CLIENT: RedisClient = RedisClient;
 static RPCBIND_CLIENT: RpcbindClient = RpcbindClient;
 static SMTP_CLIENT: SmtpClient = SmtpClient;
@@ -51,18 +70,23 @@ pub fn client_for_target(req: &ClientRequest) -> Option<&'static dyn Client> {
     }
 
     match req.protocol {
+        Protocol::Bitcoin => Some(&BITCOIN_CLIENT),
+        Protocol::Ethereum => Some(&ETHEREUM_CLIENT),
         Protocol::Ftp => Some(&FTP_CLIENT),
-        Protocol::Imap => Some(&IMAP_CLIENT),
+        Protocol::Imap | Protocol::Imaps => Some(&IMAP_CLIENT),
         Protocol::Imqbroker => Some(&IMQBROKER_CLIENT),
+        Protocol::Kafka => Some(&KAFKA_CLIENT),
         Protocol::Memcached => Some(&MEMCACHED_CLIENT),
+        Protocol::Mongodb => Some(&MONGODB_CLIENT),
         Protocol::Mqtt => Some(&MQTT_CLIENT),
         Protocol::Mssql => Some(&MSSQL_CLIENT),
         Protocol::Mysql => Some(&MYSQL_CLIENT),
-        Protocol::Pop3 => Some(&POP3_CLIENT),
+        Protocol::Pop3 | Protocol::Pop3s => Some(&POP3_CLIENT),
+        Protocol::Postgres => Some(&POSTGRES_CLIENT),
         Protocol::Redis => Some(&REDIS_CLIENT),
         Protocol::Rpcbind => Some(&RPCBIND_CLIENT),
         Protocol::Smb => Some(&SMB_CLIENT),
-        Protocol::Smtp => Some(&SMTP_CLIENT),
+        Protocol::Smtp | Protocol::Smtps => Some(&SMTP_CLIENT),
         Protocol::Ssh => Some(&SSH_CLIENT),
         Protocol::Telnet => Some(&TELNET_CLIENT),
         Protocol::Upnp => Some(&UPNP_CLIENT),
@@ -77,7 +101,13 @@ pub fn udp_client_for_target(req: &ClientRequest) -> Option<&'static dyn UdpClie
     }
 
     match req.protocol {
+        Protocol::Coap => Some(&COAP_CLIENT),
+        Protocol::Llmnr => Some(&LLMNR_CLIENT),
+        Protocol::Mdns => Some(&MDNS_CLIENT),
+        Protocol::MemcachedUdp => Some(&MEMCACHED_UDP_CLIENT),
+        Protocol::MssqlBrowser => Some(&MSSQL_BROWSER_CLIENT),
         Protocol::Ntp => Some(&NTP_CLIENT),
+        Protocol::UdpRaw => Some(&UDP_RAW_CLIENT),
         _ => None,
     }
 }
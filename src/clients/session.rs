@@ -1,29 +1,34 @@
-use crate::engine::reader::{BannerReader, ReadResult};
+use super::ClientStream;
+use crate::engine::reader::{BannerReader, ReadResult, ReadStrategy};
 use crate::model::{Config, ReadStopReason};
 use anyhow::Context;
+use std::collections::BTreeMap;
 use tokio::io::AsyncWriteExt;
-use tokio::net::TcpStream;
 
 pub(crate) struct ClientSession {
     reader: BannerReader,
     parts: Vec<ReadResult>,
     max_bytes: usize,
     truncated: bool,
+    /// Structured facts a client learned, merged into the result's fields.
+    fields: BTreeMap<String, String>,
 }
 
 impl ClientSession {
     pub(super) fn new(cfg: &Config) -> Self {
         Self {
-            reader: BannerReader::new(cfg.max_bytes, cfg.read_timeout),
+            reader: BannerReader::new(cfg.max_bytes, cfg.read_timeout)
+                .with_max_rounds(cfg.max_read_rounds),
             parts: Vec::new(),
             max_bytes: cfg.max_bytes,
             truncated: false,
+            fields: BTreeMap::new(),
         }
     }
 
     pub(super) async fn read(
         &mut self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         delimiter: Option<&[u8]>,
     ) -> anyhow::Result<()> {
         let res = self.reader.read(stream, delimiter).await?;
@@ -34,7 +39,7 @@ impl ClientSession {
 
     pub(super) async fn read_with_result(
         &mut self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         delimiter: Option<&[u8]>,
     ) -> anyhow::Result<ReadResult> {
         let res = self.reader.read(stream, delimiter).await?;
@@ -43,9 +48,32 @@ impl ClientSession {
         Ok(res)
     }
 
+    /// Reads a reply that stays out of the banner, e.g. one that may carry a
+    /// secret; the client keeps what it needs as fields.
+    pub(super) async fn read_unrecorded(
+        &mut self,
+        stream: &mut dyn ClientStream,
+        delimiter: Option<&[u8]>,
+    ) -> anyhow::Result<ReadResult> {
+        self.reader.read(stream, delimiter).await
+    }
+
+    /// Reads one message as `strategy` frames it, e.g. exactly one
+    /// length-prefixed packet instead of waiting for the idle timeout.
+    pub(super) async fn read_using(
+        &mut self,
+        stream: &mut dyn ClientStream,
+        strategy: ReadStrategy,
+    ) -> anyhow::Result<ReadResult> {
+        let res = self.reader.read_using(stream, strategy).await?;
+        self.truncated |= res.truncated;
+        self.parts.push(res.clone());
+        Ok(res)
+    }
+
     pub(super) async fn send(
         &mut self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         bytes: &[u8],
     ) -> anyhow::Result<()> {
         stream
@@ -61,19 +89,26 @@ impl ClientSession {
             reason: ReadStopReason::NotStarted,
             truncated: false,
             tls_info: None,
+            fields: Default::default(),
         });
     }
 
+    pub(super) fn insert_field(&mut self, key: &str, value: impl Into<String>) {
+        self.fields.insert(key.to_string(), value.into());
+    }
+
     pub(super) fn finish(mut self) -> ReadResult {
         let mut merged = Vec::new();
         let mut reason = ReadStopReason::NotStarted;
         let mut tls_info = None;
+        let mut fields = std::mem::take(&mut self.fields);
 
         for part in self.parts.drain(..) {
             reason = part.reason.clone();
             if tls_info.is_none() {
                 tls_info = part.tls_info.clone();
             }
+            fields.extend(part.fields);
             if merged.len() < self.max_bytes {
                 let remaining = self.max_bytes - merged.len();
                 let take = part.bytes.len().min(remaining);
@@ -92,6 +127,7 @@ impl ClientSession {
             reason,
             truncated: self.truncated || final_len >= self.max_bytes,
             tls_info,
+            fields,
         }
     }
 }
@@ -102,24 +138,8 @@ mod tests {
 
     #[test]
     fn merges_partial_reads_with_truncation() {
-        let cfg = Config {
-            target: None,
-            input: None,
-            port_filter: None,
-            concurrency: 1,
-            rate: 1,
-            connect_timeout: std::time::Duration::from_millis(100),
-            read_timeout: std::time::Duration::from_millis(100),
-            overall_timeout: std::time::Duration::from_millis(100),
-            max_bytes: 5,
-            mode: crate::model::ScanMode::Active,
-            protocol: crate::model::Protocol::Http,
-            webdriver: false,
-            tech: false,
-            output: crate::model::OutputConfig {
-                format: crate::model::OutputFormat::Jsonl,
-            },
-        };
+        let mut cfg = crate::clients::mock::config(crate::model::Protocol::Http);
+        cfg.max_bytes = 5;
         let mut session = ClientSession::new(&cfg);
         session.truncated = true;
         session.parts.push(ReadResult {
@@ -127,12 +147,14 @@ mod tests {
             reason: ReadStopReason::Delimiter,
             truncated: false,
             tls_info: None,
+            fields: Default::default(),
         });
         session.parts.push(ReadResult {
             bytes: b"world".to_vec(),
             reason: ReadStopReason::ConnectionClosed,
             truncated: false,
             tls_info: None,
+            fields: Default::default(),
         });
         let result = session.finish();
         assert_eq!(result.bytes, b"hello".to_vec());
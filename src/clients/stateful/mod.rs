@@ -1,3 +1,4 @@
 pub mod smb;
 pub mod ssh;
+mod ssh_auth;
 pub mod vnc;
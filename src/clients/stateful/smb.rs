@@ -1,12 +1,22 @@
+use crate::engine::reader::ReadStrategy;
 use crate::model::{Config, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
 
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
 
 pub(crate) struct SmbClient;
 
+/// Direct-TCP/NetBIOS session header: a zero type byte, then a 3-byte
+/// big-endian length of the SMB message that follows.
+const NETBIOS_SESSION: ReadStrategy = ReadStrategy::LengthPrefixed {
+    header_len: 4,
+    header_offset: 1,
+    size: 3,
+    little_endian: false,
+    counted: 0,
+};
+
 // SMB Negotiate Protocol Request covering SMB1 and SMB2 dialects.
 // Adapted from common version-scanning probes that include SMB 2.002 and SMB 2.??? dialect strings.
 const SMB_NEGOTIATE_REQUEST: &[u8] = &[
@@ -34,12 +44,13 @@ impl Client for SmbClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
         let mut session = ClientSession::new(cfg);
         session.send(stream, SMB_NEGOTIATE_REQUEST).await?;
-        session.read(stream, None).await?;
+        session.read_using(stream, NETBIOS_SESSION).await?;
         Ok(session.finish())
     }
 }
@@ -1,9 +1,9 @@
 use crate::model::{Config, Target};
 use async_trait::async_trait;
-use tokio::net::TcpStream;
 
+use super::ssh_auth::{self, CLIENT_VERSION};
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
 
 pub(crate) struct SshClient;
 
@@ -19,14 +19,29 @@ impl Client for SshClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
         let mut session = ClientSession::new(cfg);
-        session.read(stream, Some(b"\n")).await?;
+        let greeting = session.read_with_result(stream, Some(b"\n")).await?;
         // Sending our identification string is optional; ignore errors if the server closes early.
-        let _ = session.send(stream, b"SSH-2.0-banner-grabber\r\n").await;
-        let _ = session.read(stream, None).await;
+        let version = format!("{CLIENT_VERSION}\r\n");
+        let _ = session.send(stream, version.as_bytes()).await;
+        let kexinit = session.read_with_result(stream, None).await;
+        if let (true, Ok(kexinit)) = (cfg.ssh_auth_methods, kexinit) {
+            let received = [greeting.bytes, kexinit.bytes].concat();
+            match ssh_auth::auth_methods(stream, cfg, &received).await {
+                Ok(auth) => {
+                    session.insert_field("auth_methods", auth.methods.join(","));
+                    session.insert_field("auth_none_accepted", auth.none_accepted.to_string());
+                    if let Some(banner) = auth.banner {
+                        session.insert_field("auth_banner", banner);
+                    }
+                }
+                Err(err) => session.insert_field("auth_methods_error", err.to_string()),
+            }
+        }
         Ok(session.finish())
     }
 }
@@ -0,0 +1,780 @@
+//! Just enough of the SSH transport (RFC 4253) to learn which authentication
+//! methods a server offers: key exchange, NEWKEYS, the `ssh-userauth`
+//! service request and a `none` authentication request, whose failure lists
+//! the methods that can continue. The host key signature is not verified;
+//! nothing sent over the connection is secret.
+
+use crate::clients::ClientStream;
+use crate::model::Config;
+use anyhow::{anyhow, bail, Context};
+use openssl::bn::{BigNum, BigNumContext, MsbOption};
+use openssl::derive::Deriver;
+use openssl::hash::{hash, MessageDigest};
+use openssl::memcmp;
+use openssl::pkey::{Id, PKey};
+use openssl::sign::Signer;
+use openssl::symm::{Cipher, Crypter, Mode};
+use rand::RngCore;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::timeout;
+
+/// Identification string sent by the SSH client, without the CRLF.
+pub(super) const CLIENT_VERSION: &str = "SSH-2.0-banner-grabber";
+/// Username in the `none` request; servers answer with the same method list
+/// for unknown users so they do not reveal which accounts exist.
+const PROBE_USER: &str = "banner-grabber";
+/// RFC 4253 lets implementations refuse anything larger.
+const MAX_PACKET: usize = 35_000;
+
+const MSG_DISCONNECT: u8 = 1;
+const MSG_IGNORE: u8 = 2;
+const MSG_UNIMPLEMENTED: u8 = 3;
+const MSG_DEBUG: u8 = 4;
+const MSG_SERVICE_REQUEST: u8 = 5;
+const MSG_SERVICE_ACCEPT: u8 = 6;
+const MSG_EXT_INFO: u8 = 7;
+const MSG_KEXINIT: u8 = 20;
+const MSG_NEWKEYS: u8 = 21;
+const MSG_KEX_INIT: u8 = 30;
+const MSG_KEX_REPLY: u8 = 31;
+const MSG_USERAUTH_REQUEST: u8 = 50;
+const MSG_USERAUTH_FAILURE: u8 = 51;
+const MSG_USERAUTH_SUCCESS: u8 = 52;
+const MSG_USERAUTH_BANNER: u8 = 53;
+
+const KEX_ALGORITHMS: &[&str] = &[
+    "curve25519-sha256",
+    "curve25519-sha256@libssh.org",
+    "diffie-hellman-group14-sha256",
+];
+/// Every common host key type: the signature is never checked, so the
+/// server may pick whichever it has.
+const HOST_KEY_ALGORITHMS: &[&str] = &[
+    "ssh-ed25519",
+    "ecdsa-sha2-nistp256",
+    "ecdsa-sha2-nistp384",
+    "ecdsa-sha2-nistp521",
+    "rsa-sha2-512",
+    "rsa-sha2-256",
+    "ssh-rsa",
+];
+const CIPHERS: &[&str] = &["aes128-ctr"];
+const MACS: &[&str] = &["hmac-sha2-256", "hmac-sha1"];
+
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct AuthMethods {
+    /// Methods that can continue, in the server's order.
+    pub methods: Vec<String>,
+    /// The server let the `none` request in: no authentication at all.
+    pub none_accepted: bool,
+    /// SSH_MSG_USERAUTH_BANNER text shown before authentication.
+    pub banner: Option<String>,
+}
+
+/// Runs the exchange on a connection whose identification strings have
+/// already been swapped. `received` holds everything read from the server
+/// so far: its identification line and whatever followed it.
+pub(super) async fn auth_methods(
+    stream: &mut dyn ClientStream,
+    cfg: &Config,
+    received: &[u8],
+) -> anyhow::Result<AuthMethods> {
+    let (server_version, rest) = split_server_version(received)
+        .ok_or_else(|| anyhow!("no SSH identification line from the server"))?;
+    let mut transport = Transport {
+        stream,
+        read_timeout: cfg.read_timeout,
+        max_rounds: cfg.max_read_rounds,
+        buffered: rest.to_vec(),
+        send_seq: 0,
+        recv_seq: 0,
+        send_keys: None,
+        recv_keys: None,
+    };
+
+    let server_kexinit = transport.read_message(MSG_KEXINIT).await?;
+    let client_kexinit = client_kexinit();
+    transport.write_packet(&client_kexinit).await?;
+    let algorithms = Algorithms::negotiate(&server_kexinit)?;
+    if algorithms.skip_guess {
+        transport.read_packet().await?;
+    }
+
+    let exchange = key_exchange(&mut transport, algorithms.kex).await?;
+    let session_id = exchange_hash(server_version, &client_kexinit, &server_kexinit, &exchange)?;
+
+    transport.write_packet(&[MSG_NEWKEYS]).await?;
+    let derive = |letter, len| derive_key(&exchange.shared_secret, &session_id, letter, len);
+    transport.send_keys = Some(Keys::new(
+        Mode::Encrypt,
+        &derive(b'C', 16)?,
+        &derive(b'A', 16)?,
+        algorithms.mac_out,
+        derive(b'E', algorithms.mac_out.size())?,
+    )?);
+    transport.read_message(MSG_NEWKEYS).await?;
+    transport.recv_keys = Some(Keys::new(
+        Mode::Decrypt,
+        &derive(b'D', 16)?,
+        &derive(b'B', 16)?,
+        algorithms.mac_in,
+        derive(b'F', algorithms.mac_in.size())?,
+    )?);
+
+    let mut request = vec![MSG_SERVICE_REQUEST];
+    put_string(&mut request, b"ssh-userauth");
+    transport.write_packet(&request).await?;
+    transport.read_message(MSG_SERVICE_ACCEPT).await?;
+
+    let mut request = vec![MSG_USERAUTH_REQUEST];
+    put_string(&mut request, PROBE_USER.as_bytes());
+    put_string(&mut request, b"ssh-connection");
+    put_string(&mut request, b"none");
+    transport.write_packet(&request).await?;
+
+    let mut result = AuthMethods::default();
+    loop {
+        let payload = transport.read_packet().await?;
+        let mut reader = Reader::new(&payload[1..]);
+        match payload[0] {
+            MSG_USERAUTH_BANNER => {
+                result.banner = Some(String::from_utf8_lossy(reader.string()?).into_owned());
+            }
+            MSG_USERAUTH_FAILURE => {
+                result.methods = reader.name_list()?;
+                return Ok(result);
+            }
+            MSG_USERAUTH_SUCCESS => {
+                result.none_accepted = true;
+                return Ok(result);
+            }
+            other => bail!("unexpected SSH message {other} after the none request"),
+        }
+    }
+}
+
+/// The identification line (without CRLF) and the bytes after it. Servers
+/// may send other lines first (RFC 4253 section 4.2).
+fn split_server_version(received: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut start = 0;
+    while start < received.len() {
+        let end = start + received[start..].iter().position(|&b| b == b'\n')?;
+        let line = &received[start..end];
+        if line.starts_with(b"SSH-") {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            return Some((line, &received[end + 1..]));
+        }
+        start = end + 1;
+    }
+    None
+}
+
+fn client_kexinit() -> Vec<u8> {
+    let mut payload = vec![MSG_KEXINIT];
+    let mut cookie = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut cookie);
+    payload.extend_from_slice(&cookie);
+    for list in [
+        KEX_ALGORITHMS,
+        HOST_KEY_ALGORITHMS,
+        CIPHERS,
+        CIPHERS,
+        MACS,
+        MACS,
+        &["none"],
+        &["none"],
+        &[],
+        &[],
+    ] {
+        put_string(&mut payload, list.join(",").as_bytes());
+    }
+    // first_kex_packet_follows, reserved
+    payload.push(0);
+    payload.extend_from_slice(&[0; 4]);
+    payload
+}
+
+struct Algorithms {
+    kex: &'static str,
+    mac_out: MessageDigest,
+    mac_in: MessageDigest,
+    /// The server guessed its first key exchange packet and guessed wrong.
+    skip_guess: bool,
+}
+
+impl Algorithms {
+    fn negotiate(server_kexinit: &[u8]) -> anyhow::Result<Self> {
+        let mut reader = Reader::new(&server_kexinit[1..]);
+        reader.take(16)?;
+        let mut lists = Vec::new();
+        for _ in 0..10 {
+            lists.push(reader.name_list()?);
+        }
+        let guess_follows = reader.u8()? != 0;
+        let pick = |ours: &[&'static str], theirs: &[String], what: &str| {
+            ours.iter()
+                .find(|name| theirs.iter().any(|offered| offered == *name))
+                .copied()
+                .ok_or_else(|| anyhow!("no common {what}: server offers {}", theirs.join(",")))
+        };
+        let kex = pick(KEX_ALGORITHMS, &lists[0], "key exchange")?;
+        pick(HOST_KEY_ALGORITHMS, &lists[1], "host key algorithm")?;
+        pick(CIPHERS, &lists[2], "cipher")?;
+        pick(CIPHERS, &lists[3], "cipher")?;
+        let mac = |name| match name {
+            "hmac-sha2-256" => MessageDigest::sha256(),
+            _ => MessageDigest::sha1(),
+        };
+        let mac_out = mac(pick(MACS, &lists[4], "MAC")?);
+        let mac_in = mac(pick(MACS, &lists[5], "MAC")?);
+        pick(&["none"], &lists[6], "compression")?;
+        pick(&["none"], &lists[7], "compression")?;
+        Ok(Self {
+            kex,
+            mac_out,
+            mac_in,
+            skip_guess: guess_follows
+                && (lists[0].first().map(String::as_str) != Some(kex)
+                    || lists[1].first().map(String::as_str) != Some(HOST_KEY_ALGORITHMS[0])),
+        })
+    }
+}
+
+/// H, which is also the session identifier for the first key exchange.
+fn exchange_hash(
+    server_version: &[u8],
+    client_kexinit: &[u8],
+    server_kexinit: &[u8],
+    exchange: &Exchange,
+) -> anyhow::Result<Vec<u8>> {
+    let mut digest = Vec::new();
+    put_string(&mut digest, CLIENT_VERSION.as_bytes());
+    put_string(&mut digest, server_version);
+    put_string(&mut digest, client_kexinit);
+    put_string(&mut digest, server_kexinit);
+    put_string(&mut digest, &exchange.host_key);
+    digest.extend_from_slice(&exchange.hashed_values);
+    put_mpint(&mut digest, &exchange.shared_secret);
+    Ok(hash(MessageDigest::sha256(), &digest)?.to_vec())
+}
+
+/// One of the six keys of RFC 4253 section 7.2, `letter` 'A' through 'F'.
+fn derive_key(
+    shared_secret: &[u8],
+    session_id: &[u8],
+    letter: u8,
+    len: usize,
+) -> anyhow::Result<Vec<u8>> {
+    let mut input = Vec::new();
+    put_mpint(&mut input, shared_secret);
+    input.extend_from_slice(session_id);
+    input.push(letter);
+    input.extend_from_slice(session_id);
+    let mut key = hash(MessageDigest::sha256(), &input)?.to_vec();
+    key.truncate(len);
+    Ok(key)
+}
+
+struct Exchange {
+    host_key: Vec<u8>,
+    /// The method-specific part of the exchange hash (`Q_C || Q_S` or
+    /// `e || f`), already encoded.
+    hashed_values: Vec<u8>,
+    /// K as an unsigned big-endian integer.
+    shared_secret: Vec<u8>,
+}
+
+async fn key_exchange(transport: &mut Transport<'_>, kex: &str) -> anyhow::Result<Exchange> {
+    if kex.starts_with("curve25519") {
+        let key = PKey::generate_x25519()?;
+        let ours = key.raw_public_key()?;
+        let mut init = vec![MSG_KEX_INIT];
+        put_string(&mut init, &ours);
+        transport.write_packet(&init).await?;
+
+        let reply = transport.read_message(MSG_KEX_REPLY).await?;
+        let mut reader = Reader::new(&reply[1..]);
+        let host_key = reader.string()?.to_vec();
+        let theirs = reader.string()?;
+        let peer = PKey::public_key_from_raw_bytes(theirs, Id::X25519)
+            .context("invalid curve25519 public key from server")?;
+        let mut deriver = Deriver::new(&key)?;
+        deriver.set_peer(&peer)?;
+        let mut hashed_values = Vec::new();
+        put_string(&mut hashed_values, &ours);
+        put_string(&mut hashed_values, theirs);
+        Ok(Exchange {
+            host_key,
+            hashed_values,
+            shared_secret: deriver.derive_to_vec()?,
+        })
+    } else {
+        let prime = BigNum::get_rfc3526_prime_2048()?;
+        let generator = BigNum::from_u32(2)?;
+        let mut ctx = BigNumContext::new()?;
+        let mut secret = BigNum::new()?;
+        secret.rand(256, MsbOption::MAYBE_ZERO, false)?;
+        let mut ours = BigNum::new()?;
+        ours.mod_exp(&generator, &secret, &prime, &mut ctx)?;
+        let mut init = vec![MSG_KEX_INIT];
+        put_mpint(&mut init, &ours.to_vec());
+        transport.write_packet(&init).await?;
+
+        let reply = transport.read_message(MSG_KEX_REPLY).await?;
+        let mut reader = Reader::new(&reply[1..]);
+        let host_key = reader.string()?.to_vec();
+        let theirs = BigNum::from_slice(reader.string()?)?;
+        let mut shared = BigNum::new()?;
+        shared.mod_exp(&theirs, &secret, &prime, &mut ctx)?;
+        let mut hashed_values = Vec::new();
+        put_mpint(&mut hashed_values, &ours.to_vec());
+        put_mpint(&mut hashed_values, &theirs.to_vec());
+        Ok(Exchange {
+            host_key,
+            hashed_values,
+            shared_secret: shared.to_vec(),
+        })
+    }
+}
+
+/// One direction's aes128-ctr state and MAC.
+struct Keys {
+    crypter: Crypter,
+    mac: MessageDigest,
+    mac_key: Vec<u8>,
+}
+
+impl Keys {
+    fn new(
+        mode: Mode,
+        key: &[u8],
+        iv: &[u8],
+        mac: MessageDigest,
+        mac_key: Vec<u8>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            crypter: Crypter::new(Cipher::aes_128_ctr(), mode, key, Some(iv))?,
+            mac,
+            mac_key,
+        })
+    }
+
+    fn apply(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut out = vec![0u8; data.len() + 16];
+        let len = self.crypter.update(data, &mut out)?;
+        out.truncate(len);
+        Ok(out)
+    }
+
+    fn sign(&self, seq: u32, packet: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let key = PKey::hmac(&self.mac_key)?;
+        let mut signer = Signer::new(self.mac, &key)?;
+        signer.update(&seq.to_be_bytes())?;
+        signer.update(packet)?;
+        Ok(signer.sign_to_vec()?)
+    }
+}
+
+struct Transport<'a> {
+    stream: &'a mut dyn ClientStream,
+    read_timeout: Duration,
+    /// `--max-read-rounds`: reads that returned data before `take` gives up
+    /// on a server trickling one message.
+    max_rounds: usize,
+    /// Server bytes read along with the identification line.
+    buffered: Vec<u8>,
+    send_seq: u32,
+    recv_seq: u32,
+    send_keys: Option<Keys>,
+    recv_keys: Option<Keys>,
+}
+
+impl Transport<'_> {
+    async fn take(&mut self, len: usize) -> anyhow::Result<Vec<u8>> {
+        let mut rounds = 0;
+        while self.buffered.len() < len {
+            if rounds >= self.max_rounds {
+                bail!("SSH server did not send {len} bytes within {rounds} reads");
+            }
+            rounds += 1;
+            let mut chunk = [0u8; 4096];
+            let read = timeout(self.read_timeout, self.stream.read(&mut chunk))
+                .await
+                .context("SSH server went quiet")??;
+            if read == 0 {
+                bail!("SSH server closed the connection");
+            }
+            self.buffered.extend_from_slice(&chunk[..read]);
+        }
+        Ok(self.buffered.drain(..len).collect())
+    }
+
+    async fn write_packet(&mut self, payload: &[u8]) -> anyhow::Result<()> {
+        let block = if self.send_keys.is_some() { 16 } else { 8 };
+        let mut padding = block - (5 + payload.len()) % block;
+        if padding < 4 {
+            padding += block;
+        }
+        let mut packet = Vec::with_capacity(5 + payload.len() + padding);
+        packet.extend_from_slice(&((1 + payload.len() + padding) as u32).to_be_bytes());
+        packet.push(padding as u8);
+        packet.extend_from_slice(payload);
+        let mut filler = vec![0u8; padding];
+        rand::thread_rng().fill_bytes(&mut filler);
+        packet.extend_from_slice(&filler);
+
+        let wire = match &mut self.send_keys {
+            Some(keys) => {
+                let mac = keys.sign(self.send_seq, &packet)?;
+                let mut wire = keys.apply(&packet)?;
+                wire.extend_from_slice(&mac);
+                wire
+            }
+            None => packet,
+        };
+        self.stream
+            .write_all(&wire)
+            .await
+            .context("failed to write SSH packet")?;
+        self.send_seq = self.send_seq.wrapping_add(1);
+        Ok(())
+    }
+
+    async fn read_packet(&mut self) -> anyhow::Result<Vec<u8>> {
+        let packet = if self.recv_keys.is_some() {
+            let first = self.take(16).await?;
+            let keys = self.recv_keys.as_mut().expect("checked above");
+            let mut packet = keys.apply(&first)?;
+            let len = packet_len(&packet)?;
+            if (4 + len) % 16 != 0 || len < 12 {
+                bail!("malformed SSH packet length {len}");
+            }
+            let rest = self.take(4 + len - 16).await?;
+            let keys = self.recv_keys.as_mut().expect("checked above");
+            packet.extend_from_slice(&keys.apply(&rest)?);
+            let mac_len = keys.mac.size();
+            let expected = keys.sign(self.recv_seq, &packet)?;
+            let mac = self.take(mac_len).await?;
+            if !memcmp::eq(&mac, &expected) {
+                bail!("SSH packet failed MAC verification");
+            }
+            packet
+        } else {
+            let mut packet = self.take(4).await?;
+            let len = packet_len(&packet)?;
+            packet.extend_from_slice(&self.take(len).await?);
+            packet
+        };
+        self.recv_seq = self.recv_seq.wrapping_add(1);
+
+        let padding = usize::from(*packet.get(4).context("short SSH packet")?);
+        let end = packet.len().checked_sub(padding).filter(|end| *end > 5);
+        let end = end.ok_or_else(|| anyhow!("SSH packet padding exceeds its length"))?;
+        Ok(packet[5..end].to_vec())
+    }
+
+    /// Reads until a message of type `wanted`, skipping the ones any peer
+    /// may send at any time.
+    async fn read_message(&mut self, wanted: u8) -> anyhow::Result<Vec<u8>> {
+        loop {
+            let payload = self.read_packet().await?;
+            match payload[0] {
+                kind if kind == wanted => return Ok(payload),
+                MSG_IGNORE | MSG_DEBUG | MSG_UNIMPLEMENTED | MSG_EXT_INFO => continue,
+                MSG_DISCONNECT => {
+                    let mut reader = Reader::new(&payload[1..]);
+                    let _code = reader.u32()?;
+                    let reason = String::from_utf8_lossy(reader.string()?).into_owned();
+                    bail!("SSH server disconnected: {reason}");
+                }
+                other => bail!("expected SSH message {wanted}, got {other}"),
+            }
+        }
+    }
+}
+
+fn packet_len(header: &[u8]) -> anyhow::Result<usize> {
+    let len = u32::from_be_bytes(header[..4].try_into()?) as usize;
+    if len == 0 || len > MAX_PACKET {
+        bail!("SSH packet length {len} out of range");
+    }
+    Ok(len)
+}
+
+fn put_string(buf: &mut Vec<u8>, value: &[u8]) {
+    buf.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    buf.extend_from_slice(value);
+}
+
+/// Encodes an unsigned big-endian integer as an SSH mpint: no leading zero
+/// bytes, plus one when the top bit would otherwise read as a sign.
+fn put_mpint(buf: &mut Vec<u8>, magnitude: &[u8]) {
+    let start = magnitude
+        .iter()
+        .position(|&b| b != 0)
+        .unwrap_or(magnitude.len());
+    let magnitude = &magnitude[start..];
+    if magnitude.first().is_some_and(|&b| b & 0x80 != 0) {
+        buf.extend_from_slice(&(magnitude.len() as u32 + 1).to_be_bytes());
+        buf.push(0);
+        buf.extend_from_slice(magnitude);
+    } else {
+        put_string(buf, magnitude);
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        if self.buf.len() < len {
+            bail!("truncated SSH message");
+        }
+        let (head, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn string(&mut self) -> anyhow::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    fn name_list(&mut self) -> anyhow::Result<Vec<String>> {
+        let list = String::from_utf8_lossy(self.string()?).into_owned();
+        Ok(list
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock::{self, Step};
+    use crate::model::Protocol;
+
+    #[test]
+    fn encodes_mpints() {
+        let mut buf = Vec::new();
+        put_mpint(&mut buf, &[0x00, 0x00, 0x12, 0x34]);
+        assert_eq!(buf, [0, 0, 0, 2, 0x12, 0x34]);
+        buf.clear();
+        put_mpint(&mut buf, &[0x80]);
+        assert_eq!(buf, [0, 0, 0, 2, 0x00, 0x80]);
+        buf.clear();
+        put_mpint(&mut buf, &[0, 0]);
+        assert_eq!(buf, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn finds_the_identification_line_after_other_lines() {
+        let received = b"Welcome\r\nSSH-2.0-OpenSSH_9.6\r\n\x00\x00\x01";
+        let (version, rest) = split_server_version(received).unwrap();
+        assert_eq!(version, b"SSH-2.0-OpenSSH_9.6");
+        assert_eq!(rest, b"\x00\x00\x01");
+        assert!(split_server_version(b"SSH-2.0-partial").is_none());
+    }
+
+    #[test]
+    fn negotiates_against_a_server_kexinit() {
+        let mut kexinit = vec![MSG_KEXINIT];
+        kexinit.extend_from_slice(&[0; 16]);
+        for list in [
+            "sntrup761x25519-sha512@openssh.com,curve25519-sha256",
+            "rsa-sha2-512,ssh-ed25519",
+            "chacha20-poly1305@openssh.com,aes128-ctr",
+            "aes128-ctr",
+            "hmac-sha1",
+            "umac-64@openssh.com,hmac-sha2-256",
+            "none,zlib@openssh.com",
+            "none",
+            "",
+            "",
+        ] {
+            put_string(&mut kexinit, list.as_bytes());
+        }
+        kexinit.extend_from_slice(&[0; 5]);
+        let algorithms = Algorithms::negotiate(&kexinit).unwrap();
+        assert_eq!(algorithms.kex, "curve25519-sha256");
+        assert_eq!(algorithms.mac_out.size(), 20);
+        assert_eq!(algorithms.mac_in.size(), 32);
+        assert!(!algorithms.skip_guess);
+
+        // A server limited to ciphers we do not implement is reported.
+        let chacha_only = String::from_utf8_lossy(&kexinit)
+            .replace(",aes128-ctr", "")
+            .into_bytes();
+        assert!(Algorithms::negotiate(&chacha_only).is_err());
+    }
+
+    /// The server side of the exchange, reusing the client's transport with
+    /// the key letters swapped.
+    async fn serve_methods(mut half: tokio::io::DuplexStream) {
+        let mut server = Transport {
+            stream: &mut half,
+            read_timeout: Duration::from_secs(5),
+            max_rounds: usize::MAX,
+            buffered: Vec::new(),
+            send_seq: 0,
+            recv_seq: 0,
+            send_keys: None,
+            recv_keys: None,
+        };
+        let mut server_kexinit = vec![MSG_KEXINIT];
+        server_kexinit.extend_from_slice(&[7; 16]);
+        for list in [
+            "curve25519-sha256",
+            "ssh-ed25519",
+            "aes128-ctr",
+            "aes128-ctr",
+            "hmac-sha2-256",
+            "hmac-sha2-256",
+            "none",
+            "none",
+            "",
+            "",
+        ] {
+            put_string(&mut server_kexinit, list.as_bytes());
+        }
+        server_kexinit.extend_from_slice(&[0; 5]);
+        server.write_packet(&server_kexinit).await.unwrap();
+        let client_kexinit = server.read_message(MSG_KEXINIT).await.unwrap();
+
+        let init = server.read_message(MSG_KEX_INIT).await.unwrap();
+        let theirs = Reader::new(&init[1..]).string().unwrap().to_vec();
+        let key = PKey::generate_x25519().unwrap();
+        let ours = key.raw_public_key().unwrap();
+        let peer = PKey::public_key_from_raw_bytes(&theirs, Id::X25519).unwrap();
+        let mut deriver = Deriver::new(&key).unwrap();
+        deriver.set_peer(&peer).unwrap();
+        let mut host_key = Vec::new();
+        put_string(&mut host_key, b"ssh-ed25519");
+        put_string(&mut host_key, &[1; 32]);
+        let mut hashed_values = Vec::new();
+        put_string(&mut hashed_values, &theirs);
+        put_string(&mut hashed_values, &ours);
+        let exchange = Exchange {
+            host_key,
+            hashed_values,
+            shared_secret: deriver.derive_to_vec().unwrap(),
+        };
+        let session_id = exchange_hash(
+            b"SSH-2.0-OpenSSH_9.6",
+            &client_kexinit,
+            &server_kexinit,
+            &exchange,
+        )
+        .unwrap();
+        let mut reply = vec![MSG_KEX_REPLY];
+        put_string(&mut reply, &exchange.host_key);
+        put_string(&mut reply, &ours);
+        put_string(&mut reply, b"unchecked signature");
+        server.write_packet(&reply).await.unwrap();
+
+        let derive =
+            |letter, len| derive_key(&exchange.shared_secret, &session_id, letter, len).unwrap();
+        let sha256 = MessageDigest::sha256();
+        server.write_packet(&[MSG_NEWKEYS]).await.unwrap();
+        server.send_keys = Some(
+            Keys::new(
+                Mode::Encrypt,
+                &derive(b'D', 16),
+                &derive(b'B', 16),
+                sha256,
+                derive(b'F', 32),
+            )
+            .unwrap(),
+        );
+        server.read_message(MSG_NEWKEYS).await.unwrap();
+        server.recv_keys = Some(
+            Keys::new(
+                Mode::Decrypt,
+                &derive(b'C', 16),
+                &derive(b'A', 16),
+                sha256,
+                derive(b'E', 32),
+            )
+            .unwrap(),
+        );
+
+        let request = server.read_message(MSG_SERVICE_REQUEST).await.unwrap();
+        assert_eq!(
+            Reader::new(&request[1..]).string().unwrap(),
+            b"ssh-userauth"
+        );
+        let mut accept = vec![MSG_SERVICE_ACCEPT];
+        put_string(&mut accept, b"ssh-userauth");
+        server.write_packet(&accept).await.unwrap();
+
+        let request = server.read_message(MSG_USERAUTH_REQUEST).await.unwrap();
+        let mut reader = Reader::new(&request[1..]);
+        assert_eq!(reader.string().unwrap(), PROBE_USER.as_bytes());
+        assert_eq!(reader.string().unwrap(), b"ssh-connection");
+        assert_eq!(reader.string().unwrap(), b"none");
+        let mut banner = vec![MSG_USERAUTH_BANNER];
+        put_string(&mut banner, b"Authorized use only");
+        put_string(&mut banner, b"");
+        server.write_packet(&banner).await.unwrap();
+        let mut failure = vec![MSG_USERAUTH_FAILURE];
+        put_string(&mut failure, b"publickey,password");
+        failure.push(0);
+        server.write_packet(&failure).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn lists_methods_after_a_full_exchange() {
+        let (mut stream, server) = mock::serve_with(serve_methods);
+        let auth = auth_methods(
+            &mut stream,
+            &mock::config(Protocol::Ssh),
+            b"SSH-2.0-OpenSSH_9.6\r\n",
+        )
+        .await
+        .unwrap();
+        server.await.unwrap();
+        assert_eq!(
+            auth,
+            AuthMethods {
+                methods: vec!["publickey".into(), "password".into()],
+                none_accepted: false,
+                banner: Some("Authorized use only".into()),
+            }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reports_a_disconnect_before_the_exchange() {
+        let mut disconnect = Vec::new();
+        disconnect.extend_from_slice(&[0, 0, 0, 30, 6, MSG_DISCONNECT, 0, 0, 0, 2]);
+        put_string(&mut disconnect, b"too many users");
+        disconnect.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        let (mut stream, server) = mock::serve(vec![Step::Send(disconnect.leak())]);
+        let err = auth_methods(
+            &mut stream,
+            &mock::config(Protocol::Ssh),
+            b"SSH-2.0-OpenSSH_9.6\r\n",
+        )
+        .await
+        .unwrap_err();
+        server.await.unwrap();
+        assert_eq!(err.to_string(), "SSH server disconnected: too many users");
+    }
+}
@@ -1,11 +1,11 @@
+use crate::engine::reader::ReadStrategy;
 use crate::model::{Config, Target};
 use async_trait::async_trait;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
 use tokio::time::timeout;
 
 use crate::clients::session::ClientSession;
-use crate::clients::Client;
+use crate::clients::{Client, ClientStream};
 
 pub(crate) struct VncClient;
 
@@ -26,92 +26,26 @@ impl Client for VncClient {
 
     async fn execute(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut dyn ClientStream,
         cfg: &Config,
+        _target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
         let mut session = ClientSession::new(cfg);
         let mut metadata = String::new();
 
-        let initial = session.read_with_result(stream, Some(b"\n")).await?;
+        // ProtocolVersion is always 12 bytes ("RFB 003.008\n").
+        let initial = session.read_using(stream, ReadStrategy::Fixed(12)).await?;
 
         if let Ok(version_text) = std::str::from_utf8(&initial.bytes) {
             let version = version_text.trim_end_matches(&['\r', '\n'][..]);
             metadata.push_str(&format!("Protocol Version: {version}\n"));
+            session.insert_field("protocol_version", version);
             if version.starts_with("RFB ") && !version.is_empty() {
                 let reply = format!("{version}\n");
                 session.send(stream, reply.as_bytes()).await?;
 
                 if let Some((major, minor)) = parse_version(version) {
-                    let mut security_types = Vec::new();
-
-                    if (major, minor) >= (3, 7) {
-                        let mut count = [0u8; 1];
-                        read_exact_timeout(stream, &mut count, cfg.read_timeout).await?;
-                        let count = count[0] as usize;
-
-                        if count > 0 {
-                            let mut buf = vec![0u8; count];
-                            read_exact_timeout(stream, &mut buf, cfg.read_timeout).await?;
-                            security_types.extend(buf);
-                        }
-                    } else {
-                        let mut buf = [0u8; 4];
-                        read_exact_timeout(stream, &mut buf, cfg.read_timeout).await?;
-                        let security_type = u32::from_be_bytes(buf);
-                        security_types.push((security_type & 0xff) as u8);
-                    }
-
-                    if !security_types.is_empty() {
-                        metadata.push_str("Security Types:\n");
-                        for ty in &security_types {
-                            let name = security_type_name(*ty);
-                            metadata.push_str(&format!("  {ty}: {name}\n"));
-                        }
-                    }
-
-                    let chosen = security_types
-                        .iter()
-                        .copied()
-                        .find(|ty| *ty == 1)
-                        .or_else(|| security_types.first().copied())
-                        .unwrap_or(1);
-
-                    if (major, minor) >= (3, 7) {
-                        timeout(cfg.read_timeout, stream.write_all(&[chosen])).await??;
-                    }
-
-                    let mut result = [0u8; 4];
-                    read_exact_timeout(stream, &mut result, cfg.read_timeout).await?;
-                    let status = u32::from_be_bytes(result);
-                    if status != 0 {
-                        // server may send an error message length + message
-                        if let Ok(len_buf) = read_u32(stream, cfg.read_timeout).await {
-                            if len_buf > 0 {
-                                let mut msg = vec![0u8; len_buf as usize];
-                                read_exact_timeout(stream, &mut msg, cfg.read_timeout).await?;
-                                metadata.push_str("Security Failure: ");
-                                metadata.push_str(&String::from_utf8_lossy(&msg));
-                                metadata.push('\n');
-                            }
-                        }
-                    }
-
-                    timeout(cfg.read_timeout, stream.write_all(&[1u8])).await??;
-
-                    let mut header = [0u8; 24];
-                    read_exact_timeout(stream, &mut header, cfg.read_timeout).await?;
-
-                    let width = u16::from_be_bytes(header[0..2].try_into().unwrap());
-                    let height = u16::from_be_bytes(header[2..4].try_into().unwrap());
-                    metadata.push_str(&format!("Geometry: {width}x{height}\n"));
-
-                    let name_len = u32::from_be_bytes(header[20..24].try_into().unwrap()) as usize;
-                    if name_len > 0 {
-                        let mut name_bytes = vec![0u8; name_len];
-                        read_exact_timeout(stream, &mut name_bytes, cfg.read_timeout).await?;
-                        let server_name = String::from_utf8_lossy(&name_bytes);
-                        metadata.push_str(&format!("Server Name: {server_name}\n"));
-                    }
+                    handshake(stream, cfg, (major, minor), &mut session, &mut metadata).await?;
                 }
             }
         }
@@ -124,8 +58,115 @@ impl Client for VncClient {
     }
 }
 
+/// Runs the security handshake and, when the server lets us in without
+/// credentials, ClientInit/ServerInit. What is learned goes both into the
+/// free-text `metadata` and into the session fields.
+async fn handshake(
+    stream: &mut dyn ClientStream,
+    cfg: &Config,
+    version: (u16, u16),
+    session: &mut ClientSession,
+    metadata: &mut String,
+) -> anyhow::Result<()> {
+    let mut security_types = Vec::new();
+    if version >= (3, 7) {
+        let mut count = [0u8; 1];
+        read_exact_timeout(stream, &mut count, cfg.read_timeout).await?;
+        let count = count[0] as usize;
+
+        if count > 0 {
+            let mut buf = vec![0u8; count];
+            read_exact_timeout(stream, &mut buf, cfg.read_timeout).await?;
+            security_types.extend(buf);
+        }
+    } else {
+        // 3.3: the server picks the type itself.
+        let mut buf = [0u8; 4];
+        read_exact_timeout(stream, &mut buf, cfg.read_timeout).await?;
+        let security_type = u32::from_be_bytes(buf);
+        if security_type != 0 {
+            security_types.push((security_type & 0xff) as u8);
+        }
+    }
+
+    if security_types.is_empty() {
+        // No types at all means the connection failed; a reason follows.
+        read_failure_reason(stream, cfg, session, metadata).await;
+        return Ok(());
+    }
+    metadata.push_str("Security Types:\n");
+    for ty in &security_types {
+        let name = security_type_name(*ty);
+        metadata.push_str(&format!("  {ty}: {name}\n"));
+    }
+    let ids: Vec<String> = security_types.iter().map(u8::to_string).collect();
+    session.insert_field("security_types", ids.join(","));
+
+    // Anything past the security handshake needs the None type; other types
+    // would start an authentication exchange we have no credentials for.
+    if !security_types.contains(&1) {
+        return Ok(());
+    }
+    if version >= (3, 7) {
+        timeout(cfg.read_timeout, stream.write_all(&[1u8])).await??;
+    }
+    if version >= (3, 8) {
+        let status = read_u32(stream, cfg.read_timeout).await?;
+        if status != 0 {
+            read_failure_reason(stream, cfg, session, metadata).await;
+            return Ok(());
+        }
+    }
+
+    timeout(cfg.read_timeout, stream.write_all(&[1u8])).await??;
+
+    let mut header = [0u8; 24];
+    read_exact_timeout(stream, &mut header, cfg.read_timeout).await?;
+
+    let width = u16::from_be_bytes(header[0..2].try_into().unwrap());
+    let height = u16::from_be_bytes(header[2..4].try_into().unwrap());
+    metadata.push_str(&format!("Geometry: {width}x{height}\n"));
+    session.insert_field("geometry", format!("{width}x{height}"));
+
+    let name_len = u32::from_be_bytes(header[20..24].try_into().unwrap()) as usize;
+    if name_len > 0 {
+        let mut name_bytes = vec![0u8; name_len.min(cfg.max_bytes)];
+        read_exact_timeout(stream, &mut name_bytes, cfg.read_timeout).await?;
+        let server_name = String::from_utf8_lossy(&name_bytes);
+        metadata.push_str(&format!("Server Name: {server_name}\n"));
+        session.insert_field("server_name", server_name);
+    }
+    Ok(())
+}
+
+/// Reads the length-prefixed reason string that follows a failed handshake.
+/// Best effort: servers often just hang up.
+async fn read_failure_reason(
+    stream: &mut dyn ClientStream,
+    cfg: &Config,
+    session: &mut ClientSession,
+    metadata: &mut String,
+) {
+    let Ok(len) = read_u32(stream, cfg.read_timeout).await else {
+        return;
+    };
+    let mut msg = vec![0u8; (len as usize).min(cfg.max_bytes)];
+    if msg.is_empty()
+        || read_exact_timeout(stream, &mut msg, cfg.read_timeout)
+            .await
+            .is_err()
+    {
+        return;
+    }
+    let reason = String::from_utf8_lossy(&msg);
+    metadata.push_str("Security Failure: ");
+    metadata.push_str(&reason);
+    metadata.push('\n');
+    session.insert_field("security_failure", reason);
+}
+
 async fn read_exact_timeout(
-    stream: &mut TcpStream,
+    stream: &mut dyn ClientStream,
     buf: &mut [u8],
     dur: std::time::Duration,
 ) -> anyhow::Result<()> {
@@ -133,7 +174,7 @@ async fn read_exact_timeout(
     Ok(())
 }
 
-async fn read_u32(stream: &mut TcpStream, dur: std::time::Duration) -> anyhow::Result<u32> {
+async fn read_u32(stream: &mut dyn ClientStream, dur: std::time::Duration) -> anyhow::Result<u32> {
     let mut buf = [0u8; 4];
     read_exact_timeout(stream, &mut buf, dur).await?;
     Ok(u32::from_be_bytes(buf))
@@ -151,7 +192,7 @@ fn parse_version(version: &str) -> Option<(u16, u16)> {
     Some((major, minor))
 }
 
-fn security_type_name(ty: u8) -> &'static str {
+pub fn security_type_name(ty: u8) -> &'static str {
     match ty {
         1 => "None",
         2 => "VNC Authentication",
@@ -165,11 +206,24 @@ fn security_type_name(ty: u8) -> &'static str {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{Config, OutputConfig, OutputFormat, ScanMode, TargetSpec};
+    use crate::clients::mock;
+    use crate::model::{Config, OutputFormat, TargetSpec};
     use std::time::Duration;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tokio::net::{TcpListener, TcpStream};
 
+    fn config(port: u16) -> Config {
+        let mut cfg = mock::config(crate::model::Protocol::Vnc);
+        cfg.target = Some(TargetSpec {
+            host: "127.0.0.1".into(),
+            port,
+        });
+        cfg.overall_timeout = Duration::from_millis(1000);
+        cfg.max_bytes = 128;
+        cfg.output.format = OutputFormat::Pretty;
+        cfg
+    }
+
     #[tokio::test]
     async fn collects_vnc_metadata_during_handshake() {
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -203,35 +257,53 @@ mod tests {
             socket.write_all(name).await.unwrap();
         });
 
-        let cfg = Config {
-            target: Some(TargetSpec {
-                host: "127.0.0.1".into(),
-                port: addr.port(),
-            }),
-            input: None,
-            port_filter: None,
-            concurrency: 1,
-            rate: 1,
-            connect_timeout: Duration::from_millis(500),
-            read_timeout: Duration::from_millis(500),
-            overall_timeout: Duration::from_millis(1000),
-            max_bytes: 128,
-            mode: ScanMode::Active,
-            protocol: crate::model::Protocol::Vnc,
-            webdriver: false,
-            tech: false,
-            output: OutputConfig {
-                format: OutputFormat::Pretty,
-            },
-        };
+        let cfg = config(addr.port());
 
         let mut stream = TcpStream::connect(addr).await.unwrap();
-        let result = VncClient.execute(&mut stream, &cfg).await.unwrap();
+        let result = VncClient
+            .execute(&mut stream, &cfg, &mock::target())
+            .await
+            .unwrap();
         let printable = std::str::from_utf8(&result.bytes).unwrap();
         assert!(printable.contains("Protocol Version: RFB 003.008"));
         assert!(printable.contains("Security Types:"));
         assert!(printable.contains("1: None"));
         assert!(printable.contains("Geometry: 800x600"));
         assert!(printable.contains("Server Name: Test Server"));
+        assert_eq!(result.fields["security_types"], "1");
+        assert_eq!(result.fields["geometry"], "800x600");
+        assert_eq!(result.fields["server_name"], "Test Server");
+    }
+
+    #[tokio::test]
+    async fn records_security_type_dictated_by_rfb_3_3() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(b"RFB 003.003\n").await.unwrap();
+            let mut buf = [0u8; 12];
+            socket.read_exact(&mut buf).await.unwrap();
+            // 3.3 has no type list, selection or SecurityResult for None.
+            socket.write_all(&1u32.to_be_bytes()).await.unwrap();
+
+            let mut client_init = [0u8; 1];
+            socket.read_exact(&mut client_init).await.unwrap();
+            let mut header = Vec::new();
+            header.extend_from_slice(&1024u16.to_be_bytes());
+            header.extend_from_slice(&768u16.to_be_bytes());
+            header.extend_from_slice(&[0u8; 16]);
+            header.extend_from_slice(&0u32.to_be_bytes());
+            socket.write_all(&header).await.unwrap();
+        });
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let result = VncClient
+            .execute(&mut stream, &config(addr.port()), &mock::target())
+            .await
+            .unwrap();
+        assert_eq!(result.fields["security_types"], "1");
+        assert_eq!(result.fields["geometry"], "1024x768");
     }
 }
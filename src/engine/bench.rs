@@ -0,0 +1,88 @@
+//! `--bench-targets`: synthetic loopback targets for profiling the
+//! scheduler (`--concurrency`, `--rate`) without a real network.
+
+use crate::model::{Target, TargetSpec};
+use std::net::SocketAddr;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Sent by the mock server to every connection before closing it.
+const BANNER: &[u8] = b"SSH-2.0-banner-grabber-bench\r\n";
+
+/// Loopback server that greets and hangs up, stopped when dropped.
+pub struct BenchServer {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl BenchServer {
+    pub async fn start() -> anyhow::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let task = tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let _ = stream.write_all(BANNER).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+        Ok(Self { addr, task })
+    }
+
+    /// `count` targets, all pointing at the server, fed like the input
+    /// readers feed theirs.
+    pub fn targets(&self, count: usize) -> ReceiverStream<anyhow::Result<Target>> {
+        let (tx, rx) = mpsc::channel(256);
+        let addr = self.addr;
+        tokio::spawn(async move {
+            for _ in 0..count {
+                let target = Target {
+                    original: TargetSpec {
+                        host: addr.ip().to_string(),
+                        port: addr.port(),
+                    },
+                    resolved: addr,
+                };
+                if tx.send(Ok(target)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        ReceiverStream::new(rx)
+    }
+}
+
+impl Drop for BenchServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn serves_every_generated_target() {
+        let server = BenchServer::start().await.unwrap();
+        let targets: Vec<Target> = server
+            .targets(3)
+            .map(|target| target.unwrap())
+            .collect()
+            .await;
+        assert_eq!(targets.len(), 3);
+
+        let mut stream = tokio::net::TcpStream::connect(targets[0].resolved)
+            .await
+            .unwrap();
+        let mut banner = Vec::new();
+        stream.read_to_end(&mut banner).await.unwrap();
+        assert_eq!(banner, BANNER);
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
+
+/// Wall-clock budget for the whole run (`--deadline`). Shared between the
+/// spawn loop and the input readers, which count what they no longer resolve.
+#[derive(Clone, Default)]
+pub struct Deadline {
+    at: Option<Instant>,
+    skipped: Arc<AtomicUsize>,
+}
+
+impl Deadline {
+    pub fn new(budget: Option<Duration>) -> Self {
+        Self {
+            at: budget.map(|budget| Instant::now() + budget),
+            skipped: Arc::default(),
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        self.at.is_some_and(|at| Instant::now() >= at)
+    }
+
+    /// Resolves once the budget is spent; never resolves without a deadline.
+    pub async fn reached(&self) {
+        match self.at {
+            Some(at) => sleep_until(at).await,
+            None => std::future::pending().await,
+        }
+    }
+
+    pub fn skip(&self) {
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// The end-of-scan line reporting how many targets the deadline cut off.
+    pub fn summary(&self) -> String {
+        format!("deadline: {} targets skipped", self.skipped())
+    }
+}
@@ -0,0 +1,162 @@
+//! `--host-discovery`: a quick TCP connect ahead of the scan, so targets on
+//! hosts that never answer skip the full connect timeout.
+
+use crate::model::Target;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::OnceCell;
+use tokio::time::timeout;
+
+/// Tried alongside the target's own port: a host with that port filtered
+/// is still found through a web or SSH server.
+const COMMON_PORTS: &[u16] = &[80, 443, 22];
+
+/// Which hosts answered, shared by the discovery tasks of one scan. Each
+/// address is tested once, however many of its ports are targets: targets
+/// arriving while the test runs wait for its answer.
+#[derive(Clone)]
+pub struct HostDiscovery {
+    timeout: Duration,
+    hosts: Arc<Mutex<HashMap<IpAddr, Arc<OnceCell<bool>>>>>,
+    skipped: Arc<AtomicUsize>,
+}
+
+impl HostDiscovery {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            hosts: Arc::default(),
+            skipped: Arc::default(),
+        }
+    }
+
+    /// Whether `target`'s host answered; targets on dead hosts are counted
+    /// as skipped.
+    pub async fn is_up(&self, target: &Target) -> bool {
+        let host = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(target.resolved.ip())
+            .or_default()
+            .clone();
+        let up = *host
+            .get_or_init(|| host_answers(target.resolved, self.timeout))
+            .await;
+        if !up {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        up
+    }
+
+    /// `(hosts up, hosts down, targets skipped)`.
+    pub fn stats(&self) -> (usize, usize, usize) {
+        let hosts = self.hosts.lock().unwrap();
+        let tested: Vec<bool> = hosts
+            .values()
+            .filter_map(|host| host.get().copied())
+            .collect();
+        let up = tested.iter().filter(|up| **up).count();
+        (up, tested.len() - up, self.skipped.load(Ordering::Relaxed))
+    }
+}
+
+/// Connects to the target port and [`COMMON_PORTS`] at once; the host is up
+/// as soon as any of them answers.
+async fn host_answers(addr: SocketAddr, wait: Duration) -> bool {
+    let ports = std::iter::once(addr.port()).chain(
+        COMMON_PORTS
+            .iter()
+            .copied()
+            .filter(|&port| port != addr.port()),
+    );
+    let mut attempts: FuturesUnordered<_> = ports
+        .map(|port| async move {
+            answered(timeout(wait, TcpStream::connect(SocketAddr::new(addr.ip(), port))).await)
+        })
+        .collect();
+    while let Some(answered) = attempts.next().await {
+        if answered {
+            return true;
+        }
+    }
+    false
+}
+
+/// A refusal is an RST, so it proves the host is up as much as an accepted
+/// connection does.
+fn answered<T>(attempt: Result<std::io::Result<T>, tokio::time::error::Elapsed>) -> bool {
+    match attempt {
+        Ok(Ok(_)) => true,
+        Ok(Err(err)) => err.kind() == std::io::ErrorKind::ConnectionRefused,
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::TargetSpec;
+
+    fn target(resolved: SocketAddr) -> Target {
+        Target {
+            original: TargetSpec {
+                host: resolved.ip().to_string(),
+                port: resolved.port(),
+            },
+            resolved,
+        }
+    }
+
+    #[tokio::test]
+    async fn counts_a_refused_port_as_a_live_host() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap();
+        // Released again at once, so the connect is refused.
+        let closed = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let discovery = HostDiscovery::new(Duration::from_millis(500));
+        assert!(discovery.is_up(&target(closed)).await);
+        assert!(discovery.is_up(&target(open)).await);
+        assert_eq!(discovery.stats(), (1, 0, 0));
+    }
+
+    #[tokio::test]
+    async fn tests_each_address_once_under_concurrency() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while listener.accept().await.is_ok() {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        let discovery = HostDiscovery::new(Duration::from_millis(500));
+        let target = target(addr);
+        let (first, second) = tokio::join!(discovery.is_up(&target), discovery.is_up(&target));
+        assert!(first && second);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(accepted.load(Ordering::Relaxed), 1);
+        assert_eq!(discovery.stats(), (1, 0, 0));
+    }
+
+    #[test]
+    fn treats_other_failures_as_silence() {
+        let unreachable = std::io::Error::from(std::io::ErrorKind::HostUnreachable);
+        assert!(!answered::<()>(Ok(Err(unreachable))));
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        assert!(answered::<()>(Ok(Err(refused))));
+        assert!(answered(Ok(Ok(()))));
+    }
+}
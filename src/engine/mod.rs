@@ -1,15 +1,25 @@
+pub mod bench;
+pub mod deadline;
+pub mod discovery;
 pub mod pipeline;
+pub mod proxy;
 pub mod rate;
 pub mod reader;
+pub mod tech;
 
-use crate::model::Config;
+use crate::model::{Config, ErrorKind, ScanOutcome, Status, TargetView};
 use crate::output::OutputChannel;
+use bench::BenchServer;
+use deadline::Deadline;
+use discovery::HostDiscovery;
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use pipeline::{DefaultProcessor, TargetProcessor};
 use rate::RateLimiter;
+use std::time::Duration;
 use tokio::sync::Semaphore;
 use tokio::time::timeout;
+use tokio_util::sync::CancellationToken;
 use tracing::instrument;
 
 pub struct Engine {
@@ -18,8 +28,13 @@ pub struct Engine {
     limiter: RateLimiter,
     sem: std::sync::Arc<Semaphore>,
     processor: std::sync::Arc<dyn TargetProcessor>,
+    /// Cancelled on SIGINT: stop starting targets and flush what finished.
+    interrupt: CancellationToken,
 }
 
+/// How long in-flight targets may keep running after an interrupt.
+const INTERRUPT_GRACE: Duration = Duration::from_secs(2);
+
 impl Engine {
     pub fn new(cfg: Config, sink: OutputChannel) -> anyhow::Result<Self> {
         Self::with_processor(cfg, sink, std::sync::Arc::new(DefaultProcessor))
@@ -30,57 +45,492 @@ impl Engine {
         sink: OutputChannel,
         processor: std::sync::Arc<dyn TargetProcessor>,
     ) -> anyhow::Result<Self> {
+        tech::init(cfg.tech_rules.as_deref())?;
+        // A run scans a single protocol, so its `--max-bytes-for` entry can
+        // replace the global budget once for every reader and client. A
+        // `--protocol` list or `--protocol-by-port` applies each entry per
+        // attempt instead.
+        let mut cfg = cfg;
+        let single = cfg.protocols.len() <= 1 && cfg.protocol_by_port.is_empty();
+        if let Some(&max_bytes) = cfg
+            .max_bytes_for
+            .get(&cfg.protocol.to_string())
+            .filter(|_| single)
+        {
+            cfg.max_bytes = max_bytes;
+        }
         let cfg = std::sync::Arc::new(cfg);
         Ok(Self {
-            limiter: RateLimiter::new(cfg.rate),
+            limiter: RateLimiter::new(cfg.rate, cfg.jitter),
             sem: std::sync::Arc::new(Semaphore::new(cfg.concurrency)),
             cfg,
             sink,
             processor,
+            interrupt: CancellationToken::new(),
         })
     }
 
+    /// Token that ends the scan early, keeping the results gathered so far,
+    /// when cancelled (`main` does so on Ctrl-C).
+    pub fn interrupt_handle(&self) -> CancellationToken {
+        self.interrupt.clone()
+    }
+
     #[instrument(skip(self))]
     pub async fn run(&mut self) -> anyhow::Result<()> {
-        let mut stream = crate::input::stream_targets(self.cfg.as_ref())?;
+        if let Some(path) = self.cfg.replay.clone() {
+            return self.replay(&path).await;
+        }
+        let deadline = Deadline::new(self.cfg.deadline);
+        let result = self.run_cycles(&deadline).await;
+        // Only now, after the last cycle, is the output worker shut down.
+        self.sink.shutdown().await?;
+        result
+    }
+
+    /// Scans the targets once, or with `--repeat` every interval (start to
+    /// start) until `--count` cycles ran, the deadline passes or the run is
+    /// interrupted. A cycle that fails (`--fail-fast`) ends the run, and
+    /// `--ramp` eases into the first cycle only.
+    async fn run_cycles(&mut self, deadline: &Deadline) -> anyhow::Result<()> {
+        let mut cycles = 0;
+        loop {
+            let started = tokio::time::Instant::now();
+            self.scan_once(deadline, cycles == 0).await?;
+            cycles += 1;
+            let Some(interval) = self.cfg.repeat else {
+                return Ok(());
+            };
+            if cycles == self.cfg.repeat_count
+                || self.interrupt.is_cancelled()
+                || deadline.expired()
+            {
+                return Ok(());
+            }
+            tokio::select! {
+                _ = tokio::time::sleep_until(started + interval) => {}
+                _ = self.interrupt.cancelled() => return Ok(()),
+                _ = deadline.reached() => return Ok(()),
+            }
+        }
+    }
+
+    async fn scan_once(&mut self, deadline: &Deadline, first: bool) -> anyhow::Result<()> {
+        let ramp = match self.cfg.ramp {
+            Some(ramp) if first => Some(self.start_ramp(ramp)),
+            Some(_) => {
+                // A ramp cut short by a quick cycle may have left permits out.
+                self.limiter = RateLimiter::new(self.cfg.rate, self.cfg.jitter);
+                self.sem = std::sync::Arc::new(Semaphore::new(self.cfg.concurrency));
+                None
+            }
+            None => None,
+        };
+        let dedupe = crate::input::Dedupe::new(self.cfg.as_ref());
+        let bench = match self.cfg.bench_targets {
+            Some(count) => Some((BenchServer::start().await?, count)),
+            None => None,
+        };
+        let started = tokio::time::Instant::now();
+        let stream = match &bench {
+            Some((server, count)) => server.targets(*count),
+            None => crate::input::stream_targets(self.cfg.as_ref(), deadline, &dedupe)?,
+        };
+        let discovery = self.cfg.host_discovery.map(HostDiscovery::new);
+        let mut stream = match discovery.clone() {
+            // Hosts are tested --concurrency at a time, ahead of the scan.
+            // Past the deadline targets pass untested, so draining them to
+            // count the skipped ones sends no traffic.
+            Some(discovery) => {
+                let deadline = deadline.clone();
+                stream
+                    .map(move |next| {
+                        let discovery = discovery.clone();
+                        let deadline = deadline.clone();
+                        async move {
+                            match next {
+                                Ok(target) if deadline.expired() => Some(Ok(target)),
+                                Ok(target) => tokio::select! {
+                                    biased;
+                                    _ = deadline.reached() => Some(Ok(target)),
+                                    up = discovery.is_up(&target) => up.then_some(Ok(target)),
+                                },
+                                next => Some(next),
+                            }
+                        }
+                    })
+                    .buffer_unordered(self.cfg.concurrency)
+                    .filter_map(std::future::ready)
+                    .boxed()
+            }
+            None => stream.boxed(),
+        };
         let mut tasks = FuturesUnordered::new();
+        // Tripped by the first failing target when --fail-fast is set; the spawn
+        // loop and every in-flight task check it cooperatively.
+        let cancel = CancellationToken::new();
 
-        while let Some(next) = stream.next().await {
+        loop {
+            let next = tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = self.interrupt.cancelled() => break,
+                _ = deadline.reached() => break,
+                next = stream.next() => next,
+            };
             let target = match next {
-                Ok(target) => target,
-                Err(err) => return Err(err),
+                Some(Ok(target)) => target,
+                Some(Err(err)) => {
+                    self.report_unresolved(err).await?;
+                    continue;
+                }
+                None => break,
+            };
+            tokio::select! {
+                _ = self.interrupt.cancelled() => break,
+                _ = self.limiter.acquire() => {}
+            }
+            let permit = tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = self.interrupt.cancelled() => break,
+                _ = deadline.reached() => {
+                    deadline.skip();
+                    break;
+                }
+                permit = self.sem.clone().acquire_owned() => permit?,
             };
-            self.limiter.acquire().await;
-            let permit = self.sem.clone().acquire_owned().await?;
             let cfg = self.cfg.clone();
             let sink = self.sink.clone();
             let processor = self.processor.clone();
+            let cancel = cancel.clone();
             tasks.push(tokio::spawn(async move {
                 let _permit = permit;
-                let res = timeout(
-                    cfg.overall_timeout,
-                    processor.process_target(target.clone(), cfg.clone()),
-                )
-                .await;
-                match res {
-                    Ok(Ok(outcome)) => sink.emit(outcome).await?,
+                let addr = target.resolved;
+                let res = tokio::select! {
+                    _ = cancel.cancelled() => return Ok(()),
+                    res = timeout(
+                        cfg.overall_timeout,
+                        processor.process_target(target.clone(), cfg.clone()),
+                    ) => res,
+                };
+                let failure = match res {
+                    Ok(Ok(outcome)) => {
+                        let failure = matches!(outcome.status, Status::Error)
+                            .then(|| failure_message(&outcome));
+                        sink.emit(outcome).await?;
+                        failure
+                    }
                     Ok(Err(err)) => {
-                        sink.emit_error(target, &cfg.protocol, err.to_string())
-                            .await?
+                        let message = err.to_string();
+                        let kind = pipeline::classify_error(&err).unwrap_or_default();
+                        sink.emit_error(
+                            target.view(),
+                            &cfg.protocol,
+                            "pipeline",
+                            kind,
+                            message.clone(),
+                        )
+                        .await?;
+                        Some(message)
                     }
                     Err(_) => {
-                        sink.emit_error(target, &cfg.protocol, "overall timeout".to_string())
-                            .await?
+                        let message = "overall timeout".to_string();
+                        sink.emit_error(
+                            target.view(),
+                            &cfg.protocol,
+                            "pipeline",
+                            ErrorKind::OverallTimeout,
+                            message.clone(),
+                        )
+                        .await?;
+                        Some(message)
                     }
+                };
+                if let Some(message) = failure.filter(|_| cfg.fail_fast) {
+                    cancel.cancel();
+                    anyhow::bail!("aborting scan after {addr} failed (--fail-fast): {message}");
                 }
                 Ok::<_, anyhow::Error>(())
             }));
         }
 
-        while let Some(joined) = tasks.next().await {
-            joined??;
+        if let Some(ramp) = ramp {
+            ramp.abort();
+        }
+
+        if deadline.expired() {
+            // Targets that were already resolved still count as skipped; the
+            // input readers abandon their outstanding lookups at the deadline,
+            // so this only waits for the channel to close.
+            while let Some(next) = stream.next().await {
+                if next.is_ok() {
+                    deadline.skip();
+                }
+            }
+            eprintln!("{}", deadline.summary());
+        }
+        if dedupe.enabled() {
+            eprintln!("dedupe: {} duplicate targets skipped", dedupe.skipped());
+        }
+        if let Some(discovery) = &discovery {
+            let (up, down, skipped) = discovery.stats();
+            eprintln!("host discovery: {up} hosts up, {down} down, {skipped} targets skipped");
+        }
+
+        // After an interrupt, running targets get a short grace period; the
+        // ones still going are then cancelled and produce no record.
+        let mut grace = None;
+        let mut first_error = None;
+        loop {
+            let joined = tokio::select! {
+                joined = tasks.next() => joined,
+                _ = self.interrupt.cancelled(), if grace.is_none() => {
+                    grace = Some(tokio::time::Instant::now() + INTERRUPT_GRACE);
+                    continue;
+                }
+                _ = tokio::time::sleep_until(grace.unwrap_or_else(tokio::time::Instant::now)),
+                    if grace.is_some() && !cancel.is_cancelled() =>
+                {
+                    eprintln!("interrupted: {} in-flight targets dropped", tasks.len());
+                    cancel.cancel();
+                    continue;
+                }
+            };
+            let Some(joined) = joined else {
+                break;
+            };
+            if let Err(err) = joined? {
+                first_error.get_or_insert(err);
+            }
+        }
+        if let Some((_, count)) = &bench {
+            let elapsed = started.elapsed().as_secs_f64();
+            eprintln!(
+                "bench: {count} targets in {elapsed:.2}s ({:.0} targets/s)",
+                *count as f64 / elapsed.max(f64::EPSILON)
+            );
+        }
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Restarts the limiter and the semaphore at one connection for `--ramp`
+    /// and hands out the remaining permits evenly over `ramp`.
+    fn start_ramp(&mut self, ramp: Duration) -> tokio::task::JoinHandle<()> {
+        self.limiter = RateLimiter::new(self.cfg.rate, self.cfg.jitter).with_ramp(ramp);
+        self.sem = std::sync::Arc::new(Semaphore::new(self.cfg.concurrency.min(1)));
+        let sem = self.sem.clone();
+        let steps = self.cfg.concurrency.saturating_sub(1);
+        tokio::spawn(async move {
+            let start = tokio::time::Instant::now();
+            for step in 1..=steps {
+                tokio::time::sleep_until(start + ramp.mul_f64(step as f64 / steps as f64)).await;
+                sem.add_permits(1);
+            }
+        })
+    }
+
+    /// Reports a hostname that did not resolve as a failed target, so one
+    /// dead name does not end the scan. Other input errors, and every error
+    /// under `--strict-resolution` or `--fail-fast`, still abort.
+    async fn report_unresolved(&self, err: anyhow::Error) -> anyhow::Result<()> {
+        let Some(failure) = err.downcast_ref::<crate::input::ResolveError>() else {
+            return Err(err);
+        };
+        if self.cfg.strict_resolution {
+            return Err(err);
+        }
+        let target = TargetView {
+            host: failure.spec.host.clone(),
+            addr: String::new(),
+            port: failure.spec.port,
+            family: None,
+        };
+        let message = failure.to_string();
+        self.sink
+            .emit_error(
+                target,
+                &self.cfg.protocol,
+                "resolve",
+                ErrorKind::ResolutionFailed,
+                message.clone(),
+            )
+            .await?;
+        if self.cfg.fail_fast {
+            anyhow::bail!("aborting scan after {message} (--fail-fast)");
         }
-        self.sink.shutdown().await?;
         Ok(())
     }
+
+    /// Feeds `--replay` records through the output path without touching the
+    /// network.
+    async fn replay(&mut self, path: &str) -> anyhow::Result<()> {
+        let records = crate::input::read_replay(path).await?;
+        for (target, bytes) in records {
+            let outcome = pipeline::replay_outcome(target, bytes, self.cfg.as_ref());
+            self.sink.emit(outcome).await?;
+        }
+        self.sink.shutdown().await
+    }
+}
+
+fn failure_message(outcome: &ScanOutcome) -> String {
+    outcome
+        .diagnostics
+        .as_ref()
+        .map(|diag| format!("[{}] {}", diag.stage, diag.message))
+        .or_else(|| outcome.tcp.error.clone())
+        .unwrap_or_else(|| "unknown error".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Protocol, ScanMode, Target, TargetSpec};
+    use async_trait::async_trait;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct FailingProcessor;
+
+    struct HangingProcessor;
+
+    #[derive(Default)]
+    struct CountingProcessor(std::sync::atomic::AtomicUsize);
+
+    #[async_trait]
+    impl TargetProcessor for HangingProcessor {
+        async fn process_target(
+            &self,
+            _target: Target,
+            _config: Arc<Config>,
+        ) -> anyhow::Result<ScanOutcome> {
+            std::future::pending().await
+        }
+    }
+
+    #[async_trait]
+    impl TargetProcessor for CountingProcessor {
+        async fn process_target(
+            &self,
+            _target: Target,
+            _config: Arc<Config>,
+        ) -> anyhow::Result<ScanOutcome> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            anyhow::bail!("connection refused")
+        }
+    }
+
+    #[async_trait]
+    impl TargetProcessor for FailingProcessor {
+        async fn process_target(
+            &self,
+            _target: Target,
+            _config: Arc<Config>,
+        ) -> anyhow::Result<ScanOutcome> {
+            anyhow::bail!("proxy unreachable")
+        }
+    }
+
+    fn config(fail_fast: bool) -> Config {
+        let mut cfg = crate::clients::mock::config(Protocol::Http);
+        cfg.target = Some(TargetSpec {
+            host: "127.0.0.1".into(),
+            port: 9,
+        });
+        cfg.rate = 10;
+        cfg.connect_timeout = Duration::from_millis(100);
+        cfg.read_timeout = Duration::from_millis(100);
+        cfg.overall_timeout = Duration::from_millis(500);
+        cfg.max_bytes = 64;
+        cfg.mode = ScanMode::Passive;
+        cfg.fail_fast = fail_fast;
+        cfg
+    }
+
+    async fn run_with_failing_processor(fail_fast: bool) -> anyhow::Result<()> {
+        let cfg = config(fail_fast);
+        let sink = OutputChannel::new(cfg.output.clone())?;
+        let mut engine = Engine::with_processor(cfg, sink, Arc::new(FailingProcessor))?;
+        engine.run().await
+    }
+
+    #[tokio::test]
+    async fn fail_fast_aborts_on_first_error() {
+        let err = run_with_failing_processor(true).await.unwrap_err();
+        assert!(err.to_string().contains("--fail-fast"));
+        assert!(err.to_string().contains("proxy unreachable"));
+    }
+
+    #[tokio::test]
+    async fn target_errors_do_not_abort_by_default() {
+        run_with_failing_processor(false)
+            .await
+            .expect("errors should only be reported to the sink");
+    }
+
+    #[tokio::test]
+    async fn expired_deadline_skips_host_discovery() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut cfg = config(false);
+        cfg.target = Some(TargetSpec {
+            host: "127.0.0.1".into(),
+            port: listener.local_addr().unwrap().port(),
+        });
+        cfg.deadline = Some(Duration::ZERO);
+        cfg.host_discovery = Some(Duration::from_millis(100));
+        let sink = OutputChannel::new(cfg.output.clone()).unwrap();
+        let mut engine =
+            Engine::with_processor(cfg, sink, Arc::new(CountingProcessor::default())).unwrap();
+        engine.run().await.unwrap();
+        let accepted = tokio::time::timeout(Duration::from_millis(50), listener.accept()).await;
+        assert!(accepted.is_err(), "discovery connected after the deadline");
+    }
+
+    #[tokio::test]
+    async fn deadline_reports_the_skipped_targets() {
+        let mut cfg = config(false);
+        cfg.bench_targets = Some(10);
+        cfg.concurrency = 1;
+        let sink = OutputChannel::new(cfg.output.clone()).unwrap();
+        let mut engine = Engine::with_processor(cfg, sink, Arc::new(HangingProcessor)).unwrap();
+        let deadline = Deadline::new(Some(Duration::from_millis(100)));
+
+        // The first target holds the only permit past the deadline.
+        engine.scan_once(&deadline, true).await.unwrap();
+        assert_eq!(deadline.summary(), "deadline: 9 targets skipped");
+    }
+
+    #[tokio::test]
+    async fn repeat_rescans_for_each_cycle() {
+        let mut cfg = config(false);
+        cfg.repeat = Some(Duration::from_millis(50));
+        cfg.repeat_count = 3;
+        let sink = OutputChannel::new(cfg.output.clone()).unwrap();
+        let processor = Arc::new(CountingProcessor::default());
+        let mut engine = Engine::with_processor(cfg, sink, processor.clone()).unwrap();
+        let start = std::time::Instant::now();
+
+        engine.run().await.unwrap();
+        assert_eq!(processor.0.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn interrupt_drops_hanging_targets_after_grace() {
+        let mut cfg = config(false);
+        cfg.overall_timeout = Duration::from_secs(3600);
+        let sink = OutputChannel::new(cfg.output.clone()).unwrap();
+        let mut engine = Engine::with_processor(cfg, sink, Arc::new(HangingProcessor)).unwrap();
+        let interrupt = engine.interrupt_handle();
+        let start = std::time::Instant::now();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            interrupt.cancel();
+        });
+
+        engine.run().await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(3));
+    }
 }
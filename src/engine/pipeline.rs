@@ -1,18 +1,23 @@
-use crate::clients::{client_for_target, udp_client_for_target, ClientRequest};
+use crate::clients::{
+    client_for_target, udp_client_for_target, Client, ClientRequest, ClientStream, RecordingStream,
+    UdpClient,
+};
 use crate::model::{
-    Config, Diagnostics, Fingerprint, Protocol, ReadStopReason, ScanMode, ScanOutcome, Status,
-    TcpMeta,
+    Config, Diagnostics, ErrorKind, Fingerprint, Protocol, ProxyProtocolVersion, ReadStopReason,
+    ScanMode, ScanOutcome, Severity, Status, TcpMeta,
 };
-use crate::probe::{probe_for_target, ProbeRequest};
-use crate::util::{now_iso8601, now_millis};
+use crate::probe::{implicit_tls, probe_for_target, proxy_protocol, starttls, ProbeRequest};
+use crate::util::now_millis;
 use crate::webdriver;
 use async_trait::async_trait;
+use chrono::Utc;
 use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
-use tokio::time::timeout;
+use tokio::time::{timeout, Instant};
 use tracing::debug;
 
-use super::reader::BannerReader;
+use super::reader::{BannerReader, ReadResult};
 
 #[async_trait]
 pub trait TargetProcessor: Send + Sync {
@@ -32,14 +37,110 @@ impl TargetProcessor for DefaultProcessor {
         &self,
         target: crate::model::Target,
         config: std::sync::Arc<Config>,
+    ) -> anyhow::Result<ScanOutcome> {
+        let started = Utc::now();
+        let config = jitter_timeouts(config, rand::random::<f64>() * 2.0 - 1.0);
+        let mapped = config.protocol_by_port.get(&target.resolved.port());
+        let mut outcome = match mapped {
+            Some(protocol) => {
+                let config = for_protocol(&config, protocol);
+                self.scan(target, config.into()).await?
+            }
+            None if config.protocols.len() > 1 => self.first_match(target, config).await?,
+            // The engine left the budget alone for a mapped run; apply the
+            // fallback protocol's own.
+            None if !config.protocol_by_port.is_empty() => {
+                let config = for_protocol(&config, &config.protocol);
+                self.scan(target, config.into()).await?
+            }
+            None => self.scan(target, config).await?,
+        };
+        outcome.timestamp = started;
+        Ok(outcome)
+    }
+}
+
+/// `--probe-timeout-jitter`: this target's connect and read timeouts scaled
+/// by `1 + offset * jitter`, for an `offset` drawn from -1.0..1.0. Everything
+/// downstream, the `BannerReader` included, reads them from the config.
+fn jitter_timeouts(config: std::sync::Arc<Config>, offset: f64) -> std::sync::Arc<Config> {
+    if config.probe_timeout_jitter <= 0.0 {
+        return config;
+    }
+    // The flag stops at 90%, so no timeout drops below a tenth of itself.
+    let factor = (1.0 + offset * config.probe_timeout_jitter).max(0.1);
+    let mut config = Config::clone(&config);
+    config.connect_timeout = config.connect_timeout.mul_f64(factor);
+    config.read_timeout = config.read_timeout.mul_f64(factor);
+    config.into()
+}
+
+/// `config` narrowed to scanning `protocol`: that protocol's
+/// `--max-bytes-for` budget, and the browser only for HTTP.
+fn for_protocol(config: &Config, protocol: &Protocol) -> Config {
+    let mut config = config.clone();
+    config.protocol = protocol.clone();
+    config.webdriver &= matches!(protocol, Protocol::Http | Protocol::Https);
+    if let Some(&max_bytes) = config.max_bytes_for.get(&protocol.to_string()) {
+        config.max_bytes = max_bytes;
+    }
+    config
+}
+
+impl DefaultProcessor {
+    /// `--protocol a,b,c`: scans with each protocol in turn, on a fresh
+    /// connection, and keeps the first open target with a non-empty banner,
+    /// noting it in `matched_protocol`. A port that cannot be reached at all
+    /// is not retried, and without a match the last attempt is reported.
+    async fn first_match(
+        &self,
+        target: crate::model::Target,
+        config: std::sync::Arc<Config>,
+    ) -> anyhow::Result<ScanOutcome> {
+        let mut last = None;
+        for protocol in &config.protocols {
+            let attempt = for_protocol(&config, protocol);
+            let mut outcome = self.scan(target.clone(), attempt.into()).await?;
+            if matches!(outcome.status, Status::Open) && !outcome.banner.raw_hex.is_empty() {
+                outcome
+                    .fingerprint
+                    .fields
+                    .insert("matched_protocol".into(), protocol.to_string());
+                return Ok(outcome);
+            }
+            let unreachable = outcome.diagnostics.as_ref().is_some_and(|diag| {
+                matches!(
+                    diag.error_kind,
+                    ErrorKind::ConnectTimeout
+                        | ErrorKind::ConnectionRefused
+                        | ErrorKind::HostUnreachable
+                )
+            });
+            last = Some(outcome);
+            if unreachable {
+                break;
+            }
+        }
+        last.ok_or_else(|| anyhow::anyhow!("--protocol lists no protocol"))
+    }
+
+    async fn scan(
+        &self,
+        target: crate::model::Target,
+        config: std::sync::Arc<Config>,
     ) -> anyhow::Result<ScanOutcome> {
         let start = now_millis();
         let tcp_start = now_millis();
         let connect_timeout = adjusted_connect_timeout(config.as_ref(), &target);
 
+        // Off the --active-ports allowlist only the plain banner read runs.
+        let mode = match &config.active_ports {
+            Some(ports) if !ports.contains(&target.resolved.port()) => ScanMode::Passive,
+            _ => config.mode,
+        };
         let client_request = ClientRequest {
             target: target.clone(),
-            mode: config.mode,
+            mode,
             protocol: config.protocol.clone(),
         };
         if let Some(outcome) =
@@ -53,10 +154,25 @@ impl TargetProcessor for DefaultProcessor {
                 Ok(connection) => connection,
                 Err(outcome) => return Ok(outcome),
             };
+        if matches!(config.protocol, Protocol::Tcp) {
+            // Connect scan: the handshake is the whole answer.
+            drop(stream);
+            return Ok(build_outcome_with_context(
+                target,
+                Status::Open,
+                tcp_meta,
+                ReadStopReason::NotStarted,
+                Vec::new(),
+                None,
+                config.max_bytes,
+                config.read_timeout,
+                &config.protocol,
+            ));
+        }
 
         let probe_request = ProbeRequest {
             target: target.clone(),
-            mode: config.mode,
+            mode,
             protocol: config.protocol.clone(),
         };
 
@@ -74,8 +190,20 @@ impl TargetProcessor for DefaultProcessor {
             Err(outcome) => return Ok(outcome),
         };
 
-        let fingerprint = Fingerprint::from_protocol(&config.protocol);
+        let mut fingerprint = Fingerprint::from_protocol(&config.protocol);
+        fingerprint.fields.extend(read_result.fields.clone());
+        if let Some(accepted) = http_status_accepted(config.as_ref(), &read_result.bytes) {
+            fingerprint
+                .fields
+                .insert("http_status_accepted".into(), accepted.to_string());
+        }
         let tls_info = read_result.tls_info.clone();
+        let baseline = config
+            .test_proxy_protocol
+            .is_some()
+            .then(|| read_result.bytes.clone());
+        let builtin_tech = matches!(config.protocol, Protocol::Http | Protocol::Https)
+            .then(|| super::tech::matcher().scan(&read_result.bytes));
         let banner = BannerReader::new(config.max_bytes, config.read_timeout).render(read_result);
         let (webdriver_body, diagnostics) = if config.webdriver {
             match webdriver::fetch_rendered_body(&target, &config.protocol, config.overall_timeout)
@@ -84,21 +212,44 @@ impl TargetProcessor for DefaultProcessor {
                 Ok(body) => (Some(body), None),
                 Err(err) => (
                     None,
+                    // The banner itself was captured; only the rendered body is missing.
                     Some(Diagnostics {
-                        stage: "webdriver".into(),
-                        message: err.to_string(),
+                        severity: Severity::Warning,
+                        ..Diagnostics::new(
+                            "webdriver",
+                            classify_error(&err).unwrap_or_default(),
+                            err.to_string(),
+                        )
                     }),
                 ),
             }
         } else {
             (None, None)
         };
-        let technologies =
-            if config.tech && matches!(config.protocol, Protocol::Http | Protocol::Https) {
-                scan_technologies(&target, &config.protocol).await
-            } else {
-                None
-            };
+        let technologies = match builtin_tech {
+            Some(builtin) if config.tech => {
+                Some(match scan_technologies(&target, &config.protocol).await {
+                    Some(remote) => merge_technologies(builtin, remote),
+                    None => builtin,
+                })
+            }
+            builtin => builtin,
+        };
+        let proxy_protocol_accepted = match (config.test_proxy_protocol, baseline) {
+            (Some(version), Some(baseline)) => {
+                test_proxy_protocol(
+                    target.clone(),
+                    config.as_ref(),
+                    version,
+                    connect_timeout,
+                    &client_request,
+                    &probe_request,
+                    &baseline,
+                )
+                .await
+            }
+            _ => None,
+        };
         let total = now_millis() - start;
         debug!(target = %target.resolved, ms = total, "processed target");
 
@@ -107,17 +258,32 @@ impl TargetProcessor for DefaultProcessor {
             status: Status::Open,
             tcp: tcp_meta,
             banner,
-            timestamp: now_iso8601(),
+            timestamp: Utc::now(),
             ttl: None,
             webdriver: webdriver_body,
             technologies,
             tls_info,
             fingerprint,
             diagnostics,
+            proxy_protocol_accepted,
+            ptr: None,
         })
     }
 }
 
+/// Whether the response's status code is one `--http-accept-status` lists;
+/// `None` without the option or when no status line arrived.
+fn http_status_accepted(config: &Config, response: &[u8]) -> Option<bool> {
+    let accept = config.http_accept_status.as_ref()?;
+    let line = response.split(|byte| *byte == b'\n').next()?;
+    let mut parts = std::str::from_utf8(line).ok()?.split_whitespace();
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    let code = parts.next()?.parse::<u16>().ok()?;
+    Some(accept.contains(&code))
+}
+
 async fn attempt_udp_scan(
     target: crate::model::Target,
     config: &Config,
@@ -126,9 +292,17 @@ async fn attempt_udp_scan(
     if let Some(udp_client) = udp_client_for_target(client_request) {
         let udp_start = now_millis();
 
-        let read_result = match udp_client.execute(&target, config).await {
+        let read_result = match execute_udp(udp_client, &target, config).await {
             Ok(result) => result,
             Err(err) => {
+                let error_kind = classify_error(&err).unwrap_or(ErrorKind::ProtocolError);
+                // An ICMP port unreachable surfaces as a refused send/recv on
+                // the connected socket; report it like a refused TCP connect.
+                let stage = if error_kind == ErrorKind::ConnectionRefused {
+                    "connect".to_string()
+                } else {
+                    format!("clients:{}", udp_client.name())
+                };
                 return Ok(Some(build_outcome_with_context(
                     target,
                     Status::Error,
@@ -138,14 +312,11 @@ async fn attempt_udp_scan(
                     },
                     ReadStopReason::NotStarted,
                     Vec::new(),
-                    Some(Diagnostics {
-                        stage: format!("clients:{}", udp_client.name()),
-                        message: err.to_string(),
-                    }),
+                    Some(Diagnostics::new(stage, error_kind, err.to_string())),
                     config.max_bytes,
                     config.read_timeout,
                     &config.protocol,
-                )))
+                )));
             }
         };
 
@@ -157,7 +328,8 @@ async fn attempt_udp_scan(
         let banner =
             BannerReader::new(config.max_bytes, config.read_timeout).render(read_result.clone());
         let tls_info = read_result.tls_info.clone();
-        let fingerprint = Fingerprint::from_protocol(&config.protocol);
+        let mut fingerprint = Fingerprint::from_protocol(&config.protocol);
+        fingerprint.fields.extend(read_result.fields);
         let elapsed = now_millis() - udp_start;
 
         return Ok(Some(ScanOutcome {
@@ -168,26 +340,57 @@ async fn attempt_udp_scan(
                 error: None,
             },
             banner,
-            timestamp: now_iso8601(),
+            timestamp: Utc::now(),
             ttl: None,
             webdriver: None,
             technologies: None,
             tls_info,
             fingerprint,
             diagnostics: None,
+            proxy_protocol_accepted: None,
+            ptr: None,
         }));
     }
 
     Ok(None)
 }
 
+/// Runs a UDP client, resending up to `--udp-retries` more times while
+/// nothing comes back: a lost datagram looks just like a silent port. A
+/// retry only starts when another wait as long as the last one still fits
+/// in the overall timeout.
+async fn execute_udp(
+    client: &dyn UdpClient,
+    target: &crate::model::Target,
+    config: &Config,
+) -> anyhow::Result<ReadResult> {
+    let started = Instant::now();
+    let mut attempts = 1;
+    loop {
+        let attempt_started = Instant::now();
+        let mut result = client.execute(target, config).await?;
+        let silent = result.reason == ReadStopReason::Timeout && result.bytes.is_empty();
+        let fits = started.elapsed() + attempt_started.elapsed() <= config.overall_timeout;
+        if !silent || attempts > config.udp_retries || !fits {
+            if attempts > 1 {
+                result
+                    .fields
+                    .insert("udp_attempts".into(), attempts.to_string());
+            }
+            return Ok(result);
+        }
+        debug!(target = %target.resolved, attempt = attempts, "no UDP reply, resending");
+        attempts += 1;
+    }
+}
+
 async fn connect_tcp(
     target: crate::model::Target,
     config: &Config,
     connect_timeout: Duration,
     tcp_start: u128,
 ) -> anyhow::Result<Result<(TcpStream, TcpMeta), ScanOutcome>> {
-    let connect_result = timeout(connect_timeout, TcpStream::connect(target.resolved)).await;
+    let connect_result = timeout(connect_timeout, super::proxy::connect(&target, config)).await;
 
     let connection = match connect_result {
         Ok(Ok(stream)) => {
@@ -209,10 +412,15 @@ async fn connect_tcp(
             },
             ReadStopReason::NotStarted,
             Vec::new(),
-            Some(Diagnostics {
-                stage: "connect".into(),
-                message: err.to_string(),
-            }),
+            Some(Diagnostics::new(
+                if err.is::<super::proxy::ProxyError>() {
+                    "proxy"
+                } else {
+                    "connect"
+                },
+                classify_connect_error(&err),
+                err.to_string(),
+            )),
             config.max_bytes,
             config.read_timeout,
             &config.protocol,
@@ -226,10 +434,11 @@ async fn connect_tcp(
             },
             ReadStopReason::Timeout,
             Vec::new(),
-            Some(Diagnostics {
-                stage: "connect".into(),
-                message: "connect timeout".into(),
-            }),
+            Some(Diagnostics::new(
+                "connect",
+                ErrorKind::ConnectTimeout,
+                "connect timeout",
+            )),
             config.max_bytes,
             config.read_timeout,
             &config.protocol,
@@ -252,7 +461,25 @@ async fn process_tcp_stream(
 
     if let Some(client) = client {
         let mut stream = stream;
-        match client.execute(&mut stream, config).await {
+        let result = if client_request.protocol.implicit_tls()
+            || config.implicit_tls_ports.contains(&target.resolved.port())
+        {
+            match implicit_tls::connect(stream, &target, config).await {
+                Ok((mut tls_stream, tls_info)) => {
+                    run_client(client, &mut tls_stream, config, &target)
+                        .await
+                        .map(|mut result| {
+                            result.tls_info = Some(tls_info);
+                            result.fields.insert("implicit_tls".into(), "true".into());
+                            result
+                        })
+                }
+                Err(err) => Err(err),
+            }
+        } else {
+            run_client(client, &mut stream, config, &target).await
+        };
+        match result {
             Ok(result) => Ok(result),
             Err(err) => Err(build_outcome_with_context(
                 target,
@@ -260,10 +487,11 @@ async fn process_tcp_stream(
                 tcp_meta.clone(),
                 ReadStopReason::NotStarted,
                 Vec::new(),
-                Some(Diagnostics {
-                    stage: format!("clients:{}", client.name()),
-                    message: err.to_string(),
-                }),
+                Some(Diagnostics::new(
+                    format!("clients:{}", client.name()),
+                    classify_error(&err).unwrap_or(ErrorKind::ProtocolError),
+                    err.to_string(),
+                )),
                 config.max_bytes,
                 config.read_timeout,
                 &config.protocol,
@@ -278,10 +506,11 @@ async fn process_tcp_stream(
                 tcp_meta.clone(),
                 ReadStopReason::NotStarted,
                 Vec::new(),
-                Some(Diagnostics {
-                    stage: "probe".into(),
-                    message: err.to_string(),
-                }),
+                Some(Diagnostics::new(
+                    "probe",
+                    classify_error(&err).unwrap_or(ErrorKind::ProtocolError),
+                    err.to_string(),
+                )),
                 config.max_bytes,
                 config.read_timeout,
                 &config.protocol,
@@ -289,19 +518,63 @@ async fn process_tcp_stream(
         }
     } else {
         let mut stream = stream;
-        let mut reader = BannerReader::new(config.max_bytes, config.read_timeout);
-        match reader.read(&mut stream, None).await {
-            Ok(result) => Ok(result),
+        let mut reader = match config.peek_bytes {
+            Some(limit) => BannerReader::peek(limit.min(config.max_bytes), config.read_timeout),
+            None => BannerReader::new(config.max_bytes, config.read_timeout),
+        }
+        .with_max_rounds(config.max_read_rounds);
+        let mut read = reader.read(&mut stream, None).await;
+        if config.nudge {
+            if let Ok(first) = &read {
+                // A peer that already hung up cannot be nudged.
+                if first.bytes.is_empty() && first.reason != ReadStopReason::ConnectionClosed {
+                    let nudge = config.send_payload.as_deref().unwrap_or(b"\r\n");
+                    if stream.write_all(nudge).await.is_ok() {
+                        read = reader.read(&mut stream, None).await;
+                        if let Ok(second) = &mut read {
+                            second.record_sent(config, nudge);
+                        }
+                    }
+                }
+            }
+        }
+        match read {
+            Ok(mut result) => {
+                if config.peek_bytes.is_some() {
+                    if let Some(guess) = super::reader::classify_prefix(&result.bytes) {
+                        result.fields.insert("peek_protocol".into(), guess.into());
+                    }
+                }
+                if config.starttls {
+                    if let Some(guess) = super::reader::classify_prefix(&result.bytes)
+                        .filter(|guess| starttls::supports(guess))
+                    {
+                        match starttls::upgrade(stream, guess, config, &target).await {
+                            Ok(report) => report.record(&mut result),
+                            Err(err) => {
+                                result
+                                    .fields
+                                    .insert("starttls_protocol".into(), guess.into());
+                                result
+                                    .fields
+                                    .insert("starttls_error".into(), format!("{err:#}"));
+                            }
+                        }
+                    }
+                }
+                Ok(result)
+            }
             Err(err) => Err(build_outcome_with_context(
                 target,
                 Status::Error,
                 tcp_meta.clone(),
                 ReadStopReason::NotStarted,
                 Vec::new(),
-                Some(Diagnostics {
-                    stage: "banner-read".into(),
-                    message: err.to_string(),
-                }),
+                Some(Diagnostics::new(
+                    "banner-read",
+                    classify_error(&err).unwrap_or(ErrorKind::ProtocolError),
+                    err.to_string(),
+                )),
                 config.max_bytes,
                 config.read_timeout,
                 &config.protocol,
@@ -310,6 +583,71 @@ async fn process_tcp_stream(
     }
 }
 
+/// Runs `client` over `stream`, keeping what it sent for `--record-probe`.
+async fn run_client(
+    client: &dyn Client,
+    stream: &mut dyn ClientStream,
+    config: &Config,
+    target: &crate::model::Target,
+) -> anyhow::Result<ReadResult> {
+    if !config.record_probe {
+        return client.execute(stream, config, target).await;
+    }
+    let mut recorder = RecordingStream::new(stream);
+    let result = client.execute(&mut recorder, config, target).await;
+    let sent = recorder.into_sent();
+    result.map(|mut result| {
+        result.record_sent(config, &sent);
+        result
+    })
+}
+
+/// Replays the client/probe on a fresh connection that starts with a PROXY
+/// protocol header and reports whether the service carried on as it did for
+/// `baseline`, the response without the header. Returns `None` when the
+/// second connection could not be established, and under `--proxy`, where
+/// the address the service sees is the proxy's rather than our socket's.
+async fn test_proxy_protocol(
+    target: crate::model::Target,
+    config: &Config,
+    version: ProxyProtocolVersion,
+    connect_timeout: Duration,
+    client_request: &ClientRequest,
+    probe_request: &ProbeRequest,
+    baseline: &[u8],
+) -> Option<bool> {
+    if config.proxy.is_some() {
+        return None;
+    }
+    let mut stream = timeout(connect_timeout, super::proxy::connect(&target, config))
+        .await
+        .ok()?
+        .ok()?;
+    let source = stream.local_addr().ok()?;
+    let header = proxy_protocol::header(version, source, target.resolved);
+    if stream.write_all(&header).await.is_err() {
+        return Some(false);
+    }
+
+    let tcp_meta = TcpMeta {
+        connect_ms: None,
+        error: None,
+    };
+    match process_tcp_stream(
+        stream,
+        target,
+        config,
+        client_request,
+        probe_request,
+        &tcp_meta,
+    )
+    .await
+    {
+        Ok(result) => Some(proxy_protocol::accepted(&result.bytes, baseline)),
+        Err(_) => Some(false),
+    }
+}
+
 fn adjusted_connect_timeout(config: &Config, target: &crate::model::Target) -> Duration {
     if matches!(config.mode, ScanMode::Active) && target.resolved.port() == 21 {
         // FTP servers are often slower to finish the TCP handshake due to
@@ -324,27 +662,15 @@ fn adjusted_connect_timeout(config: &Config, target: &crate::model::Target) -> D
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{OutputConfig, OutputFormat, Protocol, Target, TargetSpec};
+    use crate::model::{Protocol, Target, TargetSpec};
 
     fn baseline_config(mode: ScanMode, connect_timeout: Duration) -> Config {
-        Config {
-            target: None,
-            input: None,
-            port_filter: None,
-            concurrency: 1,
-            rate: 1,
-            connect_timeout,
-            read_timeout: Duration::from_secs(1),
-            overall_timeout: Duration::from_secs(5),
-            max_bytes: 64,
-            mode,
-            protocol: Protocol::Http,
-            webdriver: false,
-            tech: false,
-            output: OutputConfig {
-                format: OutputFormat::Jsonl,
-            },
-        }
+        let mut config = crate::clients::mock::config(Protocol::Http);
+        config.mode = mode;
+        config.connect_timeout = connect_timeout;
+        config.read_timeout = Duration::from_secs(1);
+        config.max_bytes = 64;
+        config
     }
 
     fn ftp_target() -> Target {
@@ -357,6 +683,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn checks_status_against_accepted_codes() {
+        let mut config = baseline_config(ScanMode::Active, Duration::from_secs(1));
+        assert_eq!(
+            http_status_accepted(&config, b"HTTP/1.1 403 Forbidden\r\n"),
+            None
+        );
+
+        config.http_accept_status = Some([200, 401].into());
+        assert_eq!(
+            http_status_accepted(&config, b"HTTP/1.1 401 Unauthorized\r\n\r\n"),
+            Some(true)
+        );
+        assert_eq!(
+            http_status_accepted(&config, b"HTTP/1.0 404 Not Found\r\n\r\n"),
+            Some(false)
+        );
+        assert_eq!(
+            http_status_accepted(&config, b"SSH-2.0-OpenSSH_9.6\r\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn jitters_connect_and_read_timeouts() {
+        let config = std::sync::Arc::new(baseline_config(
+            ScanMode::Passive,
+            Duration::from_millis(1000),
+        ));
+        let untouched = jitter_timeouts(config.clone(), 1.0);
+        assert!(std::sync::Arc::ptr_eq(&untouched, &config));
+
+        let mut jittery = Config::clone(&config);
+        jittery.probe_timeout_jitter = 0.25;
+        let jittery = std::sync::Arc::new(jittery);
+        let longest = jitter_timeouts(jittery.clone(), 1.0);
+        assert_eq!(longest.connect_timeout, Duration::from_millis(1250));
+        assert_eq!(longest.read_timeout, Duration::from_millis(1250));
+        let shortest = jitter_timeouts(jittery, -1.0);
+        assert_eq!(shortest.read_timeout, Duration::from_millis(750));
+        assert_eq!(shortest.overall_timeout, config.overall_timeout);
+
+        let mut widest = Config::clone(&config);
+        widest.probe_timeout_jitter = 1.0;
+        let floored = jitter_timeouts(std::sync::Arc::new(widest), -1.0);
+        assert_eq!(floored.read_timeout, Duration::from_millis(100));
+    }
+
     #[test]
     fn extends_timeout_for_active_ftp() {
         let config = baseline_config(ScanMode::Active, Duration::from_secs(1));
@@ -376,6 +750,183 @@ mod tests {
         let timeout_active = adjusted_connect_timeout(&active_config, &active_non_ftp);
         assert_eq!(timeout_active, Duration::from_secs(1));
     }
+
+    #[tokio::test]
+    async fn keeps_first_protocol_with_a_banner() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let _ = stream.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await;
+            }
+        });
+
+        let mut config = baseline_config(ScanMode::Passive, Duration::from_secs(1));
+        config.read_timeout = Duration::from_millis(200);
+        config.protocols = vec![Protocol::Tcp, Protocol::Ssh, Protocol::Http];
+        let mut target = ftp_target();
+        target.resolved = addr;
+
+        let outcome = DefaultProcessor
+            .process_target(target, config.into())
+            .await
+            .unwrap();
+        assert_eq!(outcome.fingerprint.protocol.as_deref(), Some("ssh"));
+        assert_eq!(
+            outcome.fingerprint.fields.get("matched_protocol").unwrap(),
+            "ssh"
+        );
+    }
+
+    #[tokio::test]
+    async fn scans_the_protocol_mapped_to_the_port() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let _ = stream.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await;
+            }
+        });
+
+        let mut config = baseline_config(ScanMode::Passive, Duration::from_secs(1));
+        config.read_timeout = Duration::from_millis(200);
+        config.protocol = Protocol::Http;
+        config.protocols = vec![Protocol::Http];
+        config.protocol_by_port = [(addr.port(), Protocol::Ssh)].into();
+        config.max_bytes_for = [("ssh".to_string(), 8)].into();
+        let mut target = ftp_target();
+        target.resolved = addr;
+
+        let outcome = DefaultProcessor
+            .process_target(target, config.into())
+            .await
+            .unwrap();
+        assert_eq!(outcome.fingerprint.protocol.as_deref(), Some("ssh"));
+        assert!(outcome.banner.truncated);
+    }
+
+    #[tokio::test]
+    async fn resends_udp_probe_after_a_silent_wait() {
+        let server = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            // Drop the first probe as a lossy link would; answer the second.
+            server.recv_from(&mut buf).await.unwrap();
+            let (_, peer) = server.recv_from(&mut buf).await.unwrap();
+            server.send_to(b"pong", peer).await.unwrap();
+        });
+
+        let mut config = baseline_config(ScanMode::Active, Duration::from_secs(1));
+        config.read_timeout = Duration::from_millis(200);
+        config.send_payload = Some(b"ping".to_vec());
+        let mut target = ftp_target();
+        target.resolved = addr;
+        let client = crate::clients::UdpRawClient;
+
+        let result = execute_udp(&client, &target, &config).await.unwrap();
+        assert_eq!(result.bytes, b"pong");
+        assert_eq!(result.fields.get("udp_attempts").unwrap(), "2");
+
+        let silent = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        target.resolved = silent.local_addr().unwrap();
+        config.udp_retries = 0;
+        let result = execute_udp(&client, &target, &config).await.unwrap();
+        assert_eq!(result.reason, ReadStopReason::Timeout);
+        assert!(!result.fields.contains_key("udp_attempts"));
+    }
+
+    #[test]
+    fn classifies_errors_through_context() {
+        let refused =
+            anyhow::Error::from(std::io::Error::from(std::io::ErrorKind::ConnectionRefused))
+                .context("failed to write probe");
+        assert_eq!(classify_error(&refused), Some(ErrorKind::ConnectionRefused));
+
+        let timed_out = std::io::Error::from(std::io::ErrorKind::TimedOut);
+        assert_eq!(
+            classify_io_error(&timed_out, true),
+            ErrorKind::ConnectTimeout
+        );
+        assert_eq!(classify_io_error(&timed_out, false), ErrorKind::ReadTimeout);
+
+        assert_eq!(
+            classify_error(&anyhow::anyhow!("unexpected greeting")),
+            None
+        );
+    }
+}
+
+/// Maps low-level failures (socket errors, TLS errors, elapsed timers) found in
+/// `err`'s chain to an [`ErrorKind`]. Returns `None` when nothing in the chain
+/// is recognised, leaving the caller to pick the stage's default.
+pub(crate) fn classify_error(err: &anyhow::Error) -> Option<ErrorKind> {
+    err.chain().find_map(|cause| {
+        if let Some(io_err) = cause.downcast_ref::<std::io::Error>() {
+            return Some(classify_io_error(io_err, false));
+        }
+        if cause.is::<openssl::ssl::Error>() || cause.is::<openssl::error::ErrorStack>() {
+            return Some(ErrorKind::TlsHandshakeFailed);
+        }
+        if cause.is::<super::proxy::ProxyError>() {
+            return Some(ErrorKind::ProxyFailed);
+        }
+        if cause.is::<tokio::time::error::Elapsed>() {
+            return Some(ErrorKind::ReadTimeout);
+        }
+        None
+    })
+}
+
+fn classify_connect_error(err: &anyhow::Error) -> ErrorKind {
+    match err.downcast_ref::<std::io::Error>() {
+        Some(io_err) => classify_io_error(io_err, true),
+        None => classify_error(err).unwrap_or_default(),
+    }
+}
+
+fn classify_io_error(err: &std::io::Error, connecting: bool) -> ErrorKind {
+    use std::io::ErrorKind as Io;
+    match err.kind() {
+        Io::TimedOut | Io::WouldBlock if connecting => ErrorKind::ConnectTimeout,
+        Io::TimedOut | Io::WouldBlock => ErrorKind::ReadTimeout,
+        Io::ConnectionRefused => ErrorKind::ConnectionRefused,
+        Io::ConnectionReset | Io::ConnectionAborted | Io::BrokenPipe | Io::UnexpectedEof => {
+            ErrorKind::ConnectionReset
+        }
+        Io::HostUnreachable | Io::NetworkUnreachable | Io::AddrNotAvailable => {
+            ErrorKind::HostUnreachable
+        }
+        Io::InvalidData => ErrorKind::ProtocolError,
+        _ => ErrorKind::Other,
+    }
+}
+
+/// Builds the outcome for a banner captured elsewhere (`--replay`), running
+/// it through the same rendering and fingerprinting as a live read.
+pub(crate) fn replay_outcome(
+    target: crate::model::Target,
+    bytes: Vec<u8>,
+    config: &Config,
+) -> ScanOutcome {
+    let technologies = matches!(config.protocol, Protocol::Http | Protocol::Https)
+        .then(|| super::tech::matcher().scan(&bytes));
+    let mut outcome = build_outcome_with_context(
+        target,
+        Status::Open,
+        TcpMeta {
+            connect_ms: None,
+            error: None,
+        },
+        ReadStopReason::ConnectionClosed,
+        bytes,
+        None,
+        config.max_bytes,
+        config.read_timeout,
+        &config.protocol,
+    );
+    outcome.technologies = technologies;
+    outcome
 }
 
 fn build_outcome_with_context(
@@ -394,6 +945,7 @@ fn build_outcome_with_context(
         bytes,
         reason: reason.clone(),
         tls_info: None,
+        fields: Default::default(),
     };
     let banner = BannerReader::new(max_bytes, idle_timeout).render(read_result.clone());
     let fingerprint = Fingerprint::from_protocol(protocol);
@@ -402,13 +954,39 @@ fn build_outcome_with_context(
         status,
         tcp,
         banner,
-        timestamp: now_iso8601(),
+        timestamp: Utc::now(),
         ttl: None,
         webdriver: None,
         technologies: None,
         tls_info: None,
         fingerprint,
         diagnostics,
+        proxy_protocol_accepted: None,
+        ptr: None,
+    }
+}
+
+/// Combines the built-in matches with the wappalyzer scan, keeping each
+/// technology once.
+fn merge_technologies(
+    builtin: crate::model::TechnologyScan,
+    remote: crate::model::TechnologyScan,
+) -> crate::model::TechnologyScan {
+    let mut seen = std::collections::HashSet::new();
+    let mut list: Vec<_> = builtin
+        .list
+        .into_iter()
+        .chain(remote.list)
+        .filter(|entry| seen.insert(entry.name.to_ascii_lowercase()))
+        .collect();
+    list.sort_by(|a, b| {
+        a.category
+            .cmp(&b.category)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    crate::model::TechnologyScan {
+        scan_time_seconds: builtin.scan_time_seconds + remote.scan_time_seconds,
+        list,
     }
 }
 
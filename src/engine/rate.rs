@@ -7,6 +7,10 @@ pub struct RateLimiter {
     state: Arc<tokio::sync::Mutex<State>>,
     fill_rate: f64,
     capacity: f64,
+    /// `--jitter` as a fraction of the interval between tokens.
+    jitter: f64,
+    /// `--ramp` start and length, while the fill rate climbs to `fill_rate`.
+    ramp: Option<(Instant, Duration)>,
 }
 
 struct State {
@@ -15,7 +19,7 @@ struct State {
 }
 
 impl RateLimiter {
-    pub fn new(fill_rate: u32) -> Self {
+    pub fn new(fill_rate: u32, jitter: f64) -> Self {
         let fill_rate = fill_rate.max(1) as f64;
         Self {
             state: Arc::new(tokio::sync::Mutex::new(State {
@@ -24,41 +28,67 @@ impl RateLimiter {
             })),
             capacity: fill_rate,
             fill_rate,
+            jitter: jitter.clamp(0.0, 1.0),
+            ramp: None,
         }
     }
 
-    pub async fn acquire(&self) {
-        loop {
-            let wait_until = {
-                let mut state = self.state.lock().await;
-                let now = Instant::now();
-                let elapsed = now.duration_since(state.last_refill);
-                if elapsed > Duration::ZERO {
-                    let to_add = elapsed.as_secs_f64() * self.fill_rate;
-                    if to_add > 0.0 {
-                        state.tokens = (state.tokens + to_add).min(self.capacity);
-                        state.last_refill = now;
-                    }
-                }
+    /// Starts with a single token and raises the fill rate linearly from one
+    /// token per second to `fill_rate` over `ramp`, beginning now.
+    pub fn with_ramp(self, ramp: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            state: Arc::new(tokio::sync::Mutex::new(State {
+                tokens: 1.0,
+                last_refill: now,
+            })),
+            ramp: Some((now, ramp)),
+            ..self
+        }
+    }
+
+    fn fill_rate_at(&self, now: Instant) -> f64 {
+        match self.ramp {
+            Some((start, ramp)) if !ramp.is_zero() => {
+                let progress = now.duration_since(start).as_secs_f64() / ramp.as_secs_f64();
+                1.0 + (self.fill_rate - 1.0) * progress.min(1.0)
+            }
+            _ => self.fill_rate,
+        }
+    }
 
-                if state.tokens >= 1.0 {
-                    state.tokens -= 1.0;
-                    None
-                } else {
-                    let missing = 1.0 - state.tokens;
-                    let wait_seconds = missing / self.fill_rate;
+    /// Takes a token, waiting for one when the bucket is empty. A waiter
+    /// reserves its token up front, so its wake-up time is fixed by the fill
+    /// rate alone and jitter only moves it within one interval either way;
+    /// the average rate stays at `fill_rate`.
+    pub async fn acquire(&self) {
+        let wait_until = {
+            let mut state = self.state.lock().await;
+            let now = Instant::now();
+            let fill_rate = self.fill_rate_at(now);
+            let elapsed = now.duration_since(state.last_refill);
+            if elapsed > Duration::ZERO {
+                let to_add = elapsed.as_secs_f64() * fill_rate;
+                if to_add > 0.0 {
+                    state.tokens = (state.tokens + to_add).min(self.capacity.min(fill_rate));
                     state.last_refill = now;
-                    Some(now + Duration::from_secs_f64(wait_seconds))
                 }
-            };
+            }
 
-            match wait_until {
-                None => return,
-                Some(when) => {
-                    sleep_until(when).await;
-                }
+            state.tokens -= 1.0;
+            if state.tokens >= 0.0 {
+                return;
             }
-        }
+            // During a ramp this is the current, lowest rate of the wait, so
+            // the waiter may start a little late but never early.
+            let mut wait_seconds = -state.tokens / fill_rate;
+            if self.jitter > 0.0 {
+                let offset = rand::random::<f64>() * 2.0 - 1.0;
+                wait_seconds += offset * self.jitter / fill_rate;
+            }
+            now + Duration::from_secs_f64(wait_seconds.max(0.0))
+        };
+        sleep_until(wait_until).await;
     }
 }
 
@@ -70,7 +100,7 @@ mod tests {
 
     #[tokio::test(start_paused = true)]
     async fn rate_limiter_refills_in_smaller_steps() {
-        let limiter = RateLimiter::new(4);
+        let limiter = RateLimiter::new(4, 0.0);
 
         for _ in 0..4 {
             limiter.acquire().await;
@@ -92,4 +122,40 @@ mod tests {
         advance(Duration::from_millis(90)).await;
         assert!(next.await.is_ok());
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn jitter_keeps_the_average_rate() {
+        let limiter = RateLimiter::new(10, 0.5);
+        let start = tokio::time::Instant::now();
+
+        // The first 10 tokens are already in the bucket.
+        for _ in 0..110 {
+            limiter.acquire().await;
+        }
+
+        let elapsed = start.elapsed().as_secs_f64();
+        assert!((elapsed - 10.0).abs() <= 0.05, "took {elapsed}s");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ramp_starts_slow_and_reaches_the_full_rate() {
+        let limiter = RateLimiter::new(10, 0.0).with_ramp(Duration::from_secs(10));
+        let start = tokio::time::Instant::now();
+
+        // At full rate the bucket alone would cover these; ramping from one
+        // token per second they need several seconds.
+        for _ in 0..10 {
+            limiter.acquire().await;
+        }
+        let ramping = start.elapsed().as_secs_f64();
+        assert!((3.0..8.0).contains(&ramping), "took {ramping}s");
+
+        advance(Duration::from_secs(10)).await;
+        let full = tokio::time::Instant::now();
+        for _ in 0..20 {
+            limiter.acquire().await;
+        }
+        let elapsed = full.elapsed().as_secs_f64();
+        assert!(elapsed <= 1.05, "took {elapsed}s");
+    }
 }
@@ -1,11 +1,62 @@
-use crate::model::{Banner, ReadStopReason, TlsInfo};
+use crate::model::{Banner, Config, ReadStopReason, TlsInfo};
+use std::collections::BTreeMap;
 use std::time::Duration;
 use tokio::io::AsyncReadExt;
 use tokio::time::timeout;
 
+/// How `read_using` decides a binary banner is complete. Text protocols
+/// end on a delimiter and use `read`; binary ones announce their length, so
+/// reading exactly one message saves waiting out the idle timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadStrategy {
+    /// Exactly this many bytes, capped at `max_bytes`.
+    Fixed(usize),
+    /// One message: a `header_len`-byte header whose `size`-byte length
+    /// field at `header_offset` gives the rest. The length also counts
+    /// `counted` header bytes (TDS counts its whole header, PostgreSQL the
+    /// length field itself).
+    LengthPrefixed {
+        header_len: usize,
+        header_offset: usize,
+        size: usize,
+        little_endian: bool,
+        counted: usize,
+    },
+}
+
+impl ReadStrategy {
+    /// Body length announced by a `LengthPrefixed` header; 0 otherwise.
+    pub fn body_len(&self, header: &[u8]) -> usize {
+        let Self::LengthPrefixed {
+            header_offset,
+            size,
+            little_endian,
+            counted,
+            ..
+        } = *self
+        else {
+            return 0;
+        };
+        let Some(field) = header.get(header_offset..header_offset + size) else {
+            return 0;
+        };
+        let fold = |len: usize, byte: &u8| len << 8 | *byte as usize;
+        let len = if little_endian {
+            field.iter().rev().fold(0, fold)
+        } else {
+            field.iter().fold(0, fold)
+        };
+        len.saturating_sub(counted)
+    }
+}
+
 pub struct BannerReader {
     max_bytes: usize,
     idle_timeout: Duration,
+    /// Reaching `max_bytes` is the `--peek-bytes` limit, not `--max-bytes`.
+    peek: bool,
+    /// Reads that returned data before the read gives up with `RoundLimit`.
+    max_rounds: usize,
 }
 
 impl BannerReader {
@@ -13,10 +64,46 @@ impl BannerReader {
         Self {
             max_bytes,
             idle_timeout,
+            peek: false,
+            max_rounds: usize::MAX,
+        }
+    }
+
+    /// Caps the reads per banner (`--max-read-rounds`). The idle timeout
+    /// restarts with every read, so without a cap a peer sending a byte
+    /// just inside it would only be stopped by `max_bytes` or the overall
+    /// timeout.
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds.max(1);
+        self
+    }
+
+    /// Reads at most `limit` bytes and reports `PeekLimit` when they were all
+    /// there, leaving the rest of the banner unread.
+    pub fn peek(limit: usize, idle_timeout: Duration) -> Self {
+        Self {
+            max_bytes: limit,
+            idle_timeout,
+            peek: true,
+            max_rounds: usize::MAX,
+        }
+    }
+
+    pub async fn read_using<T: AsyncReadExt + Unpin + ?Sized>(
+        &mut self,
+        stream: &mut T,
+        strategy: ReadStrategy,
+    ) -> anyhow::Result<ReadResult> {
+        match strategy {
+            ReadStrategy::Fixed(len) => self.read_exact(stream, 0, |_| len).await,
+            ReadStrategy::LengthPrefixed { header_len, .. } => {
+                self.read_exact(stream, header_len, |header| strategy.body_len(header))
+                    .await
+            }
         }
     }
 
-    pub async fn read<T: AsyncReadExt + Unpin>(
+    pub async fn read<T: AsyncReadExt + Unpin + ?Sized>(
         &mut self,
         stream: &mut T,
         extra_delimiter: Option<&[u8]>,
@@ -24,19 +111,29 @@ impl BannerReader {
         let mut buf = vec![0u8; self.max_bytes];
         let mut total = 0usize;
         let mut reason = ReadStopReason::ConnectionClosed;
+        let mut rounds = 0usize;
         loop {
             match timeout(self.idle_timeout, stream.read(&mut buf[total..])).await {
                 Ok(Ok(0)) => break,
                 Ok(Ok(n)) => {
                     total += n;
+                    rounds += 1;
                     if total >= self.max_bytes {
-                        reason = ReadStopReason::SizeLimit;
+                        reason = if self.peek {
+                            ReadStopReason::PeekLimit
+                        } else {
+                            ReadStopReason::SizeLimit
+                        };
                         break;
                     }
                     if find_delimiter(&buf[..total], extra_delimiter).is_some() {
                         reason = ReadStopReason::Delimiter;
                         break;
                     }
+                    if rounds >= self.max_rounds {
+                        reason = ReadStopReason::RoundLimit;
+                        break;
+                    }
                 }
                 Ok(Err(err)) => return Err(err.into()),
                 Err(_) => {
@@ -51,9 +148,73 @@ impl BannerReader {
             reason,
             truncated: total >= self.max_bytes,
             tls_info: None,
+            fields: Default::default(),
+        })
+    }
+
+    /// Reads `header_len` bytes, then the body length `body_len` derives
+    /// from them. Stops with `Delimiter` once both are complete, or with
+    /// `SizeLimit` when `max_bytes` does not fit the whole header.
+    async fn read_exact<T: AsyncReadExt + Unpin + ?Sized>(
+        &mut self,
+        stream: &mut T,
+        header_len: usize,
+        body_len: impl Fn(&[u8]) -> usize,
+    ) -> anyhow::Result<ReadResult> {
+        let full_header = header_len;
+        let header_len = header_len.min(self.max_bytes);
+        let mut bytes = vec![0u8; header_len];
+        let mut rounds = 0;
+        let (mut filled, mut reason) = self.fill(stream, &mut bytes, &mut rounds).await?;
+        let mut truncated = header_len < full_header && filled == header_len;
+        if filled == full_header {
+            let len = body_len(&bytes);
+            let wanted = len.min(self.max_bytes - header_len);
+            truncated = wanted < len;
+            bytes.resize(header_len + wanted, 0);
+            let (body, body_reason) = self
+                .fill(stream, &mut bytes[header_len..], &mut rounds)
+                .await?;
+            filled += body;
+            reason = body_reason;
+        }
+        bytes.truncate(filled);
+        if truncated {
+            reason = ReadStopReason::SizeLimit;
+        }
+        Ok(ReadResult {
+            bytes,
+            reason,
+            truncated,
+            tls_info: None,
+            fields: Default::default(),
         })
     }
 
+    async fn fill<T: AsyncReadExt + Unpin + ?Sized>(
+        &self,
+        stream: &mut T,
+        buf: &mut [u8],
+        rounds: &mut usize,
+    ) -> anyhow::Result<(usize, ReadStopReason)> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            if *rounds >= self.max_rounds {
+                return Ok((filled, ReadStopReason::RoundLimit));
+            }
+            match timeout(self.idle_timeout, stream.read(&mut buf[filled..])).await {
+                Ok(Ok(0)) => return Ok((filled, ReadStopReason::ConnectionClosed)),
+                Ok(Ok(n)) => {
+                    filled += n;
+                    *rounds += 1;
+                }
+                Ok(Err(err)) => return Err(err.into()),
+                Err(_) => return Ok((filled, ReadStopReason::Timeout)),
+            }
+        }
+        Ok((filled, ReadStopReason::Delimiter))
+    }
+
     pub fn render(&self, result: ReadResult) -> Banner {
         let raw_hex = crate::util::hex::to_hex(&result.bytes);
         let printable = crate::util::sanitize_text(&result.bytes);
@@ -72,6 +233,45 @@ pub struct ReadResult {
     pub reason: ReadStopReason,
     pub truncated: bool,
     pub tls_info: Option<TlsInfo>,
+    /// Extra facts a probe learned on the side, merged into `Fingerprint.fields`.
+    pub fields: BTreeMap<String, String>,
+}
+
+impl ReadResult {
+    /// Appends `bytes` to the `probe_sent_hex` field under `--record-probe`.
+    pub fn record_sent(&mut self, cfg: &Config, bytes: &[u8]) {
+        if cfg.record_probe && !bytes.is_empty() {
+            self.fields
+                .entry("probe_sent_hex".into())
+                .or_default()
+                .push_str(&crate::util::hex::to_hex(bytes));
+        }
+    }
+}
+
+/// Guesses the service from the first bytes it sent, for `--peek-bytes`.
+pub fn classify_prefix(bytes: &[u8]) -> Option<&'static str> {
+    let guess = match bytes {
+        [b'S', b'S', b'H', b'-', ..] => "ssh",
+        [b'H', b'T', b'T', b'P', b'/', ..] => "http",
+        // Handshake or alert record, SSL 3.0 through TLS 1.3.
+        [0x15 | 0x16, 0x03, 0x00..=0x04, ..] => "tls",
+        // Handshake packet: 3-byte length, sequence 0, protocol version 10.
+        [_, _, 0x00, 0x00, 0x0a, ..] => "mysql",
+        [b'R', b'F', b'B', b' ', ..] => "vnc",
+        [b'+', b'O', b'K', ..] => "pop3",
+        [b'*', b' ', b'O', b'K', ..] => "imap",
+        [b'2', b'2', b'0', ..] => {
+            let head = String::from_utf8_lossy(bytes).to_ascii_uppercase();
+            if head.contains("SMTP") {
+                "smtp"
+            } else {
+                "ftp"
+            }
+        }
+        _ => return None,
+    };
+    Some(guess)
 }
 
 fn find_delimiter(buf: &[u8], extra: Option<&[u8]>) -> Option<usize> {
@@ -121,6 +321,107 @@ mod tests {
         assert_eq!(res.bytes, b"VTUN server ver 3.X 12/31/2013\n...");
     }
 
+    #[tokio::test]
+    async fn peek_stops_at_limit() {
+        let mut reader = BannerReader::peek(8, Duration::from_millis(200));
+        let mut data: &[u8] = b"SSH-2.0-OpenSSH_9.6\r\n";
+        let res = reader.read(&mut data, None).await.unwrap();
+        assert_eq!(res.bytes, b"SSH-2.0-");
+        assert_eq!(res.reason, ReadStopReason::PeekLimit);
+        assert!(res.truncated);
+        assert_eq!(classify_prefix(&res.bytes), Some("ssh"));
+    }
+
+    #[tokio::test]
+    async fn reads_one_length_prefixed_message() {
+        let tds = ReadStrategy::LengthPrefixed {
+            header_len: 8,
+            header_offset: 2,
+            size: 2,
+            little_endian: false,
+            counted: 8,
+        };
+        let mut reader = BannerReader::new(64, Duration::from_millis(200));
+        let mut data: &[u8] = b"\x04\x01\x00\x0b\x00\x00\x01\x00abcNEXT";
+        let res = reader.read_using(&mut data, tds).await.unwrap();
+        assert_eq!(res.bytes, b"\x04\x01\x00\x0b\x00\x00\x01\x00abc");
+        assert_eq!(res.reason, ReadStopReason::Delimiter);
+        assert_eq!(data, b"NEXT");
+
+        let mysql = ReadStrategy::LengthPrefixed {
+            header_len: 4,
+            header_offset: 0,
+            size: 3,
+            little_endian: true,
+            counted: 0,
+        };
+        let mut reader = BannerReader::new(6, Duration::from_millis(200));
+        let mut data: &[u8] = b"\x04\x00\x00\x00\x0a8.0";
+        let res = reader.read_using(&mut data, mysql).await.unwrap();
+        assert_eq!(res.bytes, b"\x04\x00\x00\x00\x0a8");
+        assert_eq!(res.reason, ReadStopReason::SizeLimit);
+        assert!(res.truncated);
+    }
+
+    #[tokio::test]
+    async fn stops_inside_a_header_longer_than_max_bytes() {
+        let mysql = ReadStrategy::LengthPrefixed {
+            header_len: 4,
+            header_offset: 0,
+            size: 3,
+            little_endian: true,
+            counted: 0,
+        };
+        let mut reader = BannerReader::new(2, Duration::from_millis(200));
+        let mut data: &[u8] = b"\x04\x00\x00\x00\x0a8.0";
+        let res = reader.read_using(&mut data, mysql).await.unwrap();
+        assert_eq!(res.bytes, b"\x04\x00");
+        assert_eq!(res.reason, ReadStopReason::SizeLimit);
+        assert!(res.truncated);
+        assert_eq!(mysql.body_len(&res.bytes), 0);
+    }
+
+    #[tokio::test]
+    async fn stops_a_trickling_peer_after_max_rounds() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        tokio::spawn(async move {
+            use tokio::io::AsyncWriteExt;
+            for _ in 0..10 {
+                if server.write_all(b"x").await.is_err() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        });
+        let mut reader = BannerReader::new(64, Duration::from_millis(200)).with_max_rounds(3);
+        let res = reader.read(&mut client, None).await.unwrap();
+        assert_eq!(res.bytes, b"xxx");
+        assert_eq!(res.reason, ReadStopReason::RoundLimit);
+        assert!(!res.truncated);
+    }
+
+    #[tokio::test]
+    async fn fixed_reads_stop_at_the_length() {
+        let mut reader = BannerReader::new(64, Duration::from_millis(200));
+        let mut data: &[u8] = b"abcdef";
+        let res = reader
+            .read_using(&mut data, ReadStrategy::Fixed(4))
+            .await
+            .unwrap();
+        assert_eq!(res.bytes, b"abcd");
+    }
+
+    #[test]
+    fn classifies_binary_prefixes() {
+        assert_eq!(
+            classify_prefix(&[0x16, 0x03, 0x03, 0x00, 0x5d]),
+            Some("tls")
+        );
+        assert_eq!(classify_prefix(&[0x4a, 0, 0, 0, 0x0a, b'8']), Some("mysql"));
+        assert_eq!(classify_prefix(b"220 mx ESMTP"), Some("smtp"));
+        assert_eq!(classify_prefix(b"\x00\x01"), None);
+    }
+
     #[tokio::test]
     async fn captures_multiline_banner_until_idle() {
         let mut reader = BannerReader::new(128, Duration::from_millis(50));
@@ -0,0 +1,236 @@
+use crate::model::{TechnologyEntry, TechnologyScan};
+use anyhow::{anyhow, Context};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+const EMBEDDED_RULES: &str = include_str!("tech_rules.json");
+
+static MATCHER: OnceLock<TechMatcher> = OnceLock::new();
+
+/// One entry of the rules file. Every map value and body entry is a
+/// case-insensitive regex; an empty pattern only checks for presence.
+#[derive(Deserialize)]
+struct RuleSpec {
+    name: String,
+    category: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    cookies: BTreeMap<String, String>,
+    #[serde(default)]
+    meta: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Vec<String>,
+}
+
+struct Rule {
+    entry: TechnologyEntry,
+    headers: Vec<(String, Regex)>,
+    cookies: Vec<(String, Regex)>,
+    meta: Vec<(String, Regex)>,
+    body: Vec<Regex>,
+}
+
+/// Offline technology detection over a captured HTTP response.
+pub struct TechMatcher {
+    rules: Vec<Rule>,
+}
+
+impl TechMatcher {
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let specs: Vec<RuleSpec> = serde_json::from_str(json).context("invalid tech rules")?;
+        let rules = specs
+            .into_iter()
+            .map(|spec| {
+                let keyed = |map: BTreeMap<String, String>| {
+                    map.into_iter()
+                        .map(|(key, pattern)| Ok((key.to_ascii_lowercase(), compile(&pattern)?)))
+                        .collect::<anyhow::Result<Vec<_>>>()
+                };
+                Ok(Rule {
+                    headers: keyed(spec.headers)?,
+                    cookies: keyed(spec.cookies)?,
+                    meta: keyed(spec.meta)?,
+                    body: spec
+                        .body
+                        .iter()
+                        .map(|pattern| compile(pattern))
+                        .collect::<anyhow::Result<_>>()?,
+                    entry: TechnologyEntry {
+                        category: spec.category,
+                        name: spec.name,
+                    },
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn embedded() -> Self {
+        Self::from_json(EMBEDDED_RULES).expect("embedded tech rules are valid")
+    }
+
+    pub fn scan(&self, response: &[u8]) -> TechnologyScan {
+        let started = Instant::now();
+        let text = String::from_utf8_lossy(response);
+        let (head, body) = text
+            .split_once("\r\n\r\n")
+            .or_else(|| text.split_once("\n\n"))
+            .unwrap_or((&text, ""));
+        let response = ParsedResponse::new(head, body);
+
+        let mut list: Vec<TechnologyEntry> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches(&response))
+            .map(|rule| rule.entry.clone())
+            .collect();
+        list.sort_by(|a, b| {
+            a.category
+                .cmp(&b.category)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        TechnologyScan {
+            scan_time_seconds: started.elapsed().as_secs_f64(),
+            list,
+        }
+    }
+}
+
+/// Loads the rules used by [`matcher`], from `path` when given. Call once
+/// before scanning so a broken rules file fails the run up front; a second
+/// call, or one after [`matcher`] settled on the embedded rules, is an
+/// error rather than a silently ignored file.
+pub fn init(path: Option<&str>) -> anyhow::Result<()> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read tech rules from {path}"))?;
+    let matcher = TechMatcher::from_json(&json).with_context(|| format!("in {path}"))?;
+    MATCHER
+        .set(matcher)
+        .map_err(|_| anyhow!("tech rules from {path} loaded after the rules were already in use"))
+}
+
+pub fn matcher() -> &'static TechMatcher {
+    MATCHER.get_or_init(TechMatcher::embedded)
+}
+
+fn compile(pattern: &str) -> anyhow::Result<Regex> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .with_context(|| format!("invalid pattern {pattern:?}"))
+}
+
+struct ParsedResponse<'a> {
+    headers: Vec<(String, String)>,
+    cookies: Vec<(String, String)>,
+    meta: Vec<(String, String)>,
+    body: &'a str,
+}
+
+impl<'a> ParsedResponse<'a> {
+    fn new(head: &'a str, body: &'a str) -> Self {
+        let headers: Vec<(String, String)> = head
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+        let cookies = headers
+            .iter()
+            .filter(|(name, _)| name == "set-cookie")
+            .filter_map(|(_, value)| value.split(';').next()?.split_once('='))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+        Self {
+            headers,
+            cookies,
+            meta: meta_tags(body),
+            body,
+        }
+    }
+}
+
+impl Rule {
+    fn matches(&self, response: &ParsedResponse<'_>) -> bool {
+        let keyed = |patterns: &[(String, Regex)], values: &[(String, String)]| {
+            patterns.iter().any(|(key, regex)| {
+                values
+                    .iter()
+                    .any(|(name, value)| name == key && regex.is_match(value))
+            })
+        };
+        keyed(&self.headers, &response.headers)
+            || keyed(&self.cookies, &response.cookies)
+            || keyed(&self.meta, &response.meta)
+            || self.body.iter().any(|regex| regex.is_match(response.body))
+    }
+}
+
+/// `(name, content)` of every `<meta name=... content=...>` tag, with the
+/// name lowercased.
+fn meta_tags(body: &str) -> Vec<(String, String)> {
+    static TAG: OnceLock<(Regex, Regex, Regex)> = OnceLock::new();
+    let (tag, name, content) = TAG.get_or_init(|| {
+        (
+            compile(r"<meta\s[^>]*>").expect("valid regex"),
+            compile(r#"\b(?:name|property)\s*=\s*["']([^"']+)["']"#).expect("valid regex"),
+            compile(r#"\bcontent\s*=\s*["']([^"']*)["']"#).expect("valid regex"),
+        )
+    });
+    tag.find_iter(body)
+        .filter_map(|found| {
+            let found = found.as_str();
+            let name = name.captures(found)?.get(1)?.as_str().to_ascii_lowercase();
+            let content = content.captures(found)?.get(1)?.as_str().to_string();
+            Some((name, content))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(scan: &TechnologyScan) -> Vec<&str> {
+        scan.list.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn detects_from_headers_cookies_meta_and_body() {
+        let response = b"HTTP/1.1 200 OK\r\n\
+            Server: nginx/1.24.0\r\n\
+            Set-Cookie: PHPSESSID=abc; path=/\r\n\r\n\
+            <html><head><meta name=\"generator\" content=\"WordPress 6.4\">\
+            <link rel=\"stylesheet\" href=\"/wp-content/themes/x/style.css\">\
+            <script src=\"/static/react-dom.production.min.js\"></script></head></html>";
+        let scan = TechMatcher::embedded().scan(response);
+        let found = names(&scan);
+        for expected in ["nginx", "PHP", "WordPress", "React"] {
+            assert!(
+                found.contains(&expected),
+                "{expected} missing from {found:?}"
+            );
+        }
+        assert!(!found.contains(&"Drupal"));
+    }
+
+    #[test]
+    fn header_rules_do_not_match_body_text() {
+        let response = b"HTTP/1.1 200 OK\r\nServer: Apache\r\n\r\nServer: nginx";
+        let scan = TechMatcher::embedded().scan(response);
+        assert_eq!(names(&scan), ["Apache HTTP Server"]);
+    }
+
+    #[test]
+    fn rejects_invalid_rule_patterns() {
+        let json = r#"[{"name": "x", "category": "y", "body": ["("]}]"#;
+        assert!(TechMatcher::from_json(json).is_err());
+    }
+}
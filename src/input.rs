@@ -1,35 +1,102 @@
-use crate::model::{Target, TargetSpec};
+use crate::engine::deadline::Deadline;
+use crate::model::{InputFormat, Target, TargetSpec};
 use anyhow::Context;
 use futures::{stream::FuturesUnordered, StreamExt};
-use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::collections::HashSet;
+use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 use tokio::net::lookup_host;
 use tokio::sync::{mpsc, Semaphore};
 use tokio_stream::wrappers::ReceiverStream;
 
 const FILE_RESOLUTION_CONCURRENCY: usize = 64;
 
+type Seen<T> = Option<Arc<Mutex<HashSet<T>>>>;
+
+/// Targets already queued under `--dedupe`/`--dedupe-resolved`. Shared by
+/// every file reader and resolver task, which skip and count repeats.
+#[derive(Clone, Default)]
+pub struct Dedupe {
+    specs: Seen<(String, u16)>,
+    addrs: Seen<SocketAddr>,
+    skipped: Arc<AtomicUsize>,
+}
+
+impl Dedupe {
+    pub fn new(cfg: &crate::model::Config) -> Self {
+        Self {
+            specs: cfg.dedupe.then(Arc::default),
+            addrs: cfg.dedupe_resolved.then(Arc::default),
+            skipped: Arc::default(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.specs.is_some() || self.addrs.is_some()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// False for a `host:port` seen before; hostnames compare case-insensitively.
+    fn first_spec(&self, spec: &TargetSpec) -> bool {
+        let Some(specs) = &self.specs else {
+            return true;
+        };
+        let key = (spec.host.to_ascii_lowercase(), spec.port);
+        self.first(specs.lock().unwrap().insert(key))
+    }
+
+    fn first_addr(&self, addr: SocketAddr) -> bool {
+        let Some(addrs) = &self.addrs else {
+            return true;
+        };
+        self.first(addrs.lock().unwrap().insert(addr))
+    }
+
+    fn first(&self, inserted: bool) -> bool {
+        if !inserted {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        inserted
+    }
+}
+
 pub fn stream_targets(
     cfg: &crate::model::Config,
+    deadline: &Deadline,
+    dedupe: &Dedupe,
 ) -> anyhow::Result<ReceiverStream<anyhow::Result<Target>>> {
     let (tx, rx) = mpsc::channel(256);
+    let resolver = Resolver::from_config(cfg)?;
 
     if let Some(spec) = cfg.target.clone() {
         let tx = tx.clone();
+        let resolver = resolver.clone();
+        let dedupe = dedupe.clone();
         tokio::spawn(async move {
             let tx_err = tx.clone();
-            if let Err(err) = resolve_and_send(spec, tx).await {
+            if let Err(err) = resolve_and_send(spec, &resolver, &dedupe, tx).await {
                 let _ = tx_err.send(Err(err)).await;
             }
         });
     }
 
-    if let Some(path) = cfg.input.clone() {
+    // One reader per file, all feeding the same channel.
+    for path in cfg.input.iter().cloned() {
         let tx = tx.clone();
+        let resolver = resolver.clone();
         let port_filter = cfg.port_filter;
+        let format = cfg.input_format;
+        let deadline = deadline.clone();
+        let dedupe = dedupe.clone();
         tokio::spawn(async move {
             let tx_err = tx.clone();
-            if let Err(err) = read_file(path, port_filter, tx).await {
+            let read = read_file(path, format, port_filter, resolver, deadline, dedupe, tx);
+            if let Err(err) = read.await {
                 tracing::error!(error = %err, "failed to read input file");
                 let _ = tx_err.send(Err(err)).await;
             }
@@ -42,40 +109,90 @@ pub fn stream_targets(
 
 async fn read_file(
     path: String,
+    format: InputFormat,
     port_filter: Option<u16>,
+    resolver: Resolver,
+    deadline: Deadline,
+    dedupe: Dedupe,
     tx: mpsc::Sender<anyhow::Result<Target>>,
 ) -> anyhow::Result<()> {
     let file = tokio::fs::File::open(&path)
         .await
         .with_context(|| format!("cannot open input {}", path))?;
-    let mut reader = BufReader::new(file).lines();
     let sem = Arc::new(Semaphore::new(FILE_RESOLUTION_CONCURRENCY));
     let mut tasks = FuturesUnordered::new();
     let mut first_error: Option<anyhow::Error> = None;
-    while let Some(line) = reader.next_line().await? {
-        let trimmed = line.trim();
-        if trimmed.is_empty() || trimmed.starts_with('#') {
-            continue;
+    let mut dispatch = |spec: TargetSpec| {
+        if port_filter.is_some_and(|filter_port| spec.port != filter_port) {
+            return;
         }
-        if let Some(spec) = parse_target(trimmed) {
-            if let Some(filter_port) = port_filter {
-                if spec.port != filter_port {
-                    continue;
-                }
+        // Past the deadline the rest of the file is only counted, so a huge
+        // list does not keep the run alive resolving names nobody will scan.
+        if deadline.expired() {
+            deadline.skip();
+            return;
+        }
+        if !dedupe.first_spec(&spec) {
+            return;
+        }
+        let tx = tx.clone();
+        let sem = sem.clone();
+        let resolver = resolver.clone();
+        let dedupe = dedupe.clone();
+        let deadline = deadline.clone();
+        tasks.push(tokio::spawn(async move {
+            let permit = sem.acquire_owned().await?;
+            let _permit = permit;
+            if deadline.expired() {
+                deadline.skip();
+                return Ok(());
+            }
+            resolve_and_send(spec, &resolver, &dedupe, tx).await
+        }));
+    };
+
+    if format == InputFormat::Lines {
+        let mut reader = BufReader::new(file).lines();
+        while let Some(line) = reader.next_line().await? {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if let Some(spec) = parse_target(trimmed) {
+                dispatch(spec);
+            } else {
+                tracing::warn!(line = %trimmed, "skipping invalid target");
             }
-            let tx = tx.clone();
-            let sem = sem.clone();
-            tasks.push(tokio::spawn(async move {
-                let permit = sem.acquire_owned().await?;
-                let _permit = permit;
-                resolve_and_send(spec, tx).await
-            }));
-        } else {
-            tracing::warn!(line = %trimmed, "skipping invalid target");
         }
+    } else {
+        let mut text = String::new();
+        BufReader::new(file)
+            .read_to_string(&mut text)
+            .await
+            .with_context(|| format!("cannot read input {}", path))?;
+        let specs = match format {
+            InputFormat::NmapXml => parse_nmap_xml(&text)?,
+            _ => parse_masscan_json(&text),
+        };
+        specs.into_iter().for_each(&mut dispatch);
     }
 
-    while let Some(res) = tasks.next().await {
+    loop {
+        let res = tokio::select! {
+            res = tasks.next() => match res {
+                Some(res) => res,
+                None => break,
+            },
+            _ = deadline.reached() => {
+                // Lookups still queued or in flight are abandoned rather than
+                // awaited, so slow DNS cannot hold the run past its deadline.
+                for task in tasks.iter().filter(|task| !task.is_finished()) {
+                    task.abort();
+                    deadline.skip();
+                }
+                break;
+            }
+        };
         match res {
             Ok(Ok(())) => {}
             Ok(Err(err)) => {
@@ -113,16 +230,281 @@ fn parse_target(line: &str) -> Option<TargetSpec> {
     None
 }
 
+/// Open TCP ports of every host in nmap `-oX` output. The host is taken from
+/// its IP address element so hostnames nmap saw are not re-resolved.
+fn parse_nmap_xml(text: &str) -> anyhow::Result<Vec<TargetSpec>> {
+    let options = roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+    };
+    let doc =
+        roxmltree::Document::parse_with_options(text, options).context("invalid nmap XML input")?;
+    let mut specs = Vec::new();
+    for host in doc.descendants().filter(|node| node.has_tag_name("host")) {
+        let Some(addr) = host
+            .children()
+            .filter(|node| node.has_tag_name("address"))
+            .find(|node| matches!(node.attribute("addrtype"), Some("ipv4" | "ipv6")))
+            .and_then(|node| node.attribute("addr"))
+        else {
+            continue;
+        };
+        let open_ports = host
+            .descendants()
+            .filter(|node| node.has_tag_name("port"))
+            .filter(|port| port.attribute("protocol") == Some("tcp"))
+            .filter(|port| {
+                port.children()
+                    .find(|node| node.has_tag_name("state"))
+                    .and_then(|state| state.attribute("state"))
+                    == Some("open")
+            })
+            .filter_map(|port| port.attribute("portid")?.parse().ok());
+        specs.extend(open_ports.map(|port| TargetSpec {
+            host: addr.to_string(),
+            port,
+        }));
+    }
+    Ok(specs)
+}
+
+/// Open TCP ports from masscan JSON. Accepts a proper array, the `-oJ`
+/// flavour older masscan versions write with dangling commas, and ndjson, by
+/// falling back to one record per line.
+fn parse_masscan_json(text: &str) -> Vec<TargetSpec> {
+    let records: Vec<serde_json::Value> = serde_json::from_str(text).unwrap_or_else(|_| {
+        text.lines()
+            .map(|line| line.trim().trim_end_matches(','))
+            .filter(|line| line.starts_with('{'))
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    });
+    let mut specs = Vec::new();
+    for record in &records {
+        let Some(ip) = record.get("ip").and_then(|ip| ip.as_str()) else {
+            continue;
+        };
+        let ports = record.get("ports").and_then(|ports| ports.as_array());
+        for entry in ports.into_iter().flatten() {
+            let field = |name: &str| entry.get(name).and_then(|value| value.as_str());
+            if field("proto").is_some_and(|proto| proto != "tcp")
+                || field("status").is_some_and(|status| status != "open")
+            {
+                continue;
+            }
+            if let Some(port) = entry
+                .get("port")
+                .and_then(|port| port.as_u64())
+                .and_then(|port| u16::try_from(port).ok())
+            {
+                specs.push(TargetSpec {
+                    host: ip.to_string(),
+                    port,
+                });
+            }
+        }
+    }
+    specs
+}
+
+/// Reads a `--replay` capture file: one `ip:port:hexbytes` record per line
+/// (IPv6 addresses bracketed, whitespace inside the hex ignored). Blank lines
+/// and `#` comments are skipped; malformed records are logged and skipped.
+pub async fn read_replay(path: &str) -> anyhow::Result<Vec<(Target, Vec<u8>)>> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .with_context(|| format!("cannot open replay file {}", path))?;
+    let mut reader = BufReader::new(file).lines();
+    let mut records = Vec::new();
+    while let Some(line) = reader.next_line().await? {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        match parse_replay_line(trimmed) {
+            Some(record) => records.push(record),
+            None => tracing::warn!(line = %trimmed, "skipping invalid replay record"),
+        }
+    }
+    Ok(records)
+}
+
+fn parse_replay_line(line: &str) -> Option<(Target, Vec<u8>)> {
+    let (target, hex) = line.rsplit_once(':')?;
+    let spec = parse_target(target)?;
+    let ip = spec.host.parse().ok()?;
+    let bytes = crate::util::hex::from_hex(hex).ok()?;
+    let target = Target {
+        resolved: SocketAddr::new(ip, spec.port),
+        original: spec,
+    };
+    Some((target, bytes))
+}
+
+/// Name resolution backend: the system resolver, or the nameservers passed
+/// with `--resolver`, queried in the order given and falling over to the next
+/// one when a server fails or times out. Behind a SOCKS4a/SOCKS5h proxy names
+/// are left for the proxy to resolve.
+#[derive(Clone)]
+enum Resolver {
+    System,
+    Proxy,
+    #[cfg(feature = "hickory-dns")]
+    Custom(Arc<hickory_resolver::TokioAsyncResolver>),
+}
+
+impl Resolver {
+    fn from_config(cfg: &crate::model::Config) -> anyhow::Result<Self> {
+        if cfg
+            .proxy
+            .as_ref()
+            .is_some_and(|proxy| proxy.kind.remote_dns())
+        {
+            return Ok(Self::Proxy);
+        }
+        if cfg.resolvers.is_empty() {
+            return Ok(Self::System);
+        }
+        Self::custom(&cfg.resolvers)
+    }
+
+    #[cfg(feature = "hickory-dns")]
+    fn custom(servers: &[SocketAddr]) -> anyhow::Result<Self> {
+        use hickory_resolver::config::{
+            NameServerConfig, NameServerConfigGroup, Protocol, ResolverConfig, ResolverOpts,
+            ServerOrderingStrategy,
+        };
+
+        let mut group = NameServerConfigGroup::new();
+        for server in servers {
+            group.push(NameServerConfig::new(*server, Protocol::Udp));
+            group.push(NameServerConfig::new(*server, Protocol::Tcp));
+        }
+        let mut opts = ResolverOpts::default();
+        opts.server_ordering_strategy = ServerOrderingStrategy::UserProvidedOrder;
+        let config = ResolverConfig::from_parts(None, Vec::new(), group);
+        Ok(Self::Custom(Arc::new(
+            hickory_resolver::TokioAsyncResolver::tokio(config, opts),
+        )))
+    }
+
+    #[cfg(not(feature = "hickory-dns"))]
+    fn custom(_servers: &[SocketAddr]) -> anyhow::Result<Self> {
+        anyhow::bail!("--resolver requires a build with the `hickory-dns` feature")
+    }
+
+    async fn lookup(&self, host: &str, port: u16) -> anyhow::Result<Vec<SocketAddr>> {
+        if let Some(addr) = scoped_ipv6(host, port)? {
+            return Ok(vec![addr]);
+        }
+        match self {
+            Self::System => Ok(lookup_host((host, port)).await?.collect()),
+            // The connect helper sends the original hostname; the unspecified
+            // address only stands in for it until then.
+            Self::Proxy => {
+                let ip = host.parse().unwrap_or(std::net::IpAddr::from([0, 0, 0, 0]));
+                Ok(vec![SocketAddr::new(ip, port)])
+            }
+            #[cfg(feature = "hickory-dns")]
+            Self::Custom(resolver) => {
+                if let Ok(ip) = host.parse::<std::net::IpAddr>() {
+                    return Ok(vec![SocketAddr::new(ip, port)]);
+                }
+                let lookup = resolver
+                    .lookup_ip(host)
+                    .await
+                    .with_context(|| format!("failed to resolve {host}"))?;
+                Ok(lookup.iter().map(|ip| SocketAddr::new(ip, port)).collect())
+            }
+        }
+    }
+}
+
+/// Parses a link-local literal such as `fe80::1%eth0` into an address carrying
+/// the zone's scope id. The zone may be an interface name or a numeric index.
+/// Returns `None` for anything that is not a scoped IPv6 literal.
+fn scoped_ipv6(host: &str, port: u16) -> anyhow::Result<Option<SocketAddr>> {
+    let Some((addr, zone)) = host.split_once('%') else {
+        return Ok(None);
+    };
+    let Ok(ip) = addr.parse::<Ipv6Addr>() else {
+        return Ok(None);
+    };
+    let scope_id = zone_scope_id(zone, interface_index)
+        .with_context(|| format!("unknown interface `{zone}` in scoped address {host}"))?;
+    Ok(Some(SocketAddr::V6(SocketAddrV6::new(
+        ip, port, 0, scope_id,
+    ))))
+}
+
+/// The zone is taken verbatim first, so `%253` is scope 253 and interfaces
+/// whose names start with "25" keep them. Only a zone that names nothing is
+/// read as the URL-escaped form (`fe80::1%25eth0`, RFC 6874).
+fn zone_scope_id(zone: &str, interface_index: impl Fn(&str) -> Option<u32>) -> Option<u32> {
+    let verbatim = |zone: &str| zone.parse::<u32>().ok().or_else(|| interface_index(zone));
+    verbatim(zone).or_else(|| {
+        zone.strip_prefix("25")
+            .filter(|unescaped| !unescaped.is_empty())
+            .and_then(verbatim)
+    })
+}
+
+#[cfg(unix)]
+fn interface_index(name: &str) -> Option<u32> {
+    let name = std::ffi::CString::new(name).ok()?;
+    // SAFETY: `name` is a valid NUL-terminated string for the duration of the call.
+    let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    (index != 0).then_some(index)
+}
+
+#[cfg(not(unix))]
+fn interface_index(_name: &str) -> Option<u32> {
+    None
+}
+
+/// A target whose host could not be resolved. Sent down the target stream
+/// in place of the target so the engine can report it and carry on.
+#[derive(Debug)]
+pub struct ResolveError {
+    pub spec: TargetSpec,
+    message: String,
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
 async fn resolve_and_send(
     spec: TargetSpec,
+    resolver: &Resolver,
+    dedupe: &Dedupe,
     tx: mpsc::Sender<anyhow::Result<Target>>,
 ) -> anyhow::Result<()> {
-    let lookup = lookup_host((spec.host.as_str(), spec.port)).await?;
+    let lookup = match resolver.lookup(&spec.host, spec.port).await {
+        Ok(lookup) => lookup,
+        Err(err) => {
+            let message = format!("failed to resolve {}: {err:#}", spec.host);
+            return tx
+                .send(Err(ResolveError { spec, message }.into()))
+                .await
+                .map_err(anyhow::Error::from)
+                .with_context(|| "failed to dispatch resolution failure");
+        }
+    };
     for addr in lookup {
         let target = Target {
             original: spec.clone(),
             resolved: addr,
         };
+        // Every name the proxy resolves shares the placeholder address;
+        // `--dedupe` still catches repeated names.
+        if !target.proxy_resolved() && !dedupe.first_addr(addr) {
+            continue;
+        }
         tx.send(Ok(target))
             .await
             .map_err(anyhow::Error::from)
@@ -144,32 +526,98 @@ mod tests {
         assert_eq!(spec.host, "::1");
     }
 
+    #[test]
+    fn extracts_open_tcp_ports_from_nmap_xml() {
+        let xml = r#"<?xml version="1.0"?>
+<!DOCTYPE nmaprun>
+<nmaprun scanner="nmap">
+  <host>
+    <address addr="192.0.2.1" addrtype="ipv4"/>
+    <address addr="00:11:22:33:44:55" addrtype="mac"/>
+    <ports>
+      <port protocol="tcp" portid="22"><state state="open"/></port>
+      <port protocol="tcp" portid="23"><state state="closed"/></port>
+      <port protocol="udp" portid="53"><state state="open"/></port>
+    </ports>
+  </host>
+</nmaprun>"#;
+        let specs = parse_nmap_xml(xml).unwrap();
+        assert_eq!(specs.len(), 1);
+        assert_eq!((specs[0].host.as_str(), specs[0].port), ("192.0.2.1", 22));
+    }
+
+    #[test]
+    fn extracts_open_ports_from_masscan_json() {
+        let legacy = "[\n{ \"ip\": \"192.0.2.1\", \"ports\": [ {\"port\": 80, \"proto\": \"tcp\", \"status\": \"open\"} ] },\n]\n";
+        let specs = parse_masscan_json(legacy);
+        assert_eq!(specs.len(), 1);
+        assert_eq!((specs[0].host.as_str(), specs[0].port), ("192.0.2.1", 80));
+
+        let ndjson = "{\"ip\":\"192.0.2.2\",\"ports\":[{\"port\":443,\"proto\":\"tcp\",\"status\":\"open\"}]}\n{\"ip\":\"192.0.2.3\",\"ports\":[{\"port\":53,\"proto\":\"udp\"}]}";
+        let specs = parse_masscan_json(ndjson);
+        assert_eq!(specs.len(), 1);
+        assert_eq!(specs[0].port, 443);
+    }
+
+    #[test]
+    fn parses_replay_records() {
+        let (target, bytes) = parse_replay_line("[2001:db8::1]:22:5353482d 322e30").unwrap();
+        assert_eq!(target.resolved, "[2001:db8::1]:22".parse().unwrap());
+        assert_eq!(bytes, b"SSH-2.0");
+
+        assert!(parse_replay_line("example.com:22:5353").is_none());
+        assert!(parse_replay_line("192.0.2.1:22:zz").is_none());
+    }
+
+    #[tokio::test]
+    async fn keeps_scope_id_of_link_local_targets() {
+        let spec = parse_target("[fe80::1%2]:22").unwrap();
+        assert_eq!(spec.host, "fe80::1%2");
+        assert_eq!(spec.port, 22);
+
+        let addrs = Resolver::System
+            .lookup(&spec.host, spec.port)
+            .await
+            .unwrap();
+        let expected: SocketAddr = SocketAddrV6::new("fe80::1".parse().unwrap(), 22, 0, 2).into();
+        assert_eq!(addrs, vec![expected]);
+    }
+
+    #[test]
+    fn reads_zones_verbatim_before_unescaping() {
+        let interfaces = |name: &str| match name {
+            "eth0" => Some(2),
+            "25gbe0" => Some(7),
+            _ => None,
+        };
+        assert_eq!(zone_scope_id("253", interfaces), Some(253));
+        assert_eq!(zone_scope_id("25gbe0", interfaces), Some(7));
+        assert_eq!(zone_scope_id("25eth0", interfaces), Some(2));
+        assert_eq!(zone_scope_id("25", interfaces), Some(25));
+        assert_eq!(zone_scope_id("25wlan0", interfaces), None);
+    }
+
+    fn file_config(input: Vec<String>, port_filter: Option<u16>) -> crate::model::Config {
+        let mut cfg = crate::clients::mock::config(crate::model::Protocol::Http);
+        cfg.input = input;
+        cfg.port_filter = port_filter;
+        cfg.connect_timeout = std::time::Duration::from_millis(100);
+        cfg.read_timeout = std::time::Duration::from_millis(100);
+        cfg.overall_timeout = std::time::Duration::from_millis(200);
+        cfg.max_bytes = 64;
+        cfg.mode = crate::model::ScanMode::Passive;
+        cfg
+    }
+
     #[tokio::test]
     async fn filters_targets_by_port_when_requested() {
         let mut file = NamedTempFile::new().unwrap();
         use std::io::Write;
         writeln!(file, "127.0.0.1:80\n127.0.0.1:81").unwrap();
 
-        let cfg = crate::model::Config {
-            target: None,
-            input: Some(file.path().to_string_lossy().into()),
-            port_filter: Some(80),
-            concurrency: 1,
-            rate: 1,
-            connect_timeout: std::time::Duration::from_millis(100),
-            read_timeout: std::time::Duration::from_millis(100),
-            overall_timeout: std::time::Duration::from_millis(200),
-            max_bytes: 64,
-            mode: crate::model::ScanMode::Passive,
-            protocol: crate::model::Protocol::Http,
-            webdriver: false,
-            tech: false,
-            output: crate::model::OutputConfig {
-                format: crate::model::OutputFormat::Jsonl,
-            },
-        };
+        let cfg = file_config(vec![file.path().to_string_lossy().into()], Some(80));
 
-        let mut stream = stream_targets(&cfg).unwrap();
+        let mut stream = stream_targets(&cfg, &Deadline::default(), &Dedupe::default()).unwrap();
         let mut targets = Vec::new();
         while let Some(res) = stream.next().await {
             let target = res.expect("target resolution should succeed");
@@ -180,6 +628,84 @@ mod tests {
         assert!(!targets.is_empty());
     }
 
+    #[tokio::test]
+    async fn reads_every_input_file() {
+        use std::io::Write;
+        let mut first = NamedTempFile::new().unwrap();
+        writeln!(first, "127.0.0.1:80").unwrap();
+        let mut second = NamedTempFile::new().unwrap();
+        writeln!(second, "127.0.0.2:80\n127.0.0.3:80").unwrap();
+
+        let cfg = file_config(
+            vec![
+                first.path().to_string_lossy().into(),
+                second.path().to_string_lossy().into(),
+            ],
+            None,
+        );
+        let mut hosts: Vec<String> = stream_targets(&cfg, &Deadline::default(), &Dedupe::default())
+            .unwrap()
+            .map(|res| res.unwrap().original.host)
+            .collect()
+            .await;
+        hosts.sort();
+        assert_eq!(hosts, ["127.0.0.1", "127.0.0.2", "127.0.0.3"]);
+    }
+
+    #[tokio::test]
+    async fn skips_repeated_targets_across_files() {
+        use std::io::Write;
+        let mut first = NamedTempFile::new().unwrap();
+        writeln!(first, "127.0.0.1:80\n127.0.0.2:80").unwrap();
+        let mut second = NamedTempFile::new().unwrap();
+        writeln!(second, "127.0.0.1:80\nlocalhost:80").unwrap();
+
+        let mut cfg = file_config(
+            vec![
+                first.path().to_string_lossy().into(),
+                second.path().to_string_lossy().into(),
+            ],
+            None,
+        );
+        cfg.dedupe = true;
+        cfg.dedupe_resolved = true;
+        let dedupe = Dedupe::new(&cfg);
+        let targets: Vec<Target> = stream_targets(&cfg, &Deadline::default(), &dedupe)
+            .unwrap()
+            .map(|res| res.unwrap())
+            .collect()
+            .await;
+        let loopback: SocketAddr = "127.0.0.1:80".parse().unwrap();
+        assert_eq!(targets.iter().filter(|t| t.resolved == loopback).count(), 1);
+        assert!(targets.iter().any(|t| t.original.host == "127.0.0.2"));
+        // The repeated line, and localhost's 127.0.0.1 address.
+        assert!(dedupe.skipped() >= 2);
+    }
+
+    #[tokio::test]
+    async fn keeps_every_name_the_proxy_resolves() {
+        let mut cfg = file_config(Vec::new(), None);
+        cfg.dedupe_resolved = true;
+        let dedupe = Dedupe::new(&cfg);
+        let (tx, mut rx) = mpsc::channel(4);
+        for host in ["a.example", "b.example"] {
+            let spec = TargetSpec {
+                host: host.into(),
+                port: 80,
+            };
+            resolve_and_send(spec, &Resolver::Proxy, &dedupe, tx.clone())
+                .await
+                .unwrap();
+        }
+        drop(tx);
+        let mut hosts = Vec::new();
+        while let Some(target) = rx.recv().await {
+            hosts.push(target.unwrap().original.host);
+        }
+        assert_eq!(hosts, ["a.example", "b.example"]);
+        assert_eq!(dedupe.skipped(), 0);
+    }
+
     #[tokio::test]
     async fn bubbling_up_send_failures() {
         let spec = TargetSpec {
@@ -189,9 +715,28 @@ mod tests {
         let (tx, rx) = mpsc::channel(1);
         drop(rx);
 
-        let err = resolve_and_send(spec, tx).await.unwrap_err();
+        let err = resolve_and_send(spec, &Resolver::System, &Dedupe::default(), tx)
+            .await
+            .unwrap_err();
         assert!(err
             .to_string()
             .contains("failed to dispatch resolved target"));
     }
+
+    #[tokio::test]
+    async fn sends_resolution_failures_down_the_stream() {
+        let spec = TargetSpec {
+            host: "fe80::1%no-such-interface".to_string(),
+            port: 22,
+        };
+        let (tx, mut rx) = mpsc::channel(1);
+
+        resolve_and_send(spec, &Resolver::System, &Dedupe::default(), tx)
+            .await
+            .expect("a failed lookup is not an input error");
+        let err = rx.recv().await.unwrap().unwrap_err();
+        let failure = err.downcast_ref::<ResolveError>().unwrap();
+        assert_eq!(failure.spec.port, 22);
+        assert!(failure.to_string().contains("no-such-interface"));
+    }
 }
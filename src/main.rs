@@ -22,12 +22,46 @@ async fn main() -> anyhow::Result<()> {
         .with_level(true)
         .init();
 
-    let cli = Cli::parse();
+    // clap exits with 2 on usage errors, which --exit-code-on-open uses for
+    // open targets; report them as the fatal errors they are.
+    let cli = Cli::try_parse().unwrap_or_else(|err| {
+        let _ = err.print();
+        std::process::exit(if err.use_stderr() { 1 } else { 0 });
+    });
+    if cli.output_schema {
+        println!("{:#}", output::output_schema());
+        return Ok(());
+    }
     let cfg = cli.into_config()?;
 
+    let exit_code_on_open = cfg.exit_code_on_open;
     let sink = OutputChannel::new(cfg.output.clone())?;
-    let mut engine = Engine::new(cfg, sink)?;
+    if cfg.output.emit_config {
+        sink.emit_config(&cfg).await?;
+    }
+    let mut engine = Engine::new(cfg, sink.clone())?;
+    let interrupt = engine.interrupt_handle();
+    tokio::spawn({
+        let interrupt = interrupt.clone();
+        async move {
+            if tokio::signal::ctrl_c().await.is_err() {
+                return;
+            }
+            eprintln!("interrupted: finishing in-flight targets (press Ctrl-C again to quit now)");
+            interrupt.cancel();
+            if tokio::signal::ctrl_c().await.is_ok() {
+                std::process::exit(130);
+            }
+        }
+    });
     engine.run().await?;
 
+    // The engine has shut the output worker down, so everything is written.
+    if interrupt.is_cancelled() {
+        std::process::exit(130);
+    }
+    if exit_code_on_open && sink.saw_open() {
+        std::process::exit(2);
+    }
     Ok(())
 }
@@ -1,6 +1,7 @@
+use chrono::{DateTime, Utc};
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 use std::net::SocketAddr;
 use std::time::Duration;
@@ -20,18 +21,119 @@ pub struct Target {
 #[derive(Debug, Clone)]
 pub struct Config {
     pub target: Option<TargetSpec>,
-    pub input: Option<String>,
+    pub input: Vec<String>,
+    pub input_format: InputFormat,
     pub port_filter: Option<u16>,
     pub concurrency: usize,
     pub rate: u32,
+    /// `--jitter` as a fraction (0.0-1.0) of the interval between connections.
+    pub jitter: f64,
+    /// `--probe-timeout-jitter` as a fraction (0.0-0.9) by which each
+    /// target's connect and read timeouts may be stretched or shortened.
+    pub probe_timeout_jitter: f64,
+    /// `--ramp`: time over which concurrency and rate grow from 1 to their
+    /// configured values. `None` starts at full speed.
+    pub ramp: Option<Duration>,
     pub connect_timeout: Duration,
     pub read_timeout: Duration,
     pub overall_timeout: Duration,
+    pub deadline: Option<Duration>,
+    /// `--repeat`: rescan every target at this interval, start to start.
+    pub repeat: Option<Duration>,
+    /// `--count`: cycles to run with `--repeat`; 0 repeats until interrupted.
+    pub repeat_count: usize,
     pub max_bytes: usize,
+    /// `--max-bytes-for` overrides keyed by canonical protocol name.
+    pub max_bytes_for: BTreeMap<String, usize>,
+    /// `--max-read-rounds`: reads per banner before giving up on a peer
+    /// that trickles bytes.
+    pub max_read_rounds: usize,
+    /// `--udp-retries`: extra sends of a UDP probe that drew no reply.
+    pub udp_retries: usize,
+    /// `--active-ports` resolved for the scanned protocol: targets on other
+    /// ports are only read passively. `None` allows every port.
+    pub active_ports: Option<BTreeSet<u16>>,
     pub mode: ScanMode,
+    /// The protocol being scanned. With a `--protocol` list this is the
+    /// first entry, swapped for each attempt in turn.
     pub protocol: Protocol,
+    /// `--protocol a,b,c`: every protocol to try, in order, keeping the first
+    /// that returns a banner. A single protocol leaves the list at one entry.
+    pub protocols: Vec<Protocol>,
+    /// `--protocol-by-port`: the protocol scanned on each listed port in
+    /// place of `protocol`/`protocols`.
+    pub protocol_by_port: BTreeMap<u16, Protocol>,
     pub webdriver: bool,
     pub tech: bool,
+    pub tech_rules: Option<String>,
+    pub tls_resumption: bool,
+    /// `--tls-reneg`: test HTTPS servers for client-initiated renegotiation.
+    pub tls_reneg: bool,
+    /// `--tls-groups`: find the key exchange groups HTTPS servers accept.
+    pub tls_groups: bool,
+    /// `--cert-warn-days`: certificates expiring within this many days are
+    /// reported as `expiring`.
+    pub cert_warn_days: u32,
+    /// `--client-cert`/`--client-key`: identity offered to TLS servers that
+    /// ask for one.
+    pub client_cert: Option<ClientCert>,
+    /// `--record-probe`: keep the bytes sent to each target as the
+    /// `probe_sent_hex` field.
+    pub record_probe: bool,
+    /// `--ssh-auth-methods`: after the key exchange, ask SSH servers which
+    /// authentication methods they accept.
+    pub ssh_auth_methods: bool,
+    /// `--smtp-users`: names the SMTP client checks with VRFY/RCPT TO.
+    pub smtp_users: Vec<String>,
+    /// `--mssql-instance`: the one instance the SQL Server Browser is asked
+    /// about, instead of all of them.
+    pub mssql_instance: Option<String>,
+    /// `--send` payload for `udp-raw`, and the `--nudge` bytes.
+    pub send_payload: Option<Vec<u8>>,
+    /// `--nudge`: on an empty plain banner read, send `send_payload` (CRLF
+    /// by default) once and read again.
+    pub nudge: bool,
+    /// `--starttls`: after a plain banner guessed to be SMTP, IMAP, POP3 or
+    /// FTP, list its capabilities and upgrade to TLS when offered.
+    pub starttls: bool,
+    /// `--implicit-tls-ports`: ports whose services speak TLS from the first
+    /// byte. A client run on one of them talks over the TLS session.
+    pub implicit_tls_ports: Vec<u16>,
+    /// `--test-smuggling`: send the CL.TE desync probe to HTTP targets.
+    pub test_smuggling: bool,
+    /// `--test-open-proxy`: ask HTTP targets to relay a request to
+    /// example.com and to open a CONNECT tunnel.
+    pub test_open_proxy: bool,
+    /// `--http-paths`: paths fetched over one keep-alive connection to HTTP
+    /// targets, each reported with its status and length.
+    pub http_paths: Vec<String>,
+    /// `--strict-resolution`: abort the scan on the first hostname that
+    /// does not resolve instead of reporting it and moving on.
+    pub strict_resolution: bool,
+    /// `--dedupe`: queue each `host:port` from the input only once.
+    pub dedupe: bool,
+    /// `--dedupe-resolved`: scan each resolved address only once, however
+    /// many names point at it.
+    pub dedupe_resolved: bool,
+    /// `--host-discovery`: connect timeout of the quick pass that drops
+    /// targets on hosts that do not answer. `None` scans every target.
+    pub host_discovery: Option<Duration>,
+    pub fail_fast: bool,
+    /// `--exit-code-on-open`: exit with 2 when any target was open.
+    pub exit_code_on_open: bool,
+    pub max_body: Option<usize>,
+    /// `--http-accept-status`: HTTP status codes that count as a successful
+    /// grab. `None` accepts every response.
+    pub http_accept_status: Option<BTreeSet<u16>>,
+    /// `--peek-bytes`: stop the plain banner read after this many bytes.
+    pub peek_bytes: Option<usize>,
+    pub test_proxy_protocol: Option<ProxyProtocolVersion>,
+    pub resolvers: Vec<SocketAddr>,
+    pub proxy: Option<ProxyConfig>,
+    pub replay: Option<String>,
+    /// `--bench-targets`: scan this many synthetic targets on a loopback
+    /// mock server instead of the input.
+    pub bench_targets: Option<usize>,
     pub output: OutputConfig,
 }
 
@@ -44,12 +146,46 @@ pub enum ScanMode {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputConfig {
     pub format: OutputFormat,
+    pub reverse_dns: bool,
+    pub count_only: bool,
+    /// Charset label used to decode `printable`; `None` keeps the ASCII view.
+    pub banner_encoding: Option<String>,
+    /// Directory receiving one `<ip>_<port>.bin` file per open target.
+    pub raw_dir: Option<String>,
+    /// Directory receiving the PEM certificate of every completed TLS
+    /// handshake as `<ip>_<port>.pem`.
+    pub cert_dir: Option<String>,
+    /// Write the whole presented chain instead of just the leaf.
+    pub cert_chain: bool,
+    /// Consumer receiving the records instead of stdout (`--stream-to`).
+    pub stream_to: Option<StreamTarget>,
+    pub stream_policy: StreamPolicy,
+    /// `--tag key=value` labels copied into every record.
+    pub tags: BTreeMap<String, String>,
+    pub scan_id: Option<String>,
+    pub timestamp_format: TimestampFormat,
+    pub color: ColorChoice,
+    /// `--dedupe-http-bodies`: mark HTTP responses an earlier target on the
+    /// same IP already returned.
+    pub dedupe_http_bodies: bool,
+    /// `--group-by-host`: hold JSONL records until the scan ends and write
+    /// one object per input hostname.
+    pub group_by_host: bool,
+    /// `--hexdump`: bytes of raw banner dumped under each pretty record.
+    pub hexdump: Option<usize>,
+    /// `--emit-config`: write a `scan_config` header record first.
+    pub emit_config: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, ValueEnum)]
 #[serde(rename_all = "lowercase")]
 pub enum Protocol {
+    Bitcoin,
+    Coap,
+    #[value(alias = "eth")]
+    Ethereum,
     Ftp,
+    Grpc,
     #[value(alias = "zeus-admin")]
     #[value(alias = "http-alt")]
     #[value(alias = "nessus")]
@@ -65,30 +201,173 @@ pub enum Protocol {
     #[value(alias = "appserv-http")]
     Https,
     Imap,
+    /// IMAP inside TLS from the first byte (993).
+    Imaps,
     #[value(alias = "imq")]
     #[value(alias = "imqbrokerd")]
     Imqbroker,
+    Kafka,
+    Llmnr,
+    Mdns,
     Memcached,
+    /// `stats` over UDP 11211, to find amplification reflectors.
+    MemcachedUdp,
     Mongodb,
     Mqtt,
     #[value(alias = "ms-sql-s")]
     Mssql,
+    #[value(alias = "ms-sql-m")]
+    MssqlBrowser,
     Mysql,
     Pop3,
+    /// POP3 inside TLS from the first byte (995).
+    Pop3s,
     Postgres,
     Redis,
     Rpcbind,
     Smb,
     #[value(alias = "submission")]
-    #[value(alias = "smtps")]
     Smtp,
+    /// SMTP inside TLS from the first byte (465).
+    #[value(alias = "submissions")]
+    Smtps,
     Ssh,
+    /// Connect scan: reports whether the port accepts connections and reads
+    /// nothing.
+    Tcp,
     Telnet,
     Tls,
     Vnc,
     Ntp,
+    /// Sends `--send` over UDP and keeps the first reply datagram.
+    UdpRaw,
     #[value(alias = "ssdp")]
     Upnp,
+    #[value(alias = "wsman")]
+    Winrm,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ValueEnum, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyProtocolVersion {
+    V1,
+    V2,
+}
+
+/// Client certificate, any chain behind it, and private key, parsed once
+/// when the flags are read.
+#[derive(Debug, Clone)]
+pub struct ClientCert {
+    pub leaf: openssl::x509::X509,
+    pub chain: Vec<openssl::x509::X509>,
+    pub key: openssl::pkey::PKey<openssl::pkey::Private>,
+}
+
+/// Outbound proxy every TCP connection is tunnelled through (`--proxy`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    pub kind: ProxyKind,
+    /// `host:port` of the proxy itself.
+    pub addr: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyKind {
+    Socks4,
+    /// SOCKS4 with hostnames resolved by the proxy.
+    Socks4a,
+    Socks5,
+    /// SOCKS5 with hostnames resolved by the proxy.
+    Socks5h,
+    /// HTTP forward proxy tunnelling with `CONNECT`; it resolves hostnames.
+    Http,
+}
+
+impl ProxyKind {
+    /// Whether target hostnames are handed to the proxy instead of being
+    /// resolved locally.
+    pub fn remote_dns(self) -> bool {
+        matches!(
+            self,
+            ProxyKind::Socks4a | ProxyKind::Socks5h | ProxyKind::Http
+        )
+    }
+}
+
+/// Where `--stream-to` sends output records.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum StreamTarget {
+    /// `host:port` of a TCP listener.
+    Tcp(String),
+    /// Path of a Unix domain socket.
+    Unix(String),
+}
+
+impl fmt::Display for StreamTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamTarget::Tcp(addr) => write!(f, "tcp://{addr}"),
+            StreamTarget::Unix(path) => write!(f, "{path}"),
+        }
+    }
+}
+
+/// What to do with records the `--stream-to` consumer is not keeping up with.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, ValueEnum, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamPolicy {
+    /// Wait for the consumer, slowing the scan down to its pace
+    #[default]
+    Block,
+    /// Discard records while the consumer's queue is full
+    Drop,
+}
+
+/// When `--color` paints pretty output.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, ValueEnum, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Only when stdout is a terminal
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// How `--timestamp-format` writes the per-record `timestamp`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, ValueEnum, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimestampFormat {
+    /// RFC 3339 string in UTC, e.g. `2024-05-01T12:00:00Z`
+    #[default]
+    Rfc3339,
+    /// Milliseconds since the Unix epoch, as a number
+    EpochMs,
+    /// Seconds since the Unix epoch, as a number
+    EpochS,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, ValueEnum, Eq, PartialEq)]
+pub enum InputFormat {
+    /// One `host:port` per line
+    #[default]
+    Lines,
+    /// Open TCP ports from nmap `-oX` output
+    NmapXml,
+    /// Open TCP ports from masscan `-oJ` or `--output-format ndjson` output
+    MasscanJson,
+}
+
+impl fmt::Display for InputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputFormat::Lines => write!(f, "lines"),
+            InputFormat::NmapXml => write!(f, "nmap-xml"),
+            InputFormat::MasscanJson => write!(f, "masscan-json"),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, ValueEnum)]
@@ -106,31 +385,54 @@ impl fmt::Display for OutputFormat {
     }
 }
 
+impl Protocol {
+    /// Whether the protocol is spoken inside TLS from the first byte, like
+    /// any client protocol on an `--implicit-tls-ports` port.
+    pub fn implicit_tls(&self) -> bool {
+        matches!(self, Protocol::Imaps | Protocol::Pop3s | Protocol::Smtps)
+    }
+}
+
 impl fmt::Display for Protocol {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let label = match self {
+            Protocol::Bitcoin => "bitcoin",
+            Protocol::Coap => "coap",
+            Protocol::Ethereum => "ethereum",
             Protocol::Ftp => "ftp",
+            Protocol::Grpc => "grpc",
             Protocol::Http => "http",
             Protocol::Https => "https",
             Protocol::Imap => "imap",
+            Protocol::Imaps => "imaps",
             Protocol::Imqbroker => "imqbroker",
+            Protocol::Kafka => "kafka",
+            Protocol::Llmnr => "llmnr",
+            Protocol::Mdns => "mdns",
             Protocol::Memcached => "memcached",
+            Protocol::MemcachedUdp => "memcached-udp",
             Protocol::Mongodb => "mongodb",
             Protocol::Mqtt => "mqtt",
             Protocol::Mssql => "mssql",
+            Protocol::MssqlBrowser => "mssql-browser",
             Protocol::Mysql => "mysql",
             Protocol::Pop3 => "pop3",
+            Protocol::Pop3s => "pop3s",
             Protocol::Postgres => "postgres",
             Protocol::Redis => "redis",
             Protocol::Rpcbind => "rpcbind",
             Protocol::Smb => "smb",
             Protocol::Smtp => "smtp",
+            Protocol::Smtps => "smtps",
             Protocol::Ssh => "ssh",
             Protocol::Telnet => "telnet",
+            Protocol::Tcp => "tcp",
             Protocol::Tls => "tls",
             Protocol::Vnc => "vnc",
             Protocol::Ntp => "ntp",
+            Protocol::UdpRaw => "udp-raw",
             Protocol::Upnp => "upnp",
+            Protocol::Winrm => "winrm",
         };
         write!(f, "{}", label)
     }
@@ -142,7 +444,8 @@ pub struct ScanOutcome {
     pub status: Status,
     pub tcp: TcpMeta,
     pub banner: Banner,
-    pub timestamp: String,
+    /// When the scan of this target started.
+    pub timestamp: DateTime<Utc>,
     pub ttl: Option<u8>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub webdriver: Option<String>,
@@ -153,13 +456,36 @@ pub struct ScanOutcome {
     pub fingerprint: Fingerprint,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub diagnostics: Option<Diagnostics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_protocol_accepted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ptr: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TargetView {
     pub host: String,
+    /// Empty when a remote-DNS proxy resolved the host or it did not
+    /// resolve; see [`Self::ip`].
     pub addr: String,
     pub port: u16,
+    /// Family of `addr`; `None` when the host never resolved here.
+    pub family: Option<AddressFamily>,
+}
+
+impl TargetView {
+    /// The scanned address, or `None` when a remote-DNS proxy resolved the
+    /// host or it did not resolve, so the scanner never learned it.
+    pub fn ip(&self) -> Option<&str> {
+        Some(self.addr.as_str()).filter(|addr| !addr.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressFamily {
+    V4,
+    V6,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -184,14 +510,123 @@ pub struct TlsInfo {
     pub cert_valid_to: String,
     pub cipher: String,
     pub version: String,
+    /// Set by `--tls-resumption`: a second handshake offering the first
+    /// session was abbreviated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_resumption_supported: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_tickets_issued: Option<bool>,
+    /// HTTPS only: the default handshake failed and the probe got through by
+    /// retrying with TLS 1.0+ and legacy ciphers allowed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required_legacy_tls: Option<bool>,
+    /// Whole days from the scan to `cert_valid_to`, negative once expired.
+    /// `None` without a certificate or when its date could not be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days_until_expiry: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expired: Option<bool>,
+    /// Issuer and subject name the same entity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_signed: Option<bool>,
+    /// `valid`, `expiring` (within `--cert-warn-days`), `expired`, or
+    /// `unknown` for an unreadable date; empty without a certificate.
+    pub cert_expiry: String,
+    /// HTTPS with `--client-cert`: the same request failed without the
+    /// certificate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_cert_required: Option<bool>,
+    /// The server supports RFC 5746 secure renegotiation; `None` over TLS
+    /// 1.3. With `--tls-reneg`, taken from the TLS 1.2 test connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secure_renegotiation: Option<bool>,
+    /// `--tls-reneg`: a client-initiated renegotiation completed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_renegotiation_allowed: Option<bool>,
+    /// `--tls-groups`: the key exchange groups (IANA names) the server
+    /// completed a handshake with when offered alone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supported_groups: Option<Vec<String>>,
+    /// PEM certificates kept for `--dump-certs`; never part of the output.
+    #[serde(skip)]
+    pub cert_pem: Vec<u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Diagnostics {
     pub stage: String,
+    pub error_kind: ErrorKind,
+    pub severity: Severity,
     pub message: String,
 }
 
+impl Diagnostics {
+    /// Diagnostics with the usual severity for `error_kind`.
+    pub fn new(
+        stage: impl Into<String>,
+        error_kind: ErrorKind,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            stage: stage.into(),
+            error_kind,
+            severity: error_kind.severity(),
+            message: message.into(),
+        }
+    }
+}
+
+/// How much attention a failed target deserves, for triaging large error
+/// sets: `Info` is ordinary scan noise, `Warning` an unexpected answer from
+/// the target, `Error` a problem on the scanner's side.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    #[default]
+    Error,
+}
+
+/// Machine-readable failure category, classified where the failure happens so
+/// consumers can aggregate errors without matching on `message`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum ErrorKind {
+    ConnectTimeout,
+    ConnectionRefused,
+    ConnectionReset,
+    HostUnreachable,
+    TlsHandshakeFailed,
+    ReadTimeout,
+    OverallTimeout,
+    /// The proxy itself failed (unreachable, refused the handshake or the
+    /// credentials), as opposed to the target behind it.
+    ProxyFailed,
+    /// The target's hostname did not resolve.
+    ResolutionFailed,
+    ProtocolError,
+    #[default]
+    Other,
+}
+
+impl ErrorKind {
+    pub fn severity(self) -> Severity {
+        match self {
+            // Closed, filtered and silent ports are what most of a scan finds.
+            ErrorKind::ConnectTimeout
+            | ErrorKind::ConnectionRefused
+            | ErrorKind::HostUnreachable
+            | ErrorKind::ReadTimeout => Severity::Info,
+            ErrorKind::ConnectionReset
+            | ErrorKind::TlsHandshakeFailed
+            | ErrorKind::OverallTimeout
+            | ErrorKind::ResolutionFailed
+            | ErrorKind::ProtocolError => Severity::Warning,
+            // A broken proxy fails every target; unclassified errors are ours.
+            ErrorKind::ProxyFailed | ErrorKind::Other => Severity::Error,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TechnologyScan {
     pub scan_time_seconds: f64,
@@ -223,6 +658,18 @@ impl Fingerprint {
     }
 }
 
+impl ScanOutcome {
+    /// Open, and not an HTTP answer `--http-accept-status` turned down.
+    pub fn is_accepted_open(&self) -> bool {
+        matches!(self.status, Status::Open)
+            && self
+                .fingerprint
+                .fields
+                .get("http_status_accepted")
+                .is_none_or(|accepted| accepted == "true")
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Status {
     Open,
@@ -237,15 +684,39 @@ pub enum ReadStopReason {
     ConnectionClosed,
     Delimiter,
     SizeLimit,
+    /// `--peek-bytes` were read; the rest of the banner was left unread.
+    PeekLimit,
     Timeout,
+    /// `--max-read-rounds` reads returned data without the banner ending.
+    RoundLimit,
 }
 
 impl Target {
+    /// True when a remote-DNS `--proxy` resolves the host name: `resolved`
+    /// then holds the unspecified address as a stand-in, not the address
+    /// that was scanned.
+    pub fn proxy_resolved(&self) -> bool {
+        self.resolved.ip().is_unspecified()
+            && self.original.host.parse::<std::net::IpAddr>().is_err()
+    }
+
     pub fn view(&self) -> TargetView {
+        if self.proxy_resolved() {
+            return TargetView {
+                host: self.original.host.clone(),
+                addr: String::new(),
+                port: self.resolved.port(),
+                family: None,
+            };
+        }
         TargetView {
             host: self.original.host.clone(),
             addr: self.resolved.ip().to_string(),
             port: self.resolved.port(),
+            family: Some(match self.resolved {
+                std::net::SocketAddr::V4(_) => AddressFamily::V4,
+                std::net::SocketAddr::V6(_) => AddressFamily::V6,
+            }),
         }
     }
 }
@@ -265,5 +736,31 @@ mod tests {
         };
         let view = target.view();
         assert_eq!(view.addr, "127.0.0.1");
+        assert_eq!(view.family, Some(AddressFamily::V4));
+
+        let proxied = Target {
+            resolved: "0.0.0.0:80".parse().unwrap(),
+            ..target
+        };
+        let view = proxied.view();
+        assert_eq!(view.ip(), None);
+        assert_eq!(view.family, None);
+    }
+
+    #[test]
+    fn diagnostics_take_the_severity_of_their_kind() {
+        let refused = Diagnostics::new("connect", ErrorKind::ConnectionRefused, "refused");
+        assert_eq!(refused.severity, Severity::Info);
+        let tls = Diagnostics::new("probe", ErrorKind::TlsHandshakeFailed, "alert");
+        assert_eq!(tls.severity, Severity::Warning);
+        assert_eq!(ErrorKind::ProxyFailed.severity(), Severity::Error);
+    }
+
+    #[test]
+    fn mail_tls_variants_are_implicit_tls() {
+        let smtps = Protocol::from_str("smtps", true).unwrap();
+        assert_eq!(smtps.to_string(), "smtps");
+        assert!(smtps.implicit_tls());
+        assert!(!Protocol::Imap.implicit_tls());
     }
 }
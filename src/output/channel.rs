@@ -1,10 +1,12 @@
 use crate::model::{
-    Diagnostics, Fingerprint, OutputConfig, Protocol, ScanOutcome, Status, Target, TcpMeta,
+    Config, Diagnostics, ErrorKind, Fingerprint, OutputConfig, Protocol, ScanOutcome, Status,
+    TargetView, TcpMeta,
 };
-use crate::util::now_iso8601;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::mpsc;
 
-use super::sink::OutputSink;
+use super::rdns::PtrCache;
+use super::sink::{scan_config_record, OutputSink};
 
 #[derive(Clone)]
 pub struct OutputChannel {
@@ -14,24 +16,41 @@ pub struct OutputChannel {
 struct OutputInner {
     tx: tokio::sync::Mutex<Option<mpsc::Sender<OutputCommand>>>,
     handle: tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    ptr_cache: Option<PtrCache>,
+    /// Set once any open outcome has gone through, for `--exit-code-on-open`.
+    saw_open: AtomicBool,
 }
 
 enum OutputCommand {
-    Emit(ScanOutcome),
+    Emit(Box<ScanOutcome>),
+    ScanConfig(serde_json::Value),
 }
 
 impl OutputChannel {
     pub fn new(cfg: OutputConfig) -> anyhow::Result<Self> {
+        if let Some(dir) = &cfg.raw_dir {
+            std::fs::create_dir_all(dir)
+                .map_err(|err| anyhow::anyhow!("failed to create raw dir {dir}: {err}"))?;
+        }
+        if let Some(dir) = &cfg.cert_dir {
+            std::fs::create_dir_all(dir)
+                .map_err(|err| anyhow::anyhow!("failed to create cert dir {dir}: {err}"))?;
+        }
+        let ptr_cache = cfg.reverse_dns.then(PtrCache::default);
+        let mut sink = OutputSink::new(cfg)?;
         let (tx, mut rx) = mpsc::channel(1024);
         let handle = tokio::task::spawn_blocking(move || {
-            let mut sink = OutputSink::new(cfg);
             while let Some(cmd) = rx.blocking_recv() {
                 if let Err(err) = match cmd {
-                    OutputCommand::Emit(outcome) => sink.write_outcome(outcome),
+                    OutputCommand::Emit(outcome) => sink.write_outcome(*outcome),
+                    OutputCommand::ScanConfig(record) => sink.write_scan_config(&record),
                 } {
                     eprintln!("failed to write scan outcome: {err}");
                 }
             }
+            if let Err(err) = sink.finish() {
+                eprintln!("failed to write scan tally: {err}");
+            }
             sink.flush();
         });
 
@@ -39,14 +58,24 @@ impl OutputChannel {
             inner: std::sync::Arc::new(OutputInner {
                 tx: tokio::sync::Mutex::new(Some(tx)),
                 handle: tokio::sync::Mutex::new(Some(handle)),
+                ptr_cache,
+                saw_open: AtomicBool::new(false),
             }),
         })
     }
 
-    pub async fn emit(&self, outcome: ScanOutcome) -> anyhow::Result<()> {
+    pub async fn emit(&self, mut outcome: ScanOutcome) -> anyhow::Result<()> {
+        if outcome.is_accepted_open() {
+            self.inner.saw_open.store(true, Ordering::Relaxed);
+        }
+        if let Some(cache) = &self.inner.ptr_cache {
+            if let Some(Ok(ip)) = outcome.target.ip().map(str::parse) {
+                outcome.ptr = cache.lookup(ip).await;
+            }
+        }
         let guard = self.inner.tx.lock().await;
         if let Some(tx) = guard.as_ref() {
-            tx.send(OutputCommand::Emit(outcome))
+            tx.send(OutputCommand::Emit(Box::new(outcome)))
                 .await
                 .map_err(|err| anyhow::anyhow!("output worker not available: {err}"))?
         } else {
@@ -55,35 +84,52 @@ impl OutputChannel {
         Ok(())
     }
 
+    /// `--emit-config`: queues the `scan_config` header record; call it
+    /// before the scan starts so it precedes every outcome.
+    pub async fn emit_config(&self, cfg: &Config) -> anyhow::Result<()> {
+        let guard = self.inner.tx.lock().await;
+        let Some(tx) = guard.as_ref() else {
+            anyhow::bail!("output worker not available; dropping scan config");
+        };
+        tx.send(OutputCommand::ScanConfig(scan_config_record(cfg)))
+            .await
+            .map_err(|err| anyhow::anyhow!("output worker not available: {err}"))
+    }
+
     pub async fn emit_error(
         &self,
-        target: Target,
+        target: TargetView,
         protocol: &Protocol,
+        stage: &str,
+        error_kind: ErrorKind,
         error: String,
     ) -> anyhow::Result<()> {
-        let view = target.view();
         let outcome = ScanOutcome {
-            target: view,
+            target,
             status: Status::Error,
             tcp: TcpMeta {
                 connect_ms: None,
                 error: Some(error.clone()),
             },
             banner: Default::default(),
-            timestamp: now_iso8601(),
+            timestamp: chrono::Utc::now(),
             ttl: None,
             webdriver: None,
             technologies: None,
             tls_info: None,
             fingerprint: Fingerprint::from_protocol(protocol),
-            diagnostics: Some(Diagnostics {
-                stage: "pipeline".into(),
-                message: error,
-            }),
+            diagnostics: Some(Diagnostics::new(stage, error_kind, error)),
+            proxy_protocol_accepted: None,
+            ptr: None,
         };
         self.emit(outcome).await
     }
 
+    /// Whether any emitted outcome was open.
+    pub fn saw_open(&self) -> bool {
+        self.inner.saw_open.load(Ordering::Relaxed)
+    }
+
     pub async fn shutdown(&self) -> anyhow::Result<()> {
         self.inner.tx.lock().await.take();
 
@@ -1,4 +1,8 @@
 mod channel;
+mod rdns;
 mod sink;
+mod stream;
+mod tally;
 
 pub use channel::OutputChannel;
+pub use sink::output_schema;
@@ -0,0 +1,31 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Reverse-DNS names shared by every outcome of a scan. Each address is looked
+/// up at most once: ports on the same host, and concurrent requests for it,
+/// wait on the first lookup instead of issuing their own.
+#[derive(Default)]
+pub(super) struct PtrCache {
+    entries: tokio::sync::Mutex<HashMap<IpAddr, Arc<OnceCell<Option<String>>>>>,
+}
+
+impl PtrCache {
+    pub(super) async fn lookup(&self, ip: IpAddr) -> Option<String> {
+        let cell = self.entries.lock().await.entry(ip).or_default().clone();
+        cell.get_or_init(|| resolve(ip)).await.clone()
+    }
+}
+
+async fn resolve(ip: IpAddr) -> Option<String> {
+    let lookup = tokio::task::spawn_blocking(move || dns_lookup::lookup_addr(&ip));
+    match tokio::time::timeout(LOOKUP_TIMEOUT, lookup).await {
+        // getnameinfo hands back the numeric address when there is no PTR record.
+        Ok(Ok(Ok(name))) if name != ip.to_string() => Some(name),
+        _ => None,
+    }
+}
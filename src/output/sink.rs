@@ -1,86 +1,423 @@
+mod ansi;
+mod bitcoin;
+mod coap;
 mod common;
+mod ethereum;
+mod ftp;
+mod grpc;
+mod hexdump;
 mod http;
 mod imap;
+mod kafka;
+mod llmnr;
+mod mdns;
+mod memcached_udp;
+mod mongodb;
 mod mssql;
+mod mssql_browser;
 mod mysql;
+mod pop3;
+mod postgres;
+mod redis;
 mod rpcbind;
+mod scan_config;
+mod schema;
+mod smtp;
 mod ssh;
+mod starttls;
+mod telnet;
+mod tls;
+mod vnc;
+mod winrm;
 
-use crate::model::{OutputConfig, OutputFormat, ScanOutcome, Status};
+use super::stream::StreamWriter;
+use super::tally::Tally;
+use crate::model::{
+    AddressFamily, ErrorKind, OutputConfig, OutputFormat, ReadStopReason, ScanOutcome, Severity,
+    Status, TimestampFormat,
+};
+use anyhow::Context;
+use chrono::{DateTime, SecondsFormat, Utc};
 use serde::Serialize;
 use serde_json::Value;
-use std::io::{BufWriter, Write};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::path::Path;
+
+pub(crate) use scan_config::scan_config_record;
+pub use schema::output_schema;
+
+/// Version of the JSONL record shape, written to every record. Adding a field
+/// bumps the minor version; removing or retyping one bumps the major.
+/// 2.0: `data` for ftp, redis and mongodb became objects instead of the
+/// raw banner string.
+/// 3.0: `ip` and `family` are left out for hosts a remote-DNS proxy
+/// resolved, instead of reporting `0.0.0.0`.
+/// 3.1: the `--count-only` tally carries `record_type` and `schema_version`.
+/// 3.2: the gRPC and STARTTLS `tls_info` objects carry every field the HTTP
+/// one does.
+pub const SCHEMA_VERSION: &str = "3.2.0";
 
 pub struct OutputSink {
     cfg: OutputConfig,
-    writer: BufWriter<std::io::Stdout>,
+    encoding: Option<&'static encoding_rs::Encoding>,
+    /// Stdout, or the `--stream-to` consumer.
+    writer: Box<dyn Write + Send>,
+    tally: Option<Tally>,
+    palette: ansi::Palette,
+    http_responses: Option<http::ResponseCache>,
+    /// `--group-by-host`: JSONL records held per input hostname until
+    /// `finish`.
+    hosts: Option<BTreeMap<String, Vec<Value>>>,
 }
 
 #[derive(Serialize)]
 struct StandardizedOutcome<'a> {
-    ip: &'a str,
-    timestamp: &'a str,
+    /// `outcome`, telling these apart from the `--emit-config` header.
+    record_type: &'static str,
+    schema_version: &'static str,
+    /// Left out when a remote-DNS proxy resolved the host.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ip: Option<&'a str>,
+    /// The name from the input when it was not an address, so each of a
+    /// dual-stack host's records says which of its addresses it used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    host: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    family: Option<AddressFamily>,
+    timestamp: Value,
     port: u16,
     proto: &'a str,
     ttl: Option<u8>,
+    #[serde(skip_serializing_if = "Value::is_null")]
     data: Value,
+    /// `--protocol tcp` only: the port state and handshake time instead of
+    /// a banner.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connect_ms: Option<u128>,
+    /// Left out of `--protocol tcp` records, which never read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    read_reason: Option<&'a ReadStopReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    had_data: Option<bool>,
+    /// `--peek-bytes` guess at the service behind the first bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peek_protocol: Option<&'a str>,
+    /// `--record-probe`: hex of every byte written to the target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    probe_sent_hex: Option<&'a str>,
+    /// `--udp-retries`: sends a UDP probe needed, when more than one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    udp_attempts: Option<u32>,
+    /// `--dedupe-http-bodies`: the target on the same IP that first
+    /// returned this response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicate_of: Option<String>,
+    /// `--protocol` list: the entry whose banner was kept.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matched_protocol: Option<&'a str>,
+    /// `--starttls`: capabilities and certificate of an upgraded session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    starttls: Option<Value>,
+    /// `--implicit-tls-ports`: certificate of the session a client ran in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    implicit_tls: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_kind: Option<ErrorKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<Severity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proxy_protocol_accepted: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ptr: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scan_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    tags: &'a BTreeMap<String, String>,
 }
 
 impl OutputSink {
-    pub fn new(cfg: OutputConfig) -> Self {
-        Self {
+    pub fn new(cfg: OutputConfig) -> anyhow::Result<Self> {
+        let color = ansi::enabled(
+            cfg.color,
+            cfg.stream_to.is_none() && std::io::stdout().is_terminal(),
+        );
+        let writer: Box<dyn Write + Send> = match &cfg.stream_to {
+            Some(target) => Box::new(StreamWriter::connect(target.clone(), cfg.stream_policy)?),
+            None => Box::new(BufWriter::new(std::io::stdout())),
+        };
+        Ok(Self {
+            tally: cfg.count_only.then(Tally::default),
+            encoding: cfg
+                .banner_encoding
+                .as_deref()
+                .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes())),
+            http_responses: cfg.dedupe_http_bodies.then(http::ResponseCache::default),
+            hosts: cfg.group_by_host.then(BTreeMap::new),
             cfg,
-            writer: BufWriter::new(std::io::stdout()),
-        }
+            writer,
+            palette: ansi::Palette::new(color),
+        })
     }
 
-    pub fn write_outcome(&mut self, outcome: ScanOutcome) -> anyhow::Result<()> {
+    pub fn write_outcome(&mut self, mut outcome: ScanOutcome) -> anyhow::Result<()> {
+        // Decoded once here; every formatter below reads the same bytes.
+        let raw_bytes =
+            common::decode_banner_raw_bytes(&outcome.banner.raw_hex).unwrap_or_default();
+        if let Some(dir) = &self.cfg.raw_dir {
+            write_raw_bytes(Path::new(dir), &outcome, &raw_bytes)?;
+        }
+        if let Some(dir) = &self.cfg.cert_dir {
+            write_cert_pem(Path::new(dir), &outcome)?;
+        }
+        if let Some(tally) = &mut self.tally {
+            tally.record(&outcome);
+            return Ok(());
+        }
+        if let Some(encoding) = self.encoding {
+            // raw_hex stays authoritative; only the human-readable view changes.
+            if !raw_bytes.is_empty() {
+                outcome.banner.printable = crate::util::decode_text(&raw_bytes, encoding);
+            }
+        }
+        let duplicate_of = match &mut self.http_responses {
+            Some(cache)
+                if matches!(outcome.status, Status::Open)
+                    && matches!(
+                        outcome.fingerprint.protocol.as_deref(),
+                        Some("http" | "https")
+                    ) =>
+            {
+                cache.duplicate_of(&outcome)
+            }
+            _ => None,
+        };
         match self.cfg.format {
             OutputFormat::Jsonl => {
                 let proto = outcome.fingerprint.protocol.as_deref().unwrap_or("unknown");
-                let data = if matches!(proto, "http" | "https") {
-                    http::http_data(&outcome, proto)
-                } else if proto == "imap" {
-                    imap::imap_data(&outcome)
-                } else if matches!(proto, "mssql" | "ms-sql-s") {
-                    mssql::mssql_data(&outcome)
-                } else if proto == "mysql" {
-                    mysql::mysql_data(&outcome)
-                } else if proto == "rpcbind" {
-                    rpcbind::rpcbind_data(&outcome)
-                } else if proto == "ssh" {
-                    ssh::ssh_data(&outcome)
-                } else {
-                    serde_json::json!(common::raw_banner_for_data(&outcome))
-                };
+                let data = protocol_data(proto, &outcome, &raw_bytes);
                 let formatted = StandardizedOutcome {
-                    ip: &outcome.target.addr,
-                    timestamp: &outcome.timestamp,
+                    record_type: "outcome",
+                    schema_version: SCHEMA_VERSION,
+                    ip: outcome.target.ip(),
+                    host: Some(outcome.target.host.as_str())
+                        .filter(|host| Some(*host) != outcome.target.ip()),
+                    family: outcome.target.family,
+                    timestamp: format_timestamp(outcome.timestamp, self.cfg.timestamp_format),
                     port: outcome.target.port,
                     proto,
                     ttl: outcome.ttl,
                     data,
+                    state: (proto == "tcp").then(|| super::tally::port_state(&outcome)),
+                    connect_ms: outcome.tcp.connect_ms.filter(|_| proto == "tcp"),
+                    read_reason: Some(&outcome.banner.read_reason).filter(|_| proto != "tcp"),
+                    had_data: Some(!outcome.banner.raw_hex.is_empty()).filter(|_| proto != "tcp"),
+                    peek_protocol: outcome
+                        .fingerprint
+                        .fields
+                        .get("peek_protocol")
+                        .map(String::as_str),
+                    probe_sent_hex: outcome
+                        .fingerprint
+                        .fields
+                        .get("probe_sent_hex")
+                        .map(String::as_str),
+                    udp_attempts: outcome
+                        .fingerprint
+                        .fields
+                        .get("udp_attempts")
+                        .and_then(|attempts| attempts.parse().ok()),
+                    duplicate_of: duplicate_of.clone(),
+                    matched_protocol: outcome
+                        .fingerprint
+                        .fields
+                        .get("matched_protocol")
+                        .map(String::as_str),
+                    starttls: starttls::starttls_data(&outcome),
+                    implicit_tls: starttls::implicit_tls_data(&outcome),
+                    error_kind: outcome.diagnostics.as_ref().map(|diag| diag.error_kind),
+                    severity: outcome.diagnostics.as_ref().map(|diag| diag.severity),
+                    proxy_protocol_accepted: outcome.proxy_protocol_accepted,
+                    ptr: outcome.ptr.as_deref(),
+                    scan_id: self.cfg.scan_id.as_deref(),
+                    tags: &self.cfg.tags,
                 };
-                let line = serde_json::to_string(&formatted)?;
-                writeln!(self.writer, "{line}")?;
+                if let Some(hosts) = &mut self.hosts {
+                    let mut record = serde_json::to_value(&formatted)?;
+                    // The group names the host once.
+                    if let Some(fields) = record.as_object_mut() {
+                        fields.remove("host");
+                    }
+                    hosts
+                        .entry(outcome.target.host.clone())
+                        .or_default()
+                        .push(record);
+                } else {
+                    let line = serde_json::to_string(&formatted)?;
+                    writeln!(self.writer, "{line}")?;
+                }
             }
             OutputFormat::Pretty => {
+                let host = match outcome.target.ip() {
+                    Some(addr) if addr != outcome.target.host => {
+                        format!("{} [{}]", outcome.target.host, addr)
+                    }
+                    _ => outcome.target.host.clone(),
+                };
+                let proto = outcome.fingerprint.protocol.as_deref().unwrap_or("unknown");
                 writeln!(
                     self.writer,
-                    "{} {} -> {}",
-                    outcome.target.host,
+                    "{} {} {} -> {}",
+                    host,
                     outcome.target.port,
-                    outcome.status_text()
+                    self.palette.label(proto),
+                    self.palette.status(&outcome.status, outcome.status_text())
                 )?;
-                writeln!(self.writer, "  banner: {}", outcome.banner.printable)?;
+                if outcome.fingerprint.protocol.as_deref() == Some("tcp") {
+                    if let Some(connect_ms) = outcome.tcp.connect_ms {
+                        writeln!(self.writer, "  connect_ms: {connect_ms}")?;
+                    }
+                } else {
+                    writeln!(self.writer, "  banner: {}", outcome.banner.printable)?;
+                    writeln!(
+                        self.writer,
+                        "  read_reason: {:?} had_data: {}",
+                        outcome.banner.read_reason,
+                        !outcome.banner.raw_hex.is_empty()
+                    )?;
+                    if let Some(limit) = self.cfg.hexdump {
+                        write!(self.writer, "{}", hexdump::hexdump(&raw_bytes, limit, "  "))?;
+                    }
+                    if let Some(guess) = outcome.fingerprint.fields.get("peek_protocol") {
+                        writeln!(self.writer, "  peek_protocol: {guess}")?;
+                    }
+                    if let Some(sent) = outcome.fingerprint.fields.get("probe_sent_hex") {
+                        writeln!(self.writer, "  probe_sent_hex: {sent}")?;
+                    }
+                    if let Some(first) = &duplicate_of {
+                        writeln!(self.writer, "  duplicate_of: {first}")?;
+                    }
+                    if let Some(attempts) = outcome.fingerprint.fields.get("udp_attempts") {
+                        writeln!(self.writer, "  udp_attempts: {attempts}")?;
+                    }
+                    if let Some(protocol) = outcome.fingerprint.fields.get("starttls_protocol") {
+                        let tls = match (
+                            &outcome.tls_info,
+                            outcome.fingerprint.fields.get("starttls_error"),
+                        ) {
+                            (Some(tls_info), _) => format!(
+                                "upgraded, {} {} subject: {}",
+                                tls_info.version, tls_info.cipher, tls_info.cert_subject
+                            ),
+                            (None, Some(err)) => format!("failed: {err}"),
+                            (None, None) => "not offered".into(),
+                        };
+                        writeln!(self.writer, "  starttls ({protocol}): {tls}")?;
+                    }
+                    if let Some(tls_info) = outcome.tls_info.as_ref().filter(|_| {
+                        outcome
+                            .fingerprint
+                            .fields
+                            .get("implicit_tls")
+                            .map(String::as_str)
+                            == Some("true")
+                    }) {
+                        writeln!(
+                            self.writer,
+                            "  implicit_tls: {} {} subject: {}",
+                            tls_info.version, tls_info.cipher, tls_info.cert_subject
+                        )?;
+                    }
+                    if outcome
+                        .fingerprint
+                        .fields
+                        .get("http_status_accepted")
+                        .is_some_and(|accepted| accepted == "false")
+                    {
+                        writeln!(self.writer, "  status not in --http-accept-status")?;
+                    }
+                }
+                if outcome.fingerprint.protocol.as_deref() == Some("ftp")
+                    && outcome
+                        .fingerprint
+                        .fields
+                        .get("anonymous_login")
+                        .map(String::as_str)
+                        == Some("true")
+                {
+                    writeln!(self.writer, "  FINDING: FTP allows anonymous login")?;
+                }
+                if outcome.fingerprint.protocol.as_deref() == Some("vnc") && vnc::no_auth(&outcome)
+                {
+                    writeln!(self.writer, "  FINDING: VNC open with no authentication")?;
+                }
+                if matches!(
+                    outcome.fingerprint.protocol.as_deref(),
+                    Some("http" | "https")
+                ) {
+                    for finding in http::cookie_findings(&outcome) {
+                        writeln!(self.writer, "  FINDING: {finding}")?;
+                    }
+                    if let Some(finding) = http::open_proxy_finding(&outcome) {
+                        writeln!(self.writer, "  FINDING: {finding}")?;
+                    }
+                }
+                if outcome.fingerprint.protocol.as_deref() == Some("memcached-udp") {
+                    if let Some(finding) = memcached_udp::amplification_finding(&outcome) {
+                        writeln!(self.writer, "  FINDING: {finding}")?;
+                    }
+                }
+                if outcome.fingerprint.protocol.as_deref() == Some("redis") {
+                    if let Some(finding) = redis::exposure_finding(&outcome) {
+                        writeln!(self.writer, "  FINDING: {finding}")?;
+                    }
+                }
+                if let Some(finding) = common::cert_expiry_finding(&outcome) {
+                    writeln!(self.writer, "  FINDING: {finding}")?;
+                }
+                if let Some(finding) = common::renegotiation_finding(&outcome) {
+                    writeln!(self.writer, "  FINDING: {finding}")?;
+                }
+                if let Some(finding) = common::weak_groups_finding(&outcome) {
+                    writeln!(self.writer, "  FINDING: {finding}")?;
+                }
+                if outcome.fingerprint.protocol.as_deref() == Some("ssh") {
+                    for finding in ssh::auth_findings(&outcome) {
+                        writeln!(self.writer, "  FINDING: {finding}")?;
+                    }
+                }
+                if let Some(ptr) = &outcome.ptr {
+                    writeln!(self.writer, "  ptr: {ptr}")?;
+                }
+                if self.cfg.scan_id.is_some() || !self.cfg.tags.is_empty() {
+                    let labels: Vec<String> = self
+                        .cfg
+                        .scan_id
+                        .iter()
+                        .map(|id| format!("scan_id={id}"))
+                        .chain(
+                            self.cfg
+                                .tags
+                                .iter()
+                                .map(|(key, value)| format!("{key}={value}")),
+                        )
+                        .collect();
+                    writeln!(self.writer, "  tags: {}", labels.join(" "))?;
+                }
                 if let Some(webdriver) = &outcome.webdriver {
                     writeln!(self.writer, "  webdriver: {}", webdriver)?;
                 }
+                if let Some(accepted) = outcome.proxy_protocol_accepted {
+                    writeln!(self.writer, "  proxy_protocol_accepted: {accepted}")?;
+                }
                 if let Some(diag) = &outcome.diagnostics {
                     writeln!(
                         self.writer,
-                        "  diagnostics: [{}] {}",
-                        diag.stage, diag.message
+                        "  diagnostics: {:?} [{}] {:?}: {}",
+                        diag.severity, diag.stage, diag.error_kind, diag.message
                     )?;
                 }
             }
@@ -88,11 +425,188 @@ impl OutputSink {
         Ok(())
     }
 
+    /// Writes the `--emit-config` header ahead of every outcome.
+    pub fn write_scan_config(&mut self, record: &Value) -> anyhow::Result<()> {
+        writeln!(self.writer, "{record}")?;
+        Ok(())
+    }
+
+    /// Writes the `--group-by-host` groups and the `--count-only` tally, if
+    /// either was collected.
+    pub fn finish(&mut self) -> anyhow::Result<()> {
+        for (host, addresses) in self.hosts.take().unwrap_or_default() {
+            let line = serde_json::json!({
+                "record_type": "host",
+                "schema_version": SCHEMA_VERSION,
+                "host": host,
+                "addresses": addresses,
+            });
+            writeln!(self.writer, "{line}")?;
+        }
+        let Some(tally) = &self.tally else {
+            return Ok(());
+        };
+        match self.cfg.format {
+            OutputFormat::Jsonl => {
+                let line = serde_json::json!({
+                    "record_type": "tally",
+                    "schema_version": SCHEMA_VERSION,
+                    "ports": tally.rows().collect::<Vec<_>>(),
+                    "total": tally.total(),
+                });
+                writeln!(self.writer, "{line}")?;
+            }
+            OutputFormat::Pretty => {
+                for row in tally.rows() {
+                    writeln!(
+                        self.writer,
+                        "{} {} -> open {} closed {} filtered {} error {}",
+                        row.proto,
+                        row.port,
+                        row.counts.open,
+                        row.counts.closed,
+                        row.counts.filtered,
+                        row.counts.error
+                    )?;
+                }
+                let total = tally.total();
+                writeln!(
+                    self.writer,
+                    "total -> open {} closed {} filtered {} error {}",
+                    total.open, total.closed, total.filtered, total.error
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn flush(&mut self) {
         let _ = self.writer.flush();
     }
 }
 
+/// The protocol-specific `data` object of a JSONL record.
+fn protocol_data(proto: &str, outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    if proto == "bitcoin" {
+        bitcoin::bitcoin_data(raw_bytes)
+    } else if proto == "coap" {
+        coap::coap_data(raw_bytes)
+    } else if proto == "ethereum" {
+        ethereum::eth_data(outcome, raw_bytes)
+    } else if proto == "ftp" {
+        ftp::ftp_data(outcome, raw_bytes)
+    } else if proto == "grpc" {
+        grpc::grpc_data(outcome, raw_bytes)
+    } else if matches!(proto, "http" | "https") {
+        http::http_data(outcome, proto)
+    } else if matches!(proto, "imap" | "imaps") {
+        imap::imap_data(outcome, raw_bytes)
+    } else if matches!(proto, "mssql" | "ms-sql-s") {
+        mssql::mssql_data(outcome, raw_bytes)
+    } else if proto == "mssql-browser" {
+        mssql_browser::mssql_browser_data(raw_bytes)
+    } else if proto == "kafka" {
+        kafka::kafka_data(outcome, raw_bytes)
+    } else if proto == "llmnr" {
+        llmnr::llmnr_data(raw_bytes)
+    } else if proto == "mdns" {
+        mdns::mdns_data(raw_bytes)
+    } else if proto == "memcached-udp" {
+        memcached_udp::memcached_udp_data(outcome, raw_bytes)
+    } else if proto == "mongodb" {
+        mongodb::mongodb_data(raw_bytes)
+    } else if proto == "mysql" {
+        mysql::mysql_data(outcome, raw_bytes)
+    } else if matches!(proto, "pop3" | "pop3s") {
+        pop3::pop3_data(outcome, raw_bytes)
+    } else if proto == "postgres" {
+        postgres::postgres_data(outcome, raw_bytes)
+    } else if proto == "redis" {
+        redis::redis_data(outcome, raw_bytes)
+    } else if proto == "rpcbind" {
+        rpcbind::rpcbind_data(outcome, raw_bytes)
+    } else if matches!(proto, "smtp" | "smtps") {
+        smtp::smtp_data(outcome, raw_bytes)
+    } else if proto == "ssh" {
+        ssh::ssh_data(outcome, raw_bytes)
+    } else if proto == "tcp" {
+        Value::Null
+    } else if proto == "telnet" {
+        telnet::telnet_data(raw_bytes)
+    } else if proto == "tls" {
+        tls::tls_data(outcome, raw_bytes)
+    } else if proto == "vnc" {
+        vnc::vnc_data(outcome)
+    } else if proto == "winrm" {
+        winrm::winrm_data(raw_bytes)
+    } else {
+        serde_json::json!(common::raw_banner_for_data(outcome, raw_bytes))
+    }
+}
+
+fn format_timestamp(timestamp: DateTime<Utc>, format: TimestampFormat) -> Value {
+    match format {
+        TimestampFormat::Rfc3339 => timestamp.to_rfc3339_opts(SecondsFormat::Secs, true).into(),
+        TimestampFormat::EpochMs => timestamp.timestamp_millis().into(),
+        TimestampFormat::EpochS => timestamp.timestamp().into(),
+    }
+}
+
+/// Saves the banner bytes of an open target as `<ip>_<port>.bin` under `dir`,
+/// adding `_1`, `_2`, ... instead of overwriting an earlier capture.
+fn write_raw_bytes(dir: &Path, outcome: &ScanOutcome, bytes: &[u8]) -> anyhow::Result<()> {
+    if !matches!(outcome.status, Status::Open) || bytes.is_empty() {
+        return Ok(());
+    }
+    write_capture(dir, outcome, "bin", bytes)
+}
+
+fn write_cert_pem(dir: &Path, outcome: &ScanOutcome) -> anyhow::Result<()> {
+    match &outcome.tls_info {
+        Some(tls_info) if !tls_info.cert_pem.is_empty() => {
+            write_capture(dir, outcome, "pem", &tls_info.cert_pem)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Writes `bytes` to `dir/<ip>_<port>.<ext>`, adding `_1`, `_2`, ... rather
+/// than overwriting an earlier capture of the same target. Hosts a proxy
+/// resolved are named after the host.
+fn write_capture(dir: &Path, outcome: &ScanOutcome, ext: &str, bytes: &[u8]) -> anyhow::Result<()> {
+    let name = outcome.target.ip().unwrap_or(&outcome.target.host);
+    let stem: String = format!("{}_{}", name, outcome.target.port)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    for counter in 0usize.. {
+        let name = if counter == 0 {
+            format!("{stem}.{ext}")
+        } else {
+            format!("{stem}_{counter}.{ext}")
+        };
+        let path = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                return file
+                    .write_all(bytes)
+                    .with_context(|| format!("failed to write {}", path.display()));
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to create {}", path.display()))
+            }
+        }
+    }
+    unreachable!("ran out of file name counters")
+}
+
 impl ScanOutcome {
     fn status_text(&self) -> &'static str {
         match self.status {
@@ -102,3 +616,68 @@ impl ScanOutcome {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Diagnostics, Fingerprint, Protocol, TargetView, TcpMeta};
+    use std::sync::{Arc, Mutex};
+
+    /// Collects what the sink writes so a test can read it back.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn unresolved_records_carry_the_host_but_no_ip() {
+        let captured = Captured::default();
+        let mut sink =
+            OutputSink::new(crate::clients::mock::config(Protocol::Http).output).unwrap();
+        sink.writer = Box::new(captured.clone());
+        sink.write_outcome(ScanOutcome {
+            target: TargetView {
+                host: "missing.example".into(),
+                addr: String::new(),
+                port: 80,
+                family: None,
+            },
+            status: Status::Error,
+            tcp: TcpMeta {
+                connect_ms: None,
+                error: Some("no such host".into()),
+            },
+            banner: Default::default(),
+            timestamp: Default::default(),
+            ttl: None,
+            webdriver: None,
+            technologies: None,
+            tls_info: None,
+            fingerprint: Fingerprint::from_protocol(&Protocol::Http),
+            diagnostics: Some(Diagnostics::new(
+                "resolve",
+                ErrorKind::ResolutionFailed,
+                "no such host",
+            )),
+            proxy_protocol_accepted: None,
+            ptr: None,
+        })
+        .unwrap();
+
+        let written = captured.0.lock().unwrap();
+        let record: Value = serde_json::from_slice(&written).unwrap();
+        assert_eq!(record["host"], "missing.example");
+        assert!(record.get("ip").is_none());
+        assert!(record.get("family").is_none());
+        assert_eq!(record["error_kind"], "ResolutionFailed");
+    }
+}
@@ -0,0 +1,81 @@
+//! ANSI colors for pretty output. With painting off every helper returns
+//! the text unchanged, so piped output carries no escape codes.
+
+use crate::model::{ColorChoice, Status};
+use std::borrow::Cow;
+
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const RED: &str = "31";
+const BOLD_CYAN: &str = "1;36";
+
+/// Whether `--color` paints: `auto` colors a terminal only, never a pipe or
+/// a `--stream-to` consumer (`to_terminal` is false for both).
+pub(super) fn enabled(choice: ColorChoice, to_terminal: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => to_terminal,
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct Palette {
+    enabled: bool,
+}
+
+impl Palette {
+    pub(super) fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    /// Open in green, timeout in yellow, error in red.
+    pub(super) fn status<'a>(&self, status: &Status, text: &'a str) -> Cow<'a, str> {
+        let code = match status {
+            Status::Open => GREEN,
+            Status::Timeout => YELLOW,
+            Status::Error => RED,
+        };
+        self.paint(code, text)
+    }
+
+    pub(super) fn label<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        self.paint(BOLD_CYAN, text)
+    }
+
+    fn paint<'a>(&self, code: &str, text: &'a str) -> Cow<'a, str> {
+        if self.enabled {
+            Cow::Owned(format!("\x1b[{code}m{text}\x1b[0m"))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pretty_line(palette: Palette) -> String {
+        format!(
+            "192.0.2.1 22 {} -> {}",
+            palette.label("ssh"),
+            palette.status(&Status::Open, "open")
+        )
+    }
+
+    #[test]
+    fn never_and_non_terminal_output_carry_no_escape_codes() {
+        for (choice, to_terminal) in [
+            (ColorChoice::Never, true),
+            (ColorChoice::Never, false),
+            (ColorChoice::Auto, false),
+        ] {
+            let line = pretty_line(Palette::new(enabled(choice, to_terminal)));
+            assert_eq!(line, "192.0.2.1 22 ssh -> open");
+        }
+        let colored = pretty_line(Palette::new(enabled(ColorChoice::Auto, true)));
+        assert!(colored.contains("\x1b[32mopen\x1b[0m"));
+        assert!(enabled(ColorChoice::Always, false));
+    }
+}
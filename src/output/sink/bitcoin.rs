@@ -0,0 +1,101 @@
+use serde_json::Value;
+
+pub(super) fn bitcoin_data(raw_bytes: &[u8]) -> Value {
+    let network = raw_bytes.get(..4).map(network_name).unwrap_or_default();
+    let command = raw_bytes
+        .get(4..16)
+        .map(|command| {
+            String::from_utf8_lossy(command)
+                .trim_end_matches('\0')
+                .to_string()
+        })
+        .unwrap_or_default();
+    let version = (command == "version")
+        .then(|| raw_bytes.get(24..).and_then(parse_version))
+        .flatten()
+        .unwrap_or_default();
+
+    serde_json::json!({
+        "network": network,
+        "command": command,
+        "protocol_version": version.protocol_version,
+        "services": version.services,
+        "service_flags": service_flags(version.services),
+        "user_agent": version.user_agent,
+        "start_height": version.start_height,
+        "relay": version.relay,
+    })
+}
+
+#[derive(Default)]
+struct VersionMessage {
+    protocol_version: Option<i32>,
+    services: u64,
+    user_agent: String,
+    start_height: Option<i32>,
+    relay: Option<bool>,
+}
+
+/// `version(4) services(8) timestamp(8) addr_recv(26) addr_from(26)
+/// nonce(8) user_agent(varstr) start_height(4) relay(1, optional)`.
+fn parse_version(payload: &[u8]) -> Option<VersionMessage> {
+    let fixed = payload.get(..80)?;
+    let mut message = VersionMessage {
+        protocol_version: Some(i32::from_le_bytes(fixed[..4].try_into().ok()?)),
+        services: u64::from_le_bytes(fixed[4..12].try_into().ok()?),
+        ..VersionMessage::default()
+    };
+    let (agent_len, rest) = read_var_int(&payload[80..])?;
+    let agent = rest.get(..usize::try_from(agent_len).ok()?)?;
+    message.user_agent = String::from_utf8_lossy(agent).into_owned();
+    let rest = &rest[agent.len()..];
+    message.start_height = rest
+        .get(..4)
+        .and_then(|height| height.try_into().ok())
+        .map(i32::from_le_bytes);
+    message.relay = rest.get(4).map(|relay| *relay != 0);
+    Some(message)
+}
+
+fn read_var_int(buf: &[u8]) -> Option<(u64, &[u8])> {
+    let (&first, rest) = buf.split_first()?;
+    let width = match first {
+        0xfd => 2,
+        0xfe => 4,
+        0xff => 8,
+        value => return Some((value as u64, rest)),
+    };
+    let bytes = rest.get(..width)?;
+    let value = bytes
+        .iter()
+        .rev()
+        .fold(0u64, |acc, byte| (acc << 8) | *byte as u64);
+    Some((value, &rest[width..]))
+}
+
+fn network_name(magic: &[u8]) -> &'static str {
+    match magic {
+        [0xf9, 0xbe, 0xb4, 0xd9] => "mainnet",
+        [0x0b, 0x11, 0x09, 0x07] => "testnet3",
+        [0x1c, 0x16, 0x3f, 0x28] => "testnet4",
+        [0x0a, 0x03, 0xcf, 0x40] => "signet",
+        [0xfa, 0xbf, 0xb5, 0xda] => "regtest",
+        _ => "unknown",
+    }
+}
+
+fn service_flags(services: u64) -> Vec<&'static str> {
+    const FLAGS: &[(u64, &str)] = &[
+        (1, "NODE_NETWORK"),
+        (1 << 2, "NODE_BLOOM"),
+        (1 << 3, "NODE_WITNESS"),
+        (1 << 6, "NODE_COMPACT_FILTERS"),
+        (1 << 10, "NODE_NETWORK_LIMITED"),
+        (1 << 11, "NODE_P2P_V2"),
+    ];
+    FLAGS
+        .iter()
+        .filter(|(bit, _)| services & bit != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
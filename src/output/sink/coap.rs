@@ -0,0 +1,175 @@
+use serde_json::Value;
+
+pub(super) fn coap_data(raw_bytes: &[u8]) -> Value {
+    let Some(message) = parse_message(raw_bytes) else {
+        return serde_json::json!({
+            "is_coap": false,
+            "resources": [],
+        });
+    };
+    let payload = String::from_utf8_lossy(message.payload);
+    let resources: Vec<Value> = if message.code == 0x45 {
+        parse_link_format(&payload)
+            .into_iter()
+            .map(|(path, attributes)| {
+                serde_json::json!({
+                    "path": path,
+                    "attributes": attributes,
+                })
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let kind = ["CON", "NON", "ACK", "RST"][message.kind as usize];
+    // Error responses usually carry a human-readable diagnostic payload.
+    let diagnostic = if message.code >> 5 >= 4 {
+        payload.trim().to_string()
+    } else {
+        String::new()
+    };
+
+    serde_json::json!({
+        "is_coap": true,
+        "type": kind,
+        "code": format!("{}.{:02}", message.code >> 5, message.code & 0x1f),
+        "code_name": code_name(message.code),
+        "success": message.code >> 5 == 2,
+        "content_format": message.content_format,
+        "resources": resources,
+        "diagnostic": diagnostic,
+    })
+}
+
+struct Message<'a> {
+    kind: u8,
+    code: u8,
+    content_format: Option<u32>,
+    payload: &'a [u8],
+}
+
+/// `ver(2) type(2) tkl(4) code(8) message_id(16) token options [0xff payload]`.
+fn parse_message(bytes: &[u8]) -> Option<Message<'_>> {
+    let header = bytes.get(..4)?;
+    if header[0] >> 6 != 1 {
+        return None;
+    }
+    let token_len = (header[0] & 0x0f) as usize;
+    if token_len > 8 {
+        return None;
+    }
+    let mut message = Message {
+        kind: (header[0] >> 4) & 0x03,
+        code: header[1],
+        content_format: None,
+        payload: &[],
+    };
+    let mut rest = bytes.get(4 + token_len..)?;
+    let mut number = 0u32;
+    while let Some((&first, tail)) = rest.split_first() {
+        if first == 0xff {
+            message.payload = tail;
+            break;
+        }
+        let (delta, tail) = option_nibble(first >> 4, tail)?;
+        let (len, tail) = option_nibble(first & 0x0f, tail)?;
+        let value = tail.get(..len as usize)?;
+        number += delta;
+        if number == 12 {
+            message.content_format = Some(
+                value
+                    .iter()
+                    .fold(0u32, |acc, byte| (acc << 8) | *byte as u32),
+            );
+        }
+        rest = &tail[value.len()..];
+    }
+    Some(message)
+}
+
+/// Option delta/length nibbles 13 and 14 take one or two extension bytes.
+fn option_nibble(nibble: u8, rest: &[u8]) -> Option<(u32, &[u8])> {
+    match nibble {
+        13 => Some((*rest.first()? as u32 + 13, &rest[1..])),
+        14 => {
+            let ext = rest.get(..2)?;
+            Some((
+                u16::from_be_bytes([ext[0], ext[1]]) as u32 + 269,
+                &rest[2..],
+            ))
+        }
+        15 => None,
+        value => Some((value as u32, rest)),
+    }
+}
+
+/// CoRE link format (RFC 6690): `</path>;rt="x";ct=40,</other>`. Quoted
+/// attribute values may themselves contain commas and semicolons.
+fn parse_link_format(payload: &str) -> Vec<(String, serde_json::Map<String, Value>)> {
+    split_unquoted(payload, ',')
+        .into_iter()
+        .filter_map(|link| {
+            let mut parts = split_unquoted(link, ';').into_iter();
+            let target = parts.next()?.trim();
+            let path = target.strip_prefix('<')?.strip_suffix('>')?.to_string();
+            let attributes = parts
+                .filter_map(|attr| {
+                    let attr = attr.trim();
+                    if attr.is_empty() {
+                        return None;
+                    }
+                    let (name, value) = attr.split_once('=').unwrap_or((attr, ""));
+                    Some((
+                        name.to_string(),
+                        Value::String(value.trim_matches('"').to_string()),
+                    ))
+                })
+                .collect();
+            Some((path, attributes))
+        })
+        .collect()
+}
+
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (idx, ch) in value.char_indices() {
+        if ch == '"' {
+            quoted = !quoted;
+        } else if ch == separator && !quoted {
+            parts.push(&value[start..idx]);
+            start = idx + 1;
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+fn code_name(code: u8) -> &'static str {
+    match code {
+        0x00 => "Empty",
+        0x41 => "Created",
+        0x42 => "Deleted",
+        0x43 => "Valid",
+        0x44 => "Changed",
+        0x45 => "Content",
+        0x80 => "Bad Request",
+        0x81 => "Unauthorized",
+        0x82 => "Bad Option",
+        0x83 => "Forbidden",
+        0x84 => "Not Found",
+        0x85 => "Method Not Allowed",
+        0x86 => "Not Acceptable",
+        0x8c => "Precondition Failed",
+        0x8d => "Request Entity Too Large",
+        0x8f => "Unsupported Content-Format",
+        0xa0 => "Internal Server Error",
+        0xa1 => "Not Implemented",
+        0xa2 => "Bad Gateway",
+        0xa3 => "Service Unavailable",
+        0xa4 => "Gateway Timeout",
+        0xa5 => "Proxying Not Supported",
+        _ => "Unknown",
+    }
+}
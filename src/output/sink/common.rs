@@ -1,17 +1,182 @@
-use crate::model::ScanOutcome;
+use crate::model::{ScanOutcome, TlsInfo};
+use serde_json::Value;
 
-pub(super) fn raw_banner_for_data(outcome: &ScanOutcome) -> String {
+pub(super) fn raw_banner_for_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> String {
     if !outcome.banner.printable.is_empty() {
         return outcome.banner.printable.clone();
     }
-    decode_banner_raw(&outcome.banner.raw_hex).unwrap_or_default()
+    String::from_utf8_lossy(raw_bytes).into_owned()
 }
 
-pub(super) fn decode_banner_raw(raw_hex: &str) -> Option<String> {
-    let bytes = decode_banner_raw_bytes(raw_hex)?;
-    Some(String::from_utf8_lossy(&bytes).to_string())
+/// The `tls_info` object of a TLS session, shared by every formatter that
+/// reports one so they all carry the same fields.
+pub(super) fn tls_info_json(tls_info: &TlsInfo) -> Value {
+    serde_json::json!({
+        "cipher": tls_info.cipher,
+        "version": tls_info.version,
+        "cert_subject": tls_info.cert_subject,
+        "cert_issuer": tls_info.cert_issuer,
+        "cert_valid_from": tls_info.cert_valid_from,
+        "cert_valid_to": tls_info.cert_valid_to,
+        "days_until_expiry": tls_info.days_until_expiry,
+        "expired": tls_info.expired,
+        "self_signed": tls_info.self_signed,
+        "cert_expiry": tls_info.cert_expiry,
+        "session_resumption_supported": tls_info.session_resumption_supported,
+        "session_tickets_issued": tls_info.session_tickets_issued,
+        "required_legacy_tls": tls_info.required_legacy_tls,
+        "client_cert_required": tls_info.client_cert_required,
+        "secure_renegotiation": tls_info.secure_renegotiation,
+        "client_renegotiation_allowed": tls_info.client_renegotiation_allowed,
+        "supported_groups": tls_info.supported_groups,
+    })
 }
 
+/// The raw banner as text, or `None` when nothing was read.
+pub(super) fn banner_text(raw_bytes: &[u8]) -> Option<String> {
+    (!raw_bytes.is_empty()).then(|| String::from_utf8_lossy(raw_bytes).into_owned())
+}
+
+/// Decodes `raw_hex` for the formatters; `write_outcome` does this once per
+/// outcome and hands every formatter the same bytes.
 pub(super) fn decode_banner_raw_bytes(raw_hex: &str) -> Option<Vec<u8>> {
     crate::util::hex::from_hex(raw_hex).ok()
 }
+
+/// SASL mechanisms that carry the password itself rather than a proof of it.
+pub(super) fn is_plaintext_mechanism(mechanism: &str) -> bool {
+    mechanism.eq_ignore_ascii_case("LOGIN") || mechanism.eq_ignore_ascii_case("PLAIN")
+}
+
+/// Whether a mail server accepts credentials in the clear: the session was
+/// not wrapped in TLS and either a plaintext SASL mechanism or a plaintext
+/// login command (IMAP `LOGIN`, POP3 `USER`) is on offer before STARTTLS.
+pub(super) fn plaintext_auth_allowed(
+    outcome: &ScanOutcome,
+    mechanisms: &[String],
+    login_command: bool,
+) -> bool {
+    outcome.tls_info.is_none()
+        && (login_command || mechanisms.iter().any(|mech| is_plaintext_mechanism(mech)))
+}
+
+/// Pretty-output FINDING for a certificate that has expired or expires
+/// within `--cert-warn-days`.
+pub(super) fn cert_expiry_finding(outcome: &ScanOutcome) -> Option<String> {
+    let tls_info = outcome.tls_info.as_ref()?;
+    let days = tls_info.days_until_expiry?;
+    match tls_info.cert_expiry.as_str() {
+        "expired" => Some(format!(
+            "[warning] TLS certificate expired {} days ago ({})",
+            -days, tls_info.cert_valid_to
+        )),
+        "expiring" => Some(format!(
+            "[warning] TLS certificate expires in {days} days ({})",
+            tls_info.cert_valid_to
+        )),
+        _ => None,
+    }
+}
+
+/// `--tls-reneg`: a server that lets clients renegotiate can be made to
+/// redo expensive handshakes at will, and without RFC 5746 the
+/// renegotiation is open to CVE-2009-3555 prefix injection.
+pub(super) fn renegotiation_finding(outcome: &ScanOutcome) -> Option<&'static str> {
+    let tls_info = outcome.tls_info.as_ref()?;
+    match (
+        tls_info.client_renegotiation_allowed?,
+        tls_info.secure_renegotiation,
+    ) {
+        (true, Some(false)) => Some("[high] TLS allows insecure client-initiated renegotiation"),
+        (true, _) => Some("[warning] TLS allows client-initiated renegotiation"),
+        (false, _) => None,
+    }
+}
+
+/// `--tls-groups`: key exchange groups below 128-bit security that the
+/// server still completes handshakes with.
+pub(super) fn weak_groups_finding(outcome: &ScanOutcome) -> Option<String> {
+    let groups = outcome.tls_info.as_ref()?.supported_groups.as_ref()?;
+    let weak: Vec<&str> = groups
+        .iter()
+        .map(String::as_str)
+        .filter(|group| matches!(*group, "secp224r1" | "ffdhe2048"))
+        .collect();
+    (!weak.is_empty()).then(|| format!("[warning] TLS accepts weak groups: {}", weak.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{
+        AddressFamily, Fingerprint, Protocol, Status, TargetView, TcpMeta, TlsInfo,
+    };
+
+    fn outcome(tls_info: TlsInfo) -> ScanOutcome {
+        ScanOutcome {
+            target: TargetView {
+                host: "web".into(),
+                addr: "192.0.2.7".into(),
+                port: 443,
+                family: Some(AddressFamily::V4),
+            },
+            status: Status::Open,
+            tcp: TcpMeta {
+                connect_ms: Some(1),
+                error: None,
+            },
+            banner: Default::default(),
+            timestamp: Default::default(),
+            ttl: None,
+            webdriver: None,
+            technologies: None,
+            tls_info: Some(tls_info),
+            fingerprint: Fingerprint::from_protocol(&Protocol::Https),
+            diagnostics: None,
+            proxy_protocol_accepted: None,
+            ptr: None,
+        }
+    }
+
+    #[test]
+    fn grades_client_renegotiation_by_rfc_5746_support() {
+        let finding = |allowed, secure| {
+            renegotiation_finding(&outcome(TlsInfo {
+                client_renegotiation_allowed: allowed,
+                secure_renegotiation: secure,
+                ..Default::default()
+            }))
+        };
+        assert_eq!(
+            finding(Some(true), Some(false)),
+            Some("[high] TLS allows insecure client-initiated renegotiation")
+        );
+        assert_eq!(
+            finding(Some(true), Some(true)),
+            Some("[warning] TLS allows client-initiated renegotiation")
+        );
+        assert_eq!(
+            finding(Some(true), None),
+            Some("[warning] TLS allows client-initiated renegotiation")
+        );
+        assert_eq!(finding(Some(false), Some(false)), None);
+        // Not tested without --tls-reneg.
+        assert_eq!(finding(None, Some(false)), None);
+    }
+
+    #[test]
+    fn lists_only_the_weak_groups() {
+        let finding = |groups: Option<&[&str]>| {
+            weak_groups_finding(&outcome(TlsInfo {
+                supported_groups: groups.map(|g| g.iter().map(|s| s.to_string()).collect()),
+                ..Default::default()
+            }))
+        };
+        assert_eq!(
+            finding(Some(&["x25519", "secp224r1", "secp256r1", "ffdhe2048"])),
+            Some("[warning] TLS accepts weak groups: secp224r1, ffdhe2048".into())
+        );
+        assert_eq!(finding(Some(&["x25519", "ffdhe3072"])), None);
+        assert_eq!(finding(None), None);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::model::ScanOutcome;
+use serde_json::Value;
+
+use super::common::{banner_text, raw_banner_for_data};
+
+pub(super) fn eth_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let response =
+        banner_text(raw_bytes).unwrap_or_else(|| raw_banner_for_data(outcome, raw_bytes));
+    let status_code = response
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or_default()
+        .to_string();
+    let body = response
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body)
+        .unwrap_or_default();
+    let rpc = serde_json::from_str::<Value>(body.trim()).ok();
+    let client_version = rpc
+        .as_ref()
+        .and_then(|rpc| rpc.get("result"))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let error = rpc
+        .as_ref()
+        .and_then(|rpc| rpc.pointer("/error/message"))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let (client, version, os, runtime) = parse_client_version(client_version);
+
+    serde_json::json!({
+        "status_code": status_code,
+        "jsonrpc": rpc.is_some(),
+        "client_version": client_version,
+        "client": client,
+        "version": version,
+        "os": os,
+        "runtime": runtime,
+        "error": error,
+        "auth_required": matches!(status_code.as_str(), "401" | "403"),
+    })
+}
+
+/// Splits `Geth/v1.13.5-stable-916d6a44/linux-amd64/go1.21.4` style strings;
+/// some clients add a node name after the client (`Geth/mynode/v1.13.5/...`).
+fn parse_client_version(value: &str) -> (&str, &str, &str, &str) {
+    let mut parts: Vec<&str> = value.split('/').collect();
+    if parts.len() > 4 {
+        parts.remove(1);
+    }
+    let part = |idx: usize| parts.get(idx).copied().unwrap_or_default();
+    (part(0), part(1).trim_start_matches('v'), part(2), part(3))
+}
@@ -0,0 +1,36 @@
+use crate::model::ScanOutcome;
+use serde_json::Value;
+
+use super::common::{banner_text, raw_banner_for_data};
+
+pub(super) fn ftp_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let fields = &outcome.fingerprint.fields;
+    let banner_raw =
+        banner_text(raw_bytes).unwrap_or_else(|| raw_banner_for_data(outcome, raw_bytes));
+    // A multi-line greeting (`220-`) ends at its `220 ` line.
+    let greeting: Vec<&str> = banner_raw
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .take_while(|line| line.starts_with("220"))
+        .map(|line| line.get(4..).unwrap_or_default().trim())
+        .collect();
+    let features: Vec<&str> = fields
+        .get("features")
+        .map(|listing| listing.lines().collect())
+        .unwrap_or_default();
+    let supports_auth_tls = features.iter().any(|feature| {
+        let feature = feature.to_ascii_uppercase();
+        feature.starts_with("AUTH ") && feature.contains("TLS")
+    });
+
+    serde_json::json!({
+        "banner": banner_raw,
+        "greeting": greeting.join("\n"),
+        "system_type": fields.get("system_type"),
+        "features": features,
+        "supports_auth_tls": supports_auth_tls,
+        "anonymous_login_allowed": fields
+            .get("anonymous_login")
+            .map(|allowed| allowed == "true"),
+    })
+}
@@ -0,0 +1,313 @@
+use super::common::tls_info_json;
+use crate::model::ScanOutcome;
+use crate::util::h2::{
+    frame_header, FRAME_DATA, FRAME_GOAWAY, FRAME_HEADERS, FRAME_RST_STREAM, FRAME_SETTINGS,
+    REQUEST_STREAM,
+};
+use serde_json::Value;
+
+pub(super) fn grpc_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let info = parse_grpc_response(raw_bytes);
+    let transport = if outcome.tls_info.is_some() {
+        "h2"
+    } else {
+        "h2c"
+    };
+    let tls_info = outcome.tls_info.clone().unwrap_or_default();
+    serde_json::json!({
+        "http2": info.http2,
+        "transport": transport,
+        "reflection_enabled": info.reflection_enabled,
+        "services": info.services,
+        "error": info.error,
+        "tls_info": tls_info_json(&tls_info),
+    })
+}
+
+#[derive(Default)]
+struct GrpcInfo {
+    http2: bool,
+    reflection_enabled: bool,
+    services: Vec<String>,
+    error: String,
+}
+
+fn parse_grpc_response(bytes: &[u8]) -> GrpcInfo {
+    let mut info = GrpcInfo::default();
+    let mut body = Vec::new();
+    let mut saw_headers = false;
+    let mut offset = 0usize;
+    let mut first = true;
+
+    while let Some((kind, _, stream_id, len)) = bytes.get(offset..).and_then(frame_header) {
+        let payload = bytes
+            .get(offset + 9..offset + 9 + len)
+            .unwrap_or(&bytes[(offset + 9).min(bytes.len())..]);
+        offset += 9 + len;
+
+        if first {
+            // A real HTTP/2 server always opens with a SETTINGS frame.
+            info.http2 = kind == FRAME_SETTINGS && stream_id == 0;
+            first = false;
+            if !info.http2 {
+                break;
+            }
+        }
+
+        match kind {
+            FRAME_DATA if stream_id == REQUEST_STREAM => body.extend_from_slice(payload),
+            FRAME_HEADERS if stream_id == REQUEST_STREAM => saw_headers = true,
+            FRAME_RST_STREAM if stream_id == REQUEST_STREAM => {
+                let code = payload
+                    .get(..4)
+                    .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                    .unwrap_or_default();
+                info.error = format!("stream reset ({})", h2_error_name(code));
+            }
+            FRAME_GOAWAY => {
+                let code = payload
+                    .get(4..8)
+                    .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+                    .unwrap_or_default();
+                if info.error.is_empty() && (code != 0 || body.is_empty()) {
+                    info.error = format!("connection closed by GOAWAY ({})", h2_error_name(code));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for message in grpc_messages(&body) {
+        for (field, value) in protobuf_fields(message) {
+            match field {
+                // list_services_response
+                6 => {
+                    info.reflection_enabled = true;
+                    for (inner, service) in protobuf_fields(value) {
+                        if inner != 1 {
+                            continue;
+                        }
+                        if let Some(name) = protobuf_fields(service)
+                            .into_iter()
+                            .find(|(tag, _)| *tag == 1)
+                            .map(|(_, name)| String::from_utf8_lossy(name).to_string())
+                        {
+                            info.services.push(name);
+                        }
+                    }
+                }
+                // error_response
+                7 => {
+                    let message = protobuf_fields(value)
+                        .into_iter()
+                        .find(|(tag, _)| *tag == 2)
+                        .map(|(_, message)| String::from_utf8_lossy(message).to_string())
+                        .unwrap_or_default();
+                    info.error = format!("reflection error: {message}");
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if info.http2 && !info.reflection_enabled && info.error.is_empty() && saw_headers {
+        // Trailers-only response: reflection is not registered on this server.
+        info.error = "reflection service unavailable".into();
+    }
+
+    info
+}
+
+/// Splits a gRPC body into messages, skipping the 5-byte length prefixes.
+fn grpc_messages(body: &[u8]) -> Vec<&[u8]> {
+    let mut messages = Vec::new();
+    let mut offset = 0usize;
+    while let Some(prefix) = body.get(offset..offset + 5) {
+        let len = u32::from_be_bytes([prefix[1], prefix[2], prefix[3], prefix[4]]) as usize;
+        let Some(message) = body.get(offset + 5..offset + 5 + len) else {
+            break;
+        };
+        messages.push(message);
+        offset += 5 + len;
+    }
+    messages
+}
+
+/// Returns the length-delimited fields of a protobuf message as `(tag, bytes)`
+/// pairs. Varint and fixed-width fields are skipped; the reflection messages
+/// we care about only carry strings and nested messages.
+fn protobuf_fields(mut data: &[u8]) -> Vec<(u64, &[u8])> {
+    let mut fields = Vec::new();
+    while !data.is_empty() {
+        let Some((key, rest)) = read_varint(data) else {
+            break;
+        };
+        data = rest;
+        let tag = key >> 3;
+        match key & 0x7 {
+            0 => match read_varint(data) {
+                Some((_, rest)) => data = rest,
+                None => break,
+            },
+            1 => match data.get(8..) {
+                Some(rest) => data = rest,
+                None => break,
+            },
+            2 => {
+                let Some((len, rest)) = read_varint(data) else {
+                    break;
+                };
+                let Some(value) = rest.get(..len as usize) else {
+                    break;
+                };
+                fields.push((tag, value));
+                data = &rest[len as usize..];
+            }
+            5 => match data.get(4..) {
+                Some(rest) => data = rest,
+                None => break,
+            },
+            _ => break,
+        }
+    }
+    fields
+}
+
+fn read_varint(data: &[u8]) -> Option<(u64, &[u8])> {
+    let mut value = 0u64;
+    for (idx, byte) in data.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * idx);
+        if byte & 0x80 == 0 {
+            return Some((value, &data[idx + 1..]));
+        }
+    }
+    None
+}
+
+fn h2_error_name(code: u32) -> &'static str {
+    match code {
+        0x0 => "NO_ERROR",
+        0x1 => "PROTOCOL_ERROR",
+        0x2 => "INTERNAL_ERROR",
+        0x3 => "FLOW_CONTROL_ERROR",
+        0x5 => "STREAM_CLOSED",
+        0x7 => "REFUSED_STREAM",
+        0x8 => "CANCEL",
+        0xb => "ENHANCE_YOUR_CALM",
+        0xc => "INADEQUATE_SECURITY",
+        0xd => "HTTP_1_1_REQUIRED",
+        _ => "UNKNOWN",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::h2::{frame, FLAG_END_HEADERS, FLAG_END_STREAM};
+
+    /// A protobuf length-delimited field; every length here fits one byte.
+    fn field(tag: u8, value: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag << 3 | 2, value.len() as u8];
+        out.extend_from_slice(value);
+        out
+    }
+
+    fn grpc_body(message: &[u8]) -> Vec<u8> {
+        let mut out = vec![0];
+        out.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        out.extend_from_slice(message);
+        out
+    }
+
+    fn response(frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = frame(FRAME_SETTINGS, 0, 0, &[]);
+        for frame in frames {
+            bytes.extend_from_slice(frame);
+        }
+        bytes
+    }
+
+    #[test]
+    fn lists_services_from_a_reflection_response() {
+        let mut services = Vec::new();
+        for name in ["helloworld.Greeter", "grpc.health.v1.Health"] {
+            services.extend(field(1, &field(1, name.as_bytes())));
+        }
+        // original_request echoed first, then list_services_response.
+        let mut message = field(4, &[0x3a, 0x00]);
+        message.extend(field(6, &services));
+        let bytes = response(&[
+            frame(FRAME_HEADERS, FLAG_END_HEADERS, REQUEST_STREAM, b"\x88"),
+            frame(FRAME_DATA, 0, REQUEST_STREAM, &grpc_body(&message)),
+            frame(
+                FRAME_HEADERS,
+                FLAG_END_HEADERS | FLAG_END_STREAM,
+                REQUEST_STREAM,
+                b"",
+            ),
+        ]);
+        let info = parse_grpc_response(&bytes);
+        assert!(info.http2 && info.reflection_enabled);
+        assert_eq!(
+            info.services,
+            ["helloworld.Greeter", "grpc.health.v1.Health"]
+        );
+        assert_eq!(info.error, "");
+    }
+
+    #[test]
+    fn reports_reflection_errors_and_missing_reflection() {
+        // error_response { error_code: 12, error_message: "unimplemented" }
+        let mut error = vec![0x08, 12];
+        error.extend(field(2, b"unimplemented"));
+        let bytes = response(&[frame(
+            FRAME_DATA,
+            FLAG_END_STREAM,
+            REQUEST_STREAM,
+            &grpc_body(&field(7, &error)),
+        )]);
+        let info = parse_grpc_response(&bytes);
+        assert!(!info.reflection_enabled);
+        assert_eq!(info.error, "reflection error: unimplemented");
+
+        let trailers_only = response(&[frame(
+            FRAME_HEADERS,
+            FLAG_END_HEADERS | FLAG_END_STREAM,
+            REQUEST_STREAM,
+            b"",
+        )]);
+        assert_eq!(
+            parse_grpc_response(&trailers_only).error,
+            "reflection service unavailable"
+        );
+
+        let reset = response(&[frame(FRAME_RST_STREAM, 0, REQUEST_STREAM, &[0, 0, 0, 7])]);
+        assert_eq!(
+            parse_grpc_response(&reset).error,
+            "stream reset (REFUSED_STREAM)"
+        );
+    }
+
+    #[test]
+    fn rejects_non_http2_replies() {
+        let info = parse_grpc_response(b"HTTP/1.1 400 Bad Request\r\n\r\n");
+        assert!(!info.http2 && !info.reflection_enabled);
+        // A truncated frame does not run past the recorded bytes.
+        let mut bytes = response(&[frame(FRAME_DATA, 0, REQUEST_STREAM, b"abcdef")]);
+        bytes.truncate(bytes.len() - 3);
+        assert!(parse_grpc_response(&bytes).http2);
+    }
+
+    #[test]
+    fn skips_scalar_protobuf_fields() {
+        // varint 150, fixed64, fixed32, then the string field.
+        let mut message = vec![0x08, 0x96, 0x01, 0x11];
+        message.extend_from_slice(&[0; 8]);
+        message.push(0x1d);
+        message.extend_from_slice(&[0; 4]);
+        message.extend(field(5, b"name"));
+        assert_eq!(protobuf_fields(&message), [(5, &b"name"[..])]);
+        assert_eq!(read_varint(&[0x96, 0x01, 0xff]), Some((150, &[0xff][..])));
+        assert_eq!(read_varint(&[0x80]), None);
+    }
+}
@@ -0,0 +1,57 @@
+//! `--hexdump`: the raw banner as `offset  hex bytes  |ascii|` lines, like
+//! `xxd`/`hexdump -C`, for checking what a protocol parser was given.
+
+const ROW: usize = 16;
+
+/// Dump of the first `limit` bytes, one row of 16 per line, each line
+/// starting with `indent`. A last line notes how many bytes were left out.
+pub(super) fn hexdump(bytes: &[u8], limit: usize, indent: &str) -> String {
+    let shown = &bytes[..bytes.len().min(limit)];
+    let mut dump = String::new();
+    for (row, chunk) in shown.chunks(ROW).enumerate() {
+        let mut hex = String::with_capacity(ROW * 3 + 1);
+        for idx in 0..ROW {
+            if idx == ROW / 2 {
+                hex.push(' ');
+            }
+            match chunk.get(idx) {
+                Some(byte) => hex.push_str(&format!("{byte:02x} ")),
+                None => hex.push_str("   "),
+            }
+        }
+        let ascii: String = chunk
+            .iter()
+            .map(|byte| match byte {
+                0x20..=0x7e => *byte as char,
+                _ => '.',
+            })
+            .collect();
+        dump.push_str(&format!("{indent}{:08x}  {hex} |{ascii}|\n", row * ROW));
+    }
+    if bytes.len() > shown.len() {
+        dump.push_str(&format!(
+            "{indent}... {} more bytes\n",
+            bytes.len() - shown.len()
+        ));
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dumps_rows_and_notes_the_cut() {
+        let dump = hexdump(b"SSH-2.0-OpenSSH_9.6\r\n", 20, "  ");
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "  00000000  53 53 48 2d 32 2e 30 2d  4f 70 65 6e 53 53 48 5f  |SSH-2.0-OpenSSH_|",
+                "  00000010  39 2e 36 0d                                       |9.6.|",
+                "  ... 1 more bytes",
+            ]
+        );
+    }
+}
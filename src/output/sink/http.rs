@@ -1,6 +1,25 @@
+use super::common::tls_info_json;
 use crate::model::{ScanOutcome, TlsInfo};
+use serde::Serialize;
 use serde_json::Value;
-use std::collections::BTreeMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// Headers that may repeat but whose values cannot be joined with ", ":
+/// `Expires` dates in cookies contain commas themselves.
+const LIST_HEADERS: &[&str] = &["Set-Cookie"];
+
+/// A parsed header: one string, with repeats joined as RFC 9110 allows, or
+/// every value of a `LIST_HEADERS` entry in order.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+enum HeaderValue {
+    Single(String),
+    List(Vec<String>),
+}
+
+type Headers = BTreeMap<String, HeaderValue>;
 
 pub(super) fn http_data(outcome: &ScanOutcome, proto: &str) -> Value {
     let status_reqwest = parse_http_status_code(&outcome.banner.printable).unwrap_or_default();
@@ -28,28 +47,340 @@ pub(super) fn http_data(outcome: &ScanOutcome, proto: &str) -> Value {
         .as_deref()
         .map(|url| serde_json::json!({ "url": url, "status": status_reqwest }))
         .unwrap_or_else(|| serde_json::json!({ "url": "", "status": "" }));
+    let product = detect_product(&headers, &body, outcome);
+    let security_headers = security_headers(&headers, proto == "https");
+    let cookies: Vec<Value> = parse_cookies(&headers)
+        .iter()
+        .map(Cookie::to_json)
+        .collect();
+    let fields = &outcome.fingerprint.fields;
+    let smuggling_probe = fields.get("smuggling_probe");
+    // `--http-paths`: `http_path.<path>` = `<status> <length>`.
+    let paths: serde_json::Map<String, Value> = fields
+        .iter()
+        .filter_map(|(key, value)| {
+            let path = key.strip_prefix("http_path.")?;
+            let (status, length) = value.split_once(' ')?;
+            Some((
+                path.to_string(),
+                serde_json::json!({ "status": status, "length": length.parse::<u64>().ok() }),
+            ))
+        })
+        .collect();
     serde_json::json!({
         "status_code": status_reqwest,
+        // `null` unless `--http-accept-status` was given.
+        "status_accepted": outcome
+            .fingerprint
+            .fields
+            .get("http_status_accepted")
+            .map(|accepted| accepted == "true"),
         "headers": headers,
         "body": body,
         "engine_body": engine_body,
         "title": title,
         "favicon_hash": "",
         "technologies": technologies,
+        "product": product,
+        "security_headers": security_headers,
+        "cookies": cookies,
         "redirects": [
             redirect_entry
         ],
-        "tls_info": {
-            "cipher": tls_info.cipher,
-            "version": tls_info.version,
-            "cert_subject": tls_info.cert_subject,
-            "cert_issuer": tls_info.cert_issuer,
-            "cert_valid_from": tls_info.cert_valid_from,
-            "cert_valid_to": tls_info.cert_valid_to,
-        },
+        // Only a hang is an indicator; `null` when `--test-smuggling` was off
+        // or the probe could not connect.
+        "smuggling_probe": smuggling_probe.cloned().unwrap_or_default(),
+        "te_cl_ambiguous": smuggling_probe.map(|behavior| behavior == "hang"),
+        // `null` unless `--test-open-proxy` reached the target.
+        "open_proxy": fields.get("open_proxy").map(|open| open == "true"),
+        "open_proxy_get": fields.get("open_proxy_get"),
+        "open_proxy_connect": fields.get("open_proxy_connect"),
+        "paths": paths,
+        "tls_info": tls_info_json(&tls_info),
     })
 }
 
+/// Recognises databases that speak HTTP on their main port (CouchDB's JSON
+/// welcome document, InfluxDB's `/ping` headers, the etcd, Consul and Riak
+/// APIs the HTTP probe asked for their version) and reports whether the
+/// admin API probed by the HTTP probe answered without auth.
+fn detect_product(headers: &Headers, body: &str, outcome: &ScanOutcome) -> Value {
+    let fields = &outcome.fingerprint.fields;
+    let (name, version, vendor) = if let Some(product) = fields.get("kv_product") {
+        (
+            product.as_str(),
+            fields.get("kv_version").cloned().unwrap_or_default(),
+            String::new(),
+        )
+    } else if let Some(version) = fields
+        .get("influxdb_version")
+        .cloned()
+        .or_else(|| find_header_value(headers, "X-Influxdb-Version"))
+    {
+        let edition = fields
+            .get("influxdb_build")
+            .cloned()
+            .or_else(|| find_header_value(headers, "X-Influxdb-Build"))
+            .unwrap_or_default();
+        (
+            "influxdb",
+            version.trim_start_matches('v').to_string(),
+            edition,
+        )
+    } else if let Some(welcome) = serde_json::from_str::<Value>(body.trim())
+        .ok()
+        .filter(|json| json.get("couchdb").is_some())
+    {
+        let text = |value: Option<&Value>| {
+            value
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        (
+            "couchdb",
+            text(welcome.get("version")),
+            text(welcome.pointer("/vendor/name")),
+        )
+    } else {
+        return serde_json::json!({
+            "name": "",
+            "version": "",
+            "vendor": "",
+            "admin_path": "",
+            "admin_without_auth": Value::Null,
+            "details": {},
+        });
+    };
+
+    let admin_path = fields.get("admin_path");
+    let admin_without_auth = match fields.get("admin_status").map(String::as_str) {
+        Some(status) if status.starts_with('2') => Value::Bool(true),
+        Some("401" | "403") => Value::Bool(false),
+        _ => Value::Null,
+    };
+    serde_json::json!({
+        "name": name,
+        "version": version,
+        "vendor": vendor,
+        "admin_path": admin_path.cloned().unwrap_or_default(),
+        "admin_without_auth": admin_without_auth,
+        // Store-specific facts such as the Consul datacenter or whether it
+        // enforces ACLs.
+        "details": fields
+            .iter()
+            .filter_map(|(key, value)| Some((key.strip_prefix("kv.")?, value)))
+            .collect::<BTreeMap<_, _>>(),
+    })
+}
+
+/// Response headers browsers use to harden a site: their raw values, whether
+/// each one is missing or set to something that does not protect, and a
+/// letter grade from how many of them are in place. HSTS only counts over
+/// HTTPS; browsers ignore it on plain HTTP. `null` without a response.
+fn security_headers(headers: &Headers, https: bool) -> Value {
+    if headers.is_empty() {
+        return Value::Null;
+    }
+    let value = |name: &str| find_header_value(headers, name);
+    let hsts = value("Strict-Transport-Security");
+    let csp = value("Content-Security-Policy");
+    let frame_options = value("X-Frame-Options");
+    let content_type_options = value("X-Content-Type-Options");
+    let referrer_policy = value("Referrer-Policy");
+
+    let hsts_max_age = hsts.as_deref().and_then(|hsts| {
+        hsts.split(';').find_map(|directive| {
+            let (key, value) = directive.split_once('=')?;
+            if !key.trim().eq_ignore_ascii_case("max-age") {
+                return None;
+            }
+            value.trim().trim_matches('"').parse::<u64>().ok()
+        })
+    });
+    let hsts_directive = |name: &str| {
+        hsts.as_deref().is_some_and(|hsts| {
+            hsts.split(';')
+                .any(|directive| directive.trim().eq_ignore_ascii_case(name))
+        })
+    };
+    // A frame-ancestors directive supersedes X-Frame-Options.
+    let frame_ancestors = csp
+        .as_deref()
+        .is_some_and(|csp| csp.to_ascii_lowercase().contains("frame-ancestors"));
+    let frame_options_valid = frame_options.as_deref().is_some_and(|value| {
+        value.eq_ignore_ascii_case("DENY") || value.eq_ignore_ascii_case("SAMEORIGIN")
+    });
+    let nosniff = content_type_options
+        .as_deref()
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("nosniff"));
+    let referrer_leaks = referrer_policy.as_deref().is_some_and(|policy| {
+        // The last recognised token wins; `unsafe-url` sends full URLs.
+        policy
+            .rsplit(',')
+            .next()
+            .is_some_and(|last| last.trim().eq_ignore_ascii_case("unsafe-url"))
+    });
+
+    let missing = serde_json::json!({
+        "strict_transport_security": https.then_some(hsts_max_age.is_none_or(|age| age == 0)),
+        "content_security_policy": csp.is_none(),
+        "x_frame_options": !frame_options_valid && !frame_ancestors,
+        "x_content_type_options": !nosniff,
+        "referrer_policy": referrer_policy.is_none() || referrer_leaks,
+    });
+    let gaps = missing
+        .as_object()
+        .map(|flags| {
+            flags
+                .values()
+                .filter(|flag| **flag == Value::Bool(true))
+                .count()
+        })
+        .unwrap_or_default();
+    let grade = match gaps {
+        0 => "A",
+        1 => "B",
+        2 => "C",
+        3 => "D",
+        _ => "F",
+    };
+    serde_json::json!({
+        "strict_transport_security": hsts,
+        "hsts_max_age": hsts_max_age,
+        "hsts_include_subdomains": hsts_directive("includeSubDomains"),
+        "hsts_preload": hsts_directive("preload"),
+        "content_security_policy": csp,
+        "x_frame_options": frame_options,
+        "x_content_type_options": content_type_options,
+        "referrer_policy": referrer_policy,
+        "missing": missing,
+        "grade": grade,
+    })
+}
+
+struct Cookie {
+    name: String,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<String>,
+}
+
+impl Cookie {
+    fn parse(header: &str) -> Option<Self> {
+        let mut parts = header.split(';');
+        let (name, _) = parts.next()?.split_once('=')?;
+        let mut cookie = Cookie {
+            name: name.trim().to_string(),
+            secure: false,
+            http_only: false,
+            same_site: None,
+        };
+        for attribute in parts {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            match key.trim().to_ascii_lowercase().as_str() {
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => cookie.same_site = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+        (!cookie.name.is_empty()).then_some(cookie)
+    }
+
+    fn to_json(&self) -> Value {
+        serde_json::json!({
+            "name": self.name,
+            "secure": self.secure,
+            "http_only": self.http_only,
+            "same_site": self.same_site,
+            "missing_secure": !self.secure,
+            "missing_http_only": !self.http_only,
+        })
+    }
+}
+
+/// Cookies from every `Set-Cookie` header of the response.
+fn parse_cookies(headers: &Headers) -> Vec<Cookie> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Set-Cookie"))
+        .flat_map(|(_, value)| match value {
+            HeaderValue::Single(value) => std::slice::from_ref(value),
+            HeaderValue::List(values) => values.as_slice(),
+        })
+        .filter_map(|value| Cookie::parse(value))
+        .collect()
+}
+
+/// `--test-open-proxy` verdict for the pretty output.
+/// `--dedupe-http-bodies`: the first target on each IP to return a given
+/// status, title and body, so catch-all vhosts point back at it.
+#[derive(Default)]
+pub(super) struct ResponseCache {
+    first: HashMap<(String, u64), String>,
+}
+
+impl ResponseCache {
+    /// `host:port` of the earlier target on the same IP that returned the
+    /// same response, or `None` when this one is the first (and is kept).
+    pub(super) fn duplicate_of(&mut self, outcome: &ScanOutcome) -> Option<String> {
+        let printable = &outcome.banner.printable;
+        let status = parse_http_status_code(printable)?;
+        let mut hasher = DefaultHasher::new();
+        status.hash(&mut hasher);
+        extract_html_title(printable).hash(&mut hasher);
+        extract_http_body(printable).hash(&mut hasher);
+        let addr = outcome.target.ip().unwrap_or(&outcome.target.host);
+        let key = (addr.to_string(), hasher.finish());
+        let label = format!("{}:{}", outcome.target.host, outcome.target.port);
+        match self.first.get(&key) {
+            Some(first) => Some(first.clone()),
+            None => {
+                self.first.insert(key, label);
+                None
+            }
+        }
+    }
+}
+
+pub(super) fn open_proxy_finding(outcome: &ScanOutcome) -> Option<String> {
+    let fields = &outcome.fingerprint.fields;
+    if fields.get("open_proxy").map(String::as_str) != Some("true") {
+        return None;
+    }
+    let relayed: Vec<&str> = [("open_proxy_get", "GET"), ("open_proxy_connect", "CONNECT")]
+        .into_iter()
+        .filter(|(field, _)| fields.get(*field).map(String::as_str) == Some("proxied"))
+        .map(|(_, method)| method)
+        .collect();
+    Some(format!("open proxy, relays {}", relayed.join(" and ")))
+}
+
+/// One line per cookie set without `Secure` or `HttpOnly`, for the pretty
+/// output.
+pub(super) fn cookie_findings(outcome: &ScanOutcome) -> Vec<String> {
+    parse_cookies(&parse_http_headers(&outcome.banner.printable))
+        .into_iter()
+        .filter_map(|cookie| {
+            let missing: Vec<&str> = [
+                (!cookie.secure).then_some("Secure"),
+                (!cookie.http_only).then_some("HttpOnly"),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            (!missing.is_empty()).then(|| {
+                format!(
+                    "cookie {} set without {}",
+                    cookie.name,
+                    missing.join(" or ")
+                )
+            })
+        })
+        .collect()
+}
+
 fn extract_http_body(printable: &str) -> String {
     if let Some(idx) = printable.find("\r\n\r\n") {
         return printable[idx + 4..].to_string();
@@ -71,8 +402,8 @@ fn parse_http_status_code(printable: &str) -> Option<String> {
     Some(code.to_string())
 }
 
-fn parse_http_headers(printable: &str) -> BTreeMap<String, String> {
-    let mut headers = BTreeMap::new();
+fn parse_http_headers(printable: &str) -> Headers {
+    let mut headers = Headers::new();
     let mut lines = printable.lines();
     let first_line = match lines.next() {
         Some(line) => line.trim_start(),
@@ -91,26 +422,45 @@ fn parse_http_headers(printable: &str) -> BTreeMap<String, String> {
         };
         let key = name.trim().to_string();
         let val = value.trim().to_string();
+        if LIST_HEADERS
+            .iter()
+            .any(|list| list.eq_ignore_ascii_case(&key))
+        {
+            match headers
+                .entry(key)
+                .or_insert_with(|| HeaderValue::List(Vec::new()))
+            {
+                HeaderValue::List(values) => values.push(val),
+                HeaderValue::Single(_) => unreachable!("list headers are never stored as single"),
+            }
+            continue;
+        }
         headers
             .entry(key)
             .and_modify(|existing| {
-                if !val.is_empty() {
-                    if !existing.is_empty() {
-                        existing.push_str(", ");
+                if let HeaderValue::Single(existing) = existing {
+                    if !val.is_empty() {
+                        if !existing.is_empty() {
+                            existing.push_str(", ");
+                        }
+                        existing.push_str(&val);
                     }
-                    existing.push_str(&val);
                 }
             })
-            .or_insert(val);
+            .or_insert(HeaderValue::Single(val));
     }
     headers
 }
 
-fn find_header_value(headers: &BTreeMap<String, String>, name: &str) -> Option<String> {
+/// The value of a single-valued header; `None` for list headers.
+fn find_header_value(headers: &Headers, name: &str) -> Option<String> {
     headers
         .iter()
         .find(|(key, _)| key.eq_ignore_ascii_case(name))
-        .map(|(_, value)| value.clone())
+        .and_then(|(_, value)| match value {
+            HeaderValue::Single(value) => Some(value.clone()),
+            HeaderValue::List(_) => None,
+        })
 }
 
 fn extract_html_title(printable: &str) -> Option<String> {
@@ -126,3 +476,90 @@ fn extract_html_title(printable: &str) -> Option<String> {
         Some(title.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(host: &str, addr: &str, port: u16, printable: &str) -> ScanOutcome {
+        let mut outcome = super::super::schema::empty_outcome("http");
+        outcome.target.host = host.into();
+        outcome.target.addr = addr.into();
+        outcome.target.port = port;
+        outcome.banner.printable = printable.into();
+        outcome
+    }
+
+    #[test]
+    fn marks_repeated_responses_per_ip() {
+        let parked = "HTTP/1.1 200 OK\r\n\r\n<title>Parked</title>";
+        let mut cache = ResponseCache::default();
+        assert_eq!(
+            cache.duplicate_of(&response("a.example", "192.0.2.1", 80, parked)),
+            None
+        );
+        assert_eq!(
+            cache
+                .duplicate_of(&response("b.example", "192.0.2.1", 80, parked))
+                .as_deref(),
+            Some("a.example:80")
+        );
+        // Another IP, another status or no HTTP answer at all is new.
+        assert_eq!(
+            cache.duplicate_of(&response("c.example", "192.0.2.2", 80, parked)),
+            None
+        );
+        let not_found = "HTTP/1.1 404 Not Found\r\n\r\n<title>Parked</title>";
+        assert_eq!(
+            cache.duplicate_of(&response("d.example", "192.0.2.1", 80, not_found)),
+            None
+        );
+        assert_eq!(
+            cache.duplicate_of(&response("e.example", "192.0.2.1", 80, "")),
+            None
+        );
+    }
+
+    #[test]
+    fn keeps_each_set_cookie_header() {
+        let response = "HTTP/1.1 200 OK\r\n\
+            Set-Cookie: sid=abc; Expires=Wed, 21 Oct 2026 07:28:00 GMT; HttpOnly\r\n\
+            Vary: Accept\r\n\
+            Set-Cookie: pref=1; Secure\r\n\
+            Vary: Cookie\r\n\r\nbody";
+        let headers = parse_http_headers(response);
+        assert_eq!(
+            headers["Set-Cookie"],
+            HeaderValue::List(vec![
+                "sid=abc; Expires=Wed, 21 Oct 2026 07:28:00 GMT; HttpOnly".into(),
+                "pref=1; Secure".into(),
+            ])
+        );
+        assert_eq!(
+            headers["Vary"],
+            HeaderValue::Single("Accept, Cookie".into())
+        );
+
+        let cookies = parse_cookies(&headers);
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].name, "sid");
+        assert!(cookies[0].http_only && !cookies[0].secure);
+        assert_eq!(cookies[1].name, "pref");
+        assert!(cookies[1].secure && !cookies[1].http_only);
+    }
+
+    #[test]
+    fn serializes_list_headers_as_arrays() {
+        let headers =
+            parse_http_headers("HTTP/1.1 200 OK\r\nServer: nginx\r\nSet-Cookie: a=1\r\n\r\n");
+        assert_eq!(
+            serde_json::to_value(&headers).unwrap(),
+            serde_json::json!({ "Server": "nginx", "Set-Cookie": ["a=1"] })
+        );
+        assert_eq!(find_header_value(&headers, "set-cookie"), None);
+        assert_eq!(
+            find_header_value(&headers, "server").as_deref(),
+            Some("nginx")
+        );
+    }
+}
@@ -1,12 +1,14 @@
 use crate::model::ScanOutcome;
+use crate::util::mail_banner;
 use serde_json::Value;
 
-use super::common::{decode_banner_raw, raw_banner_for_data};
+use super::common::{
+    banner_text, is_plaintext_mechanism, plaintext_auth_allowed, raw_banner_for_data,
+};
 
-pub(super) fn imap_data(outcome: &ScanOutcome) -> Value {
-    let banner_raw = decode_banner_raw(&outcome.banner.raw_hex)
-        .filter(|value| !value.is_empty())
-        .unwrap_or_else(|| raw_banner_for_data(outcome));
+pub(super) fn imap_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let banner_raw =
+        banner_text(raw_bytes).unwrap_or_else(|| raw_banner_for_data(outcome, raw_bytes));
     let mut pre_login_capabilities = Vec::new();
     let mut errors_observed = Vec::new();
     let mut server_identity = String::new();
@@ -44,13 +46,18 @@ pub(super) fn imap_data(outcome: &ScanOutcome) -> Value {
         .any(|cap| cap.eq_ignore_ascii_case("STARTTLS"));
     let weak_auth = auth_mechanisms
         .iter()
-        .any(|mech| mech.eq_ignore_ascii_case("LOGIN") || mech.eq_ignore_ascii_case("PLAIN"));
-    let server_software = extract_imap_server_software(&server_identity);
+        .any(|mech| is_plaintext_mechanism(mech));
+    // LOGINDISABLED is how servers say `LOGIN` waits for STARTTLS.
+    let login_disabled = pre_login_capabilities
+        .iter()
+        .any(|cap| cap.eq_ignore_ascii_case("LOGINDISABLED"));
+    let login_command = !pre_login_capabilities.is_empty() && !login_disabled;
+    let (server_software, software_version) = extract_imap_server_software(&server_identity);
 
     serde_json::json!({
         "banner": banner_raw,
         "server_software": server_software,
-        "software_version": Value::Null,
+        "software_version": software_version,
         "capabilities": {
             "pre_login": pre_login_capabilities,
             "post_login": [],
@@ -60,6 +67,8 @@ pub(super) fn imap_data(outcome: &ScanOutcome) -> Value {
         "requires_auth_before_capability": requires_auth_before_capability,
         "server_identity": server_identity,
         "weak_auth": weak_auth,
+        "login_disabled": login_disabled,
+        "plaintext_auth_allowed": plaintext_auth_allowed(outcome, &auth_mechanisms, login_command),
         "errors_observed": errors_observed,
     })
 }
@@ -122,18 +131,14 @@ fn extract_imap_tag_status(line: &str) -> Option<(&str, &str)> {
     Some((tag, status))
 }
 
-fn extract_imap_server_software(identity: &str) -> String {
-    if identity.contains("Dovecot") {
-        return "Dovecot".to_string();
-    }
-    if identity.contains("Cyrus") {
-        return "Cyrus".to_string();
+/// Product and version from the greeting, or its first word when no known
+/// product is named.
+fn extract_imap_server_software(identity: &str) -> (String, Option<String>) {
+    if let Some(software) = mail_banner::identify(identity) {
+        return (software.product.to_string(), software.version);
     }
-    identity
-        .split_whitespace()
-        .next()
-        .unwrap_or_default()
-        .to_string()
+    let first_word = identity.split_whitespace().next().unwrap_or_default();
+    (first_word.to_string(), None)
 }
 
 fn extend_unique(target: &mut Vec<String>, incoming: Vec<String>) {
@@ -0,0 +1,161 @@
+use crate::model::{ReadStopReason, ScanOutcome};
+use serde_json::Value;
+
+pub(super) fn kafka_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let info = parse_api_versions(raw_bytes);
+    // SSL- or SASL-only listeners drop a plaintext ApiVersions request on the
+    // floor and close the connection.
+    let auth_required = raw_bytes.is_empty()
+        && matches!(outcome.banner.read_reason, ReadStopReason::ConnectionClosed);
+
+    let api_versions: Vec<Value> = info
+        .api_versions
+        .iter()
+        .map(|(key, min, max)| {
+            serde_json::json!({
+                "api_key": key,
+                "name": api_name(*key),
+                "min_version": min,
+                "max_version": max,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "error_code": info.error_code.map(Value::from).unwrap_or(Value::Null),
+        "api_versions": api_versions,
+        "release_estimate": release_estimate(&info.api_versions).unwrap_or_default(),
+        "auth_required": auth_required,
+    })
+}
+
+#[derive(Default)]
+struct ApiVersionsInfo {
+    error_code: Option<i16>,
+    api_versions: Vec<(i16, i16, i16)>,
+}
+
+fn parse_api_versions(raw: &[u8]) -> ApiVersionsInfo {
+    let mut info = ApiVersionsInfo::default();
+    // length(4) correlation_id(4) error_code(2) count(4)
+    let Some(header) = raw.get(..14) else {
+        return info;
+    };
+    info.error_code = Some(i16::from_be_bytes([header[8], header[9]]));
+    let count = i32::from_be_bytes([header[10], header[11], header[12], header[13]]).max(0);
+    for entry in raw[14..].chunks_exact(6).take(count as usize) {
+        info.api_versions.push((
+            i16::from_be_bytes([entry[0], entry[1]]),
+            i16::from_be_bytes([entry[2], entry[3]]),
+            i16::from_be_bytes([entry[4], entry[5]]),
+        ));
+    }
+    info
+}
+
+/// Oldest Kafka release that introduced an API the broker advertises. Only
+/// client-facing APIs are used since controller APIs may be hidden.
+fn release_estimate(api_versions: &[(i16, i16, i16)]) -> Option<String> {
+    const MARKERS: &[(i16, &str)] = &[
+        (75, "3.8"),
+        (71, "3.7"),
+        (66, "3.0"),
+        (60, "2.8"),
+        (50, "2.7"),
+        (48, "2.6"),
+        (45, "2.4"),
+        (44, "2.3"),
+        (43, "2.2"),
+        (42, "1.1"),
+        (36, "1.0"),
+        (21, "0.11"),
+        (19, "0.10.1"),
+        (18, "0.10.0"),
+    ];
+    MARKERS
+        .iter()
+        .find(|(key, _)| api_versions.iter().any(|(api, _, _)| api == key))
+        .map(|(_, release)| format!(">= {release}"))
+}
+
+fn api_name(key: i16) -> &'static str {
+    match key {
+        0 => "Produce",
+        1 => "Fetch",
+        2 => "ListOffsets",
+        3 => "Metadata",
+        4 => "LeaderAndIsr",
+        5 => "StopReplica",
+        6 => "UpdateMetadata",
+        7 => "ControlledShutdown",
+        8 => "OffsetCommit",
+        9 => "OffsetFetch",
+        10 => "FindCoordinator",
+        11 => "JoinGroup",
+        12 => "Heartbeat",
+        13 => "LeaveGroup",
+        14 => "SyncGroup",
+        15 => "DescribeGroups",
+        16 => "ListGroups",
+        17 => "SaslHandshake",
+        18 => "ApiVersions",
+        19 => "CreateTopics",
+        20 => "DeleteTopics",
+        21 => "DeleteRecords",
+        22 => "InitProducerId",
+        23 => "OffsetForLeaderEpoch",
+        24 => "AddPartitionsToTxn",
+        25 => "AddOffsetsToTxn",
+        26 => "EndTxn",
+        27 => "WriteTxnMarkers",
+        28 => "TxnOffsetCommit",
+        29 => "DescribeAcls",
+        30 => "CreateAcls",
+        31 => "DeleteAcls",
+        32 => "DescribeConfigs",
+        33 => "AlterConfigs",
+        34 => "AlterReplicaLogDirs",
+        35 => "DescribeLogDirs",
+        36 => "SaslAuthenticate",
+        37 => "CreatePartitions",
+        38 => "CreateDelegationToken",
+        39 => "RenewDelegationToken",
+        40 => "ExpireDelegationToken",
+        41 => "DescribeDelegationToken",
+        42 => "DeleteGroups",
+        43 => "ElectLeaders",
+        44 => "IncrementalAlterConfigs",
+        45 => "AlterPartitionReassignments",
+        46 => "ListPartitionReassignments",
+        47 => "OffsetDelete",
+        48 => "DescribeClientQuotas",
+        49 => "AlterClientQuotas",
+        50 => "DescribeUserScramCredentials",
+        51 => "AlterUserScramCredentials",
+        52 => "Vote",
+        53 => "BeginQuorumEpoch",
+        54 => "EndQuorumEpoch",
+        55 => "DescribeQuorum",
+        56 => "AlterPartition",
+        57 => "UpdateFeatures",
+        58 => "Envelope",
+        59 => "FetchSnapshot",
+        60 => "DescribeCluster",
+        61 => "DescribeProducers",
+        62 => "BrokerRegistration",
+        63 => "BrokerHeartbeat",
+        64 => "UnregisterBroker",
+        65 => "DescribeTransactions",
+        66 => "ListTransactions",
+        67 => "AllocateProducerIds",
+        68 => "ConsumerGroupHeartbeat",
+        69 => "ConsumerGroupDescribe",
+        70 => "ControllerRegistration",
+        71 => "GetTelemetrySubscriptions",
+        72 => "PushTelemetry",
+        73 => "AssignReplicasToDirs",
+        74 => "ListClientMetricsResources",
+        75 => "DescribeTopicPartitions",
+        _ => "Unknown",
+    }
+}
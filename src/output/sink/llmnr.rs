@@ -0,0 +1,39 @@
+use crate::util::dns::{self, RData};
+use serde_json::Value;
+
+pub(super) fn llmnr_data(raw_bytes: &[u8]) -> Value {
+    let Some(message) = dns::parse_message(raw_bytes).filter(|message| message.is_response) else {
+        return serde_json::json!({
+            "is_llmnr": false,
+            "query": "",
+            "rcode": Value::Null,
+            "answers": [],
+            "answers_random_name": false,
+        });
+    };
+    let answers: Vec<Value> = message
+        .records
+        .iter()
+        .filter_map(|record| {
+            let address = match &record.data {
+                RData::A(ip) => ip.to_string(),
+                RData::Aaaa(ip) => ip.to_string(),
+                _ => return None,
+            };
+            Some(serde_json::json!({
+                "name": record.name,
+                "address": address,
+            }))
+        })
+        .collect();
+
+    serde_json::json!({
+        "is_llmnr": true,
+        "query": message.questions.first().cloned().unwrap_or_default(),
+        "rcode": message.rcode,
+        // The client asks for a random name nobody owns; answering it is the
+        // behaviour of an LLMNR poisoner.
+        "answers_random_name": !answers.is_empty(),
+        "answers": answers,
+    })
+}
@@ -0,0 +1,96 @@
+use crate::util::dns::{self, RData, Record};
+use serde_json::Value;
+
+const SERVICES: &str = "_services._dns-sd._udp.local";
+
+pub(super) fn mdns_data(raw_bytes: &[u8]) -> Value {
+    let messages: Vec<dns::Message> = frames(raw_bytes)
+        .filter_map(dns::parse_message)
+        .filter(|message| message.is_response)
+        .collect();
+    let records: Vec<&Record> = messages.iter().flat_map(|m| &m.records).collect();
+
+    let mut service_types = Vec::new();
+    let mut instances = Vec::new();
+    let mut hostnames: Vec<&str> = Vec::new();
+    let mut addresses = Vec::new();
+    for record in &records {
+        match &record.data {
+            RData::Ptr(target) if record.name.eq_ignore_ascii_case(SERVICES) => {
+                push_unique(&mut service_types, target.as_str());
+            }
+            RData::Ptr(instance) if record.name.starts_with('_') => {
+                instances.push(instance_data(instance, &record.name, &records));
+            }
+            RData::A(ip) => {
+                push_unique(&mut hostnames, record.name.as_str());
+                push_unique(&mut addresses, ip.to_string());
+            }
+            RData::Aaaa(ip) => {
+                push_unique(&mut hostnames, record.name.as_str());
+                push_unique(&mut addresses, ip.to_string());
+            }
+            RData::Srv { target, .. } => push_unique(&mut hostnames, target.as_str()),
+            _ => {}
+        }
+    }
+
+    serde_json::json!({
+        "is_mdns": !messages.is_empty(),
+        "hostnames": hostnames,
+        "addresses": addresses,
+        "service_types": service_types,
+        "instance_count": instances.len(),
+        "instances": instances,
+    })
+}
+
+/// `instance` is the full instance name, e.g. `Office._ipp._tcp.local`; its
+/// SRV and TXT records usually arrive as additional records.
+fn instance_data(instance: &str, service: &str, records: &[&Record]) -> Value {
+    let named = |record: &&&Record| record.name.eq_ignore_ascii_case(instance);
+    let (host, port) = records
+        .iter()
+        .filter(named)
+        .find_map(|record| match &record.data {
+            RData::Srv { port, target } => Some((target.as_str(), Some(*port))),
+            _ => None,
+        })
+        .unwrap_or(("", None));
+    let txt: Vec<&str> = records
+        .iter()
+        .filter(named)
+        .flat_map(|record| match &record.data {
+            RData::Txt(strings) => strings.iter().map(String::as_str).collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    let name = instance
+        .strip_suffix(service)
+        .map(|name| name.trim_end_matches('.'))
+        .unwrap_or(instance);
+
+    serde_json::json!({
+        "name": name,
+        "service": service,
+        "host": host,
+        "port": port,
+        "txt": txt,
+    })
+}
+
+/// Splits the client's two-byte length framed datagrams.
+fn frames(mut bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    std::iter::from_fn(move || {
+        let len = u16::from_be_bytes([*bytes.first()?, *bytes.get(1)?]) as usize;
+        let frame = bytes.get(2..2 + len)?;
+        bytes = &bytes[2 + len..];
+        Some(frame)
+    })
+}
+
+fn push_unique<T: PartialEq>(values: &mut Vec<T>, value: T) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::model::ScanOutcome;
+use serde_json::{json, Map, Value};
+
+pub(super) fn memcached_udp_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let fields = &outcome.fingerprint.fields;
+    let count = |key: &str| fields.get(key).and_then(|value| value.parse::<u64>().ok());
+    let udp_enabled = fields.get("udp_enabled").map(String::as_str) == Some("true");
+    let stats = parse_stats(&String::from_utf8_lossy(raw_bytes));
+
+    json!({
+        "udp_enabled": udp_enabled,
+        "amplification_factor": fields
+            .get("amplification_factor")
+            .and_then(|factor| factor.parse::<f64>().ok()),
+        "amplification_risk": if udp_enabled { "high" } else { "none" },
+        "request_bytes": count("request_bytes"),
+        "response_bytes": count("response_bytes"),
+        "datagrams_received": count("datagrams_received"),
+        "datagrams_expected": count("datagrams_expected"),
+        "version": stats.get("version").cloned().unwrap_or(Value::Null),
+        "stats": stats,
+    })
+}
+
+/// Pretty-output FINDING for a server that answers over UDP.
+pub(super) fn amplification_finding(outcome: &ScanOutcome) -> Option<String> {
+    let fields = &outcome.fingerprint.fields;
+    if fields.get("udp_enabled").map(String::as_str) != Some("true") {
+        return None;
+    }
+    let factor = fields
+        .get("amplification_factor")
+        .map_or("?", String::as_str);
+    Some(format!(
+        "[high] memcached answers over UDP, amplification factor {factor}x"
+    ))
+}
+
+/// `STAT <name> <value>` lines up to `END`.
+fn parse_stats(text: &str) -> Map<String, Value> {
+    text.lines()
+        .filter_map(|line| line.strip_prefix("STAT "))
+        .filter_map(|stat| stat.trim_end().split_once(' '))
+        .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+        .collect()
+}
@@ -0,0 +1,115 @@
+use crate::clients::mongodb::{BUILD_INFO_REQUEST, HELLO_REQUEST, IS_MASTER_REQUEST};
+use crate::util::bson::{self, Bson};
+use serde_json::Value;
+
+pub(super) fn mongodb_data(raw_bytes: &[u8]) -> Value {
+    let replies = bson::parse_replies(raw_bytes);
+    let reply_to = |request: i32| {
+        replies
+            .iter()
+            .find(|(response_to, _)| *response_to == request)
+            .map(|(_, body)| body.as_slice())
+    };
+    let ok = |body: &&[(String, Bson)]| bson::get(body, "ok").and_then(Bson::as_i64) == Some(1);
+
+    let (hello_command, hello) = match (
+        reply_to(HELLO_REQUEST).filter(ok),
+        reply_to(IS_MASTER_REQUEST).filter(ok),
+    ) {
+        (Some(hello), _) => ("hello", hello),
+        (None, Some(is_master)) => ("isMaster", is_master),
+        (None, None) => ("", &[][..]),
+    };
+    let string = |body: &[(String, Bson)], key: &str| {
+        bson::get(body, key)
+            .and_then(Bson::as_str)
+            .map(str::to_string)
+    };
+    let int = |body: &[(String, Bson)], key: &str| bson::get(body, key).and_then(Bson::as_i64);
+    let writable_primary = bson::get(hello, "isWritablePrimary")
+        .or_else(|| bson::get(hello, "ismaster"))
+        .and_then(Bson::as_bool);
+
+    // Refused with `ok: 0` (e.g. Unauthorized) when the deployment locks
+    // buildInfo behind authentication; the hello fields still stand.
+    let build_info_reply = reply_to(BUILD_INFO_REQUEST);
+    let build_info = build_info_reply.filter(ok).map(|body| {
+        let strings = |key: &str| -> Vec<String> {
+            bson::get(body, key)
+                .and_then(Bson::as_array)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(Bson::as_str)
+                .map(str::to_string)
+                .collect()
+        };
+        let modules = strings("modules");
+        serde_json::json!({
+            "version": string(body, "version"),
+            "git_version": string(body, "gitVersion"),
+            "storage_engines": strings("storageEngines"),
+            "enterprise": modules.iter().any(|module| module == "enterprise"),
+            "modules": modules,
+            "max_bson_object_size": int(body, "maxBsonObjectSize"),
+        })
+    });
+    let build_info_error = match (build_info_reply, bson::truncated_reply(raw_bytes)) {
+        (Some(body), _) => (!ok(&body)).then(|| string(body, "errmsg").unwrap_or_default()),
+        (None, Some((BUILD_INFO_REQUEST, len))) => Some(format!(
+            "reply of {len} bytes cut short by --max-bytes; raise --max-bytes-for mongodb"
+        )),
+        (None, _) => None,
+    };
+
+    serde_json::json!({
+        "hello_command": hello_command,
+        "is_writable_primary": writable_primary,
+        "set_name": string(hello, "setName"),
+        "msg": string(hello, "msg"),
+        "min_wire_version": int(hello, "minWireVersion"),
+        "max_wire_version": int(hello, "maxWireVersion"),
+        "max_bson_object_size": int(hello, "maxBsonObjectSize"),
+        "max_message_size_bytes": int(hello, "maxMessageSizeBytes"),
+        "build_info": build_info,
+        "build_info_error": build_info_error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An OP_MSG reply to `request_id` with the body `{ok: 1.0}`.
+    fn ok_reply(request_id: i32) -> Vec<u8> {
+        let mut message = 38i32.to_le_bytes().to_vec();
+        message.extend_from_slice(&9i32.to_le_bytes());
+        message.extend_from_slice(&request_id.to_le_bytes());
+        message.extend_from_slice(&2013i32.to_le_bytes());
+        message.extend_from_slice(&[0, 0, 0, 0, 0]);
+        message.extend_from_slice(&17i32.to_le_bytes());
+        message.extend_from_slice(b"\x01ok\0");
+        message.extend_from_slice(&1.0f64.to_le_bytes());
+        message.push(0);
+        message
+    }
+
+    #[test]
+    fn reports_a_build_info_reply_cut_short() {
+        let mut bytes = ok_reply(HELLO_REQUEST);
+        let build_info = ok_reply(BUILD_INFO_REQUEST);
+        bytes.extend_from_slice(&build_info[..30]);
+        let data = mongodb_data(&bytes);
+        assert_eq!(data["hello_command"], "hello");
+        assert_eq!(data["build_info"], Value::Null);
+        assert_eq!(
+            data["build_info_error"],
+            "reply of 38 bytes cut short by --max-bytes; raise --max-bytes-for mongodb"
+        );
+
+        bytes.truncate(38);
+        bytes.extend_from_slice(&build_info);
+        let data = mongodb_data(&bytes);
+        assert_eq!(data["build_info_error"], Value::Null);
+        assert!(data["build_info"].is_object());
+    }
+}
@@ -1,11 +1,8 @@
 use crate::model::ScanOutcome;
 use serde_json::Value;
 
-use super::common::decode_banner_raw_bytes;
-
-pub(super) fn mssql_data(outcome: &ScanOutcome) -> Value {
-    let raw_bytes = decode_banner_raw_bytes(&outcome.banner.raw_hex).unwrap_or_default();
-    let version_info = parse_mssql_prelogin_version(&raw_bytes);
+pub(super) fn mssql_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let version_info = parse_mssql_prelogin_version(raw_bytes);
     let version_json = match version_info {
         Some(info) => serde_json::json!({
             "name": info.name,
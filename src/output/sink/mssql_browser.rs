@@ -0,0 +1,87 @@
+use serde_json::Value;
+
+pub(super) fn mssql_browser_data(raw_bytes: &[u8]) -> Value {
+    // SVR_RESP: 0x05, u16 little-endian length, then the instance list.
+    let (is_browser, text) = match raw_bytes.split_first() {
+        Some((0x05, rest)) if rest.len() >= 2 => (true, String::from_utf8_lossy(&rest[2..])),
+        _ => (false, String::from_utf8_lossy(raw_bytes)),
+    };
+    let instances: Vec<Value> = parse_instances(&text)
+        .into_iter()
+        .map(|fields| {
+            let field = |name: &str| {
+                fields
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value.as_str())
+                    .unwrap_or_default()
+            };
+            serde_json::json!({
+                "server_name": field("ServerName"),
+                "instance_name": field("InstanceName"),
+                "is_clustered": field("IsClustered").eq_ignore_ascii_case("yes"),
+                "version": field("Version"),
+                "tcp_port": field("tcp").parse::<u16>().ok(),
+                "named_pipe": field("np"),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "is_browser": is_browser,
+        "instance_count": instances.len(),
+        "instances": instances,
+    })
+}
+
+/// Instances are `key;value;` pairs, each instance closed by an extra `;`:
+/// `ServerName;DB1;InstanceName;SQLEXPRESS;IsClustered;No;Version;15.0.2000.5;tcp;49733;;`.
+/// The pairs are read in order, so an empty value (`Version;;tcp;...`) is
+/// not mistaken for the end of the instance: only an empty key is.
+fn parse_instances(text: &str) -> Vec<Vec<(String, String)>> {
+    let mut instances = Vec::new();
+    let mut fields = Vec::new();
+    let mut tokens = text.split(';');
+    while let Some(key) = tokens.next() {
+        if key.is_empty() {
+            if !fields.is_empty() {
+                instances.push(std::mem::take(&mut fields));
+            }
+            continue;
+        }
+        let Some(value) = tokens.next() else {
+            break;
+        };
+        fields.push((key.to_string(), value.to_string()));
+    }
+    if !fields.is_empty() {
+        instances.push(fields);
+    }
+    instances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_instances_with_empty_values() {
+        let mut reply = vec![0x05, 0, 0];
+        reply.extend_from_slice(
+            b"ServerName;DB1;InstanceName;MSSQLSERVER;IsClustered;No;Version;;tcp;1433;;\
+              ServerName;DB1;InstanceName;SQLEXPRESS;IsClustered;Yes;Version;15.0.2000.5;\
+              tcp;49733;np;\\\\DB1\\pipe\\sql\\query;;",
+        );
+        let data = mssql_browser_data(&reply);
+        assert_eq!(data["is_browser"], true);
+        assert_eq!(data["instance_count"], 2);
+        let first = &data["instances"][0];
+        assert_eq!(first["version"], "");
+        assert_eq!(first["tcp_port"], 1433);
+        let second = &data["instances"][1];
+        assert_eq!(second["instance_name"], "SQLEXPRESS");
+        assert_eq!(second["is_clustered"], true);
+        assert_eq!(second["tcp_port"], 49733);
+        assert_eq!(second["named_pipe"], "\\\\DB1\\pipe\\sql\\query");
+    }
+}
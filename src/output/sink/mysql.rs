@@ -1,11 +1,9 @@
 use crate::model::ScanOutcome;
 use serde_json::Value;
 
-use super::common::decode_banner_raw_bytes;
-
-pub(super) fn mysql_data(outcome: &ScanOutcome) -> Value {
-    let raw_bytes = decode_banner_raw_bytes(&outcome.banner.raw_hex).unwrap_or_default();
-    let info = parse_mysql_greeting(&raw_bytes);
+pub(super) fn mysql_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let info = parse_mysql_greeting(raw_bytes);
+    let login = login_outcome(raw_bytes);
 
     let capabilities = info.capabilities.clone().unwrap_or_default();
     let status = info.status.map(|value| {
@@ -26,9 +24,86 @@ pub(super) fn mysql_data(outcome: &ScanOutcome) -> Value {
         "salt": info.salt.unwrap_or_default(),
         "auth_plugin_name": info.auth_plugin_name.unwrap_or_default(),
         "tcp_port": outcome.target.port,
+        "connection_status": login.status,
+        "refusal_reason": login.refusal_reason,
+        "error_code": login.error_code,
+        "error_message": login.error_message,
+        "anonymous_login": login.anonymous,
     })
 }
 
+#[derive(Default)]
+struct LoginOutcome {
+    status: &'static str,
+    refusal_reason: &'static str,
+    error_code: Option<u16>,
+    error_message: String,
+    anonymous: bool,
+}
+
+/// What the server did with the client's throwaway login: an `Access denied`
+/// still proves it would let a real client authenticate, while errors such
+/// as `Too many connections` or `Host is blocked` mean it will not, whether
+/// they replace the greeting or answer the login.
+fn login_outcome(raw_bytes: &[u8]) -> LoginOutcome {
+    let mut outcome = LoginOutcome {
+        status: "unknown",
+        ..LoginOutcome::default()
+    };
+    let Some(first) = extract_mysql_payload(raw_bytes) else {
+        return outcome;
+    };
+    let reply = if first.first() == Some(&0xff) {
+        first
+    } else {
+        match extract_mysql_payload(&raw_bytes[4 + first.len()..]) {
+            Some(reply) => reply,
+            None => return outcome,
+        }
+    };
+    match reply.first() {
+        Some(0x00) => {
+            outcome.status = "accepts_connections";
+            outcome.anonymous = true;
+        }
+        // Auth switch request: the server wants another plugin, i.e. auth goes on.
+        Some(0xfe) => outcome.status = "accepts_connections",
+        Some(0xff) => {
+            let code = read_u16_le(&reply, 1).unwrap_or_default();
+            let message = match reply.get(3) {
+                Some(b'#') => reply.get(9..).unwrap_or_default(),
+                _ => reply.get(3..).unwrap_or_default(),
+            };
+            outcome.error_code = Some(code);
+            outcome.error_message = String::from_utf8_lossy(message).into_owned();
+            match refusal_reason(code) {
+                Some(reason) => {
+                    outcome.status = "connection_refused_post_greeting";
+                    outcome.refusal_reason = reason;
+                }
+                None => outcome.status = "accepts_connections",
+            }
+        }
+        _ => {}
+    }
+    outcome
+}
+
+/// `None` for errors that come from authentication itself.
+fn refusal_reason(code: u16) -> Option<&'static str> {
+    match code {
+        // Access denied, unknown auth plugin, bad handshake, db access denied.
+        1043 | 1044 | 1045 | 1251 | 1524 => None,
+        1040 => Some("too_many_connections"),
+        1129 => Some("host_blocked"),
+        1130 => Some("host_not_allowed"),
+        1203 | 1226 => Some("user_limit_reached"),
+        3159 => Some("tls_required"),
+        1053 | 1077 => Some("shutting_down"),
+        _ => Some("error"),
+    }
+}
+
 #[derive(Default)]
 struct MysqlGreetingInfo {
     protocol: Option<u8>,
@@ -44,7 +119,8 @@ struct MysqlGreetingInfo {
 fn parse_mysql_greeting(raw_bytes: &[u8]) -> MysqlGreetingInfo {
     let mut info = MysqlGreetingInfo::default();
     let payload = extract_mysql_payload(raw_bytes).unwrap_or_else(|| raw_bytes.to_vec());
-    if payload.is_empty() {
+    // An ERR packet in place of the greeting is reported by `login_outcome`.
+    if payload.is_empty() || payload[0] == 0xff {
         return info;
     }
 
@@ -0,0 +1,55 @@
+use crate::model::ScanOutcome;
+use crate::util::mail_banner;
+use serde_json::Value;
+
+use super::common::{banner_text, plaintext_auth_allowed, raw_banner_for_data};
+
+pub(super) fn pop3_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let banner_raw =
+        banner_text(raw_bytes).unwrap_or_else(|| raw_banner_for_data(outcome, raw_bytes));
+    let mut lines = banner_raw.lines().map(|line| line.trim_end_matches('\r'));
+    let greeting = lines
+        .next()
+        .and_then(|line| line.strip_prefix("+OK"))
+        .map(str::trim)
+        .unwrap_or_default()
+        .to_string();
+
+    // The CAPA listing runs from its `+OK` line to the lone `.`.
+    let capabilities: Vec<String> = lines
+        .skip_while(|line| !line.starts_with("+OK"))
+        .skip(1)
+        .take_while(|line| *line != ".")
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect();
+    let sasl_mechanisms: Vec<String> = capabilities
+        .iter()
+        .filter_map(|cap| {
+            let (name, mechs) = cap.split_once(' ')?;
+            name.eq_ignore_ascii_case("SASL").then_some(mechs)
+        })
+        .flat_map(|mechs| mechs.split_whitespace().map(str::to_string))
+        .collect();
+    let supports_stls = capabilities
+        .iter()
+        .any(|cap| cap.eq_ignore_ascii_case("STLS"));
+    // USER/PASS sends the password as typed.
+    let user_command = capabilities
+        .iter()
+        .any(|cap| cap.eq_ignore_ascii_case("USER"));
+
+    let software = mail_banner::identify(&greeting);
+
+    serde_json::json!({
+        "banner": banner_raw,
+        "greeting": greeting,
+        "server_software": software.as_ref().map(|software| software.product),
+        "software_version": software.and_then(|software| software.version),
+        "capabilities": capabilities,
+        "sasl_mechanisms": sasl_mechanisms,
+        "supports_stls": supports_stls,
+        "user_command": user_command,
+        "plaintext_auth_allowed": plaintext_auth_allowed(outcome, &sasl_mechanisms, user_command),
+    })
+}
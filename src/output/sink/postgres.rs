@@ -0,0 +1,253 @@
+use crate::model::ScanOutcome;
+use serde_json::Value;
+
+pub(super) fn postgres_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let mut auth_method = "";
+    let mut sasl_mechanisms = Vec::new();
+    let mut error = ErrorFields::default();
+    let mut negotiation = None;
+    let mut status = ("unknown", "");
+    for (kind, body) in parse_postgres_messages(raw_bytes) {
+        match kind {
+            b'v' => negotiation = parse_negotiate_protocol_version(body),
+            b'R' => {
+                let code = body
+                    .get(..4)
+                    .map(|code| u32::from_be_bytes([code[0], code[1], code[2], code[3]]));
+                auth_method = code.map(auth_method_name).unwrap_or_default();
+                if code == Some(10) {
+                    sasl_mechanisms = body[4..]
+                        .split(|byte| *byte == 0)
+                        .filter(|name| !name.is_empty())
+                        .map(|name| String::from_utf8_lossy(name).into_owned())
+                        .collect();
+                }
+                status = ("accepts_connections", "");
+            }
+            b'E' => {
+                error = parse_error(body);
+                status = match refusal_reason(&error) {
+                    Some(reason) => ("connection_refused_post_greeting", reason),
+                    None => ("accepts_connections", ""),
+                };
+            }
+            _ => {}
+        }
+    }
+    let (status, refusal_reason) = status;
+    // Without NegotiateProtocolVersion the requested version was accepted;
+    // servers predating the message refuse it with the range they support.
+    let negotiated_protocol_version = match &negotiation {
+        Some(negotiation) => Some(negotiation.newest_version()),
+        None if !error.code.is_empty() => supported_version_from_error(&error),
+        None if status == "accepts_connections" => outcome
+            .fingerprint
+            .fields
+            .get("requested_protocol_version")
+            .cloned(),
+        None => None,
+    };
+    let unrecognized_protocol_options = negotiation
+        .map(|negotiation| negotiation.unrecognized_options)
+        .unwrap_or_default();
+
+    serde_json::json!({
+        "connection_status": status,
+        "refusal_reason": refusal_reason,
+        "auth_method": auth_method,
+        "sasl_mechanisms": sasl_mechanisms,
+        "trust_auth": auth_method == "ok",
+        "severity": error.severity,
+        "error_code": error.code,
+        "error_message": error.message,
+        "negotiated_protocol_version": negotiated_protocol_version,
+        "unrecognized_protocol_options": unrecognized_protocol_options,
+    })
+}
+
+/// Splits backend messages (`type byte, Int32 length, body`); the last body
+/// is cut short when the read was.
+fn parse_postgres_messages(bytes: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut messages = Vec::new();
+    let mut rest = bytes;
+    while rest.len() >= 5 {
+        let len = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+        if len < 4 {
+            break;
+        }
+        let end = (1 + len).min(rest.len());
+        messages.push((rest[0], &rest[5..end]));
+        rest = &rest[end..];
+    }
+    messages
+}
+
+struct ProtocolNegotiation {
+    newest: u32,
+    unrecognized_options: Vec<String>,
+}
+
+impl ProtocolNegotiation {
+    /// The protocol documents a bare minor version, but servers send the
+    /// whole version number (`3 << 16 | minor`); accept both.
+    fn newest_version(&self) -> String {
+        match self.newest >> 16 {
+            0 => format!("3.{}", self.newest),
+            major => format!("{major}.{}", self.newest & 0xffff),
+        }
+    }
+}
+
+/// NegotiateProtocolVersion body: the newest version the server supports
+/// for the requested major, then the count and names of the
+/// `_pq_.` startup options it did not recognise.
+fn parse_negotiate_protocol_version(body: &[u8]) -> Option<ProtocolNegotiation> {
+    let newest = u32::from_be_bytes(body.get(..4)?.try_into().ok()?);
+    let count = u32::from_be_bytes(body.get(4..8)?.try_into().ok()?) as usize;
+    let unrecognized_options = body[8..]
+        .split(|byte| *byte == 0)
+        .filter(|name| !name.is_empty())
+        .take(count)
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect();
+    Some(ProtocolNegotiation {
+        newest,
+        unrecognized_options,
+    })
+}
+
+/// "unsupported frontend protocol 3.2: server supports 3.0 to 3.0" names
+/// the newest version at the end.
+fn supported_version_from_error(error: &ErrorFields) -> Option<String> {
+    if !error.message.starts_with("unsupported frontend protocol") {
+        return None;
+    }
+    let (_, newest) = error.message.rsplit_once(" to ")?;
+    Some(newest.trim().to_string())
+}
+
+#[derive(Default)]
+struct ErrorFields {
+    severity: String,
+    code: String,
+    message: String,
+}
+
+/// ErrorResponse body: `(field_type byte, cstring)*` terminated by a zero byte.
+fn parse_error(body: &[u8]) -> ErrorFields {
+    let mut fields = ErrorFields::default();
+    let mut rest = body;
+    while let Some((&kind, tail)) = rest.split_first() {
+        if kind == 0 {
+            break;
+        }
+        let end = tail
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(tail.len());
+        let value = String::from_utf8_lossy(&tail[..end]).into_owned();
+        match kind {
+            b'S' => fields.severity = value,
+            b'C' => fields.code = value,
+            b'M' => fields.message = value,
+            _ => {}
+        }
+        rest = tail.get(end + 1..).unwrap_or_default();
+    }
+    fields
+}
+
+/// `None` when the error comes from authenticating our made-up role, which
+/// means a real client would have been let through to log in.
+fn refusal_reason(error: &ErrorFields) -> Option<&'static str> {
+    match error.code.as_str() {
+        // Only our protocol version was refused; a 3.0 client gets in.
+        "0A000" if error.message.starts_with("unsupported frontend protocol") => None,
+        "53300" => Some("too_many_connections"),
+        "28000" if error.message.contains("pg_hba.conf") => Some("host_not_allowed"),
+        "28000" | "28P01" | "3D000" => None,
+        "57P03" => Some("not_ready"),
+        _ => Some("error"),
+    }
+}
+
+fn auth_method_name(code: u32) -> &'static str {
+    match code {
+        0 => "ok",
+        2 => "kerberos_v5",
+        3 => "cleartext_password",
+        5 => "md5_password",
+        7 => "gss",
+        9 => "sspi",
+        10 => "sasl",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{AddressFamily, Fingerprint, Protocol, Status, TargetView, TcpMeta};
+    use crate::util::hex::to_hex;
+
+    fn outcome(bytes: &[u8]) -> ScanOutcome {
+        let mut fingerprint = Fingerprint::from_protocol(&Protocol::Postgres);
+        fingerprint
+            .fields
+            .insert("requested_protocol_version".into(), "3.2".into());
+        ScanOutcome {
+            target: TargetView {
+                host: "db".into(),
+                addr: "192.0.2.5".into(),
+                port: 5432,
+                family: Some(AddressFamily::V4),
+            },
+            status: Status::Open,
+            tcp: TcpMeta {
+                connect_ms: Some(1),
+                error: None,
+            },
+            banner: crate::model::Banner {
+                raw_hex: to_hex(bytes),
+                ..Default::default()
+            },
+            timestamp: Default::default(),
+            ttl: None,
+            webdriver: None,
+            technologies: None,
+            tls_info: None,
+            fingerprint,
+            diagnostics: None,
+            proxy_protocol_accepted: None,
+            ptr: None,
+        }
+    }
+
+    #[test]
+    fn decodes_negotiate_protocol_version_before_authentication() {
+        let mut bytes = b"v\0\0\0\x1d\0\x03\0\0\0\0\0\x01_pq_.compression\0".to_vec();
+        bytes.extend_from_slice(b"R\0\0\0\x08\0\0\0\x05");
+        let data = postgres_data(&outcome(&bytes), &bytes);
+        assert_eq!(data["negotiated_protocol_version"], "3.0");
+        assert_eq!(
+            data["unrecognized_protocol_options"],
+            serde_json::json!(["_pq_.compression"])
+        );
+        assert_eq!(data["auth_method"], "md5_password");
+        assert_eq!(data["connection_status"], "accepts_connections");
+    }
+
+    #[test]
+    fn reads_the_supported_range_from_old_servers() {
+        let bytes = b"E\0\0\0\x52SFATAL\0C0A000\0Munsupported frontend protocol 3.2: \
+            server supports 2.0 to 3.0\0\0";
+        let data = postgres_data(&outcome(bytes), bytes);
+        assert_eq!(data["negotiated_protocol_version"], "3.0");
+        assert_eq!(data["connection_status"], "accepts_connections");
+
+        let auth_ok = b"R\0\0\0\x08\0\0\0\0";
+        let accepted = postgres_data(&outcome(auth_ok), auth_ok);
+        assert_eq!(accepted["negotiated_protocol_version"], "3.2");
+        assert_eq!(accepted["trust_auth"], true);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::model::ScanOutcome;
+use serde_json::Value;
+
+use super::common::{banner_text, raw_banner_for_data};
+
+pub(super) fn redis_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let fields = &outcome.fingerprint.fields;
+    let field = |key: &str| fields.get(key).map(String::as_str);
+    let flag = |key: &str| field(key).map(|value| value == "true");
+    let banner_raw =
+        banner_text(raw_bytes).unwrap_or_else(|| raw_banner_for_data(outcome, raw_bytes));
+    // INFO lists `name:value` pairs under `# Section` headings.
+    let info = |key: &str| {
+        banner_raw
+            .lines()
+            .find_map(|line| line.trim_end().strip_prefix(key)?.strip_prefix(':'))
+            .map(str::to_string)
+    };
+    let dangerous_commands: Vec<&str> = field("dangerous_commands")
+        .map(|commands| commands.split(',').filter(|c| !c.is_empty()).collect())
+        .unwrap_or_default();
+
+    serde_json::json!({
+        "banner": banner_raw,
+        "version": info("redis_version"),
+        "mode": info("redis_mode"),
+        "os": info("os"),
+        "unauthenticated": flag("unauthenticated"),
+        "protected_mode": flag("protected_mode").unwrap_or(false),
+        "requirepass_empty": flag("requirepass_empty"),
+        "config_dir": field("config_dir"),
+        "dangerous_commands": dangerous_commands,
+    })
+}
+
+/// Pretty-output FINDING for a server that takes commands without AUTH.
+pub(super) fn exposure_finding(outcome: &ScanOutcome) -> Option<String> {
+    let fields = &outcome.fingerprint.fields;
+    if fields.get("unauthenticated").map(String::as_str) != Some("true") {
+        return None;
+    }
+    let mut finding = "[critical] Redis accepts commands without authentication".to_string();
+    if let Some(commands) = fields.get("dangerous_commands").filter(|c| !c.is_empty()) {
+        finding.push_str(&format!("; reachable: {}", commands.replace(',', ", ")));
+    }
+    Some(finding)
+}
@@ -3,11 +3,8 @@ use serde_json::Value;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::OnceLock;
 
-use super::common::decode_banner_raw_bytes;
-
-pub(super) fn rpcbind_data(outcome: &ScanOutcome) -> Value {
-    let raw_bytes = decode_banner_raw_bytes(&outcome.banner.raw_hex).unwrap_or_default();
-    let programs = parse_rpcbind_dump(&raw_bytes);
+pub(super) fn rpcbind_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let programs = parse_rpcbind_dump(raw_bytes);
     serde_json::json!({
         "programs": programs,
         "tcp_port": outcome.target.port,
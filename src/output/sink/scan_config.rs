@@ -0,0 +1,157 @@
+use super::SCHEMA_VERSION;
+use crate::model::{Config, ScanMode};
+use clap::ValueEnum;
+use serde_json::Value;
+
+/// The `--emit-config` header record: the effective settings of the scan,
+/// written before any outcome. Secrets (proxy credentials, the client key)
+/// are reduced to whether they were set.
+pub(crate) fn scan_config_record(cfg: &Config) -> Value {
+    let millis = |duration: std::time::Duration| duration.as_millis() as u64;
+    let protocols: Vec<String> = cfg.protocols.iter().map(ToString::to_string).collect();
+    let protocol_by_port: serde_json::Map<String, Value> = cfg
+        .protocol_by_port
+        .iter()
+        .map(|(port, protocol)| (port.to_string(), protocol.to_string().into()))
+        .collect();
+    let proxy = cfg.proxy.as_ref().map(|proxy| {
+        serde_json::json!({
+            "kind": format!("{:?}", proxy.kind).to_lowercase(),
+            "addr": proxy.addr,
+            "authenticated": proxy.username.is_some(),
+        })
+    });
+    let flags = serde_json::json!({
+        "webdriver": cfg.webdriver,
+        "tech": cfg.tech,
+        "tech_rules": cfg.tech_rules,
+        "tls_resumption": cfg.tls_resumption,
+        "tls_reneg": cfg.tls_reneg,
+        "tls_groups": cfg.tls_groups,
+        "record_probe": cfg.record_probe,
+        "ssh_auth_methods": cfg.ssh_auth_methods,
+        "nudge": cfg.nudge,
+        "starttls": cfg.starttls,
+        "test_smuggling": cfg.test_smuggling,
+        "test_open_proxy": cfg.test_open_proxy,
+        "strict_resolution": cfg.strict_resolution,
+        "dedupe": cfg.dedupe,
+        "dedupe_resolved": cfg.dedupe_resolved,
+        "fail_fast": cfg.fail_fast,
+        "exit_code_on_open": cfg.exit_code_on_open,
+        "reverse_dns": cfg.output.reverse_dns,
+        "dedupe_http_bodies": cfg.output.dedupe_http_bodies,
+        "group_by_host": cfg.output.group_by_host,
+    });
+
+    let mut record = serde_json::json!({
+        "record_type": "scan_config",
+        "schema_version": SCHEMA_VERSION,
+        "tool_version": env!("CARGO_PKG_VERSION"),
+        "started_at": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "mode": match cfg.mode {
+            ScanMode::Active => "active",
+            ScanMode::Passive => "passive",
+        },
+        "protocols": protocols,
+        "protocol_by_port": protocol_by_port,
+        "target": cfg.target,
+        "input": cfg.input,
+        "input_format": cfg
+            .input_format
+            .to_possible_value()
+            .map(|value| value.get_name().to_string()),
+        "port_filter": cfg.port_filter,
+        "replay": cfg.replay,
+        "concurrency": cfg.concurrency,
+        "rate": cfg.rate,
+        "jitter": cfg.jitter,
+        "probe_timeout_jitter": cfg.probe_timeout_jitter,
+        "ramp_ms": cfg.ramp.map(millis),
+        "connect_timeout_ms": millis(cfg.connect_timeout),
+        "read_timeout_ms": millis(cfg.read_timeout),
+        "overall_timeout_ms": millis(cfg.overall_timeout),
+        "deadline_ms": cfg.deadline.map(millis),
+        "repeat_ms": cfg.repeat.map(millis),
+        "repeat_count": cfg.repeat_count,
+        "max_bytes": cfg.max_bytes,
+        "max_bytes_for": cfg.max_bytes_for,
+        "max_read_rounds": cfg.max_read_rounds,
+        "udp_retries": cfg.udp_retries,
+        "active_ports": cfg.active_ports,
+        "peek_bytes": cfg.peek_bytes,
+        "max_body": cfg.max_body,
+        "http_accept_status": cfg.http_accept_status,
+        "cert_warn_days": cfg.cert_warn_days,
+        "client_cert": cfg.client_cert.is_some(),
+    });
+    // Split in two: one `json!` this long exceeds the macro recursion limit.
+    let rest = serde_json::json!({
+        "proxy": proxy,
+        "resolvers": cfg.resolvers,
+        "host_discovery_ms": cfg.host_discovery.map(millis),
+        "test_proxy_protocol": cfg.test_proxy_protocol,
+        "smtp_users": cfg.smtp_users,
+        "mssql_instance": cfg.mssql_instance,
+        "implicit_tls_ports": cfg.implicit_tls_ports,
+        "http_paths": cfg.http_paths,
+        "flags": flags,
+        "scan_id": cfg.output.scan_id,
+        "tags": cfg.output.tags,
+    });
+    if let (Some(record), Value::Object(rest)) = (record.as_object_mut(), rest) {
+        record.extend(rest);
+    }
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clients::mock;
+    use crate::model::{ClientCert, Protocol, ProxyConfig, ProxyKind};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::x509::X509;
+
+    #[test]
+    fn reports_secrets_only_as_set() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut leaf = X509::builder().unwrap();
+        leaf.set_pubkey(&key).unwrap();
+        leaf.sign(&key, MessageDigest::sha256()).unwrap();
+        let key_pem = String::from_utf8(key.private_key_to_pem_pkcs8().unwrap()).unwrap();
+
+        let mut cfg = mock::config(Protocol::Http);
+        cfg.proxy = Some(ProxyConfig {
+            kind: ProxyKind::Socks5,
+            addr: "proxy.test:1080".into(),
+            username: Some("scanner".into()),
+            password: Some("hunter2".into()),
+        });
+        cfg.client_cert = Some(ClientCert {
+            leaf: leaf.build(),
+            chain: Vec::new(),
+            key,
+        });
+        let record = scan_config_record(&cfg);
+        assert_eq!(record["record_type"], "scan_config");
+        assert_eq!(record["schema_version"], SCHEMA_VERSION);
+        assert_eq!(
+            record["proxy"],
+            serde_json::json!({"kind": "socks5", "addr": "proxy.test:1080", "authenticated": true})
+        );
+        assert_eq!(record["client_cert"], true);
+        let text = record.to_string();
+        assert!(!text.contains("scanner") && !text.contains("hunter2"));
+        let key_body = key_pem.lines().nth(1).unwrap();
+        assert!(!text.contains(key_body));
+
+        let record = scan_config_record(&mock::config(Protocol::Http));
+        assert_eq!(record["proxy"], Value::Null);
+        assert_eq!(record["client_cert"], false);
+    }
+}
@@ -0,0 +1,207 @@
+//! JSON Schema of the JSONL records, printed by `--output-schema`.
+//!
+//! The `data` objects are built with `json!`, so their schemas are derived
+//! from what each formatter returns for an empty outcome: every key a
+//! formatter always writes is listed with the type of its default value.
+//! Keys only present for some answers are not listed, and `null` defaults
+//! are left untyped.
+
+use super::{protocol_data, SCHEMA_VERSION};
+use crate::model::{Banner, Fingerprint, ScanOutcome, Status, TargetView, TcpMeta};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+
+/// Every `proto` value with a dedicated `data` formatter. Aliases (`https`,
+/// `ms-sql-s`) share their protocol's formatter.
+const PROTOCOLS: &[&str] = &[
+    "bitcoin",
+    "coap",
+    "ethereum",
+    "ftp",
+    "grpc",
+    "http",
+    "https",
+    "imap",
+    "imaps",
+    "kafka",
+    "llmnr",
+    "mdns",
+    "memcached-udp",
+    "mongodb",
+    "ms-sql-s",
+    "mssql",
+    "mssql-browser",
+    "mysql",
+    "pop3",
+    "pop3s",
+    "postgres",
+    "redis",
+    "rpcbind",
+    "smtp",
+    "smtps",
+    "ssh",
+    "telnet",
+    "tls",
+    "vnc",
+    "winrm",
+];
+
+/// Schema of one JSONL record, with the `data` object selected by `proto`.
+pub fn output_schema() -> Value {
+    let mut defs = Map::new();
+    let mut cases = Vec::new();
+    for proto in PROTOCOLS {
+        defs.insert(
+            (*proto).into(),
+            value_schema(&protocol_data(proto, &empty_outcome(proto), &[])),
+        );
+        cases.push(json!({
+            "if": { "properties": { "proto": { "const": proto } } },
+            "then": { "properties": { "data": { "$ref": format!("#/$defs/{proto}") } } },
+        }));
+    }
+    defs.insert(
+        "raw_banner".into(),
+        value_schema(&protocol_data("unknown", &empty_outcome("unknown"), &[])),
+    );
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "banner-grabber JSONL record",
+        "description": "Protocols without a $defs entry carry the raw_banner data object; \
+            `--protocol tcp` records carry state and connect_ms instead of data, \
+            read_reason and had_data. \
+            With --group-by-host, records lose `host` and are nested in \
+            `{record_type: \"host\", schema_version, host, addresses: [record, ...]}` lines. \
+            With --emit-config, a `{record_type: \"scan_config\", ...}` header comes first. \
+            With --count-only, records are replaced by one closing \
+            `{record_type: \"tally\", schema_version, ports: [...], total}` summary.",
+        "schema_version": SCHEMA_VERSION,
+        "type": "object",
+        "required": ["record_type", "schema_version", "timestamp", "port", "proto", "ttl"],
+        "properties": {
+            "record_type": { "const": "outcome" },
+            "schema_version": { "type": "string", "const": SCHEMA_VERSION },
+            "ip": { "type": "string", "description": "absent when a remote-DNS proxy resolved the host, or when it did not resolve" },
+            "host": { "type": "string", "description": "input hostname, when it was not an address" },
+            "family": { "enum": ["v4", "v6"] },
+            "timestamp": {
+                "type": ["string", "integer"],
+                "description": "RFC 3339, or epoch milliseconds/seconds with --timestamp-format",
+            },
+            "port": { "type": "integer" },
+            "proto": { "type": "string" },
+            "ttl": { "type": ["integer", "null"] },
+            "data": { "type": "object" },
+            "state": { "enum": ["open", "closed", "filtered", "error"] },
+            "connect_ms": { "type": "integer" },
+            "read_reason": {
+                "enum": ["NotStarted", "ConnectionClosed", "Delimiter", "SizeLimit", "PeekLimit", "Timeout", "RoundLimit"],
+            },
+            "had_data": { "type": "boolean" },
+            "peek_protocol": { "type": "string" },
+            "probe_sent_hex": { "type": "string", "description": "only with --record-probe" },
+            "udp_attempts": { "type": "integer", "minimum": 2, "description": "UDP probes resent per --udp-retries" },
+            "matched_protocol": { "type": "string", "description": "only with a --protocol list" },
+            "starttls": {
+                "type": "object",
+                "description": "only with --starttls, for greetings guessed as smtp, imap, pop3 or ftp",
+                "properties": {
+                    "protocol": { "enum": ["smtp", "imap", "pop3", "ftp"] },
+                    "capabilities": { "type": "array", "items": { "type": "string" } },
+                    "offered": { "type": ["boolean", "null"] },
+                    "tls_info": { "type": ["object", "null"] },
+                    "error": { "type": ["string", "null"] },
+                },
+            },
+            "implicit_tls": {
+                "type": "object",
+                "description": "certificate of the TLS session a client ran inside, on --implicit-tls-ports",
+            },
+            "duplicate_of": { "type": "string", "description": "host:port first returning this HTTP response, with --dedupe-http-bodies" },
+            "error_kind": {
+                "enum": [
+                    "ConnectTimeout", "ConnectionRefused", "ConnectionReset", "HostUnreachable",
+                    "TlsHandshakeFailed", "ReadTimeout", "OverallTimeout", "ProxyFailed",
+                    "ResolutionFailed", "ProtocolError", "Other",
+                ],
+            },
+            "severity": { "enum": ["Info", "Warning", "Error"] },
+            "proxy_protocol_accepted": { "type": "boolean" },
+            "ptr": { "type": "string" },
+            "scan_id": { "type": "string" },
+            "tags": { "type": "object", "additionalProperties": { "type": "string" } },
+        },
+        "allOf": cases,
+        "$defs": defs,
+    })
+}
+
+pub(super) fn empty_outcome(proto: &str) -> ScanOutcome {
+    ScanOutcome {
+        target: TargetView {
+            host: String::new(),
+            addr: String::new(),
+            port: 0,
+            family: None,
+        },
+        status: Status::Open,
+        tcp: TcpMeta {
+            connect_ms: None,
+            error: None,
+        },
+        banner: Banner::default(),
+        timestamp: Default::default(),
+        ttl: None,
+        webdriver: None,
+        technologies: None,
+        tls_info: None,
+        fingerprint: Fingerprint {
+            protocol: Some(proto.into()),
+            score: 1.0,
+            fields: BTreeMap::new(),
+        },
+        diagnostics: None,
+        proxy_protocol_accepted: None,
+        ptr: None,
+    }
+}
+
+fn value_schema(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({}),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(number) if number.is_f64() => json!({ "type": "number" }),
+        Value::Number(_) => json!({ "type": "integer" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(items) => match items.first() {
+            Some(item) => json!({ "type": "array", "items": value_schema(item) }),
+            None => json!({ "type": "array" }),
+        },
+        Value::Object(map) => {
+            let properties: Map<String, Value> = map
+                .iter()
+                .map(|(key, value)| (key.clone(), value_schema(value)))
+                .collect();
+            json!({ "type": "object", "properties": properties })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_every_protocol_data_object() {
+        let schema = output_schema();
+        let ssh = &schema["$defs"]["ssh"]["properties"];
+        assert_eq!(ssh["banner"]["type"], "string");
+        assert_eq!(ssh["key_exchange"]["type"], "array");
+        assert_eq!(ssh["software"]["properties"]["product"]["type"], "string");
+        assert_eq!(schema["allOf"].as_array().unwrap().len(), PROTOCOLS.len());
+        for proto in PROTOCOLS {
+            assert_eq!(schema["$defs"][proto]["type"], "object", "{proto}");
+        }
+        assert_eq!(schema["$defs"]["ms-sql-s"], schema["$defs"]["mssql"]);
+    }
+}
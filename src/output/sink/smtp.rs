@@ -0,0 +1,100 @@
+use crate::model::ScanOutcome;
+use crate::util::mail_banner;
+use serde_json::Value;
+
+use super::common::{banner_text, plaintext_auth_allowed, raw_banner_for_data};
+
+pub(super) fn smtp_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let banner_raw =
+        banner_text(raw_bytes).unwrap_or_else(|| raw_banner_for_data(outcome, raw_bytes));
+    let mut greeting = String::new();
+    let mut extensions: Vec<String> = Vec::new();
+    let mut seen_ehlo = false;
+
+    for line in banner_raw.lines().map(|line| line.trim_end_matches('\r')) {
+        let Some((code, text)) = split_reply(line) else {
+            continue;
+        };
+        match code {
+            "220" if greeting.is_empty() => greeting = text.to_string(),
+            // The first 250 line of the EHLO reply is the server's hostname;
+            // every later one names an extension.
+            "250" if !seen_ehlo => seen_ehlo = true,
+            "250" => extensions.push(text.to_string()),
+            _ => {}
+        }
+    }
+
+    let mut auth_mechanisms: Vec<String> = Vec::new();
+    for ext in &extensions {
+        let upper = ext.to_ascii_uppercase();
+        // Old clients only understood the `AUTH=` spelling, so some servers
+        // still list both.
+        let Some(mechs) = upper
+            .strip_prefix("AUTH ")
+            .or_else(|| upper.strip_prefix("AUTH="))
+        else {
+            continue;
+        };
+        for mech in mechs.split_whitespace() {
+            if !auth_mechanisms.iter().any(|known| known == mech) {
+                auth_mechanisms.push(mech.to_string());
+            }
+        }
+    }
+    let supports_starttls = extensions
+        .iter()
+        .any(|ext| ext.eq_ignore_ascii_case("STARTTLS"));
+
+    let user_enum: Vec<Value> = outcome
+        .fingerprint
+        .fields
+        .iter()
+        .filter_map(|(key, value)| {
+            let user = key.strip_prefix("user_enum.")?;
+            let (method, code) = value.split_once(' ')?;
+            let result = match code {
+                "250" | "251" => "exists",
+                "252" => "cannot_verify",
+                "550" | "551" | "553" => "rejected",
+                _ => "error",
+            };
+            Some(serde_json::json!({
+                "user": user,
+                "method": method,
+                "code": code.parse::<u16>().ok(),
+                "result": result,
+            }))
+        })
+        .collect();
+    let user_enum_complete = outcome
+        .fingerprint
+        .fields
+        .get("user_enum_complete")
+        .map(|value| value == "true");
+
+    let software = mail_banner::identify(&greeting);
+
+    serde_json::json!({
+        "banner": banner_raw,
+        "greeting": greeting,
+        "server_software": software.as_ref().map(|software| software.product),
+        "software_version": software.and_then(|software| software.version),
+        "extensions": extensions,
+        "auth_mechanisms": auth_mechanisms,
+        "supports_starttls": supports_starttls,
+        "plaintext_auth_allowed": plaintext_auth_allowed(outcome, &auth_mechanisms, false),
+        "user_enum": user_enum,
+        "user_enum_complete": user_enum_complete,
+    })
+}
+
+/// Splits `250-PIPELINING` or `220 mx.example ESMTP` into code and text.
+fn split_reply(line: &str) -> Option<(&str, &str)> {
+    let code = line.get(..3)?;
+    if !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let text = line.get(4..).unwrap_or_default().trim();
+    Some((code, text))
+}
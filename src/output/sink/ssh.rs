@@ -1,20 +1,19 @@
 use crate::model::ScanOutcome;
 use serde_json::Value;
 
-use super::common::decode_banner_raw_bytes;
-
-pub(super) fn ssh_data(outcome: &ScanOutcome) -> Value {
-    let raw_bytes = decode_banner_raw_bytes(&outcome.banner.raw_hex).unwrap_or_default();
-    let banner_raw = String::from_utf8_lossy(&raw_bytes).to_string();
+pub(super) fn ssh_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let banner_raw = String::from_utf8_lossy(raw_bytes).to_string();
     let banner = extract_ssh_banner_line(&banner_raw)
         .unwrap_or_else(|| banner_raw.trim_end_matches(&['\r', '\n'][..]).to_string());
     let (product, version, os) = parse_ssh_software(&banner);
-    let kex = parse_ssh_kexinit(&raw_bytes).unwrap_or_default();
+    let kex = parse_ssh_kexinit(raw_bytes).unwrap_or_default();
     let compression_algorithms = merge_algorithms(
         &kex.compression_algorithms_client_to_server,
         &kex.compression_algorithms_server_to_client,
     );
     let weak_algorithms = collect_weak_algorithms(&kex);
+    let fields = &outcome.fingerprint.fields;
+    let auth_methods = auth_methods(outcome);
     serde_json::json!({
         "banner_raw": banner_raw,
         "banner": banner,
@@ -30,11 +29,23 @@ pub(super) fn ssh_data(outcome: &ScanOutcome) -> Value {
         "mac_algorithms_client_to_server": kex.mac_algorithms_client_to_server,
         "mac_algorithms_server_to_client": kex.mac_algorithms_server_to_client,
         "compression_algorithms": compression_algorithms,
+        "first_kex_packet_follows": kex.first_kex_packet_follows,
+        "kex_guess_sent": kex.kex_guess_sent,
+        "kex_reserved": kex.reserved,
         "strict_key_exchange": kex
             .key_exchange
             .iter()
             .any(|algo| algo == "kex-strict-s-v00@openssh.com"),
         "weak_algorithms": weak_algorithms,
+        "auth_methods": auth_methods,
+        "password_auth_enabled": auth_methods
+            .as_ref()
+            .map(|methods| methods.iter().any(|method| method == "password")),
+        "none_auth_accepted": fields
+            .get("auth_none_accepted")
+            .map(|accepted| accepted == "true"),
+        "auth_banner": fields.get("auth_banner"),
+        "auth_methods_error": fields.get("auth_methods_error"),
         "fingerprint": {
             "rsa": "",
             "ecdsa": "",
@@ -43,6 +54,37 @@ pub(super) fn ssh_data(outcome: &ScanOutcome) -> Value {
     })
 }
 
+/// Methods from the `--ssh-auth-methods` exchange; `None` when it did not
+/// run or failed.
+fn auth_methods(outcome: &ScanOutcome) -> Option<Vec<String>> {
+    let methods = outcome.fingerprint.fields.get("auth_methods")?;
+    Some(
+        methods
+            .split(',')
+            .filter(|method| !method.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Authentication weaknesses `--ssh-auth-methods` found, for pretty output.
+pub(super) fn auth_findings(outcome: &ScanOutcome) -> Vec<&'static str> {
+    let mut findings = Vec::new();
+    if outcome
+        .fingerprint
+        .fields
+        .get("auth_none_accepted")
+        .map(String::as_str)
+        == Some("true")
+    {
+        findings.push("SSH accepts logins with no authentication");
+    }
+    if auth_methods(outcome).is_some_and(|methods| methods.iter().any(|m| m == "password")) {
+        findings.push("SSH password authentication enabled");
+    }
+    findings
+}
+
 #[derive(Default)]
 struct SshKexInitData {
     key_exchange: Vec<String>,
@@ -53,6 +95,14 @@ struct SshKexInitData {
     mac_algorithms_server_to_client: Vec<String>,
     compression_algorithms_client_to_server: Vec<String>,
     compression_algorithms_server_to_client: Vec<String>,
+    /// The KEXINIT flag promising a guessed key exchange packet; `None`
+    /// when the payload ended before it.
+    first_kex_packet_follows: Option<bool>,
+    /// The flag is set and a key exchange method message (30-49) follows
+    /// the KEXINIT in the capture.
+    kex_guess_sent: Option<bool>,
+    /// Reserved for future extension and zero in every known server.
+    reserved: Option<u32>,
 }
 
 fn extract_ssh_banner_line(raw: &str) -> Option<String> {
@@ -116,13 +166,28 @@ fn parse_ssh_kexinit(bytes: &[u8]) -> Option<SshKexInitData> {
         }
         let payload = &bytes[payload_start..payload_end];
         if payload.first() == Some(&20) {
-            return parse_ssh_kexinit_payload(payload);
+            let mut kex = parse_ssh_kexinit_payload(payload)?;
+            if kex.first_kex_packet_follows == Some(true) {
+                let next = next_packet_type(bytes, packet_end);
+                kex.kex_guess_sent = Some(next.is_some_and(|kind| (30..=49).contains(&kind)));
+            }
+            return Some(kex);
         }
         pos = packet_end;
     }
     None
 }
 
+/// Message number of the binary packet starting at `pos`, when captured.
+fn next_packet_type(bytes: &[u8], pos: usize) -> Option<u8> {
+    let packet_len = read_u32(bytes, pos)? as usize;
+    let padding_len = *bytes.get(pos + 4)? as usize;
+    if packet_len <= padding_len + 1 {
+        return None;
+    }
+    bytes.get(pos + 5).copied()
+}
+
 fn parse_ssh_kexinit_payload(payload: &[u8]) -> Option<SshKexInitData> {
     if payload.len() < 17 {
         return None;
@@ -138,6 +203,9 @@ fn parse_ssh_kexinit_payload(payload: &[u8]) -> Option<SshKexInitData> {
     let compression_algorithms_server_to_client = parse_name_list(payload, &mut idx)?;
     let _languages_client_to_server = parse_name_list(payload, &mut idx)?;
     let _languages_server_to_client = parse_name_list(payload, &mut idx)?;
+    // boolean first_kex_packet_follows, uint32 reserved (RFC 4253 7.1).
+    let first_kex_packet_follows = payload.get(idx).map(|flag| *flag != 0);
+    let reserved = read_u32(payload, idx + 1);
     Some(SshKexInitData {
         key_exchange,
         server_host_key_algorithms,
@@ -147,6 +215,9 @@ fn parse_ssh_kexinit_payload(payload: &[u8]) -> Option<SshKexInitData> {
         mac_algorithms_server_to_client,
         compression_algorithms_client_to_server,
         compression_algorithms_server_to_client,
+        first_kex_packet_follows,
+        kex_guess_sent: first_kex_packet_follows.map(|_| false),
+        reserved,
     })
 }
 
@@ -212,3 +283,64 @@ fn collect_weak_algorithms(kex: &SshKexInitData) -> Vec<String> {
     }
     found
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A binary packet with four bytes of padding around `payload`.
+    fn packet(payload: &[u8]) -> Vec<u8> {
+        let mut packet = ((payload.len() + 5) as u32).to_be_bytes().to_vec();
+        packet.push(4);
+        packet.extend_from_slice(payload);
+        packet.extend_from_slice(&[0; 4]);
+        packet
+    }
+
+    fn kexinit(first_kex_packet_follows: bool) -> Vec<u8> {
+        let mut payload = vec![20];
+        payload.extend_from_slice(&[0xaa; 16]);
+        let lists = [
+            "curve25519-sha256",
+            "ssh-ed25519",
+            "aes128-ctr",
+            "aes128-ctr",
+            "hmac-sha2-256",
+            "hmac-sha2-256",
+            "none",
+            "none",
+            "",
+            "",
+        ];
+        for list in lists {
+            payload.extend_from_slice(&(list.len() as u32).to_be_bytes());
+            payload.extend_from_slice(list.as_bytes());
+        }
+        payload.push(first_kex_packet_follows.into());
+        payload.extend_from_slice(&7u32.to_be_bytes());
+        let mut bytes = b"SSH-2.0-OpenSSH_9.6\r\n".to_vec();
+        bytes.extend(packet(&payload));
+        bytes
+    }
+
+    #[test]
+    fn reads_the_kex_guess_flags() {
+        let plain = parse_ssh_kexinit(&kexinit(false)).unwrap();
+        assert_eq!(plain.key_exchange, ["curve25519-sha256"]);
+        assert_eq!(plain.first_kex_packet_follows, Some(false));
+        assert_eq!(plain.kex_guess_sent, Some(false));
+        assert_eq!(plain.reserved, Some(7));
+
+        // SSH_MSG_KEX_ECDH_INIT (30) right behind the KEXINIT.
+        let mut guessed = kexinit(true);
+        guessed.extend(packet(&[30, 0, 0, 0, 0]));
+        let guessed = parse_ssh_kexinit(&guessed).unwrap();
+        assert_eq!(guessed.first_kex_packet_follows, Some(true));
+        assert_eq!(guessed.kex_guess_sent, Some(true));
+
+        // The flag alone, with no guessed packet captured.
+        let promised = parse_ssh_kexinit(&kexinit(true)).unwrap();
+        assert_eq!(promised.first_kex_packet_follows, Some(true));
+        assert_eq!(promised.kex_guess_sent, Some(false));
+    }
+}
@@ -0,0 +1,31 @@
+use super::common::tls_info_json;
+use crate::model::ScanOutcome;
+use serde_json::Value;
+
+/// Top-level `starttls` object for `--starttls`: the plaintext capability
+/// listing, and the certificate when the upgrade went through.
+pub(super) fn starttls_data(outcome: &ScanOutcome) -> Option<Value> {
+    let fields = &outcome.fingerprint.fields;
+    let protocol = fields.get("starttls_protocol")?;
+    let capabilities: Vec<&str> = fields
+        .get("starttls_capabilities")
+        .map(|listing| listing.lines().collect())
+        .unwrap_or_default();
+
+    Some(serde_json::json!({
+        "protocol": protocol,
+        "capabilities": capabilities,
+        "offered": fields.get("starttls_offered").map(|offered| offered == "true"),
+        "tls_info": outcome.tls_info.as_ref().map(tls_info_json),
+        "error": fields.get("starttls_error"),
+    }))
+}
+
+/// Top-level `implicit_tls` object: the certificate of the TLS session a
+/// client ran inside on an `--implicit-tls-ports` port.
+pub(super) fn implicit_tls_data(outcome: &ScanOutcome) -> Option<Value> {
+    if outcome.fingerprint.fields.get("implicit_tls")? != "true" {
+        return None;
+    }
+    outcome.tls_info.as_ref().map(tls_info_json)
+}
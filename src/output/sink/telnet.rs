@@ -0,0 +1,112 @@
+use crate::clients::telnet::{DONT, IAC, SB, SE, WILL};
+use serde_json::Value;
+
+pub(super) fn telnet_data(raw_bytes: &[u8]) -> Value {
+    let banner = strip_telnet_commands(raw_bytes);
+    let prompt = banner
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .unwrap_or_default();
+    let prompt_type = classify_prompt(prompt);
+    let login_required = match prompt_type {
+        "username" | "password" => Value::Bool(true),
+        "shell" => Value::Bool(false),
+        _ => Value::Null,
+    };
+
+    serde_json::json!({
+        "banner": banner,
+        "prompt": prompt,
+        "prompt_type": prompt_type,
+        "login_required": login_required,
+        "device_hint": device_hint(&banner, prompt).unwrap_or_default(),
+    })
+}
+
+/// Drops IAC option negotiation and subnegotiation blocks, leaving the text
+/// the server meant for a terminal.
+fn strip_telnet_commands(bytes: &[u8]) -> String {
+    let mut text = Vec::with_capacity(bytes.len());
+    let mut i = 0usize;
+    while i < bytes.len() {
+        if bytes[i] != IAC {
+            // Bare CR is sent as CR NUL.
+            if bytes[i] != 0 {
+                text.push(bytes[i]);
+            }
+            i += 1;
+            continue;
+        }
+        match bytes.get(i + 1) {
+            Some(&IAC) => {
+                text.push(IAC);
+                i += 2;
+            }
+            Some(&SB) => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == IAC && bytes.get(i + 1) == Some(&SE)) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            Some(&(WILL..=DONT)) => i += 3,
+            _ => i += 2,
+        }
+    }
+    String::from_utf8_lossy(&text).into_owned()
+}
+
+fn classify_prompt(prompt: &str) -> &'static str {
+    let lowered = prompt.to_ascii_lowercase();
+    let label = lowered.trim_end_matches(|c: char| c == ':' || c == '>' || c.is_whitespace());
+    if label.ends_with("password") || label.ends_with("passcode") {
+        "password"
+    } else if label.ends_with("username")
+        || label.ends_with("user name")
+        || label.ends_with("login")
+        || label.ends_with("user")
+    {
+        "username"
+    } else if prompt.ends_with(['#', '>', '$']) {
+        "shell"
+    } else {
+        ""
+    }
+}
+
+/// Vendor guessed from well-known banner text, falling back to the shape of
+/// the login prompt itself.
+fn device_hint(banner: &str, prompt: &str) -> Option<&'static str> {
+    const BANNER_MARKERS: &[(&str, &str)] = &[
+        ("user access verification", "cisco"),
+        ("cisco", "cisco"),
+        ("mikrotik", "mikrotik"),
+        ("huawei", "huawei"),
+        ("h3c", "h3c"),
+        ("comware", "h3c"),
+        ("junos", "juniper"),
+        ("juniper", "juniper"),
+        ("zyxel", "zyxel"),
+        ("procurve", "hp"),
+        ("fortigate", "fortinet"),
+        ("routeros", "mikrotik"),
+        ("busybox", "busybox"),
+        ("microsoft telnet service", "windows"),
+    ];
+    let lowered = banner.to_ascii_lowercase();
+    if let Some((_, vendor)) = BANNER_MARKERS
+        .iter()
+        .find(|(marker, _)| lowered.contains(marker))
+    {
+        return Some(vendor);
+    }
+    match prompt {
+        // Huawei VRP leads with this line when no banner is configured.
+        "Username:" | "Password:" if lowered.contains("login authentication") => Some("huawei"),
+        "Username:" => Some("cisco"),
+        "Login:" => Some("mikrotik"),
+        _ if prompt.ends_with(" login:") => Some("linux"),
+        _ => None,
+    }
+}
@@ -0,0 +1,149 @@
+use crate::model::ScanOutcome;
+use serde_json::Value;
+
+use super::common::raw_banner_for_data;
+
+const CHANGE_CIPHER_SPEC: u8 = 0x14;
+const ALERT: u8 = 0x15;
+const HANDSHAKE: u8 = 0x16;
+const APPLICATION_DATA: u8 = 0x17;
+
+pub(super) fn tls_data(outcome: &ScanOutcome, raw_bytes: &[u8]) -> Value {
+    let Some(record) = parse_record_header(raw_bytes) else {
+        // Whatever answered our ClientHello is not speaking TLS; keep its
+        // bytes as a plain banner instead.
+        return serde_json::json!({
+            "is_tls": false,
+            "record_type": "",
+            "record_version": "",
+            "alert": Value::Null,
+            "handshake_type": "",
+            "banner": raw_banner_for_data(outcome, raw_bytes),
+        });
+    };
+
+    let alert = (record.content_type == ALERT)
+        .then(|| record.fragment.get(..2))
+        .flatten()
+        .map(|alert| {
+            serde_json::json!({
+                "level": match alert[0] {
+                    1 => "warning",
+                    2 => "fatal",
+                    _ => "unknown",
+                },
+                "description": alert_description(alert[1]),
+                "code": alert[1],
+            })
+        })
+        .unwrap_or(Value::Null);
+    let handshake_type = if record.content_type == HANDSHAKE {
+        record
+            .fragment
+            .first()
+            .map(|kind| handshake_name(*kind))
+            .unwrap_or_default()
+    } else {
+        ""
+    };
+
+    serde_json::json!({
+        "is_tls": true,
+        "record_type": match record.content_type {
+            CHANGE_CIPHER_SPEC => "change_cipher_spec",
+            ALERT => "alert",
+            HANDSHAKE => "handshake",
+            _ => "application_data",
+        },
+        "record_version": version_name(record.version),
+        "alert": alert,
+        "handshake_type": handshake_type,
+        "banner": "",
+    })
+}
+
+struct RecordHeader<'a> {
+    content_type: u8,
+    version: u16,
+    fragment: &'a [u8],
+}
+
+/// Accepts the first record only if its header is plausible: a known content
+/// type, a 3.x version and a length within the TLS limit. Anything else came
+/// from a non-TLS service.
+fn parse_record_header(raw: &[u8]) -> Option<RecordHeader<'_>> {
+    let header = raw.get(..5)?;
+    let content_type = header[0];
+    if !(CHANGE_CIPHER_SPEC..=APPLICATION_DATA).contains(&content_type) || header[1] != 3 {
+        return None;
+    }
+    let len = u16::from_be_bytes([header[3], header[4]]) as usize;
+    if len == 0 || len > (1 << 14) + 2048 {
+        return None;
+    }
+    let end = raw.len().min(5 + len);
+    Some(RecordHeader {
+        content_type,
+        version: u16::from_be_bytes([header[1], header[2]]),
+        fragment: &raw[5..end],
+    })
+}
+
+fn version_name(version: u16) -> &'static str {
+    match version {
+        0x0300 => "SSL 3.0",
+        0x0301 => "TLS 1.0",
+        0x0302 => "TLS 1.1",
+        0x0303 => "TLS 1.2",
+        0x0304 => "TLS 1.3",
+        _ => "unknown",
+    }
+}
+
+fn handshake_name(kind: u8) -> &'static str {
+    match kind {
+        0 => "hello_request",
+        2 => "server_hello",
+        4 => "new_session_ticket",
+        8 => "encrypted_extensions",
+        11 => "certificate",
+        12 => "server_key_exchange",
+        13 => "certificate_request",
+        14 => "server_hello_done",
+        _ => "unknown",
+    }
+}
+
+fn alert_description(code: u8) -> &'static str {
+    match code {
+        0 => "close_notify",
+        10 => "unexpected_message",
+        20 => "bad_record_mac",
+        22 => "record_overflow",
+        40 => "handshake_failure",
+        42 => "bad_certificate",
+        43 => "unsupported_certificate",
+        44 => "certificate_revoked",
+        45 => "certificate_expired",
+        46 => "certificate_unknown",
+        47 => "illegal_parameter",
+        48 => "unknown_ca",
+        49 => "access_denied",
+        50 => "decode_error",
+        51 => "decrypt_error",
+        70 => "protocol_version",
+        71 => "insufficient_security",
+        80 => "internal_error",
+        86 => "inappropriate_fallback",
+        90 => "user_canceled",
+        100 => "no_renegotiation",
+        109 => "missing_extension",
+        110 => "unsupported_extension",
+        112 => "unrecognized_name",
+        113 => "bad_certificate_status_response",
+        115 => "unknown_psk_identity",
+        116 => "certificate_required",
+        120 => "no_application_protocol",
+        _ => "unknown",
+    }
+}
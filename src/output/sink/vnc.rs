@@ -0,0 +1,46 @@
+use crate::clients::vnc::security_type_name;
+use crate::model::ScanOutcome;
+use serde_json::Value;
+
+pub(super) fn vnc_data(outcome: &ScanOutcome) -> Value {
+    let fields = &outcome.fingerprint.fields;
+    let field = |key: &str| fields.get(key).map(String::as_str);
+    let security_types = security_types(outcome);
+    let geometry = field("geometry")
+        .and_then(|value| value.split_once('x'))
+        .and_then(|(width, height)| Some((width.parse::<u16>().ok()?, height.parse::<u16>().ok()?)))
+        .map(|(width, height)| serde_json::json!({ "width": width, "height": height }))
+        .unwrap_or(Value::Null);
+
+    serde_json::json!({
+        "protocol_version": field("protocol_version"),
+        "security_types": security_types
+            .iter()
+            .map(|&id| serde_json::json!({ "id": id, "name": security_type_name(id) }))
+            .collect::<Vec<_>>(),
+        // Unknown until the server has listed its types.
+        "auth_required": (!security_types.is_empty()).then(|| !security_types.contains(&1)),
+        "no_auth": no_auth(outcome),
+        "geometry": geometry,
+        "server_name": field("server_name"),
+        "security_failure": field("security_failure"),
+    })
+}
+
+/// Whether the server offered security type 1 (None), or dictated it on the
+/// pre-3.7 path: anyone can open the desktop.
+pub(super) fn no_auth(outcome: &ScanOutcome) -> bool {
+    security_types(outcome).contains(&1)
+}
+
+fn security_types(outcome: &ScanOutcome) -> Vec<u8> {
+    outcome
+        .fingerprint
+        .fields
+        .get("security_types")
+        .map(String::as_str)
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|id| id.parse().ok())
+        .collect()
+}
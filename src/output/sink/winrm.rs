@@ -0,0 +1,67 @@
+use crate::util::ntlm::parse_ntlm_challenge;
+use base64::Engine as _;
+use serde_json::Value;
+
+pub(super) fn winrm_data(raw_bytes: &[u8]) -> Value {
+    let response = String::from_utf8_lossy(raw_bytes);
+    let head = response
+        .split_once("\r\n\r\n")
+        .map(|(head, _)| head)
+        .unwrap_or(&response);
+    let mut lines = head.lines();
+    let status_code = lines
+        .next()
+        .filter(|line| line.starts_with("HTTP/"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or_default()
+        .to_string();
+    let headers: Vec<(&str, &str)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    let header = |name: &str| -> Vec<&str> {
+        headers
+            .iter()
+            .filter(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+            .collect()
+    };
+
+    let auth_methods: Vec<&str> = header("WWW-Authenticate")
+        .into_iter()
+        .filter_map(|value| value.split_whitespace().next())
+        .collect();
+    let challenge = header("WWW-Authenticate")
+        .into_iter()
+        .filter_map(|value| value.strip_prefix("Negotiate "))
+        .find_map(|token| {
+            base64::engine::general_purpose::STANDARD
+                .decode(token.trim())
+                .ok()
+        })
+        .and_then(|blob| parse_ntlm_challenge(&blob));
+    let server = header("Server").first().copied().unwrap_or_default();
+    let ntlm = challenge
+        .as_ref()
+        .map(|info| {
+            serde_json::json!({
+                "domain": info.domain,
+                "computer": info.computer,
+                "dns_domain": info.dns_domain,
+                "dns_computer": info.dns_computer,
+                "target_name": info.target_name,
+                "version": info.version,
+                "timestamp": info.timestamp,
+            })
+        })
+        .unwrap_or(Value::Null);
+
+    serde_json::json!({
+        // WinRM runs on HTTP.sys; the NTLM challenge alone is proof enough.
+        "is_winrm": challenge.is_some() || server.starts_with("Microsoft-HTTPAPI"),
+        "status_code": status_code,
+        "server": server,
+        "auth_methods": auth_methods,
+        "ntlm": ntlm,
+    })
+}
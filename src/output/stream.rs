@@ -0,0 +1,212 @@
+use crate::model::{StreamPolicy, StreamTarget};
+use anyhow::Context;
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Complete lines waiting for the consumer before `--stream-policy` applies.
+const QUEUE_LINES: usize = 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// A consumer that stops reading for this long is treated as gone.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+const MIN_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// `--stream-to` writer. Complete lines are queued for a thread that owns the
+/// connection and reconnects, with backoff, whenever the consumer goes away,
+/// so the output worker never waits on the network itself.
+pub struct StreamWriter {
+    tx: Option<SyncSender<Vec<u8>>>,
+    pending: Vec<u8>,
+    policy: StreamPolicy,
+    shared: Arc<Shared>,
+    handle: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct Shared {
+    /// Set once the scan is done; a consumer that is still unreachable then
+    /// gets one more connection attempt, not one per queued line.
+    closing: AtomicBool,
+    dropped: AtomicU64,
+}
+
+impl StreamWriter {
+    /// Connects once up front so a wrong target fails the scan immediately.
+    pub fn connect(target: StreamTarget, policy: StreamPolicy) -> anyhow::Result<Self> {
+        let conn = Connection::open(&target)
+            .with_context(|| format!("failed to connect to stream target {target}"))?;
+        let (tx, rx) = mpsc::sync_channel::<Vec<u8>>(QUEUE_LINES);
+        let shared = Arc::new(Shared::default());
+        let worker = shared.clone();
+        let handle = std::thread::spawn(move || {
+            let mut conn = Some(conn);
+            let mut backoff = MIN_BACKOFF;
+            // Set when a reconnect fails at shutdown: the rest of the queue is
+            // only counted, not retried line by line.
+            let mut gave_up = false;
+            for line in rx {
+                loop {
+                    if let Some(stream) = conn.as_mut() {
+                        match stream.write_all(&line) {
+                            Ok(()) => break,
+                            Err(err) => {
+                                eprintln!("stream consumer {target} went away: {err}");
+                                conn = None;
+                            }
+                        }
+                    }
+                    if gave_up {
+                        worker.dropped.fetch_add(1, Ordering::Relaxed);
+                        break;
+                    }
+                    match Connection::open(&target) {
+                        Ok(stream) => {
+                            conn = Some(stream);
+                            backoff = MIN_BACKOFF;
+                        }
+                        Err(_) if worker.closing.load(Ordering::Relaxed) => {
+                            gave_up = true;
+                            worker.dropped.fetch_add(1, Ordering::Relaxed);
+                            break;
+                        }
+                        Err(_) => {
+                            std::thread::sleep(backoff);
+                            backoff = (backoff * 2).min(MAX_BACKOFF);
+                        }
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            tx: Some(tx),
+            pending: Vec::new(),
+            policy,
+            shared,
+            handle: Some(handle),
+        })
+    }
+
+    fn send(&mut self, line: Vec<u8>) -> io::Result<()> {
+        let Some(tx) = &self.tx else {
+            return Ok(());
+        };
+        let sent = match self.policy {
+            StreamPolicy::Block => tx.send(line).is_ok(),
+            StreamPolicy::Drop => match tx.try_send(line) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    self.shared.dropped.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            },
+        };
+        if sent {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "stream writer thread exited",
+            ))
+        }
+    }
+}
+
+impl Write for StreamWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(end) = self.pending.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.send(line)?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Lines are queued as soon as they are complete; a partial line waits
+    /// for its newline so the consumer never sees half a record.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for StreamWriter {
+    fn drop(&mut self) {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            let _ = self.send(line);
+        }
+        self.shared.closing.store(true, Ordering::Relaxed);
+        self.tx.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        let dropped = self.shared.dropped.load(Ordering::Relaxed);
+        if dropped > 0 {
+            eprintln!("stream consumer missed {dropped} output lines");
+        }
+    }
+}
+
+enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Connection {
+    fn open(target: &StreamTarget) -> io::Result<Self> {
+        match target {
+            StreamTarget::Tcp(addr) => {
+                let mut last_err = None;
+                for addr in addr.to_socket_addrs()? {
+                    match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+                        Ok(stream) => {
+                            stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                            return Ok(Connection::Tcp(stream));
+                        }
+                        Err(err) => last_err = Some(err),
+                    }
+                }
+                Err(last_err.unwrap_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, "no addresses resolved")
+                }))
+            }
+            #[cfg(unix)]
+            StreamTarget::Unix(path) => {
+                let stream = UnixStream::connect(path)?;
+                stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+                Ok(Connection::Unix(stream))
+            }
+            #[cfg(not(unix))]
+            StreamTarget::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Unix domain sockets are not supported on this platform",
+            )),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Connection::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.flush(),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::model::{ErrorKind, ScanOutcome, Status};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Open, closed, filtered or error, as counted by `--count-only` and reported
+/// by `--protocol tcp`.
+pub(super) fn port_state(outcome: &ScanOutcome) -> &'static str {
+    match outcome.status {
+        Status::Open => "open",
+        Status::Timeout => "filtered",
+        // A refused connection means nothing listens there, while silence or
+        // an ICMP unreachable means something dropped the SYN. Any later
+        // failure happened against an open port.
+        Status::Error => match &outcome.diagnostics {
+            Some(diag) if diag.stage == "connect" => match diag.error_kind {
+                ErrorKind::ConnectionRefused => "closed",
+                ErrorKind::ConnectTimeout | ErrorKind::HostUnreachable => "filtered",
+                _ => "error",
+            },
+            _ => "error",
+        },
+    }
+}
+
+/// Per protocol/port state counts collected by `--count-only`.
+#[derive(Default)]
+pub(super) struct Tally {
+    ports: BTreeMap<(String, u16), PortCounts>,
+}
+
+#[derive(Clone, Copy, Default, Serialize)]
+pub(super) struct PortCounts {
+    pub open: u64,
+    pub closed: u64,
+    pub filtered: u64,
+    pub error: u64,
+}
+
+#[derive(Serialize)]
+pub(super) struct TallyRow<'a> {
+    pub proto: &'a str,
+    pub port: u16,
+    #[serde(flatten)]
+    pub counts: PortCounts,
+}
+
+impl Tally {
+    pub(super) fn record(&mut self, outcome: &ScanOutcome) {
+        let proto = outcome
+            .fingerprint
+            .protocol
+            .clone()
+            .unwrap_or_else(|| "unknown".into());
+        let counts = self.ports.entry((proto, outcome.target.port)).or_default();
+        match port_state(outcome) {
+            "open" => counts.open += 1,
+            "filtered" => counts.filtered += 1,
+            "closed" => counts.closed += 1,
+            _ => counts.error += 1,
+        }
+    }
+
+    pub(super) fn rows(&self) -> impl Iterator<Item = TallyRow<'_>> {
+        self.ports.iter().map(|((proto, port), counts)| TallyRow {
+            proto,
+            port: *port,
+            counts: *counts,
+        })
+    }
+
+    pub(super) fn total(&self) -> PortCounts {
+        self.ports
+            .values()
+            .fold(PortCounts::default(), |acc, counts| PortCounts {
+                open: acc.open + counts.open,
+                closed: acc.closed + counts.closed,
+                filtered: acc.filtered + counts.filtered,
+                error: acc.error + counts.error,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Diagnostics, Fingerprint, Protocol, TargetView, TcpMeta};
+
+    fn outcome(status: Status, diagnostics: Option<Diagnostics>) -> ScanOutcome {
+        ScanOutcome {
+            target: TargetView {
+                host: "192.0.2.7".into(),
+                addr: "192.0.2.7".into(),
+                port: 22,
+                family: None,
+            },
+            status,
+            tcp: TcpMeta {
+                connect_ms: None,
+                error: None,
+            },
+            banner: Default::default(),
+            timestamp: Default::default(),
+            ttl: None,
+            webdriver: None,
+            technologies: None,
+            tls_info: None,
+            fingerprint: Fingerprint::from_protocol(&Protocol::Tcp),
+            diagnostics,
+            proxy_protocol_accepted: None,
+            ptr: None,
+        }
+    }
+
+    fn failed(stage: &str, kind: ErrorKind) -> &'static str {
+        port_state(&outcome(
+            Status::Error,
+            Some(Diagnostics::new(stage, kind, "failed")),
+        ))
+    }
+
+    #[test]
+    fn maps_connect_failures_to_port_states() {
+        assert_eq!(port_state(&outcome(Status::Open, None)), "open");
+        assert_eq!(port_state(&outcome(Status::Timeout, None)), "filtered");
+        assert_eq!(failed("connect", ErrorKind::ConnectionRefused), "closed");
+        assert_eq!(failed("connect", ErrorKind::ConnectTimeout), "filtered");
+        assert_eq!(failed("connect", ErrorKind::HostUnreachable), "filtered");
+        assert_eq!(failed("connect", ErrorKind::ProxyFailed), "error");
+        assert_eq!(failed("read", ErrorKind::ConnectionReset), "error");
+        assert_eq!(port_state(&outcome(Status::Error, None)), "error");
+    }
+}
@@ -0,0 +1,259 @@
+use super::https::{connect_tls, extract_tls_info, server_name};
+use super::Prober;
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, ReadStopReason, Target};
+use crate::util::h2::{
+    frame, frame_header, FLAG_ACK, FLAG_END_HEADERS, FLAG_END_STREAM, FRAME_DATA, FRAME_GOAWAY,
+    FRAME_HEADERS, FRAME_RST_STREAM, FRAME_SETTINGS, REQUEST_STREAM,
+};
+use anyhow::Context;
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+const CLIENT_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const REFLECTION_PATH: &str = "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo";
+
+/// Asks a gRPC server for its service list through the reflection API. The
+/// probe speaks cleartext HTTP/2 first and retries over TLS (ALPN `h2`) when
+/// the server turns out to require it. The raw server frames are returned so
+/// the output layer can decode the reflection response.
+pub(super) struct GrpcProbe;
+
+#[async_trait]
+impl Prober for GrpcProbe {
+    fn name(&self) -> &'static str {
+        "grpc"
+    }
+
+    fn probe_bytes(&self) -> &'static [u8] {
+        &[]
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        matches!(target.resolved.port(), 50051 | 9090)
+    }
+
+    async fn execute(
+        &self,
+        mut stream: TcpStream,
+        cfg: &Config,
+        target: &Target,
+    ) -> anyhow::Result<ReadResult> {
+        let authority = server_name(target);
+        if let Ok(result) = list_services(&mut stream, cfg, &authority, "http").await {
+            if !requires_tls(&result.bytes) {
+                return Ok(result);
+            }
+        }
+
+        let stream = timeout(
+            cfg.connect_timeout,
+            crate::engine::proxy::connect(target, cfg),
+        )
+        .await
+        .context("connect timeout while retrying gRPC over TLS")??;
+        let mut tls_stream = connect_tls(stream, target, Some(b"\x02h2"), cfg).await?;
+        let tls_info = extract_tls_info(&tls_stream, cfg);
+        let mut result = list_services(&mut tls_stream, cfg, &authority, "https").await?;
+        result.tls_info = Some(tls_info);
+        Ok(result)
+    }
+}
+
+/// A TLS-only endpoint answers our cleartext preface with an alert record (or
+/// just hangs up) instead of an HTTP/2 SETTINGS frame.
+fn requires_tls(response: &[u8]) -> bool {
+    matches!(response.first(), None | Some(0x15) | Some(0x16))
+}
+
+async fn list_services<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    cfg: &Config,
+    authority: &str,
+    scheme: &str,
+) -> anyhow::Result<ReadResult> {
+    let mut request = CLIENT_PREFACE.to_vec();
+    request.extend(frame(FRAME_SETTINGS, 0, 0, &[]));
+    request.extend(frame(
+        FRAME_HEADERS,
+        FLAG_END_HEADERS,
+        REQUEST_STREAM,
+        &request_headers(authority, scheme),
+    ));
+    request.extend(frame(
+        FRAME_DATA,
+        FLAG_END_STREAM,
+        REQUEST_STREAM,
+        &grpc_message(&list_services_request()),
+    ));
+    stream
+        .write_all(&request)
+        .await
+        .context("failed to write gRPC reflection request")?;
+
+    let mut result = read_frames(stream, cfg).await?;
+    result.record_sent(cfg, &request);
+    Ok(result)
+}
+
+/// Collects server frames until the reflection call finishes, the connection
+/// is torn down, or the byte, read-round or time budget runs out. SETTINGS
+/// frames are acknowledged so servers that wait for the ACK keep going.
+async fn read_frames<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    cfg: &Config,
+) -> anyhow::Result<ReadResult> {
+    let mut bytes = Vec::new();
+    let mut parsed = 0usize;
+    let mut chunk = vec![0u8; 4096];
+    let mut rounds = 0;
+    let reason = loop {
+        if bytes.len() >= cfg.max_bytes {
+            break ReadStopReason::SizeLimit;
+        }
+        if rounds >= cfg.max_read_rounds {
+            break ReadStopReason::RoundLimit;
+        }
+        let n = match timeout(cfg.read_timeout, stream.read(&mut chunk)).await {
+            Ok(Ok(0)) => break ReadStopReason::ConnectionClosed,
+            Ok(Ok(n)) => n,
+            Ok(Err(err)) => return Err(err.into()),
+            Err(_) => break ReadStopReason::Timeout,
+        };
+        rounds += 1;
+        bytes.extend_from_slice(&chunk[..n]);
+
+        let mut finished = false;
+        while let Some((kind, flags, stream_id, len)) = frame_header(&bytes[parsed..]) {
+            if bytes.len() < parsed + 9 + len {
+                break;
+            }
+            parsed += 9 + len;
+            match kind {
+                FRAME_SETTINGS if flags & FLAG_ACK == 0 => {
+                    stream
+                        .write_all(&frame(FRAME_SETTINGS, FLAG_ACK, 0, &[]))
+                        .await?;
+                }
+                FRAME_DATA | FRAME_HEADERS
+                    if stream_id == REQUEST_STREAM && flags & FLAG_END_STREAM != 0 =>
+                {
+                    finished = true;
+                }
+                FRAME_RST_STREAM if stream_id == REQUEST_STREAM => finished = true,
+                FRAME_GOAWAY => finished = true,
+                _ => {}
+            }
+        }
+        if finished {
+            break ReadStopReason::Delimiter;
+        }
+    };
+
+    let truncated = bytes.len() > cfg.max_bytes;
+    bytes.truncate(cfg.max_bytes);
+    Ok(ReadResult {
+        bytes,
+        reason,
+        truncated,
+        tls_info: None,
+        fields: Default::default(),
+    })
+}
+
+/// HPACK block for the reflection call, using static-table entries where they
+/// exist and plain (non-Huffman) literals otherwise.
+fn request_headers(authority: &str, scheme: &str) -> Vec<u8> {
+    let mut block = vec![
+        // :method POST
+        0x83,
+        // :scheme http / https
+        if scheme == "https" { 0x87 } else { 0x86 },
+    ];
+    literal_indexed_name(&mut block, 4, REFLECTION_PATH);
+    literal_indexed_name(&mut block, 1, authority);
+    literal_indexed_name(&mut block, 31, "application/grpc");
+    literal_new_name(&mut block, "te", "trailers");
+    block
+}
+
+fn literal_indexed_name(block: &mut Vec<u8>, index: usize, value: &str) {
+    hpack_integer(block, index, 4, 0x00);
+    hpack_string(block, value);
+}
+
+fn literal_new_name(block: &mut Vec<u8>, name: &str, value: &str) {
+    block.push(0x00);
+    hpack_string(block, name);
+    hpack_string(block, value);
+}
+
+fn hpack_string(block: &mut Vec<u8>, value: &str) {
+    hpack_integer(block, value.len(), 7, 0x00);
+    block.extend_from_slice(value.as_bytes());
+}
+
+fn hpack_integer(block: &mut Vec<u8>, value: usize, prefix_bits: u32, flags: u8) {
+    let max_prefix = (1usize << prefix_bits) - 1;
+    if value < max_prefix {
+        block.push(flags | value as u8);
+        return;
+    }
+    block.push(flags | max_prefix as u8);
+    let mut rest = value - max_prefix;
+    while rest >= 0x80 {
+        block.push((rest as u8 & 0x7f) | 0x80);
+        rest >>= 7;
+    }
+    block.push(rest as u8);
+}
+
+/// `ServerReflectionRequest { list_services: "" }` (field 7, length-delimited).
+fn list_services_request() -> Vec<u8> {
+    vec![0x3a, 0x00]
+}
+
+fn grpc_message(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(5 + payload.len());
+    out.push(0);
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_hpack_integers_past_the_prefix() {
+        let mut block = Vec::new();
+        hpack_integer(&mut block, 1337, 5, 0x00);
+        assert_eq!(block, vec![31, 154, 10]);
+    }
+
+    #[test]
+    fn builds_reflection_headers() {
+        let block = request_headers("svc.local", "http");
+        assert_eq!(&block[..3], &[0x83, 0x86, 0x04]);
+        assert_eq!(block[3] as usize, REFLECTION_PATH.len());
+        assert!(block.ends_with(b"\x02te\x08trailers"));
+
+        // :authority (index 1) and content-type (index 31, past the 4-bit
+        // prefix) follow the path as literals without indexing.
+        let rest = &block[4 + REFLECTION_PATH.len()..];
+        assert_eq!(&rest[..11], b"\x01\x09svc.local");
+        assert_eq!(&rest[11..30], b"\x0f\x10\x10application/grpc");
+        assert_eq!(rest[30], 0x00);
+        assert_eq!(request_headers("svc.local", "https")[1], 0x87);
+    }
+
+    #[test]
+    fn detects_tls_only_servers() {
+        assert!(requires_tls(b""));
+        assert!(requires_tls(b"\x15\x03\x01\x00\x02\x02\x46"));
+        assert!(!requires_tls(&frame(FRAME_SETTINGS, 0, 0, &[])));
+    }
+}
@@ -1,10 +1,17 @@
+use super::http_paths::check_http_paths;
+use super::https::{connect_tls, server_name};
+use super::kv_store::check_kv_store;
+use super::open_proxy::check_open_proxy;
+use super::smuggling::check_smuggling;
 use super::Prober;
-use crate::engine::reader::BannerReader;
-use crate::model::{Config, Target};
+use crate::engine::reader::{BannerReader, ReadResult, ReadStrategy};
+use crate::model::{Config, ReadStopReason, Target};
 use anyhow::Context;
 use async_trait::async_trait;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncRead, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tracing::debug;
 
 pub(super) struct HttpProbe;
 
@@ -28,12 +35,7 @@ impl Prober for HttpProbe {
         cfg: &Config,
         target: &Target,
     ) -> anyhow::Result<crate::engine::reader::ReadResult> {
-        let host = target.original.host.as_str();
-        let host_header = if host.is_empty() {
-            target.resolved.ip().to_string()
-        } else {
-            host.to_string()
-        };
+        let host_header = server_name(target);
         let request = format!("GET / HTTP/1.0\r\nHost: {host_header}\r\n\r\n");
 
         stream
@@ -41,7 +43,379 @@ impl Prober for HttpProbe {
             .await
             .with_context(|| format!("failed to write probe {}", self.name()))?;
 
-        let mut reader = BannerReader::new(cfg.max_bytes, cfg.read_timeout);
-        reader.read(&mut stream, self.expected_delimiter()).await
+        let mut result = read_response(&mut stream, cfg).await?;
+        result.record_sent(cfg, request.as_bytes());
+        check_admin_endpoint(&mut result, cfg, target, false).await;
+        check_kv_store(&mut result, cfg, target, false).await;
+        check_smuggling(&mut result, cfg, target, false).await;
+        check_open_proxy(&mut result, cfg, target, false).await;
+        check_http_paths(&mut result, cfg, target, false).await;
+        Ok(result)
+    }
+}
+
+/// InfluxDB's default HTTP API port.
+const INFLUXDB_PORT: u16 = 8086;
+
+/// When the target looks like a database with an HTTP admin API (CouchDB's
+/// welcome document at the root; InfluxDB's `/ping`, asked on its default
+/// port or when the root response names it), requests that API on a fresh
+/// connection and records the status in `admin_path`/`admin_status` so the
+/// output can tell whether it is reachable without credentials. `/ping`
+/// answers with the version in `influxdb_version` and `influxdb_build`.
+pub(super) async fn check_admin_endpoint(
+    result: &mut ReadResult,
+    cfg: &Config,
+    target: &Target,
+    tls: bool,
+) {
+    let path = if couchdb_welcome(&result.bytes) {
+        "/_all_dbs"
+    } else if target.resolved.port() == INFLUXDB_PORT
+        || header_value(&result.bytes, "X-Influxdb-Version").is_some()
+    {
+        match influxdb_ping(cfg, target, tls).await {
+            Ok(Some((version, build))) => {
+                let path = influxdb_admin_path(&version);
+                result.fields.insert("influxdb_version".into(), version);
+                if let Some(build) = build {
+                    result.fields.insert("influxdb_build".into(), build);
+                }
+                path
+            }
+            Ok(None) => return,
+            Err(err) => {
+                debug!(target = %target.resolved, error = %err, "influxdb ping failed");
+                return;
+            }
+        }
+    } else {
+        return;
+    };
+    match fetch_status(cfg, target, path, tls).await {
+        Ok(Some(status)) => {
+            result.fields.insert("admin_path".into(), path.into());
+            result.fields.insert("admin_status".into(), status);
+        }
+        Ok(None) => {}
+        Err(err) => debug!(target = %target.resolved, error = %err, "admin endpoint check failed"),
+    }
+}
+
+fn couchdb_welcome(response: &[u8]) -> bool {
+    header_value(response, "Server").is_some_and(|s| s.starts_with("CouchDB"))
+        || body(response).windows(9).any(|w| w == b"\"couchdb\"")
+}
+
+/// `(X-Influxdb-Version, X-Influxdb-Build)` from `/ping`, or `None` when
+/// whatever answered is not InfluxDB.
+async fn influxdb_ping(
+    cfg: &Config,
+    target: &Target,
+    tls: bool,
+) -> anyhow::Result<Option<(String, Option<String>)>> {
+    let response = fetch(cfg, target, &get_request(target, "/ping"), tls).await?;
+    Ok(influxdb_headers(&response.bytes))
+}
+
+fn influxdb_headers(response: &[u8]) -> Option<(String, Option<String>)> {
+    let version = header_value(response, "X-Influxdb-Version")?;
+    Some((version, header_value(response, "X-Influxdb-Build")))
+}
+
+/// The 1.x InfluxQL endpoint, or the 2.x organisations API.
+fn influxdb_admin_path(version: &str) -> &'static str {
+    if version.trim_start_matches('v').starts_with('2') {
+        "/api/v2/orgs"
+    } else {
+        "/query?q=SHOW+DATABASES"
+    }
+}
+
+async fn fetch_status(
+    cfg: &Config,
+    target: &Target,
+    path: &str,
+    tls: bool,
+) -> anyhow::Result<Option<String>> {
+    let response = fetch(cfg, target, &get_request(target, path), tls).await?;
+    Ok(status_code(&response.bytes))
+}
+
+pub(super) fn get_request(target: &Target, path: &str) -> String {
+    format!(
+        "GET {path} HTTP/1.0\r\nHost: {}\r\n\r\n",
+        server_name(target)
+    )
+}
+
+/// Sends `request` on a fresh connection (through the proxy, and over TLS
+/// when `tls` is set) and reads the response.
+pub(super) async fn fetch(
+    cfg: &Config,
+    target: &Target,
+    request: &str,
+    tls: bool,
+) -> anyhow::Result<ReadResult> {
+    let stream = timeout(
+        cfg.connect_timeout,
+        crate::engine::proxy::connect(target, cfg),
+    )
+    .await
+    .context("connect timeout")??;
+    if tls {
+        let mut stream = connect_tls(stream, target, None, cfg).await?;
+        stream.write_all(request.as_bytes()).await?;
+        read_response(&mut stream, cfg).await
+    } else {
+        let mut stream = stream;
+        stream.write_all(request.as_bytes()).await?;
+        read_response(&mut stream, cfg).await
+    }
+}
+
+pub(super) fn status_code(response: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(response)
+        .lines()
+        .next()
+        .filter(|line| line.starts_with("HTTP/"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .map(str::to_string)
+}
+
+/// The bytes after the header block; empty when the headers never ended.
+pub(super) fn body(response: &[u8]) -> &[u8] {
+    find_header_end(response).map_or(&[], |end| &response[end..])
+}
+
+/// Upper bound on an HTTP header block, which is kept whole even past
+/// `max_bytes` so a small budget never cuts the status line or headers.
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+/// Reads an HTTP response: the whole header block first, then as much of the
+/// body as `Content-Length` allows within what the headers left of
+/// `max_bytes`, and at most `max_body`.
+pub(super) async fn read_response<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    cfg: &Config,
+) -> anyhow::Result<ReadResult> {
+    let mut reader = BannerReader::new(cfg.max_bytes.max(MAX_HEADER_BYTES), cfg.read_timeout)
+        .with_max_rounds(cfg.max_read_rounds);
+    let mut result = reader.read(stream, None).await?;
+
+    let Some(header_end) = find_header_end(&result.bytes) else {
+        // Not an HTTP header block, so only `max_bytes` of it is kept.
+        if result.bytes.len() > cfg.max_bytes {
+            result.bytes.truncate(cfg.max_bytes);
+            result.truncated = true;
+            result.reason = ReadStopReason::SizeLimit;
+        }
+        return Ok(result);
+    };
+    let mut budget = cfg.max_bytes.saturating_sub(header_end);
+    if let Some(limit) = cfg.max_body {
+        budget = budget.min(limit);
+    }
+    if clamp_body(&mut result, header_end, budget) {
+        return Ok(result);
+    }
+
+    if let Some(content_length) = parse_content_length(&result.bytes) {
+        let already_have_body = result.bytes.len() - header_end;
+        let available = budget - already_have_body;
+        let missing = content_length.saturating_sub(already_have_body);
+
+        if available == 0 || missing == 0 {
+            if missing > 0 {
+                result.truncated = true;
+                result.reason = ReadStopReason::SizeLimit;
+            }
+            return Ok(result);
+        }
+
+        let expected = missing.min(available);
+        let body = BannerReader::new(expected, cfg.read_timeout)
+            .with_max_rounds(cfg.max_read_rounds)
+            .read_using(stream, ReadStrategy::Fixed(expected))
+            .await?;
+        let read = body.bytes.len();
+        if read < expected {
+            result.reason = body.reason;
+        }
+
+        if missing > available {
+            result.truncated = true;
+            result.reason = ReadStopReason::SizeLimit;
+        } else if read == expected {
+            // We successfully pulled the entire expected body.
+            result.reason = ReadStopReason::ConnectionClosed;
+        }
+
+        result.bytes.extend_from_slice(&body.bytes);
+    }
+
+    Ok(result)
+}
+
+/// Drops body bytes beyond `limit`, keeping the headers intact. Returns
+/// whether any were dropped.
+fn clamp_body(result: &mut ReadResult, header_end: usize, limit: usize) -> bool {
+    if result.bytes.len() - header_end <= limit {
+        return false;
+    }
+    result.bytes.truncate(header_end + limit);
+    result.truncated = true;
+    result.reason = ReadStopReason::SizeLimit;
+    true
+}
+
+pub(super) fn parse_content_length(bytes: &[u8]) -> Option<usize> {
+    for line in bytes.split(|b| *b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            break;
+        }
+
+        if line
+            .get(..15)
+            .map(|prefix| prefix.eq_ignore_ascii_case(b"Content-Length:"))
+            .unwrap_or(false)
+        {
+            let value = std::str::from_utf8(&line[15..]).ok()?.trim();
+            if let Ok(len) = value.parse::<usize>() {
+                return Some(len);
+            }
+        }
+    }
+
+    None
+}
+
+pub(super) fn header_value(bytes: &[u8], name: &str) -> Option<String> {
+    let head = &bytes[..find_header_end(bytes).unwrap_or(bytes.len())];
+    String::from_utf8_lossy(head)
+        .lines()
+        .skip(1)
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().to_string())
+        })
+}
+
+pub(super) fn find_header_end(bytes: &[u8]) -> Option<usize> {
+    bytes
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|pos| pos + 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        clamp_body, couchdb_welcome, find_header_end, influxdb_admin_path, influxdb_headers,
+        parse_content_length, read_response,
+    };
+    use crate::clients::mock::config;
+    use crate::engine::reader::ReadResult;
+    use crate::model::{Protocol, ReadStopReason};
+
+    fn response(bytes: &[u8]) -> ReadResult {
+        ReadResult {
+            bytes: bytes.to_vec(),
+            reason: ReadStopReason::Delimiter,
+            truncated: false,
+            tls_info: None,
+            fields: Default::default(),
+        }
+    }
+
+    #[test]
+    fn parses_length_without_delimiter() {
+        let headers = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 12\r\n";
+        assert_eq!(parse_content_length(headers), Some(12));
+    }
+
+    #[test]
+    fn finds_header_end_with_body() {
+        let resp = b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nBody";
+        assert_eq!(find_header_end(resp), Some(38));
+    }
+
+    #[test]
+    fn clamps_body_but_keeps_headers() {
+        let mut result = response(b"HTTP/1.1 200 OK\r\nServer: x\r\n\r\n0123456789");
+        let header_end = find_header_end(&result.bytes).unwrap();
+        assert!(clamp_body(&mut result, header_end, 4));
+        assert_eq!(result.bytes, b"HTTP/1.1 200 OK\r\nServer: x\r\n\r\n0123");
+        assert!(result.truncated);
+        assert_eq!(result.reason, ReadStopReason::SizeLimit);
+    }
+
+    #[test]
+    fn leaves_short_bodies_untouched() {
+        let mut result = response(b"HTTP/1.1 200 OK\r\n\r\nhi");
+        let header_end = find_header_end(&result.bytes).unwrap();
+        assert!(!clamp_body(&mut result, header_end, 4));
+        assert!(!clamp_body(&mut result, header_end, usize::MAX));
+        assert!(!result.truncated);
+    }
+
+    #[tokio::test]
+    async fn keeps_headers_longer_than_max_bytes() {
+        let mut wire: &[u8] = b"HTTP/1.1 200 OK\r\nServer: x\r\nContent-Length: 4\r\n\r\nbody";
+        let mut cfg = config(Protocol::Http);
+        cfg.max_bytes = 8;
+        let result = read_response(&mut wire, &cfg).await.unwrap();
+        assert_eq!(
+            result.bytes,
+            b"HTTP/1.1 200 OK\r\nServer: x\r\nContent-Length: 4\r\n\r\n"
+        );
+        assert!(result.truncated);
+        assert_eq!(result.reason, ReadStopReason::SizeLimit);
+    }
+
+    #[tokio::test]
+    async fn keeps_the_body_within_max_bytes() {
+        let mut wire: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n0123456789";
+        let mut cfg = config(Protocol::Http);
+        cfg.max_bytes = 43;
+        cfg.max_body = Some(100);
+        let result = read_response(&mut wire, &cfg).await.unwrap();
+        assert_eq!(
+            result.bytes,
+            b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n0123"
+        );
+        assert!(result.truncated);
+    }
+
+    #[test]
+    fn recognises_couchdb_from_root_response() {
+        let couchdb = b"HTTP/1.1 200 OK\r\nServer: CouchDB/3.3.2 (Erlang OTP/24)\r\n\r\n{\"couchdb\":\"Welcome\",\"version\":\"3.3.2\"}";
+        assert!(couchdb_welcome(couchdb));
+        let proxied = b"HTTP/1.1 200 OK\r\nServer: nginx\r\n\r\n{\"couchdb\":\"Welcome\"}";
+        assert!(couchdb_welcome(proxied));
+        assert!(!couchdb_welcome(
+            b"HTTP/1.1 200 OK\r\nServer: nginx\r\n\r\n"
+        ));
+    }
+
+    #[test]
+    fn picks_influxdb_admin_endpoint_from_ping() {
+        let v1 = b"HTTP/1.1 204 No Content\r\nX-Influxdb-Build: OSS\r\nX-Influxdb-Version: 1.8.10\r\n\r\n";
+        let (version, build) = influxdb_headers(v1).unwrap();
+        assert_eq!(
+            (version.as_str(), build.as_deref()),
+            ("1.8.10", Some("OSS"))
+        );
+        assert_eq!(influxdb_admin_path(&version), "/query?q=SHOW+DATABASES");
+
+        let v2 = b"HTTP/1.1 204 No Content\r\nx-influxdb-version: v2.7.1\r\n\r\n";
+        let (version, build) = influxdb_headers(v2).unwrap();
+        assert_eq!((version.as_str(), build), ("v2.7.1", None));
+        assert_eq!(influxdb_admin_path(&version), "/api/v2/orgs");
+
+        assert!(influxdb_headers(b"HTTP/1.1 404 Not Found\r\nServer: nginx\r\n\r\n").is_none());
     }
 }
@@ -0,0 +1,383 @@
+//! `--http-paths`: a handful of well-known paths (`/robots.txt`,
+//! `/.well-known/security.txt`, ...) fetched one after another over a single
+//! HTTP/1.1 keep-alive connection, keeping only each status and length.
+
+use super::http::{find_header_end, header_value, parse_content_length, status_code};
+use super::https::{connect_tls, server_name};
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, Target};
+use anyhow::Context;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::timeout;
+use tracing::debug;
+
+/// One response off the shared connection.
+#[derive(Debug, PartialEq)]
+struct PathResponse {
+    status: String,
+    /// Body bytes: the `Content-Length`, or what was read of a chunked or
+    /// close-delimited body.
+    length: usize,
+    /// Whether the connection can carry the next request.
+    reusable: bool,
+}
+
+/// What the reads of one check may still use, shared by every response.
+#[derive(Debug)]
+struct Budget {
+    /// `--max-bytes`.
+    bytes: usize,
+    /// `--max-read-rounds`: reads that returned data.
+    rounds: usize,
+}
+
+/// Records `http_path.<path>` = `<status> <length>` for each path answered.
+/// All responses share the `--max-bytes` and `--max-read-rounds` budget; a
+/// server that closes after a response gets a new connection for the
+/// remaining paths.
+pub(super) async fn check_http_paths(
+    result: &mut ReadResult,
+    cfg: &Config,
+    target: &Target,
+    tls: bool,
+) {
+    if cfg.http_paths.is_empty() {
+        return;
+    }
+    let mut budget = Budget {
+        bytes: cfg.max_bytes,
+        rounds: cfg.max_read_rounds,
+    };
+    let mut next = 0;
+    while next < cfg.http_paths.len() && budget.bytes > 0 && budget.rounds > 0 {
+        match fetch_paths(
+            result,
+            cfg,
+            target,
+            tls,
+            &cfg.http_paths[next..],
+            &mut budget,
+        )
+        .await
+        {
+            Ok(0) => break,
+            Ok(answered) => next += answered,
+            Err(err) => {
+                debug!(target = %target.resolved, error = %err, "HTTP path fetch failed");
+                break;
+            }
+        }
+    }
+}
+
+/// Opens a connection and requests `paths` in order until one goes
+/// unanswered or the server closes; returns how many were answered.
+async fn fetch_paths(
+    result: &mut ReadResult,
+    cfg: &Config,
+    target: &Target,
+    tls: bool,
+    paths: &[String],
+    budget: &mut Budget,
+) -> anyhow::Result<usize> {
+    let stream = timeout(
+        cfg.connect_timeout,
+        crate::engine::proxy::connect(target, cfg),
+    )
+    .await
+    .context("connect timeout")??;
+    if tls {
+        let mut stream = connect_tls(stream, target, None, cfg).await?;
+        Ok(request_paths(&mut stream, result, cfg, target, paths, budget).await)
+    } else {
+        let mut stream = stream;
+        Ok(request_paths(&mut stream, result, cfg, target, paths, budget).await)
+    }
+}
+
+async fn request_paths<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    result: &mut ReadResult,
+    cfg: &Config,
+    target: &Target,
+    paths: &[String],
+    budget: &mut Budget,
+) -> usize {
+    let host = server_name(target);
+    let mut buf = Vec::new();
+    for (idx, path) in paths.iter().enumerate() {
+        let connection = if idx + 1 == paths.len() {
+            "close"
+        } else {
+            "keep-alive"
+        };
+        let request =
+            format!("GET {path} HTTP/1.1\r\nHost: {host}\r\nConnection: {connection}\r\n\r\n");
+        if stream.write_all(request.as_bytes()).await.is_err() {
+            return idx;
+        }
+        let Some(response) = read_one(stream, &mut buf, budget, cfg.read_timeout).await else {
+            return idx;
+        };
+        result.fields.insert(
+            format!("http_path.{path}"),
+            format!("{} {}", response.status, response.length),
+        );
+        if !response.reusable {
+            return idx + 1;
+        }
+    }
+    paths.len()
+}
+
+/// Reads one response, leaving anything after it in `buf`. `None` when the
+/// headers never arrived.
+async fn read_one<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buf: &mut Vec<u8>,
+    budget: &mut Budget,
+    read_timeout: Duration,
+) -> Option<PathResponse> {
+    let (head, status) = loop {
+        let header_end = loop {
+            if let Some(end) = find_header_end(buf) {
+                break end;
+            }
+            if !fill(stream, buf, budget, read_timeout).await {
+                return None;
+            }
+        };
+        let head: Vec<u8> = buf.drain(..header_end).collect();
+        let status = status_code(&head)?;
+        // `100 Continue` and friends precede the real response.
+        if !status.starts_with('1') {
+            break (head, status);
+        }
+    };
+    let closes = head.starts_with(b"HTTP/1.0")
+        || header_value(&head, "Connection")
+            .is_some_and(|value| value.eq_ignore_ascii_case("close"));
+    let chunked = header_value(&head, "Transfer-Encoding")
+        .is_some_and(|value| value.to_ascii_lowercase().contains("chunked"));
+
+    let (length, complete) = if status == "204" || status == "304" {
+        (0, true)
+    } else if chunked {
+        read_chunked(stream, buf, budget, read_timeout).await
+    } else if let Some(length) = parse_content_length(&head) {
+        while buf.len() < length && fill(stream, buf, budget, read_timeout).await {}
+        let complete = buf.len() >= length;
+        buf.drain(..length.min(buf.len()));
+        (length, complete)
+    } else {
+        // The body runs until the server closes.
+        while fill(stream, buf, budget, read_timeout).await {}
+        let length = buf.len();
+        buf.clear();
+        (length, false)
+    };
+    Some(PathResponse {
+        status,
+        length,
+        reusable: complete && !closes,
+    })
+}
+
+/// Consumes a chunked body and any trailer fields after it; returns its
+/// decoded length and whether the blank line ending the message was reached.
+async fn read_chunked<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buf: &mut Vec<u8>,
+    budget: &mut Budget,
+    read_timeout: Duration,
+) -> (usize, bool) {
+    let mut length = 0;
+    loop {
+        let Some(line_end) = next_line(stream, buf, budget, read_timeout).await else {
+            return (length, false);
+        };
+        let size_line = String::from_utf8_lossy(&buf[..line_end]);
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let Ok(size) = usize::from_str_radix(size_hex, 16) else {
+            return (length, false);
+        };
+        if size == 0 {
+            buf.drain(..line_end + 2);
+            // Trailer fields, if any, up to the blank line.
+            loop {
+                let Some(line_end) = next_line(stream, buf, budget, read_timeout).await else {
+                    return (length, false);
+                };
+                buf.drain(..line_end + 2);
+                if line_end == 0 {
+                    return (length, true);
+                }
+            }
+        }
+        // Size line, data, CRLF. A size too large to add up is malformed.
+        let (Some(chunk_end), Some(total)) =
+            ((line_end + 4).checked_add(size), length.checked_add(size))
+        else {
+            return (length, false);
+        };
+        while buf.len() < chunk_end {
+            if !fill(stream, buf, budget, read_timeout).await {
+                let data = buf.len().saturating_sub(line_end + 2).min(size);
+                return (length + data, false);
+            }
+        }
+        buf.drain(..chunk_end);
+        length = total;
+    }
+}
+
+/// Where the first line in `buf` ends (its CR), reading more as needed.
+async fn next_line<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buf: &mut Vec<u8>,
+    budget: &mut Budget,
+    read_timeout: Duration,
+) -> Option<usize> {
+    loop {
+        if let Some(pos) = buf.windows(2).position(|window| window == b"\r\n") {
+            return Some(pos);
+        }
+        if !fill(stream, buf, budget, read_timeout).await {
+            return None;
+        }
+    }
+}
+
+/// Appends one read to `buf`. `false` at EOF, on an error or timeout, or
+/// once either part of `budget` is spent.
+async fn fill<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buf: &mut Vec<u8>,
+    budget: &mut Budget,
+    read_timeout: Duration,
+) -> bool {
+    if budget.bytes == 0 || budget.rounds == 0 {
+        return false;
+    }
+    let mut chunk = vec![0u8; budget.bytes.min(8192)];
+    match timeout(read_timeout, stream.read(&mut chunk)).await {
+        Ok(Ok(read)) if read > 0 => {
+            buf.extend_from_slice(&chunk[..read]);
+            budget.bytes -= read;
+            budget.rounds -= 1;
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    fn budget(bytes: usize) -> Budget {
+        Budget {
+            bytes,
+            rounds: 1000,
+        }
+    }
+
+    #[tokio::test]
+    async fn reads_back_to_back_responses() {
+        let mut wire: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello\
+            HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n\
+            HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\ngone";
+        let mut buf = Vec::new();
+        let mut budget = budget(4096);
+
+        let first = read_one(&mut wire, &mut buf, &mut budget, TIMEOUT).await;
+        assert_eq!(
+            first,
+            Some(PathResponse {
+                status: "200".into(),
+                length: 5,
+                reusable: true
+            })
+        );
+        let second = read_one(&mut wire, &mut buf, &mut budget, TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!((second.length, second.reusable), (5, true));
+        let third = read_one(&mut wire, &mut buf, &mut budget, TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(third.status, "404");
+        assert!(!third.reusable);
+        assert!(read_one(&mut wire, &mut buf, &mut budget, TIMEOUT)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn consumes_chunked_trailers() {
+        let mut wire: &[u8] = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            4\r\nbody\r\n0\r\nDigest: sha-256=abc\r\nExpires: never\r\n\r\n\
+            HTTP/1.1 204 No Content\r\n\r\n";
+        let mut buf = Vec::new();
+        let mut budget = budget(4096);
+        let first = read_one(&mut wire, &mut buf, &mut budget, TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!((first.length, first.reusable), (4, true));
+        let second = read_one(&mut wire, &mut buf, &mut budget, TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(second.status, "204");
+
+        // Trailers that never end leave the connection unusable.
+        let mut wire: &[u8] =
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n0\r\nDigest: x\r\n";
+        let mut buf = Vec::new();
+        let response = read_one(&mut wire, &mut buf, &mut budget, TIMEOUT)
+            .await
+            .unwrap();
+        assert!(!response.reusable);
+    }
+
+    #[tokio::test]
+    async fn rejects_an_oversized_chunk() {
+        let mut wire: &[u8] = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            3\r\nabc\r\nffffffffffffffff\r\nabc\r\n0\r\n\r\n";
+        let mut buf = Vec::new();
+        let mut budget = budget(4096);
+        let response = read_one(&mut wire, &mut buf, &mut budget, TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!((response.length, response.reusable), (3, false));
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_read_round_budget() {
+        let mut wire = (&b"HTTP/1.1 200 OK\r\n"[..]).chain(&b"Content-Length: 0\r\n\r\n"[..]);
+        let mut buf = Vec::new();
+        let mut budget = Budget {
+            bytes: 4096,
+            rounds: 1,
+        };
+        assert!(read_one(&mut wire, &mut buf, &mut budget, TIMEOUT)
+            .await
+            .is_none());
+        assert_eq!(budget.rounds, 0);
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_byte_budget() {
+        let mut wire: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\nshort";
+        let mut buf = Vec::new();
+        let mut budget = budget(45);
+        let response = read_one(&mut wire, &mut buf, &mut budget, TIMEOUT)
+            .await
+            .unwrap();
+        assert_eq!(response.length, 100);
+        assert!(!response.reusable);
+        assert_eq!(budget.bytes, 0);
+    }
+}
@@ -1,14 +1,24 @@
+use super::http::{check_admin_endpoint, read_response, status_code};
+use super::http_paths::check_http_paths;
+use super::kv_store::check_kv_store;
+use super::open_proxy::check_open_proxy;
+use super::smuggling::check_smuggling;
 use super::Prober;
-use crate::engine::reader::{BannerReader, ReadResult};
-use crate::model::{Config, Target, TlsInfo};
+use crate::engine::reader::ReadResult;
+use crate::model::{ClientCert, Config, Target, TlsInfo};
 use anyhow::{anyhow, Context};
 use async_trait::async_trait;
-use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
+use openssl::ssl::{
+    Ssl, SslConnector, SslMethod, SslOptions, SslRef, SslSession, SslVerifyMode, SslVersion,
+};
 use openssl::x509::X509NameRef;
+use std::ffi::{c_int, c_long, c_void};
 use std::pin::Pin;
 use std::sync::OnceLock;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
+use tokio::time::timeout;
 use tokio_openssl::SslStream;
 
 pub(super) struct HttpsProbe;
@@ -33,140 +43,388 @@ impl Prober for HttpsProbe {
         cfg: &Config,
         target: &Target,
     ) -> anyhow::Result<ReadResult> {
-        let connector = https_connector()?;
+        let (mut tls_stream, required_legacy_tls) =
+            match connect_tls(stream, target, None, cfg).await {
+                Ok(tls_stream) => (tls_stream, false),
+                Err(err) => match connect_legacy_tls(target, cfg).await {
+                    Ok(tls_stream) => (tls_stream, true),
+                    // Report the modern failure; the legacy one adds nothing.
+                    Err(_) => return Err(err),
+                },
+            };
+        let mut tls_info = extract_tls_info(&tls_stream, cfg);
+        tls_info.required_legacy_tls = Some(required_legacy_tls);
 
-        let host = target.original.host.as_str();
-        let sni_host = if host.is_empty() {
-            target.resolved.ip().to_string()
-        } else {
-            host.to_string()
-        };
-
-        let ssl = connector
-            .configure()
-            .context("failed to configure TLS connector")?
-            .into_ssl(&sni_host)
-            .context("failed to configure TLS SNI")?;
-        let mut tls_stream =
-            SslStream::new(ssl, stream).context("failed to initialize TLS stream")?;
-        Pin::new(&mut tls_stream)
-            .connect()
-            .await
-            .with_context(|| format!("TLS handshake failed for host {host}"))?;
-        let tls_info = extract_tls_info(&tls_stream);
-
-        let host_header = if host.is_empty() {
-            sni_host.as_str()
-        } else {
-            host
-        };
+        let host_header = server_name(target);
         let request = format!("GET / HTTP/1.0\r\nHost: {host_header}\r\n\r\n");
         tls_stream
             .write_all(request.as_bytes())
             .await
             .context("failed to write HTTPS request")?;
 
-        let mut reader = BannerReader::new(cfg.max_bytes, cfg.read_timeout);
-        let mut result = reader.read(&mut tls_stream, None).await?;
+        let mut result = read_response(&mut tls_stream, cfg).await?;
+        result.record_sent(cfg, request.as_bytes());
+        if cfg.tls_resumption {
+            // Read the response first: TLS 1.3 tickets arrive after the handshake.
+            // Close cleanly before reconnecting; servers drop sessions from
+            // connections that were cut off.
+            let session = tls_stream.ssl().session().map(|s| s.to_owned());
+            let _ = tls_stream.shutdown().await;
+            drop(tls_stream);
+            if let Some(session) = session {
+                tls_info.session_tickets_issued = Some(has_session_ticket(&session));
+                tls_info.session_resumption_supported =
+                    resumes_session(target, cfg, &session).await.ok();
+            }
+        }
+        if cfg.tls_reneg {
+            if let Ok((secure, allowed)) = client_renegotiation(target, cfg).await {
+                tls_info.secure_renegotiation = Some(secure);
+                tls_info.client_renegotiation_allowed = Some(allowed);
+            }
+        }
+        if cfg.tls_groups {
+            tls_info.supported_groups = supported_groups(target, cfg).await.ok();
+        }
+        if cfg.client_cert.is_some() {
+            let with_cert = status_code(&result.bytes);
+            tls_info.client_cert_required = status_without_client_cert(target, cfg)
+                .await
+                .ok()
+                .map(|without_cert| client_cert_required(with_cert.as_deref(), without_cert));
+        }
+        result.tls_info = Some(tls_info);
+        check_admin_endpoint(&mut result, cfg, target, true).await;
+        check_kv_store(&mut result, cfg, target, true).await;
+        check_smuggling(&mut result, cfg, target, true).await;
+        check_open_proxy(&mut result, cfg, target, true).await;
+        check_http_paths(&mut result, cfg, target, true).await;
+        Ok(result)
+    }
+}
+
+/// Completes a TLS handshake over `stream`, sending the target host as SNI,
+/// optionally advertising `alpn` (wire format, e.g. `b"\x02h2"`), and
+/// offering the `--client-cert` identity when one is configured.
+pub(super) async fn connect_tls(
+    stream: TcpStream,
+    target: &Target,
+    alpn: Option<&[u8]>,
+    cfg: &Config,
+) -> anyhow::Result<SslStream<TcpStream>> {
+    let ssl = client_ssl(target, alpn, cfg.client_cert.as_ref())?;
+    handshake(ssl, stream, target).await
+}
 
-        if let Some(content_length) = parse_content_length(&result.bytes) {
-            let header_end = find_header_end(&result.bytes).unwrap_or(result.bytes.len());
-            let already_have_body = result.bytes.len().saturating_sub(header_end);
-            // Respect the remaining budget when attempting to pull the body.
-            let available = cfg.max_bytes.saturating_sub(result.bytes.len());
-            let missing = content_length.saturating_sub(already_have_body);
+/// Repeats the HTTPS request on a fresh connection without the client
+/// certificate and returns the response status. `None` when the handshake or
+/// the response fails (TLS 1.3 servers reject a missing certificate only
+/// after the handshake, so the request must go through too).
+async fn status_without_client_cert(
+    target: &Target,
+    cfg: &Config,
+) -> anyhow::Result<Option<String>> {
+    let stream = timeout(
+        cfg.connect_timeout,
+        crate::engine::proxy::connect(target, cfg),
+    )
+    .await
+    .context("connect timeout")??;
+    let Ok(mut tls_stream) = handshake(client_ssl(target, None, None)?, stream, target).await
+    else {
+        return Ok(None);
+    };
+    let request = format!("GET / HTTP/1.0\r\nHost: {}\r\n\r\n", server_name(target));
+    if tls_stream.write_all(request.as_bytes()).await.is_err() {
+        return Ok(None);
+    }
+    Ok(read_response(&mut tls_stream, cfg)
+        .await
+        .ok()
+        .and_then(|response| status_code(&response.bytes)))
+}
 
-            if available == 0 || missing == 0 {
-                if missing > 0 {
-                    result.truncated = true;
-                    result.reason = crate::model::ReadStopReason::SizeLimit;
-                }
-                result.tls_info = Some(tls_info);
-                return Ok(result);
-            }
+/// Whether the server held back without the certificate what it served with
+/// it: no answer at all, or a 4xx the authenticated request did not get.
+/// nginx with `ssl_verify_client on` completes the handshake and answers
+/// `400 No required SSL certificate was sent`.
+fn client_cert_required(with_cert: Option<&str>, without_cert: Option<String>) -> bool {
+    match without_cert {
+        None => true,
+        Some(status) => with_cert != Some(status.as_str()) && status.starts_with('4'),
+    }
+}
 
-            let expected = missing.min(available);
-            let mut buf = vec![0u8; expected];
-            let mut read = 0usize;
-
-            while read < expected {
-                match tokio::time::timeout(cfg.read_timeout, tls_stream.read(&mut buf[read..]))
-                    .await
-                {
-                    Ok(Ok(0)) => {
-                        result.reason = crate::model::ReadStopReason::ConnectionClosed;
-                        break;
-                    }
-                    Ok(Ok(n)) => {
-                        read += n;
-                    }
-                    Ok(Err(err)) => return Err(err.into()),
-                    Err(_) => {
-                        result.reason = crate::model::ReadStopReason::Timeout;
-                        break;
-                    }
-                }
-            }
+/// `SSL_CTRL_GET_RI_SUPPORT`, the control behind OpenSSL's
+/// `SSL_get_secure_renegotiation_support` macro.
+const SSL_CTRL_GET_RI_SUPPORT: c_int = 76;
 
-            if content_length > already_have_body + available {
-                result.truncated = true;
-                result.reason = crate::model::ReadStopReason::SizeLimit;
-            } else if read == expected {
-                // We successfully pulled the entire expected body.
-                result.reason = crate::model::ReadStopReason::ConnectionClosed;
-            }
+// Neither call is wrapped by the openssl crate; libssl is already linked.
+extern "C" {
+    fn SSL_ctrl(ssl: *mut c_void, cmd: c_int, larg: c_long, parg: *mut c_void) -> c_long;
+    fn SSL_renegotiate(ssl: *mut c_void) -> c_int;
+}
 
-            result.bytes.extend_from_slice(&buf[..read]);
-        }
+/// The `SSL *` behind `ssl`: the crate's references are the C pointers.
+fn ssl_ptr(ssl: &SslRef) -> *mut c_void {
+    ssl as *const SslRef as *mut c_void
+}
 
-        result.tls_info = Some(tls_info);
-        Ok(result)
+/// Whether the ServerHello carried the renegotiation_info extension (RFC
+/// 5746). `None` for TLS 1.3, which has no renegotiation.
+fn secure_renegotiation(ssl: &SslRef) -> Option<bool> {
+    if ssl.version2() == Some(SslVersion::TLS1_3) {
+        return None;
     }
+    // SAFETY: a read-only query of the completed handshake.
+    let supported = unsafe {
+        SSL_ctrl(
+            ssl_ptr(ssl),
+            SSL_CTRL_GET_RI_SUPPORT,
+            0,
+            std::ptr::null_mut(),
+        )
+    };
+    Some(supported != 0)
 }
 
-fn parse_content_length(bytes: &[u8]) -> Option<usize> {
-    for line in bytes.split(|b| *b == b'\n') {
-        let line = line.strip_suffix(b"\r").unwrap_or(line);
-        if line.is_empty() {
-            break;
-        }
+/// `--tls-reneg`: on a fresh TLS 1.2 connection, starts a client-initiated
+/// renegotiation and reports `(secure_renegotiation, renegotiation
+/// completed)`. Servers that refuse answer with an alert, drop the
+/// connection or ignore the new ClientHello until the read timeout.
+async fn client_renegotiation(target: &Target, cfg: &Config) -> anyhow::Result<(bool, bool)> {
+    let stream = timeout(
+        cfg.connect_timeout,
+        crate::engine::proxy::connect(target, cfg),
+    )
+    .await
+    .context("connect timeout")??;
+    let mut ssl = renegotiation_connector()?
+        .configure()
+        .context("failed to configure TLS connector")?
+        .into_ssl(&server_name(target))
+        .context("failed to configure TLS SNI")?;
+    if let Some(identity) = &cfg.client_cert {
+        use_client_cert(&mut ssl, identity)?;
+    }
+    let mut tls_stream = handshake(ssl, stream, target).await?;
+    let secure = secure_renegotiation(tls_stream.ssl()).unwrap_or(false);
+    // SAFETY: only schedules a new handshake on the idle connection; the
+    // stream drives it below.
+    if unsafe { SSL_renegotiate(ssl_ptr(tls_stream.ssl())) } != 1 {
+        return Ok((secure, false));
+    }
+    let renegotiated = timeout(cfg.read_timeout, Pin::new(&mut tls_stream).do_handshake()).await;
+    Ok((secure, matches!(renegotiated, Ok(Ok(())))))
+}
 
-        if line
-            .get(..15)
-            .map(|prefix| prefix.eq_ignore_ascii_case(b"Content-Length:"))
-            .unwrap_or(false)
-        {
-            let value = std::str::from_utf8(&line[15..]).ok()?.trim();
-            if let Ok(len) = value.parse::<usize>() {
-                return Some(len);
-            }
+/// `--tls-groups` candidates as (OpenSSL name, IANA registry name, whether
+/// TLS 1.3 may carry it), from the post-quantum hybrid down to curves too
+/// small for today's use. Curves TLS 1.3 dropped are offered over TLS 1.2.
+const GROUPS: &[(&str, &str, bool)] = &[
+    ("X25519MLKEM768", "X25519MLKEM768", true),
+    ("X25519", "x25519", true),
+    ("X448", "x448", true),
+    ("P-256", "secp256r1", true),
+    ("P-384", "secp384r1", true),
+    ("P-521", "secp521r1", true),
+    ("brainpoolP256r1", "brainpoolP256r1", false),
+    ("brainpoolP384r1", "brainpoolP384r1", false),
+    ("P-224", "secp224r1", false),
+    ("ffdhe2048", "ffdhe2048", true),
+    ("ffdhe3072", "ffdhe3072", true),
+];
+
+/// Connections `--tls-groups` opens at most: one per candidate group.
+pub const GROUP_PROBES: usize = GROUPS.len();
+
+/// `--tls-groups`: offers each candidate group alone on a fresh connection
+/// and lists those the server completes a handshake with. Groups the local
+/// OpenSSL does not know are skipped; a failed connect ends the search.
+async fn supported_groups(target: &Target, cfg: &Config) -> anyhow::Result<Vec<String>> {
+    let mut supported = Vec::new();
+    for &(openssl_name, iana_name, tls13) in GROUPS {
+        let Ok(connector) = groups_connector(openssl_name, tls13) else {
+            continue;
+        };
+        let stream = timeout(
+            cfg.connect_timeout,
+            crate::engine::proxy::connect(target, cfg),
+        )
+        .await
+        .context("connect timeout")??;
+        let mut ssl = connector
+            .configure()
+            .context("failed to configure TLS connector")?
+            .into_ssl(&server_name(target))
+            .context("failed to configure TLS SNI")?;
+        if let Some(identity) = &cfg.client_cert {
+            use_client_cert(&mut ssl, identity)?;
+        }
+        let accepted = timeout(cfg.read_timeout, handshake(ssl, stream, target)).await;
+        if matches!(accepted, Ok(Ok(_))) {
+            supported.push(iana_name.to_string());
         }
     }
+    Ok(supported)
+}
 
-    None
+/// A connector offering only `group`. TLS 1.2 is limited to ECDHE suites,
+/// the only ones whose key exchange depends on the groups offered.
+fn groups_connector(group: &str, tls13: bool) -> anyhow::Result<SslConnector> {
+    let mut builder = SslConnector::builder(SslMethod::tls()).map_err(|e| anyhow!(e))?;
+    builder.set_verify(SslVerifyMode::NONE);
+    if !tls13 {
+        builder
+            .set_max_proto_version(Some(SslVersion::TLS1_2))
+            .map_err(|e| anyhow!(e))?;
+    }
+    builder.set_groups_list(group).map_err(|e| anyhow!(e))?;
+    builder
+        .set_cipher_list("ECDHE:@SECLEVEL=0")
+        .map_err(|e| anyhow!(e))?;
+    Ok(builder.build())
 }
 
-fn find_header_end(bytes: &[u8]) -> Option<usize> {
-    bytes
-        .windows(4)
-        .position(|window| window == b"\r\n\r\n")
-        .map(|pos| pos + 4)
+/// Second attempt for servers the default connector cannot talk to: a fresh
+/// connection allowing TLS 1.0+, every cipher at security level 0, servers
+/// without secure renegotiation, and no ALPN.
+async fn connect_legacy_tls(target: &Target, cfg: &Config) -> anyhow::Result<SslStream<TcpStream>> {
+    let stream = timeout(
+        cfg.connect_timeout,
+        crate::engine::proxy::connect(target, cfg),
+    )
+    .await
+    .context("connect timeout")??;
+    let mut ssl = legacy_connector()?
+        .configure()
+        .context("failed to configure TLS connector")?
+        .into_ssl(&server_name(target))
+        .context("failed to configure TLS SNI")?;
+    if let Some(identity) = &cfg.client_cert {
+        use_client_cert(&mut ssl, identity)?;
+    }
+    handshake(ssl, stream, target).await
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{find_header_end, parse_content_length};
+fn client_ssl(
+    target: &Target,
+    alpn: Option<&[u8]>,
+    client_cert: Option<&ClientCert>,
+) -> anyhow::Result<Ssl> {
+    let connector = https_connector()?;
+    let sni_host = server_name(target);
 
-    #[test]
-    fn parses_length_without_delimiter() {
-        let headers = b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 12\r\n";
-        assert_eq!(parse_content_length(headers), Some(12));
+    let mut config = connector
+        .configure()
+        .context("failed to configure TLS connector")?;
+    if let Some(protos) = alpn {
+        config
+            .set_alpn_protos(protos)
+            .context("failed to configure ALPN")?;
     }
+    let mut ssl = config
+        .into_ssl(&sni_host)
+        .context("failed to configure TLS SNI")?;
+    if let Some(identity) = client_cert {
+        use_client_cert(&mut ssl, identity)?;
+    }
+    Ok(ssl)
+}
 
-    #[test]
-    fn finds_header_end_with_body() {
-        let resp = b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nBody";
-        assert_eq!(find_header_end(resp), Some(38));
+/// Presents `identity` when the server asks for a certificate.
+fn use_client_cert(ssl: &mut SslRef, identity: &ClientCert) -> anyhow::Result<()> {
+    ssl.set_certificate(&identity.leaf)
+        .context("failed to set client certificate")?;
+    ssl.set_private_key(&identity.key)
+        .context("failed to set client key")?;
+    for cert in &identity.chain {
+        ssl.add_chain_cert(cert.clone())
+            .context("failed to add client certificate chain")?;
+    }
+    Ok(())
+}
+
+async fn handshake(
+    ssl: Ssl,
+    stream: TcpStream,
+    target: &Target,
+) -> anyhow::Result<SslStream<TcpStream>> {
+    let mut tls_stream = SslStream::new(ssl, stream).context("failed to initialize TLS stream")?;
+    Pin::new(&mut tls_stream)
+        .connect()
+        .await
+        .with_context(|| format!("TLS handshake failed for host {}", target.original.host))?;
+    Ok(tls_stream)
+}
+
+/// Reconnects offering `session` and reports whether the server accepted the
+/// abbreviated handshake.
+async fn resumes_session(
+    target: &Target,
+    cfg: &Config,
+    session: &SslSession,
+) -> anyhow::Result<bool> {
+    let stream = timeout(
+        cfg.connect_timeout,
+        crate::engine::proxy::connect(target, cfg),
+    )
+    .await
+    .context("connect timeout")??;
+    let mut ssl = client_ssl(target, None, cfg.client_cert.as_ref())?;
+    // SAFETY: the session was negotiated through the same shared connector.
+    unsafe { ssl.set_session(session) }.context("failed to set TLS session")?;
+    let tls_stream = handshake(ssl, stream, target).await?;
+    Ok(tls_stream.ssl().session_reused())
+}
+
+/// Whether the server handed out a session ticket (RFC 5077 / TLS 1.3) rather
+/// than relying on its session-ID cache. The crate exposes no accessor, so
+/// look for the `[10] tlsext_tick` member of OpenSSL's DER session encoding.
+fn has_session_ticket(session: &SslSession) -> bool {
+    const TICKET_TAG: u8 = 0xaa;
+    let Ok(der) = session.to_der() else {
+        return false;
+    };
+    let Some((0x30, body)) = der_element(&der).map(|(tag, body, _)| (tag, body)) else {
+        return false;
+    };
+    let mut rest = body;
+    while let Some((tag, _, next)) = der_element(rest) {
+        if tag == TICKET_TAG {
+            return true;
+        }
+        rest = next;
+    }
+    false
+}
+
+/// Splits one DER element off `data` as `(tag, contents, remainder)`.
+fn der_element(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first & 0x80 == 0 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let len = rest[..count]
+            .iter()
+            .fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
+        (len, &rest[count..])
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+/// Host name used for SNI and the `Host`/`:authority` header, falling back to
+/// the resolved IP when the target was given as a bare address.
+pub(super) fn server_name(target: &Target) -> String {
+    if target.original.host.is_empty() {
+        target.resolved.ip().to_string()
+    } else {
+        target.original.host.clone()
     }
 }
 
@@ -185,7 +443,51 @@ fn https_connector() -> anyhow::Result<&'static SslConnector> {
         .map_err(|err| anyhow!("failed to create TLS connector: {err}"))
 }
 
-fn extract_tls_info(stream: &SslStream<TcpStream>) -> TlsInfo {
+fn legacy_connector() -> anyhow::Result<&'static SslConnector> {
+    static CONNECTOR: OnceLock<anyhow::Result<SslConnector>> = OnceLock::new();
+
+    CONNECTOR
+        .get_or_init(|| {
+            let mut builder = SslConnector::builder(SslMethod::tls()).map_err(|e| anyhow!(e))?;
+            builder.set_verify(SslVerifyMode::NONE);
+            builder
+                .set_min_proto_version(Some(SslVersion::TLS1))
+                .map_err(|e| anyhow!(e))?;
+            builder
+                .set_cipher_list("ALL:@SECLEVEL=0")
+                .map_err(|e| anyhow!(e))?;
+            // SSL_OP_LEGACY_SERVER_CONNECT, which the openssl crate does not name.
+            builder.set_options(SslOptions::from_bits_retain(0x0000_0004));
+            Ok(builder.build())
+        })
+        .as_ref()
+        .map_err(|err| anyhow!("failed to create legacy TLS connector: {err}"))
+}
+
+/// TLS 1.2 at most, where renegotiation exists, and willing to renegotiate
+/// with servers lacking RFC 5746 so insecure renegotiation shows up too.
+fn renegotiation_connector() -> anyhow::Result<&'static SslConnector> {
+    static CONNECTOR: OnceLock<anyhow::Result<SslConnector>> = OnceLock::new();
+
+    CONNECTOR
+        .get_or_init(|| {
+            let mut builder = SslConnector::builder(SslMethod::tls()).map_err(|e| anyhow!(e))?;
+            builder.set_verify(SslVerifyMode::NONE);
+            builder
+                .set_max_proto_version(Some(SslVersion::TLS1_2))
+                .map_err(|e| anyhow!(e))?;
+            // SSL_OP_LEGACY_SERVER_CONNECT, as in the legacy connector.
+            builder.set_options(
+                SslOptions::from_bits_retain(0x0000_0004)
+                    | SslOptions::ALLOW_UNSAFE_LEGACY_RENEGOTIATION,
+            );
+            Ok(builder.build())
+        })
+        .as_ref()
+        .map_err(|err| anyhow!("failed to create renegotiation TLS connector: {err}"))
+}
+
+pub(super) fn extract_tls_info(stream: &SslStream<TcpStream>, cfg: &Config) -> TlsInfo {
     let ssl = stream.ssl();
     let mut info = TlsInfo {
         cipher: ssl
@@ -193,6 +495,7 @@ fn extract_tls_info(stream: &SslStream<TcpStream>) -> TlsInfo {
             .map(|cipher| cipher.name().to_string())
             .unwrap_or_default(),
         version: ssl.version_str().to_string(),
+        secure_renegotiation: secure_renegotiation(ssl),
         ..TlsInfo::default()
     };
 
@@ -201,11 +504,57 @@ fn extract_tls_info(stream: &SslStream<TcpStream>) -> TlsInfo {
         info.cert_issuer = format_x509_name(cert.issuer_name());
         info.cert_valid_from = cert.not_before().to_string();
         info.cert_valid_to = cert.not_after().to_string();
+        info.self_signed = Some(info.cert_subject == info.cert_issuer);
+        let seconds_left = seconds_until(cert.not_after());
+        info.days_until_expiry = seconds_left.map(|secs| secs.div_euclid(86_400));
+        info.expired = seconds_left.map(|secs| secs < 0);
+        info.cert_expiry = expiry_status(seconds_left, cfg.cert_warn_days).into();
+    }
+    if cfg.output.cert_dir.is_some() {
+        info.cert_pem = peer_certificates_pem(ssl, cfg.output.cert_chain);
     }
 
     info
 }
 
+/// Seconds from now until `not_after`, negative once it has passed.
+fn seconds_until(not_after: &Asn1TimeRef) -> Option<i64> {
+    let now = Asn1Time::days_from_now(0).ok()?;
+    let diff = now.diff(not_after).ok()?;
+    Some(i64::from(diff.days) * 86_400 + i64::from(diff.secs))
+}
+
+fn expiry_status(seconds_left: Option<i64>, warn_days: u32) -> &'static str {
+    match seconds_left {
+        None => "unknown",
+        Some(secs) if secs < 0 => "expired",
+        Some(secs) if secs < i64::from(warn_days) * 86_400 => "expiring",
+        Some(_) => "valid",
+    }
+}
+
+/// PEM of the leaf certificate, or of every certificate the server sent when
+/// `chain` is set. Empty when the server presented none.
+fn peer_certificates_pem(ssl: &SslRef, chain: bool) -> Vec<u8> {
+    let mut pem = Vec::new();
+    match ssl
+        .peer_cert_chain()
+        .filter(|certs| chain && !certs.is_empty())
+    {
+        Some(certs) => {
+            for cert in certs {
+                pem.extend(cert.to_pem().unwrap_or_default());
+            }
+        }
+        None => {
+            if let Some(cert) = ssl.peer_certificate() {
+                pem.extend(cert.to_pem().unwrap_or_default());
+            }
+        }
+    }
+    pem
+}
+
 fn format_x509_name(name: &X509NameRef) -> String {
     let mut parts = Vec::new();
     for entry in name.entries() {
@@ -221,3 +570,171 @@ fn format_x509_name(name: &X509NameRef) -> String {
     }
     parts.join(", ")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Protocol, TargetSpec};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::ssl::SslAcceptor;
+    use openssl::x509::{X509NameBuilder, X509};
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    /// A self-signed P-256 certificate for `cn`, valid from today.
+    fn identity(cn: &str) -> (X509, PKey<Private>) {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", cn).unwrap();
+        let name = name.build();
+        let mut cert = X509::builder().unwrap();
+        cert.set_version(2).unwrap();
+        cert.set_subject_name(&name).unwrap();
+        cert.set_issuer_name(&name).unwrap();
+        cert.set_pubkey(&key).unwrap();
+        cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        cert.set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        cert.sign(&key, MessageDigest::sha256()).unwrap();
+        (cert.build(), key)
+    }
+
+    /// An HTTPS server on a local port that asks for client certificates as
+    /// `verify` says, accepts any it gets, and answers requests made
+    /// without one with `no_cert_status`.
+    async fn mtls_server(verify: SslVerifyMode, no_cert_status: &'static str) -> Target {
+        let (cert, key) = identity("server.test");
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_certificate(&cert).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        acceptor.set_verify_callback(verify, |_, _| true);
+        let acceptor = acceptor.build();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let ssl = Ssl::new(acceptor.context()).unwrap();
+                let mut tls = SslStream::new(ssl, stream).unwrap();
+                if Pin::new(&mut tls).accept().await.is_err() {
+                    continue;
+                }
+                let mut request = [0u8; 1024];
+                let _ = tls.read(&mut request).await;
+                let status = match tls.ssl().peer_certificate() {
+                    Some(_) => "200 OK",
+                    None => no_cert_status,
+                };
+                let response = format!("HTTP/1.0 {status}\r\n\r\n");
+                let _ = tls.write_all(response.as_bytes()).await;
+                let _ = tls.shutdown().await;
+            }
+        });
+        Target {
+            original: TargetSpec {
+                host: "server.test".into(),
+                port: addr.port(),
+            },
+            resolved: addr,
+        }
+    }
+
+    async fn client_cert_required_by(target: &Target) -> Option<bool> {
+        let (leaf, key) = identity("client.test");
+        let mut cfg = crate::clients::mock::config(Protocol::Https);
+        cfg.client_cert = Some(ClientCert {
+            leaf,
+            chain: Vec::new(),
+            key,
+        });
+        let stream = TcpStream::connect(target.resolved).await.unwrap();
+        let result = HttpsProbe.execute(stream, &cfg, target).await.unwrap();
+        result.tls_info.unwrap().client_cert_required
+    }
+
+    #[tokio::test]
+    async fn detects_required_client_certificates() {
+        let demanded = SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT;
+        let target = mtls_server(demanded, "200 OK").await;
+        assert_eq!(client_cert_required_by(&target).await, Some(true));
+
+        // nginx `ssl_verify_client on`: the handshake completes, the request
+        // is refused.
+        let nginx = mtls_server(SslVerifyMode::PEER, "400 Bad Request").await;
+        assert_eq!(client_cert_required_by(&nginx).await, Some(true));
+
+        let optional = mtls_server(SslVerifyMode::PEER, "200 OK").await;
+        assert_eq!(client_cert_required_by(&optional).await, Some(false));
+
+        let none = mtls_server(SslVerifyMode::NONE, "200 OK").await;
+        assert_eq!(client_cert_required_by(&none).await, Some(false));
+    }
+
+    #[test]
+    fn compares_statuses_with_and_without_the_certificate() {
+        assert!(client_cert_required(Some("200"), None));
+        assert!(client_cert_required(Some("200"), Some("403".into())));
+        assert!(!client_cert_required(Some("404"), Some("404".into())));
+        assert!(!client_cert_required(Some("200"), Some("302".into())));
+    }
+
+    #[test]
+    fn grades_certificate_expiry() {
+        assert_eq!(expiry_status(None, 30), "unknown");
+        assert_eq!(expiry_status(Some(-1), 30), "expired");
+        assert_eq!(expiry_status(Some(29 * 86_400), 30), "expiring");
+        assert_eq!(expiry_status(Some(31 * 86_400), 30), "valid");
+        assert_eq!(expiry_status(Some(3_600), 0), "valid");
+
+        let next_week = Asn1Time::days_from_now(7).unwrap();
+        let days = seconds_until(&next_week).unwrap().div_euclid(86_400);
+        assert!((6..=7).contains(&days), "{days}");
+        let epoch = Asn1Time::from_unix(0).unwrap();
+        assert!(seconds_until(&epoch).unwrap() < 0);
+    }
+
+    #[test]
+    fn walks_der_elements() {
+        let der = [0x30, 0x06, 0x02, 0x01, 0x01, 0xaa, 0x01, 0x00, 0xff];
+        let (tag, body, rest) = der_element(&der).unwrap();
+        assert_eq!((tag, rest), (0x30, &[0xff][..]));
+        let (tag, _, rest) = der_element(body).unwrap();
+        assert_eq!(tag, 0x02);
+        assert_eq!(der_element(rest).unwrap().0, 0xaa);
+        assert!(der_element(&[0x04, 0x82, 0x01]).is_none());
+    }
+
+    /// Handshakes with a local OpenSSL server capped at `max_version` and
+    /// returns what the client side reports.
+    fn handshake_secure_renegotiation(max_version: SslVersion) -> Option<bool> {
+        let (cert, key) = identity("server.test");
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+        acceptor.set_certificate(&cert).unwrap();
+        acceptor.set_private_key(&key).unwrap();
+        let acceptor = acceptor.build();
+        let (client, server) = std::os::unix::net::UnixStream::pair().unwrap();
+        let server = std::thread::spawn(move || {
+            let _stream = acceptor.accept(server).unwrap();
+        });
+        let mut connector = SslConnector::builder(SslMethod::tls()).unwrap();
+        connector.set_verify(SslVerifyMode::NONE);
+        connector.set_max_proto_version(Some(max_version)).unwrap();
+        let stream = connector.build().connect("server.test", client).unwrap();
+        server.join().unwrap();
+        secure_renegotiation(stream.ssl())
+    }
+
+    #[test]
+    fn reads_rfc_5746_support_from_the_handshake() {
+        assert_eq!(
+            handshake_secure_renegotiation(SslVersion::TLS1_2),
+            Some(true)
+        );
+        assert_eq!(handshake_secure_renegotiation(SslVersion::TLS1_3), None);
+    }
+}
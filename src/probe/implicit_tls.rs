@@ -0,0 +1,24 @@
+//! `--implicit-tls-ports`: services such as IMAPS or SMTPS expect a TLS
+//! ClientHello before their first line, so the pipeline completes the
+//! handshake here and runs the plain-text client inside the session.
+
+use super::https::{connect_tls, extract_tls_info};
+use crate::model::{Config, Target, TlsInfo};
+use anyhow::Context;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_openssl::SslStream;
+
+/// Wraps `stream` in TLS and returns the session with its certificate.
+pub async fn connect(
+    stream: TcpStream,
+    target: &Target,
+    cfg: &Config,
+) -> anyhow::Result<(SslStream<TcpStream>, TlsInfo)> {
+    let tls_stream = timeout(cfg.read_timeout, connect_tls(stream, target, None, cfg))
+        .await
+        .context("TLS handshake timeout")?
+        .context("implicit TLS handshake failed")?;
+    let tls_info = extract_tls_info(&tls_stream, cfg);
+    Ok((tls_stream, tls_info))
+}
@@ -0,0 +1,237 @@
+use super::http::{body, fetch, get_request, header_value, status_code};
+use super::https::server_name;
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, Target};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// Consul's `/v1/agent/self` and Riak's `/stats` run to tens of kilobytes,
+/// well past the banner-sized `--max-bytes` default.
+const INFO_MAX_BYTES: usize = 256 * 1024;
+
+/// Key-value stores with an HTTP API on their default port.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum KvStore {
+    Etcd,
+    Consul,
+    Riak,
+}
+
+impl KvStore {
+    fn for_port(port: u16) -> Option<Self> {
+        match port {
+            2379 => Some(KvStore::Etcd),
+            8500 => Some(KvStore::Consul),
+            8098 => Some(KvStore::Riak),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            KvStore::Etcd => "etcd",
+            KvStore::Consul => "consul",
+            KvStore::Riak => "riak",
+        }
+    }
+
+    /// The endpoint that names the product and its version.
+    fn info_path(self) -> &'static str {
+        match self {
+            KvStore::Etcd => "/version",
+            KvStore::Consul => "/v1/agent/self",
+            KvStore::Riak => "/stats",
+        }
+    }
+}
+
+/// On the default port of etcd, Consul or Riak, asks the store's HTTP API for
+/// its version and records `kv_product`, `kv_version` and `kv.<detail>`
+/// fields. `admin_path`/`admin_status` tell whether an endpoint that needs a
+/// token once auth is on answered without one, as for CouchDB and InfluxDB.
+pub(super) async fn check_kv_store(
+    result: &mut ReadResult,
+    cfg: &Config,
+    target: &Target,
+    tls: bool,
+) {
+    let Some(store) = KvStore::for_port(target.resolved.port()) else {
+        return;
+    };
+    let cfg = Config {
+        max_bytes: INFO_MAX_BYTES,
+        max_body: None,
+        ..cfg.clone()
+    };
+    if let Err(err) = probe_store(store, result, &cfg, target, tls).await {
+        debug!(target = %target.resolved, error = %err, "{} API check failed", store.name());
+    }
+}
+
+async fn probe_store(
+    store: KvStore,
+    result: &mut ReadResult,
+    cfg: &Config,
+    target: &Target,
+    tls: bool,
+) -> anyhow::Result<()> {
+    let info = fetch(cfg, target, &get_request(target, store.info_path()), tls).await?;
+    let Some(mut fields) = parse_info(store, &info.bytes) else {
+        return Ok(());
+    };
+    if store == KvStore::Etcd {
+        // `/version` stays public with auth on; reading a key does not.
+        let request = etcd_range_request(target);
+        let range = fetch(cfg, target, &request, tls).await?;
+        if let Some(status) = etcd_auth_status(&range.bytes) {
+            fields.insert("admin_path".into(), "/v3/kv/range".into());
+            fields.insert("admin_status".into(), status);
+        }
+    }
+    fields.insert("kv_product".into(), store.name().into());
+    result.fields.extend(fields);
+    Ok(())
+}
+
+/// Fields from the info endpoint, or `None` when the answer does not come
+/// from `store`.
+fn parse_info(store: KvStore, response: &[u8]) -> Option<BTreeMap<String, String>> {
+    let status = status_code(response)?;
+    let json = serde_json::from_slice::<Value>(body(response)).ok();
+    let text = |pointer: &str| {
+        json.as_ref()
+            .and_then(|json| json.pointer(pointer))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    let mut fields = BTreeMap::new();
+    match store {
+        KvStore::Etcd => {
+            fields.insert("kv_version".into(), text("/etcdserver")?);
+            if let Some(cluster) = text("/etcdcluster") {
+                fields.insert("kv.cluster_version".into(), cluster);
+            }
+        }
+        KvStore::Consul if status.starts_with('2') => {
+            fields.insert("kv_version".into(), text("/Config/Version")?);
+            if let Some(datacenter) = text("/Config/Datacenter") {
+                fields.insert("kv.datacenter".into(), datacenter);
+            }
+            let acls = json.as_ref().and_then(|json| {
+                json.pointer("/DebugConfig/ACLsEnabled")
+                    .or_else(|| json.pointer("/Config/ACLsEnabled"))
+                    .and_then(Value::as_bool)
+            });
+            if let Some(acls) = acls {
+                fields.insert("kv.acl_enabled".into(), acls.to_string());
+            }
+        }
+        // A default-deny ACL policy refuses the agent endpoint outright.
+        KvStore::Consul => {
+            let denied = String::from_utf8_lossy(body(response));
+            if !(status == "403"
+                && (denied.contains("Permission denied") || denied.contains("ACL not found")))
+            {
+                return None;
+            }
+            fields.insert("kv.acl_enabled".into(), "true".into());
+        }
+        KvStore::Riak if status.starts_with('2') => {
+            fields.insert("kv_version".into(), text("/riak_kv_version")?);
+            if let Some(node) = text("/nodename") {
+                fields.insert("kv.node".into(), node);
+            }
+        }
+        KvStore::Riak => {
+            let realm = header_value(response, "WWW-Authenticate")?;
+            if !(status == "401" && realm.contains("Riak")) {
+                return None;
+            }
+        }
+    }
+    if store != KvStore::Etcd {
+        fields.insert("admin_path".into(), store.info_path().into());
+        fields.insert("admin_status".into(), status);
+    }
+    Some(fields)
+}
+
+/// A keys-only lookup of a single, almost certainly absent key: it needs
+/// read access but returns nothing stored.
+fn etcd_range_request(target: &Target) -> String {
+    let body = r#"{"key":"AA==","keys_only":true,"count_only":true}"#;
+    format!(
+        "POST /v3/kv/range HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\n\r\n{body}",
+        server_name(target),
+        body.len()
+    )
+}
+
+/// etcd rejects a request without a token as an invalid argument (HTTP 400)
+/// rather than with a 401; report it as the auth failure it is.
+fn etcd_auth_status(response: &[u8]) -> Option<String> {
+    let status = status_code(response)?;
+    let text = String::from_utf8_lossy(body(response));
+    if status == "400"
+        && (text.contains("user name is empty") || text.contains("invalid auth token"))
+    {
+        return Some("401".into());
+    }
+    Some(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_versions_from_info_endpoints() {
+        let etcd = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n\
+            {\"etcdserver\":\"3.5.9\",\"etcdcluster\":\"3.5.0\"}";
+        let fields = parse_info(KvStore::Etcd, etcd).unwrap();
+        assert_eq!(fields["kv_version"], "3.5.9");
+        assert_eq!(fields["kv.cluster_version"], "3.5.0");
+        assert!(!fields.contains_key("admin_status"));
+
+        let consul = b"HTTP/1.1 200 OK\r\n\r\n{\"Config\":{\"Datacenter\":\"dc1\",\
+            \"Version\":\"1.15.2\"},\"DebugConfig\":{\"ACLsEnabled\":false}}";
+        let fields = parse_info(KvStore::Consul, consul).unwrap();
+        assert_eq!(fields["kv_version"], "1.15.2");
+        assert_eq!(fields["kv.datacenter"], "dc1");
+        assert_eq!(fields["kv.acl_enabled"], "false");
+        assert_eq!(fields["admin_status"], "200");
+
+        let riak = b"HTTP/1.1 200 OK\r\n\r\n{\"nodename\":\"riak@127.0.0.1\",\
+            \"riak_kv_version\":\"2.9.10\"}";
+        assert_eq!(
+            parse_info(KvStore::Riak, riak).unwrap()["kv_version"],
+            "2.9.10"
+        );
+    }
+
+    #[test]
+    fn recognises_refusals_and_other_servers() {
+        let denied = b"HTTP/1.1 403 Forbidden\r\n\r\nPermission denied";
+        let fields = parse_info(KvStore::Consul, denied).unwrap();
+        assert_eq!(fields["admin_status"], "403");
+        assert!(!fields.contains_key("kv_version"));
+
+        let nginx = b"HTTP/1.1 403 Forbidden\r\nServer: nginx\r\n\r\n<html></html>";
+        assert_eq!(parse_info(KvStore::Consul, nginx), None);
+        assert_eq!(
+            parse_info(KvStore::Etcd, b"HTTP/1.1 404 Not Found\r\n\r\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn treats_missing_etcd_token_as_unauthorized() {
+        let refused = b"HTTP/1.1 400 Bad Request\r\n\r\n\
+            {\"error\":\"etcdserver: user name is empty\",\"code\":3}";
+        assert_eq!(etcd_auth_status(refused).as_deref(), Some("401"));
+        let open = b"HTTP/1.1 200 OK\r\n\r\n{\"header\":{}}";
+        assert_eq!(etcd_auth_status(open).as_deref(), Some("200"));
+    }
+}
@@ -1,7 +1,18 @@
+mod grpc;
 mod http;
+mod http_paths;
 mod https;
+pub mod implicit_tls;
+mod kv_store;
+mod open_proxy;
+pub mod proxy_protocol;
 mod redis;
 mod registry;
+mod smuggling;
+pub mod starttls;
 mod tls;
+mod winrm;
 
+pub use https::GROUP_PROBES as TLS_GROUP_PROBES;
 pub use registry::{probe_for_target, ProbeRequest, Prober};
+pub use smuggling::PROBE_TIMEOUT as SMUGGLING_PROBE_TIMEOUT;
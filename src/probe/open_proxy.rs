@@ -0,0 +1,124 @@
+use super::http::{body, fetch, status_code};
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, Target};
+use tracing::debug;
+
+/// Host the target is asked to reach. Its page is stable and well known, so
+/// a relayed response can be told apart from the target's own error page.
+const CANARY_HOST: &str = "example.com";
+const CANARY_MARKER: &[u8] = b"Example Domain";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Verdict {
+    /// The request was relayed (canary page returned, tunnel opened).
+    Proxied,
+    Rejected,
+}
+
+impl Verdict {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::Proxied => "proxied",
+            Verdict::Rejected => "rejected",
+        }
+    }
+}
+
+/// `--test-open-proxy`: asks the target, on two fresh connections, for
+/// `http://example.com/` in absolute form and for a `CONNECT` tunnel to
+/// `example.com:443`. Records each verdict in `open_proxy_get` and
+/// `open_proxy_connect`, and `open_proxy` when either was relayed.
+pub(super) async fn check_open_proxy(
+    result: &mut ReadResult,
+    cfg: &Config,
+    target: &Target,
+    tls: bool,
+) {
+    if !cfg.test_open_proxy {
+        return;
+    }
+    let checks = [
+        ("open_proxy_get", absolute_get_request()),
+        ("open_proxy_connect", connect_request()),
+    ];
+    let mut open = None;
+    for (field, request) in checks {
+        match fetch(cfg, target, &request, tls).await {
+            Ok(response) => {
+                let verdict = if field == "open_proxy_get" {
+                    classify_get(&response.bytes)
+                } else {
+                    classify_connect(&response.bytes)
+                };
+                *open.get_or_insert(false) |= verdict == Verdict::Proxied;
+                result.fields.insert(field.into(), verdict.as_str().into());
+            }
+            Err(err) => {
+                debug!(target = %target.resolved, error = %err, "open proxy check failed")
+            }
+        }
+    }
+    if let Some(open) = open {
+        result.fields.insert("open_proxy".into(), open.to_string());
+    }
+}
+
+fn absolute_get_request() -> String {
+    format!(
+        "GET http://{CANARY_HOST}/ HTTP/1.1\r\nHost: {CANARY_HOST}\r\nConnection: close\r\n\r\n"
+    )
+}
+
+fn connect_request() -> String {
+    format!("CONNECT {CANARY_HOST}:443 HTTP/1.1\r\nHost: {CANARY_HOST}:443\r\n\r\n")
+}
+
+/// A 200 alone is not enough: many servers answer any path with their own
+/// page, so the canary's content must come back.
+fn classify_get(response: &[u8]) -> Verdict {
+    let ok = status_code(response).is_some_and(|code| code.starts_with('2'));
+    let canary = body(response)
+        .windows(CANARY_MARKER.len())
+        .any(|window| window == CANARY_MARKER);
+    if ok && canary {
+        Verdict::Proxied
+    } else {
+        Verdict::Rejected
+    }
+}
+
+fn classify_connect(response: &[u8]) -> Verdict {
+    if status_code(response).is_some_and(|code| code.starts_with('2')) {
+        Verdict::Proxied
+    } else {
+        Verdict::Rejected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_the_canary_page_to_call_get_proxied() {
+        let relayed = b"HTTP/1.1 200 OK\r\n\r\n<title>Example Domain</title>";
+        assert_eq!(classify_get(relayed), Verdict::Proxied);
+        let own_page = b"HTTP/1.1 200 OK\r\n\r\n<title>Welcome to nginx!</title>";
+        assert_eq!(classify_get(own_page), Verdict::Rejected);
+        let refused = b"HTTP/1.1 403 Forbidden\r\n\r\nExample Domain";
+        assert_eq!(classify_get(refused), Verdict::Rejected);
+    }
+
+    #[test]
+    fn classifies_connect_by_status() {
+        assert_eq!(
+            classify_connect(b"HTTP/1.1 200 Connection established\r\n\r\n"),
+            Verdict::Proxied
+        );
+        assert_eq!(
+            classify_connect(b"HTTP/1.1 405 Method Not Allowed\r\n\r\n"),
+            Verdict::Rejected
+        );
+        assert!(connect_request().starts_with("CONNECT example.com:443 HTTP/1.1\r\n"));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::model::ProxyProtocolVersion;
+use std::net::SocketAddr;
+
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\x00\r\nQUIT\n";
+
+/// Builds a HAProxy PROXY protocol header claiming `source` as the client of
+/// a connection to `destination`.
+pub fn header(
+    version: ProxyProtocolVersion,
+    source: SocketAddr,
+    destination: SocketAddr,
+) -> Vec<u8> {
+    match version {
+        ProxyProtocolVersion::V1 => v1_header(source, destination),
+        ProxyProtocolVersion::V2 => v2_header(source, destination),
+    }
+}
+
+fn v1_header(source: SocketAddr, destination: SocketAddr) -> Vec<u8> {
+    let family = match (source, destination) {
+        (SocketAddr::V4(_), SocketAddr::V4(_)) => "TCP4",
+        (SocketAddr::V6(_), SocketAddr::V6(_)) => "TCP6",
+        _ => return b"PROXY UNKNOWN\r\n".to_vec(),
+    };
+    format!(
+        "PROXY {family} {} {} {} {}\r\n",
+        source.ip(),
+        destination.ip(),
+        source.port(),
+        destination.port()
+    )
+    .into_bytes()
+}
+
+fn v2_header(source: SocketAddr, destination: SocketAddr) -> Vec<u8> {
+    let mut header = V2_SIGNATURE.to_vec();
+    // Version 2, PROXY command.
+    header.push(0x21);
+    let mut addresses = Vec::new();
+    let family = match (source, destination) {
+        (SocketAddr::V4(src), SocketAddr::V4(dst)) => {
+            addresses.extend_from_slice(&src.ip().octets());
+            addresses.extend_from_slice(&dst.ip().octets());
+            0x11
+        }
+        (SocketAddr::V6(src), SocketAddr::V6(dst)) => {
+            addresses.extend_from_slice(&src.ip().octets());
+            addresses.extend_from_slice(&dst.ip().octets());
+            0x21
+        }
+        // Mixed families cannot be expressed; send an UNSPEC header instead.
+        _ => 0x00,
+    };
+    if family != 0x00 {
+        addresses.extend_from_slice(&source.port().to_be_bytes());
+        addresses.extend_from_slice(&destination.port().to_be_bytes());
+    }
+    header.push(family);
+    header.extend_from_slice(&(addresses.len() as u16).to_be_bytes());
+    header.extend_from_slice(&addresses);
+    header
+}
+
+/// Decides whether a service kept talking normally after receiving a PROXY
+/// header, given `baseline`, its response to the same exchange without one.
+/// Services that don't understand the header either hang up, answer with a
+/// protocol error, or send their unprompted greeting and then stop, so this
+/// looks for the usual error replies (HTTP 400, 5xx line replies, `-ERR`, SSH
+/// protocol mismatch) and requires the reply to get as far as the baseline.
+pub fn accepted(response: &[u8], baseline: &[u8]) -> bool {
+    if response.is_empty() {
+        return false;
+    }
+    let text = String::from_utf8_lossy(response);
+    !text.lines().any(is_rejection_line) && head_lines(response) >= head_lines(baseline)
+}
+
+/// Lines up to the first blank one, so HTTP bodies of varying length do not
+/// count.
+fn head_lines(bytes: &[u8]) -> usize {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .take_while(|line| !line.trim().is_empty())
+        .count()
+}
+
+fn is_rejection_line(line: &str) -> bool {
+    let line = line.trim();
+    if let Some(rest) = line.strip_prefix("HTTP/") {
+        return rest.split_whitespace().nth(1) == Some("400");
+    }
+    let bytes = line.as_bytes();
+    let line_reply_error = bytes.len() >= 3
+        && bytes[0] == b'5'
+        && bytes[1..3].iter().all(u8::is_ascii_digit)
+        && bytes.get(3).is_none_or(|b| *b == b' ' || *b == b'-');
+    line_reply_error
+        || line.starts_with("-ERR")
+        || line.to_ascii_lowercase().contains("protocol mismatch")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_v1_header() {
+        let header = header(
+            ProxyProtocolVersion::V1,
+            "192.0.2.1:40000".parse().unwrap(),
+            "198.51.100.7:80".parse().unwrap(),
+        );
+        assert_eq!(header, b"PROXY TCP4 192.0.2.1 198.51.100.7 40000 80\r\n");
+    }
+
+    #[test]
+    fn builds_v2_header() {
+        let header = header(
+            ProxyProtocolVersion::V2,
+            "192.0.2.1:40000".parse().unwrap(),
+            "198.51.100.7:80".parse().unwrap(),
+        );
+        assert_eq!(&header[..12], V2_SIGNATURE);
+        assert_eq!(header[12], 0x21);
+        assert_eq!(header[13], 0x11);
+        assert_eq!(u16::from_be_bytes([header[14], header[15]]), 12);
+        assert_eq!(header.len(), 16 + 12);
+    }
+
+    #[test]
+    fn classifies_responses() {
+        assert!(accepted(b"HTTP/1.1 200 OK\r\n\r\n", b""));
+        assert!(accepted(b"220 mail.example ESMTP\r\n", b""));
+        assert!(!accepted(b"", b""));
+        assert!(!accepted(b"HTTP/1.1 400 Bad Request\r\n\r\n", b""));
+        assert!(!accepted(
+            b"220 ready\r\n500 Syntax error, command unrecognized\r\n",
+            b""
+        ));
+        assert!(!accepted(b"-ERR unknown command 'PROXY'\r\n", b""));
+        assert!(!accepted(
+            b"SSH-2.0-OpenSSH_9.6\r\nProtocol mismatch.\n",
+            b""
+        ));
+    }
+
+    #[test]
+    fn requires_more_than_the_unprompted_greeting() {
+        let baseline = b"220 mail.example ESMTP\r\n250-mail.example\r\n250 SIZE 1000\r\n";
+        assert!(!accepted(b"220 mail.example ESMTP\r\n", baseline));
+        assert!(accepted(baseline, baseline));
+        assert!(accepted(
+            b"HTTP/1.1 200 OK\r\nServer: x\r\n\r\nshort",
+            b"HTTP/1.1 200 OK\r\nServer: x\r\n\r\nlonger\nbody\n"
+        ));
+    }
+}
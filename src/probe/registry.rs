@@ -5,10 +5,12 @@ use async_trait::async_trait;
 use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
 
+use super::grpc::GrpcProbe;
 use super::http::HttpProbe;
 use super::https::HttpsProbe;
 use super::redis::RedisProbe;
 use super::tls::TlsProbe;
+use super::winrm::WinrmProbe;
 
 #[async_trait]
 pub trait Prober: Send + Sync {
@@ -34,8 +36,11 @@ pub trait Prober: Send + Sync {
                 .with_context(|| format!("failed to write probe {}", self.name()))?;
         }
 
-        let mut reader = BannerReader::new(cfg.max_bytes, cfg.read_timeout);
-        reader.read(&mut stream, self.expected_delimiter()).await
+        let mut reader =
+            BannerReader::new(cfg.max_bytes, cfg.read_timeout).with_max_rounds(cfg.max_read_rounds);
+        let mut result = reader.read(&mut stream, self.expected_delimiter()).await?;
+        result.record_sent(cfg, self.probe_bytes());
+        Ok(result)
     }
 }
 
@@ -46,10 +51,12 @@ pub struct ProbeRequest {
     pub protocol: Protocol,
 }
 
+static GRPC_PROBE: GrpcProbe = GrpcProbe;
 static HTTP_PROBE: HttpProbe = HttpProbe;
 static HTTPS_PROBE: HttpsProbe = HttpsProbe;
 static REDIS_PROBE: RedisProbe = RedisProbe;
 static TLS_PROBE: TlsProbe = TlsProbe;
+static WINRM_PROBE: WinrmProbe = WinrmProbe;
 
 pub fn probe_for_target(req: &ProbeRequest) -> Option<&'static dyn Prober> {
     if matches!(req.mode, ScanMode::Passive) {
@@ -57,10 +64,12 @@ pub fn probe_for_target(req: &ProbeRequest) -> Option<&'static dyn Prober> {
     }
 
     match req.protocol {
+        Protocol::Grpc => Some(&GRPC_PROBE as &'static dyn Prober),
         Protocol::Http => Some(&HTTP_PROBE as &'static dyn Prober),
         Protocol::Https => Some(&HTTPS_PROBE as &'static dyn Prober),
         Protocol::Tls => Some(&TLS_PROBE as &'static dyn Prober),
         Protocol::Redis => Some(&REDIS_PROBE as &'static dyn Prober),
+        Protocol::Winrm => Some(&WINRM_PROBE as &'static dyn Prober),
         _ => None,
     }
 }
@@ -0,0 +1,133 @@
+use super::https::{connect_tls, server_name};
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, Target};
+use anyhow::Context;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::time::timeout;
+use tracing::debug;
+
+/// How long the CL.TE probe waits for an answer. A desync-prone chain stalls
+/// until the back end gives up, so silence past this is reported as a hang.
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Body of the CL.TE timing probe. `Content-Length: 4` covers `1\r\nA` only: a
+/// front end honouring the length forwards a truncated chunk and a chunked
+/// back end waits for the rest, while a server honouring `Transfer-Encoding`
+/// rejects `X` as a chunk size straight away.
+const PROBE_BODY: &str = "1\r\nA\r\nX";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Behavior {
+    /// No response within [`PROBE_TIMEOUT`].
+    Hang,
+    /// 4xx/5xx, a non-HTTP reply or the connection dropped.
+    Error,
+    Normal,
+}
+
+impl Behavior {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Behavior::Hang => "hang",
+            Behavior::Error => "error",
+            Behavior::Normal => "normal",
+        }
+    }
+}
+
+/// `--test-smuggling`: sends a request carrying both `Content-Length` and
+/// `Transfer-Encoding: chunked` on a fresh connection and records how the
+/// server reacted in `smuggling_probe`; a hang is the CL.TE desync indicator.
+pub(super) async fn check_smuggling(
+    result: &mut ReadResult,
+    cfg: &Config,
+    target: &Target,
+    tls: bool,
+) {
+    if !cfg.test_smuggling {
+        return;
+    }
+    match probe(cfg, target, tls).await {
+        Ok(behavior) => {
+            result
+                .fields
+                .insert("smuggling_probe".into(), behavior.as_str().into());
+        }
+        Err(err) => debug!(target = %target.resolved, error = %err, "smuggling probe failed"),
+    }
+}
+
+async fn probe(cfg: &Config, target: &Target, tls: bool) -> anyhow::Result<Behavior> {
+    let stream = timeout(
+        cfg.connect_timeout,
+        crate::engine::proxy::connect(target, cfg),
+    )
+    .await
+    .context("connect timeout")??;
+    if tls {
+        let mut stream = connect_tls(stream, target, None, cfg).await?;
+        send_probe(&mut stream, target).await
+    } else {
+        let mut stream = stream;
+        send_probe(&mut stream, target).await
+    }
+}
+
+async fn send_probe<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    target: &Target,
+) -> anyhow::Result<Behavior> {
+    let request = format!(
+        "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: application/x-www-form-urlencoded\r\n\
+         Content-Length: 4\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n{PROBE_BODY}",
+        server_name(target)
+    );
+    stream.write_all(request.as_bytes()).await?;
+    let mut buf = [0u8; 64];
+    let reply = match timeout(PROBE_TIMEOUT, stream.read(&mut buf)).await {
+        Err(_) => return Ok(Behavior::Hang),
+        Ok(Ok(n)) => &buf[..n],
+        Ok(Err(_)) => return Ok(Behavior::Error),
+    };
+    Ok(classify(reply))
+}
+
+/// The read is cut at 64 bytes, possibly inside a multibyte character, so
+/// the text is decoded lossily.
+fn classify(reply: &[u8]) -> Behavior {
+    let text = String::from_utf8_lossy(reply);
+    let status = text
+        .lines()
+        .next()
+        .filter(|line| line.starts_with("HTTP/"))
+        .and_then(|line| line.split_whitespace().nth(1));
+    match status {
+        Some(code) if code.starts_with(['1', '2', '3']) => Behavior::Normal,
+        _ => Behavior::Error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_length_covers_only_the_truncated_chunk() {
+        assert_eq!(&PROBE_BODY[..4], "1\r\nA");
+    }
+
+    #[test]
+    fn classifies_replies() {
+        assert_eq!(classify(b"HTTP/1.1 200 OK\r\n"), Behavior::Normal);
+        assert_eq!(classify(b"HTTP/1.1 400 Bad Request\r\n"), Behavior::Error);
+        assert_eq!(classify(b""), Behavior::Error);
+        assert_eq!(classify(b"\x15\x03\x01"), Behavior::Error);
+    }
+
+    #[test]
+    fn classifies_replies_cut_inside_a_character() {
+        let reply = "HTTP/1.1 200 OK\r\nServer: caf\u{e9}".as_bytes();
+        assert_eq!(classify(&reply[..reply.len() - 1]), Behavior::Normal);
+    }
+}
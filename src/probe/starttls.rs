@@ -0,0 +1,295 @@
+//! `--starttls`: after a plain banner from a service without a client or
+//! probe, ask a mail or FTP server for its capabilities and, when it offers
+//! STARTTLS, upgrade the session to read the certificate.
+
+use super::https::{connect_tls, extract_tls_info};
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, Target, TlsInfo};
+use anyhow::{bail, Context};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// How one protocol lists its capabilities and switches to TLS.
+struct Dialect {
+    protocol: &'static str,
+    capabilities_request: &'static [u8],
+    /// Whether a reply line ends the capability listing.
+    capabilities_end: fn(&str) -> bool,
+    /// The capabilities named by the listing, without codes or tags.
+    capabilities: fn(&[&str]) -> Vec<String>,
+    /// Whether a capability advertises the upgrade.
+    offers: fn(&str) -> bool,
+    upgrade_request: &'static [u8],
+    /// The final line of the upgrade reply, and whether it accepts.
+    upgrade_end: fn(&str) -> Option<bool>,
+}
+
+/// Keyed by the `peek_protocol` guess for the greeting.
+const DIALECTS: &[Dialect] = &[
+    Dialect {
+        protocol: "smtp",
+        capabilities_request: b"EHLO banner-grabber\r\n",
+        capabilities_end: is_final_reply_line,
+        // The first line greets the client; the rest are extensions.
+        capabilities: |lines| lines.iter().skip(1).map(|line| reply_text(line)).collect(),
+        offers: |capability| capability.eq_ignore_ascii_case("STARTTLS"),
+        upgrade_request: b"STARTTLS\r\n",
+        upgrade_end: |line| is_final_reply_line(line).then(|| line.starts_with("220")),
+    },
+    Dialect {
+        protocol: "imap",
+        capabilities_request: b"a1 CAPABILITY\r\n",
+        capabilities_end: |line| line.starts_with("a1 "),
+        capabilities: |lines| {
+            lines
+                .iter()
+                .filter_map(|line| line.strip_prefix("* CAPABILITY "))
+                .flat_map(str::split_whitespace)
+                .map(str::to_string)
+                .collect()
+        },
+        offers: |capability| capability.eq_ignore_ascii_case("STARTTLS"),
+        upgrade_request: b"a2 STARTTLS\r\n",
+        upgrade_end: |line| line.starts_with("a2 ").then(|| line.starts_with("a2 OK")),
+    },
+    Dialect {
+        protocol: "pop3",
+        capabilities_request: b"CAPA\r\n",
+        capabilities_end: |line| line == "." || line.starts_with("-ERR"),
+        capabilities: |lines| {
+            lines
+                .iter()
+                .skip(1)
+                .take_while(|line| **line != ".")
+                .map(|line| line.to_string())
+                .collect()
+        },
+        offers: |capability| capability.eq_ignore_ascii_case("STLS"),
+        upgrade_request: b"STLS\r\n",
+        upgrade_end: |line| {
+            if line.starts_with("+OK") {
+                Some(true)
+            } else {
+                line.starts_with("-ERR").then_some(false)
+            }
+        },
+    },
+    Dialect {
+        protocol: "ftp",
+        capabilities_request: b"FEAT\r\n",
+        capabilities_end: is_final_reply_line,
+        // Features are the indented lines between `211-` and `211 End`.
+        capabilities: |lines| {
+            lines
+                .iter()
+                .filter(|line| line.starts_with(' '))
+                .map(|line| line.trim().to_string())
+                .collect()
+        },
+        offers: |capability| {
+            let capability = capability.to_ascii_uppercase();
+            capability.starts_with("AUTH ") && capability.contains("TLS")
+        },
+        upgrade_request: b"AUTH TLS\r\n",
+        upgrade_end: |line| is_final_reply_line(line).then(|| line.starts_with("234")),
+    },
+];
+
+/// What the plain and the TLS layer of one session showed.
+#[derive(Debug)]
+pub struct Starttls {
+    pub protocol: &'static str,
+    pub capabilities: Vec<String>,
+    /// Whether a capability advertised the upgrade.
+    pub offered: bool,
+    /// The upgraded session, or why it could not be reached. `None` when
+    /// the upgrade was not offered.
+    pub tls: Option<Result<TlsInfo, String>>,
+}
+
+impl Starttls {
+    /// Adds the `starttls_*` fields, and the certificate as `tls_info`.
+    pub fn record(self, result: &mut ReadResult) {
+        result
+            .fields
+            .insert("starttls_protocol".into(), self.protocol.into());
+        result
+            .fields
+            .insert("starttls_capabilities".into(), self.capabilities.join("\n"));
+        result
+            .fields
+            .insert("starttls_offered".into(), self.offered.to_string());
+        match self.tls {
+            Some(Ok(tls_info)) => result.tls_info = Some(tls_info),
+            Some(Err(err)) => {
+                result.fields.insert("starttls_error".into(), err);
+            }
+            None => {}
+        }
+    }
+}
+
+/// Whether `--starttls` knows how to upgrade a `peek_protocol` guess.
+pub fn supports(protocol: &str) -> bool {
+    DIALECTS.iter().any(|dialect| dialect.protocol == protocol)
+}
+
+/// Lists the capabilities of the session on `stream`, whose greeting was
+/// guessed to be `protocol`, and completes the TLS upgrade when offered.
+pub async fn upgrade(
+    mut stream: TcpStream,
+    protocol: &str,
+    cfg: &Config,
+    target: &Target,
+) -> anyhow::Result<Starttls> {
+    let Some(dialect) = DIALECTS.iter().find(|dialect| dialect.protocol == protocol) else {
+        bail!("no STARTTLS upgrade for {protocol}");
+    };
+    let listing = exchange(
+        &mut stream,
+        dialect.capabilities_request,
+        dialect.capabilities_end,
+        cfg,
+    )
+    .await?;
+    let lines: Vec<&str> = listing.lines().collect();
+    let capabilities = (dialect.capabilities)(&lines);
+    let offered = capabilities.iter().any(|cap| (dialect.offers)(cap));
+    let mut report = Starttls {
+        protocol: dialect.protocol,
+        capabilities,
+        offered,
+        tls: None,
+    };
+    if !offered {
+        return Ok(report);
+    }
+
+    let reply = exchange(
+        &mut stream,
+        dialect.upgrade_request,
+        |line| (dialect.upgrade_end)(line).is_some(),
+        cfg,
+    )
+    .await?;
+    let accepted = reply.lines().find_map(dialect.upgrade_end).unwrap_or(false);
+    report.tls = Some(if accepted {
+        match timeout(cfg.read_timeout, connect_tls(stream, target, None, cfg)).await {
+            Ok(Ok(tls_stream)) => Ok(extract_tls_info(&tls_stream, cfg)),
+            Ok(Err(err)) => Err(format!("{err:#}")),
+            Err(_) => Err("TLS handshake timeout".into()),
+        }
+    } else {
+        Err(format!("upgrade refused: {}", reply.trim_end()))
+    });
+    Ok(report)
+}
+
+/// Sends `request` and reads reply lines until `end` accepts one.
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    request: &[u8],
+    end: impl Fn(&str) -> bool,
+    cfg: &Config,
+) -> anyhow::Result<String> {
+    stream
+        .write_all(request)
+        .await
+        .context("failed to write STARTTLS request")?;
+    let mut reply = Vec::new();
+    let mut chunk = [0u8; 1024];
+    for _ in 0..cfg.max_read_rounds {
+        let read = timeout(cfg.read_timeout, stream.read(&mut chunk))
+            .await
+            .context("STARTTLS reply timeout")?
+            .context("failed to read STARTTLS reply")?;
+        if read == 0 {
+            bail!("connection closed during STARTTLS negotiation");
+        }
+        reply.extend_from_slice(&chunk[..read]);
+        if reply.len() > cfg.max_bytes {
+            bail!("STARTTLS reply exceeds {} bytes", cfg.max_bytes);
+        }
+        let text = String::from_utf8_lossy(&reply);
+        // Only complete lines count; the last one may still be arriving.
+        let complete = text.rsplit_once('\n').map_or("", |(lines, _)| lines);
+        if complete
+            .lines()
+            .any(|line| end(line.trim_end_matches('\r')))
+        {
+            return Ok(text.into_owned());
+        }
+    }
+    bail!(
+        "STARTTLS reply not complete after {} reads",
+        cfg.max_read_rounds
+    )
+}
+
+/// `250 text`: the last line of an SMTP or FTP reply, as opposed to `250-`.
+fn is_final_reply_line(line: &str) -> bool {
+    let bytes = line.as_bytes();
+    bytes.len() >= 3 && bytes[..3].iter().all(u8::is_ascii_digit) && bytes.get(3) != Some(&b'-')
+}
+
+fn reply_text(line: &str) -> String {
+    line.get(4..).unwrap_or_default().trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dialect(protocol: &str) -> &'static Dialect {
+        DIALECTS
+            .iter()
+            .find(|dialect| dialect.protocol == protocol)
+            .unwrap()
+    }
+
+    #[test]
+    fn reads_smtp_extensions() {
+        let smtp = dialect("smtp");
+        let lines = [
+            "250-mx.example.com Hello",
+            "250-SIZE 10240000",
+            "250-STARTTLS",
+            "250 8BITMIME",
+        ];
+        assert!(!(smtp.capabilities_end)(lines[2]));
+        assert!((smtp.capabilities_end)(lines[3]));
+        let capabilities = (smtp.capabilities)(&lines);
+        assert_eq!(capabilities, ["SIZE 10240000", "STARTTLS", "8BITMIME"]);
+        assert!(capabilities.iter().any(|cap| (smtp.offers)(cap)));
+        assert_eq!(
+            (smtp.upgrade_end)("220 2.0.0 Ready to start TLS"),
+            Some(true)
+        );
+        assert_eq!((smtp.upgrade_end)("454 TLS not available"), Some(false));
+    }
+
+    #[test]
+    fn reads_imap_pop3_and_ftp_capabilities() {
+        let imap = dialect("imap");
+        let capabilities = (imap.capabilities)(&[
+            "* CAPABILITY IMAP4rev1 STARTTLS LOGINDISABLED",
+            "a1 OK done",
+        ]);
+        assert!(capabilities.iter().any(|cap| (imap.offers)(cap)));
+        assert_eq!((imap.upgrade_end)("a2 OK Begin TLS"), Some(true));
+
+        let pop3 = dialect("pop3");
+        let capabilities = (pop3.capabilities)(&["+OK", "USER", "STLS", "."]);
+        assert_eq!(capabilities, ["USER", "STLS"]);
+        assert!((pop3.capabilities_end)("."));
+
+        let ftp = dialect("ftp");
+        let lines = ["211-Features:", " AUTH TLS", " PBSZ", "211 End"];
+        let capabilities = (ftp.capabilities)(&lines);
+        assert_eq!(capabilities, ["AUTH TLS", "PBSZ"]);
+        assert!((ftp.offers)("AUTH SSL;TLS"));
+        assert!(!(ftp.offers)("MDTM"));
+        assert!(!supports("ssh"));
+    }
+}
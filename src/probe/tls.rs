@@ -1,21 +1,131 @@
 use super::Prober;
-use crate::model::Target;
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, ReadStopReason, Target};
+use anyhow::Context;
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
 
 pub(super) struct TlsProbe;
 
+#[async_trait]
 impl Prober for TlsProbe {
     fn name(&self) -> &'static str {
         "tls"
     }
 
     fn probe_bytes(&self) -> &'static [u8] {
-        // Minimal TLS ClientHello that negotiates modern cipher suites without
-        // allocating on the hot path.
-        const CLIENT_HELLO: &[u8] = b"\x16\x03\x01\x00\x31\x01\x00\x00\x2d\x03\x03\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x00\x00\x02\x13\x01\x00\x00\x05\x00\xff\x01\x00\x01\x00";
+        // Minimal TLS ClientHello offering only TLS_AES_128_GCM_SHA256. Most
+        // servers answer it with an alert, which still proves they speak TLS.
+        const CLIENT_HELLO: &[u8] = b"\x16\x03\x01\x00\x34\x01\x00\x00\x30\x03\x03\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x5a\x00\x00\x02\x13\x01\x01\x00\x00\x05\xff\x01\x00\x01\x00";
         CLIENT_HELLO
     }
 
     fn matches(&self, target: &Target) -> bool {
         target.resolved.port() > 0 && target.resolved.port() <= u16::MAX
     }
+
+    async fn execute(
+        &self,
+        mut stream: TcpStream,
+        cfg: &Config,
+        _target: &Target,
+    ) -> anyhow::Result<ReadResult> {
+        stream
+            .write_all(self.probe_bytes())
+            .await
+            .context("failed to write probe tls")?;
+        let mut result = read_first_record(
+            &mut stream,
+            cfg.max_bytes,
+            cfg.read_timeout,
+            cfg.max_read_rounds,
+        )
+        .await?;
+        result.record_sent(cfg, self.probe_bytes());
+        Ok(result)
+    }
+}
+
+/// Reads until the first TLS record is complete. Servers that reject the
+/// ClientHello often reset the connection right after their alert, so a reset
+/// keeps whatever already arrived instead of failing the probe.
+async fn read_first_record<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    max_bytes: usize,
+    idle_timeout: Duration,
+    max_rounds: usize,
+) -> anyhow::Result<ReadResult> {
+    let mut bytes = Vec::new();
+    let mut chunk = [0u8; 1024];
+    let mut rounds = 0;
+    let reason = loop {
+        if record_len(&bytes).is_some_and(|len| bytes.len() >= len) {
+            break ReadStopReason::Delimiter;
+        }
+        if bytes.len() >= max_bytes {
+            break ReadStopReason::SizeLimit;
+        }
+        if rounds >= max_rounds {
+            break ReadStopReason::RoundLimit;
+        }
+        match timeout(idle_timeout, stream.read(&mut chunk)).await {
+            Ok(Ok(0)) => break ReadStopReason::ConnectionClosed,
+            Ok(Ok(n)) => {
+                rounds += 1;
+                bytes.extend_from_slice(&chunk[..n]);
+            }
+            Ok(Err(err))
+                if !bytes.is_empty() && err.kind() == std::io::ErrorKind::ConnectionReset =>
+            {
+                break ReadStopReason::ConnectionClosed
+            }
+            Ok(Err(err)) => return Err(err.into()),
+            Err(_) => break ReadStopReason::Timeout,
+        }
+    };
+    let truncated = bytes.len() >= max_bytes;
+    bytes.truncate(max_bytes);
+    Ok(ReadResult {
+        bytes,
+        reason,
+        truncated,
+        tls_info: None,
+        fields: Default::default(),
+    })
+}
+
+/// Length of the first record, header included, when `bytes` starts with
+/// something shaped like a TLS record header.
+fn record_len(bytes: &[u8]) -> Option<usize> {
+    let header = bytes.get(..5)?;
+    ((0x14..=0x17).contains(&header[0]) && header[1] == 3)
+        .then(|| 5 + u16::from_be_bytes([header[3], header[4]]) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stops_after_first_record() {
+        let mut data: &[u8] = b"\x15\x03\x03\x00\x02\x02\x46\x17\x03\x03";
+        let res = read_first_record(&mut data, 64, Duration::from_millis(200), 1000)
+            .await
+            .unwrap();
+        assert_eq!(res.bytes, b"\x15\x03\x03\x00\x02\x02\x46\x17\x03\x03");
+        assert_eq!(res.reason, ReadStopReason::Delimiter);
+    }
+
+    #[tokio::test]
+    async fn keeps_non_tls_replies_as_banner() {
+        let mut data: &[u8] = b"SSH-2.0-OpenSSH_9.6\r\n";
+        let res = read_first_record(&mut data, 64, Duration::from_millis(200), 1000)
+            .await
+            .unwrap();
+        assert_eq!(res.bytes, b"SSH-2.0-OpenSSH_9.6\r\n");
+        assert_eq!(res.reason, ReadStopReason::ConnectionClosed);
+    }
 }
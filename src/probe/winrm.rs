@@ -0,0 +1,70 @@
+use super::http::read_response;
+use super::https::{connect_tls, extract_tls_info, server_name};
+use super::Prober;
+use crate::engine::reader::ReadResult;
+use crate::model::{Config, Target};
+use crate::util::ntlm;
+use anyhow::Context;
+use async_trait::async_trait;
+use base64::Engine as _;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// WS-Management listener (5985, or 5986 over TLS). Posts an empty request to
+/// `/wsman` with an NTLM NEGOTIATE token so the 401 carries a challenge.
+pub(super) struct WinrmProbe;
+
+#[async_trait]
+impl Prober for WinrmProbe {
+    fn name(&self) -> &'static str {
+        "winrm"
+    }
+
+    fn probe_bytes(&self) -> &'static [u8] {
+        &[]
+    }
+
+    fn matches(&self, target: &Target) -> bool {
+        matches!(target.resolved.port(), 5985 | 5986)
+    }
+
+    async fn execute(
+        &self,
+        stream: TcpStream,
+        cfg: &Config,
+        target: &Target,
+    ) -> anyhow::Result<ReadResult> {
+        if target.resolved.port() == 5986 {
+            let mut tls_stream = connect_tls(stream, target, None, cfg).await?;
+            let tls_info = extract_tls_info(&tls_stream, cfg);
+            let mut result = post_wsman(&mut tls_stream, cfg, target).await?;
+            result.tls_info = Some(tls_info);
+            Ok(result)
+        } else {
+            let mut stream = stream;
+            post_wsman(&mut stream, cfg, target).await
+        }
+    }
+}
+
+async fn post_wsman<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    cfg: &Config,
+    target: &Target,
+) -> anyhow::Result<ReadResult> {
+    let token = base64::engine::general_purpose::STANDARD.encode(ntlm::negotiate_blob());
+    let request = format!(
+        "POST /wsman HTTP/1.1\r\nHost: {}:{}\r\nAuthorization: Negotiate {token}\r\n\
+         Content-Type: application/soap+xml;charset=UTF-8\r\nContent-Length: 0\r\n\
+         Connection: close\r\n\r\n",
+        server_name(target),
+        target.resolved.port()
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .context("failed to write probe winrm")?;
+    let mut result = read_response(stream, cfg).await?;
+    result.record_sent(cfg, request.as_bytes());
+    Ok(result)
+}
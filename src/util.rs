@@ -1,6 +1,10 @@
+pub mod bson;
+pub mod dns;
+pub mod h2;
 pub mod hex;
+pub mod mail_banner;
+pub mod ntlm;
 
-use chrono::SecondsFormat;
 use std::sync::OnceLock;
 use std::time::Instant;
 
@@ -16,12 +20,22 @@ pub fn sanitize_text(bytes: &[u8]) -> String {
         .collect()
 }
 
+/// Like [`sanitize_text`], but decodes `bytes` with `encoding` first so
+/// non-ASCII text survives; only control characters are masked.
+pub fn decode_text(bytes: &[u8], encoding: &'static encoding_rs::Encoding) -> String {
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    text.chars()
+        .map(|c| match c {
+            '\n' | '\r' => c,
+            '\u{fffd}' => '.',
+            c if c.is_control() => '.',
+            c => c,
+        })
+        .collect()
+}
+
 pub fn now_millis() -> u128 {
     static START: OnceLock<Instant> = OnceLock::new();
     let start = START.get_or_init(Instant::now);
     Instant::now().duration_since(*start).as_millis()
 }
-
-pub fn now_iso8601() -> String {
-    chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
-}
@@ -0,0 +1,294 @@
+//! Just enough BSON, and the OP_MSG framing MongoDB (3.6+) sends it in, to
+//! run the unauthenticated `hello` and `buildInfo` commands and read their
+//! replies.
+
+/// `OP_MSG` opcode; the only framing current servers accept for commands.
+const OP_MSG: i32 = 2013;
+/// messageLength, requestID, responseTo, opCode.
+pub const HEADER_LEN: usize = 16;
+
+/// The element types a command reply uses; anything else is skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bson {
+    Double(f64),
+    String(String),
+    Document(Vec<(String, Bson)>),
+    Array(Vec<Bson>),
+    Boolean(bool),
+    Int32(i32),
+    Int64(i64),
+    Other,
+}
+
+impl Bson {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Bson::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Integers and doubles alike: servers send `ok` and sizes as either.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Bson::Int32(value) => Some(i64::from(*value)),
+            Bson::Int64(value) => Some(*value),
+            Bson::Double(value) => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Bson::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Bson]> {
+        match self {
+            Bson::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Looks `key` up in a decoded document.
+pub fn get<'a>(document: &'a [(String, Bson)], key: &str) -> Option<&'a Bson> {
+    document
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value)
+}
+
+/// `{<command>: 1, $db: "admin"}` in an OP_MSG with one body section.
+pub fn command_message(request_id: i32, command: &str) -> Vec<u8> {
+    let mut elements = vec![0x10];
+    elements.extend_from_slice(command.as_bytes());
+    elements.push(0);
+    elements.extend_from_slice(&1i32.to_le_bytes());
+    elements.push(0x02);
+    elements.extend_from_slice(b"$db\0");
+    elements.extend_from_slice(&6i32.to_le_bytes());
+    elements.extend_from_slice(b"admin\0");
+    elements.push(0);
+    let mut document = ((elements.len() + 4) as i32).to_le_bytes().to_vec();
+    document.extend(elements);
+
+    let len = HEADER_LEN + 4 + 1 + document.len();
+    let mut message = Vec::with_capacity(len);
+    message.extend_from_slice(&(len as i32).to_le_bytes());
+    message.extend_from_slice(&request_id.to_le_bytes());
+    message.extend_from_slice(&0i32.to_le_bytes());
+    message.extend_from_slice(&OP_MSG.to_le_bytes());
+    message.extend_from_slice(&0u32.to_le_bytes());
+    message.push(0);
+    message.extend(document);
+    message
+}
+
+/// Splits `bytes` into OP_MSG replies as `(responseTo, body document)`.
+/// Stops at the first message cut short or not in OP_MSG framing.
+pub fn parse_replies(bytes: &[u8]) -> Vec<(i32, Vec<(String, Bson)>)> {
+    let mut replies = Vec::new();
+    let mut rest = bytes;
+    while rest.len() >= HEADER_LEN {
+        let len = i32_at(rest, 0).unwrap_or_default().max(0) as usize;
+        let Some(message) = rest.get(..len).filter(|_| len > HEADER_LEN) else {
+            break;
+        };
+        if i32_at(message, 12) != Some(OP_MSG) {
+            break;
+        }
+        let response_to = i32_at(message, 8).unwrap_or_default();
+        // flagBits, then a kind 0 section holding the body.
+        let body = message
+            .get(HEADER_LEN + 4..)
+            .filter(|sections| sections.first() == Some(&0))
+            .and_then(|sections| parse_document(&sections[1..]));
+        if let Some(body) = body {
+            replies.push((response_to, body));
+        }
+        rest = &rest[len..];
+    }
+    replies
+}
+
+/// `(responseTo, messageLength)` of a reply at the end of `bytes` that was
+/// cut short, as happens when it outgrows `--max-bytes`.
+pub fn truncated_reply(bytes: &[u8]) -> Option<(i32, usize)> {
+    let mut rest = bytes;
+    while rest.len() >= HEADER_LEN {
+        let len = i32_at(rest, 0)?.max(0) as usize;
+        if len <= HEADER_LEN || i32_at(rest, 12) != Some(OP_MSG) {
+            return None;
+        }
+        if len > rest.len() {
+            return Some((i32_at(rest, 8)?, len));
+        }
+        rest = &rest[len..];
+    }
+    None
+}
+
+/// Decodes one document from the start of `bytes`. `None` when it is cut
+/// short or malformed.
+pub fn parse_document(bytes: &[u8]) -> Option<Vec<(String, Bson)>> {
+    let len = usize::try_from(i32_at(bytes, 0)?).ok()?;
+    if len < 5 || bytes.get(len - 1) != Some(&0) {
+        return None;
+    }
+    let mut elements = &bytes[4..len - 1];
+    let mut document = Vec::new();
+    while let Some((&kind, rest)) = elements.split_first() {
+        let (name, rest) = cstring(rest)?;
+        let (value, size) = parse_value(kind, rest)?;
+        document.push((name, value));
+        elements = rest.get(size..)?;
+    }
+    Some(document)
+}
+
+/// The value of a `kind` element at the start of `bytes`, and its size.
+fn parse_value(kind: u8, bytes: &[u8]) -> Option<(Bson, usize)> {
+    let fixed = |size: usize| bytes.get(..size).map(|_| (Bson::Other, size));
+    match kind {
+        0x01 => Some((
+            Bson::Double(f64::from_le_bytes(bytes.get(..8)?.try_into().ok()?)),
+            8,
+        )),
+        // String, JavaScript code, symbol: length-prefixed UTF-8.
+        0x02 | 0x0d | 0x0e => {
+            let len = usize::try_from(i32_at(bytes, 0)?).ok()?;
+            let text = bytes.get(4..4 + len)?.strip_suffix(&[0])?;
+            let value = String::from_utf8_lossy(text).into_owned();
+            let value = if kind == 0x02 {
+                Bson::String(value)
+            } else {
+                Bson::Other
+            };
+            Some((value, 4 + len))
+        }
+        0x03 | 0x04 => {
+            let len = usize::try_from(i32_at(bytes, 0)?).ok()?;
+            let document = parse_document(bytes)?;
+            let value = if kind == 0x03 {
+                Bson::Document(document)
+            } else {
+                Bson::Array(document.into_iter().map(|(_, item)| item).collect())
+            };
+            Some((value, len))
+        }
+        0x05 => {
+            let len = usize::try_from(i32_at(bytes, 0)?).ok()?;
+            fixed(4 + 1 + len)
+        }
+        0x06 | 0x0a | 0x7f | 0xff => Some((Bson::Other, 0)),
+        0x07 => fixed(12),
+        0x08 => Some((Bson::Boolean(*bytes.first()? != 0), 1)),
+        0x09 | 0x11 => fixed(8),
+        0x0b => {
+            let (_, pattern) = cstring(bytes)?;
+            let (_, options) = cstring(pattern)?;
+            Some((Bson::Other, bytes.len() - options.len()))
+        }
+        0x0c => {
+            let len = usize::try_from(i32_at(bytes, 0)?).ok()?;
+            fixed(4 + len + 12)
+        }
+        0x0f => fixed(usize::try_from(i32_at(bytes, 0)?).ok()?),
+        0x10 => Some((Bson::Int32(i32_at(bytes, 0)?), 4)),
+        0x12 => Some((
+            Bson::Int64(i64::from_le_bytes(bytes.get(..8)?.try_into().ok()?)),
+            8,
+        )),
+        0x13 => fixed(16),
+        _ => None,
+    }
+}
+
+/// A NUL-terminated name, and what follows it.
+fn cstring(bytes: &[u8]) -> Option<(String, &[u8])> {
+    let end = bytes.iter().position(|byte| *byte == 0)?;
+    Some((
+        String::from_utf8_lossy(&bytes[..end]).into_owned(),
+        &bytes[end + 1..],
+    ))
+}
+
+fn i32_at(bytes: &[u8], idx: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(
+        bytes.get(idx..idx + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An OP_MSG reply to `request_id` whose body is the given elements.
+    fn reply(request_id: i32, elements: &[u8]) -> Vec<u8> {
+        let mut document = ((elements.len() + 5) as i32).to_le_bytes().to_vec();
+        document.extend_from_slice(elements);
+        document.push(0);
+        let len = HEADER_LEN + 5 + document.len();
+        let mut message = (len as i32).to_le_bytes().to_vec();
+        message.extend_from_slice(&7i32.to_le_bytes());
+        message.extend_from_slice(&request_id.to_le_bytes());
+        message.extend_from_slice(&OP_MSG.to_le_bytes());
+        message.extend_from_slice(&[0, 0, 0, 0, 0]);
+        message.extend(document);
+        message
+    }
+
+    #[test]
+    fn frames_command_as_op_msg() {
+        let message = command_message(3, "buildInfo");
+        assert_eq!(i32_at(&message, 0), Some(message.len() as i32));
+        assert_eq!(i32_at(&message, 4), Some(3));
+        assert_eq!(i32_at(&message, 12), Some(OP_MSG));
+        let body = parse_document(&message[HEADER_LEN + 5..]).unwrap();
+        assert_eq!(get(&body, "buildInfo"), Some(&Bson::Int32(1)));
+        assert_eq!(get(&body, "$db").and_then(Bson::as_str), Some("admin"));
+    }
+
+    #[test]
+    fn decodes_strings_arrays_and_numbers() {
+        // {version: "7.0.2", modules: ["enterprise"], ok: 1.0, id: ObjectId}
+        let mut elements = b"\x02version\0\x06\0\0\x007.0.2\0".to_vec();
+        elements.extend_from_slice(b"\x04modules\0\x17\0\0\0\x020\0\x0b\0\0\0enterprise\0\0");
+        elements.extend_from_slice(b"\x01ok\0");
+        elements.extend_from_slice(&1.0f64.to_le_bytes());
+        elements.extend_from_slice(b"\x07id\0");
+        elements.extend_from_slice(&[0xaa; 12]);
+        let bytes = reply(2, &elements);
+
+        let replies = parse_replies(&bytes);
+        assert_eq!(replies.len(), 1);
+        let (response_to, body) = &replies[0];
+        assert_eq!(*response_to, 2);
+        assert_eq!(get(body, "version").and_then(Bson::as_str), Some("7.0.2"));
+        assert_eq!(
+            get(body, "modules").and_then(Bson::as_array),
+            Some(&[Bson::String("enterprise".into())][..])
+        );
+        assert_eq!(get(body, "ok").and_then(Bson::as_i64), Some(1));
+        assert_eq!(get(body, "id"), Some(&Bson::Other));
+    }
+
+    #[test]
+    fn stops_at_a_truncated_reply() {
+        let bytes = reply(1, b"\x10ok\0\x01\0\0\0");
+        assert_eq!(parse_replies(&bytes).len(), 1);
+        assert!(parse_replies(&bytes[..bytes.len() - 1]).is_empty());
+    }
+
+    #[test]
+    fn reports_which_reply_was_cut_short() {
+        let mut bytes = reply(1, b"\x10ok\0\x01\0\0\0");
+        let build_info = reply(3, b"\x02version\0\x06\0\0\x007.0.2\0");
+        assert_eq!(truncated_reply(&bytes), None);
+        bytes.extend_from_slice(&build_info[..HEADER_LEN + 4]);
+        assert_eq!(truncated_reply(&bytes), Some((3, build_info.len())));
+    }
+}
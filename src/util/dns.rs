@@ -0,0 +1,175 @@
+//! Minimal DNS wire format shared by the LLMNR and mDNS clients and their
+//! output formatters.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+pub const TYPE_A: u16 = 1;
+pub const TYPE_PTR: u16 = 12;
+pub const TYPE_TXT: u16 = 16;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_SRV: u16 = 33;
+pub const CLASS_IN: u16 = 1;
+
+/// Builds a query with one question per `(name, type)`, recursion not
+/// desired. `class` lets mDNS set the unicast-response bit.
+pub fn encode_query(id: u16, questions: &[(&str, u16)], class: u16) -> Vec<u8> {
+    let mut message = Vec::with_capacity(64);
+    message.extend_from_slice(&id.to_be_bytes());
+    message.extend_from_slice(&[0, 0]);
+    message.extend_from_slice(&(questions.len() as u16).to_be_bytes());
+    message.extend_from_slice(&[0; 6]);
+    for (name, qtype) in questions {
+        for label in name.trim_end_matches('.').split('.') {
+            let label = &label.as_bytes()[..label.len().min(63)];
+            message.push(label.len() as u8);
+            message.extend_from_slice(label);
+        }
+        message.push(0);
+        message.extend_from_slice(&qtype.to_be_bytes());
+        message.extend_from_slice(&class.to_be_bytes());
+    }
+    message
+}
+
+#[derive(Debug, Default)]
+pub struct Message {
+    pub is_response: bool,
+    pub rcode: u8,
+    pub questions: Vec<String>,
+    /// Answer, authority and additional records in wire order.
+    pub records: Vec<Record>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub name: String,
+    pub rtype: u16,
+    pub data: RData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RData {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    Ptr(String),
+    Srv { port: u16, target: String },
+    Txt(Vec<String>),
+    Other(Vec<u8>),
+}
+
+/// Parses a complete message; `None` when the header or any name or record
+/// runs past the end of `bytes`.
+pub fn parse_message(bytes: &[u8]) -> Option<Message> {
+    let header = bytes.get(..12)?;
+    let count = |idx: usize| u16::from_be_bytes([header[idx], header[idx + 1]]) as usize;
+    let mut message = Message {
+        is_response: header[2] & 0x80 != 0,
+        rcode: header[3] & 0x0f,
+        ..Message::default()
+    };
+    let mut pos = 12;
+    for _ in 0..count(4) {
+        let (name, next) = read_name(bytes, pos)?;
+        bytes.get(next..next + 4)?;
+        message.questions.push(name);
+        pos = next + 4;
+    }
+    for _ in 0..count(6) + count(8) + count(10) {
+        let (name, next) = read_name(bytes, pos)?;
+        let fixed = bytes.get(next..next + 10)?;
+        let rtype = u16::from_be_bytes([fixed[0], fixed[1]]);
+        let len = u16::from_be_bytes([fixed[8], fixed[9]]) as usize;
+        let start = next + 10;
+        let rdata = bytes.get(start..start + len)?;
+        let data = match (rtype, rdata.len()) {
+            (TYPE_A, 4) => RData::A(Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3])),
+            (TYPE_AAAA, 16) => RData::Aaaa(Ipv6Addr::from(<[u8; 16]>::try_from(rdata).ok()?)),
+            (TYPE_PTR, _) => RData::Ptr(read_name(bytes, start)?.0),
+            (TYPE_SRV, 7..) => RData::Srv {
+                port: u16::from_be_bytes([rdata[4], rdata[5]]),
+                target: read_name(bytes, start + 6)?.0,
+            },
+            (TYPE_TXT, _) => RData::Txt(read_strings(rdata)),
+            _ => RData::Other(rdata.to_vec()),
+        };
+        message.records.push(Record { name, rtype, data });
+        pos = start + len;
+    }
+    Some(message)
+}
+
+/// Reads a possibly compressed name at `pos`, returning it without the root
+/// dot and the offset just past it in the original position.
+fn read_name(bytes: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    // Every pointer must go backwards, which rules out loops.
+    let mut limit = pos;
+    loop {
+        let len = *bytes.get(pos)? as usize;
+        match len {
+            0 => break,
+            0xc0.. => {
+                let target = ((len & 0x3f) << 8) | *bytes.get(pos + 1)? as usize;
+                if target >= limit {
+                    return None;
+                }
+                end.get_or_insert(pos + 2);
+                limit = target;
+                pos = target;
+            }
+            1..=63 => {
+                let label = bytes.get(pos + 1..pos + 1 + len)?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                pos += 1 + len;
+            }
+            _ => return None,
+        }
+    }
+    Some((labels.join("."), end.unwrap_or(pos + 1)))
+}
+
+fn read_strings(mut rdata: &[u8]) -> Vec<String> {
+    let mut strings = Vec::new();
+    while let Some((&len, rest)) = rdata.split_first() {
+        let Some(value) = rest.get(..len as usize) else {
+            break;
+        };
+        if !value.is_empty() {
+            strings.push(String::from_utf8_lossy(value).into_owned());
+        }
+        rdata = &rest[len as usize..];
+    }
+    strings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_query_and_follows_compression() {
+        let mut response = encode_query(7, &[("_http._tcp.local", TYPE_PTR)], CLASS_IN);
+        response[2] = 0x84;
+        response[7] = 1;
+        // Answer: pointer to the question name, PTR to `web.<pointer>`.
+        response.extend_from_slice(&[0xc0, 12, 0, 12, 0, 1, 0, 0, 0, 120, 0, 6]);
+        response.extend_from_slice(&[3, b'w', b'e', b'b', 0xc0, 12]);
+
+        let message = parse_message(&response).unwrap();
+        assert!(message.is_response);
+        assert_eq!(message.questions, ["_http._tcp.local"]);
+        assert_eq!(
+            message.records[0].data,
+            RData::Ptr("web._http._tcp.local".into())
+        );
+    }
+
+    #[test]
+    fn rejects_forward_pointers() {
+        let mut message = vec![0; 12];
+        message[5] = 1;
+        message.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1]);
+        assert!(parse_message(&message).is_none());
+    }
+}
@@ -0,0 +1,50 @@
+//! HTTP/2 framing (RFC 9113) shared by the gRPC probe, which writes frames,
+//! and the gRPC formatter, which walks the frames the probe recorded.
+
+pub const FRAME_DATA: u8 = 0x0;
+pub const FRAME_HEADERS: u8 = 0x1;
+pub const FRAME_RST_STREAM: u8 = 0x3;
+pub const FRAME_SETTINGS: u8 = 0x4;
+pub const FRAME_GOAWAY: u8 = 0x7;
+
+pub const FLAG_END_STREAM: u8 = 0x1;
+pub const FLAG_ACK: u8 = 0x1;
+pub const FLAG_END_HEADERS: u8 = 0x4;
+
+/// The first client-initiated stream, which carries the reflection call.
+pub const REQUEST_STREAM: u32 = 1;
+
+/// `(type, flags, stream id, payload length)` of the frame at the start of
+/// `bytes`, once its 9-byte header is there.
+pub fn frame_header(bytes: &[u8]) -> Option<(u8, u8, u32, usize)> {
+    let header = bytes.get(..9)?;
+    let len = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    let stream_id = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+    Some((header[3], header[4], stream_id, len))
+}
+
+pub fn frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(9 + payload.len());
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+    out.push(kind);
+    out.push(flags);
+    out.extend_from_slice(&stream_id.to_be_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_frame_header() {
+        let bytes = frame(FRAME_HEADERS, FLAG_END_HEADERS, 1, b"abc");
+        assert_eq!(&bytes[..9], &[0, 0, 3, 1, 4, 0, 0, 0, 1]);
+        assert_eq!(
+            frame_header(&bytes),
+            Some((FRAME_HEADERS, FLAG_END_HEADERS, 1, 3))
+        );
+        assert_eq!(frame_header(&bytes[..8]), None);
+    }
+}
@@ -7,22 +7,30 @@ pub fn to_hex(bytes: &[u8]) -> String {
 }
 
 pub fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
-    let cleaned: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
-    if cleaned.len() % 2 != 0 {
-        return Err("hex string has an odd length".into());
-    }
-    let mut bytes = Vec::with_capacity(cleaned.len() / 2);
-    let mut idx = 0;
-    while idx < cleaned.len() {
-        let pair: String = cleaned[idx..idx + 2].iter().collect();
-        let byte =
-            u8::from_str_radix(&pair, 16).map_err(|_| format!("invalid hex pair: {pair}"))?;
-        bytes.push(byte);
-        idx += 2;
+    let mut bytes = Vec::with_capacity(hex.len() / 2);
+    for byte in decode(hex) {
+        bytes.push(byte?);
     }
     Ok(bytes)
 }
 
+/// Decodes `hex` one pair at a time as the iterator is driven, skipping
+/// whitespace, so a multi-megabyte `raw_hex` is never copied or split into
+/// per-pair strings first. A trailing lone digit yields an error.
+pub fn decode(hex: &str) -> impl Iterator<Item = Result<u8, String>> + '_ {
+    let mut digits = hex.chars().filter(|c| !c.is_whitespace());
+    std::iter::from_fn(move || {
+        let high = digits.next()?;
+        let Some(low) = digits.next() else {
+            return Some(Err("hex string has an odd length".into()));
+        };
+        Some(match (high.to_digit(16), low.to_digit(16)) {
+            (Some(high), Some(low)) => Ok((high << 4 | low) as u8),
+            _ => Err(format!("invalid hex pair: {high}{low}")),
+        })
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -38,4 +46,17 @@ mod tests {
         let bytes = from_hex("de ad").unwrap();
         assert_eq!(bytes, vec![0xde, 0xad]);
     }
+
+    #[test]
+    fn decodes_unspaced_and_rejects_bad_input() {
+        assert_eq!(
+            from_hex("DEad\nbe ef").unwrap(),
+            vec![0xde, 0xad, 0xbe, 0xef]
+        );
+        assert_eq!(
+            from_hex("de a").unwrap_err(),
+            "hex string has an odd length"
+        );
+        assert_eq!(from_hex("de zz").unwrap_err(), "invalid hex pair: zz");
+    }
 }
@@ -0,0 +1,148 @@
+//! Mail server product and version from SMTP, IMAP and POP3 greetings.
+
+use regex::{Regex, RegexBuilder};
+use std::sync::OnceLock;
+
+/// `(product, pattern)`, tried in order; the first capture group, when it
+/// matches, is the version. Exchange and Zimbra come before the MTAs they
+/// front, since their greetings may name those too.
+const PATTERNS: &[(&str, &str)] = &[
+    (
+        "Exchange",
+        r"\bMicrosoft (?:Exchange\b|ESMTP MAIL Service\b)(?:[^\r\n]*?\bversion:?\s*(\d+(?:\.\d+)+))?",
+    ),
+    ("Zimbra", r"\bZimbra\b(?:\s+(\d+(?:\.\d+)+(?:_\w+)?))?"),
+    ("Exim", r"\bExim\s+(\d+(?:\.\d+)+)"),
+    ("Sendmail", r"\bSendmail\s+(\d+(?:\.\d+)+)"),
+    ("Postfix", r"\bPostfix\b(?:\s+\(?(\d+(?:\.\d+)+))?"),
+    (
+        "Courier",
+        r"\bCourier(?:-IMAP|-POP3|-MTA)?\b(?:\s+(\d+(?:\.\d+)+))?",
+    ),
+    ("Dovecot", r"\bDovecot\b(?:\s+(\d+(?:\.\d+)+))?"),
+    ("Cyrus", r"\bCyrus\b[^\r\n]*?\bv?(\d+(?:\.\d+)+)"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MailSoftware {
+    pub product: &'static str,
+    pub version: Option<String>,
+}
+
+/// The first product in [`PATTERNS`] named by `greeting`.
+pub fn identify(greeting: &str) -> Option<MailSoftware> {
+    static COMPILED: OnceLock<Vec<(&'static str, Regex)>> = OnceLock::new();
+    let compiled = COMPILED.get_or_init(|| {
+        PATTERNS
+            .iter()
+            .map(|&(product, pattern)| {
+                let regex = RegexBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .expect("valid regex");
+                (product, regex)
+            })
+            .collect()
+    });
+    compiled.iter().find_map(|(product, regex)| {
+        let captures = regex.captures(greeting)?;
+        Some(MailSoftware {
+            product,
+            version: captures.get(1).map(|version| version.as_str().to_string()),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_found(greeting: &str, product: &'static str, version: Option<&str>) {
+        let expected = MailSoftware {
+            product,
+            version: version.map(str::to_string),
+        };
+        assert_eq!(identify(greeting), Some(expected), "{greeting}");
+    }
+
+    #[test]
+    fn reads_postfix() {
+        assert_found("220 mx.example.com ESMTP Postfix (Ubuntu)", "Postfix", None);
+        assert_found(
+            "220 mail.example.org ESMTP Postfix 3.7.11",
+            "Postfix",
+            Some("3.7.11"),
+        );
+    }
+
+    #[test]
+    fn reads_exim() {
+        assert_found(
+            "220 mx.example.com ESMTP Exim 4.96 Tue, 07 May 2024 10:01:02 +0000",
+            "Exim",
+            Some("4.96"),
+        );
+    }
+
+    #[test]
+    fn reads_sendmail() {
+        assert_found(
+            "220 host.example.com ESMTP Sendmail 8.15.2/8.15.2; Tue, 7 May 2024 10:01:02 GMT",
+            "Sendmail",
+            Some("8.15.2"),
+        );
+    }
+
+    #[test]
+    fn reads_exchange() {
+        assert_found(
+            "220 mail.corp.local Microsoft ESMTP MAIL Service, Version: 6.0.3790.3959 ready",
+            "Exchange",
+            Some("6.0.3790.3959"),
+        );
+        assert_found(
+            "+OK Microsoft Exchange Server 2003 POP3 server version 6.5.7638.1 ready.",
+            "Exchange",
+            Some("6.5.7638.1"),
+        );
+        assert_found(
+            "The Microsoft Exchange IMAP4 service is ready.",
+            "Exchange",
+            None,
+        );
+    }
+
+    #[test]
+    fn reads_courier() {
+        assert_found(
+            "Courier-IMAP ready. Copyright 1998-2018 Double Precision, Inc.",
+            "Courier",
+            None,
+        );
+    }
+
+    #[test]
+    fn reads_zimbra() {
+        assert_found(
+            "+OK mail.example.com Zimbra POP3 server ready",
+            "Zimbra",
+            None,
+        );
+        assert_found(
+            "mail.example.com Zimbra 8.8.15_GA IMAP4rev1 server ready",
+            "Zimbra",
+            Some("8.8.15_GA"),
+        );
+    }
+
+    #[test]
+    fn reads_imap_servers() {
+        assert_found("Dovecot (Ubuntu) ready.", "Dovecot", None);
+        assert_found(
+            "mail.example.com Cyrus IMAP v2.4.17-Debian-2.4.17+nocaps-1 server ready",
+            "Cyrus",
+            Some("2.4.17"),
+        );
+        assert_eq!(identify("Hello there."), None);
+    }
+}
@@ -0,0 +1,187 @@
+//! NTLM message handling shared by the probes that elicit a CHALLENGE to
+//! fingerprint Windows hosts.
+
+use chrono::{DateTime, SecondsFormat};
+
+const SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+const NEGOTIATE_VERSION: u32 = 0x0200_0000;
+/// Seconds between 1601-01-01 (the FILETIME epoch) and 1970-01-01.
+const FILETIME_UNIX_OFFSET: i64 = 11_644_473_600;
+
+/// NEGOTIATE (type 1): flags for unicode, OEM, request target, NTLM, always
+/// sign, extended session security, version, 128 and 56, with empty domain
+/// and workstation buffers and a zero version.
+const NEGOTIATE: &[u8; 40] =
+    b"NTLMSSP\0\x01\0\0\0\x07\x82\x08\xa2\0\0\0\0\x28\0\0\0\0\0\0\0\x28\0\0\0\0\0\0\0\0\0\0\0";
+
+/// The NEGOTIATE message to send, raw or wrapped by the caller's protocol,
+/// so the server answers with a CHALLENGE.
+pub fn negotiate_blob() -> &'static [u8] {
+    NEGOTIATE
+}
+
+/// What a CHALLENGE (type 2) reveals about the server. Empty strings stand
+/// for AV pairs the server left out.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NtlmInfo {
+    pub target_name: String,
+    pub domain: String,
+    pub computer: String,
+    pub dns_domain: String,
+    pub dns_computer: String,
+    pub dns_tree: String,
+    /// Windows version as `major.minor.build`, when the server sends one.
+    pub version: Option<String>,
+    /// Server clock from the timestamp AV pair, in RFC 3339.
+    pub timestamp: Option<String>,
+}
+
+/// Parses a CHALLENGE, raw or wrapped in SPNEGO or another envelope, where
+/// it is found by its `NTLMSSP\0` signature. `None` when there is no type 2
+/// message or its header is cut short.
+pub fn parse_ntlm_challenge(blob: &[u8]) -> Option<NtlmInfo> {
+    let start = blob.windows(8).position(|w| w == SIGNATURE)?;
+    let message = &blob[start..];
+    let u16_at = |idx: usize| {
+        Some(u16::from_le_bytes(
+            message.get(idx..idx + 2)?.try_into().ok()?,
+        ))
+    };
+    let u32_at = |idx: usize| {
+        Some(u32::from_le_bytes(
+            message.get(idx..idx + 4)?.try_into().ok()?,
+        ))
+    };
+    if u32_at(8)? != 2 {
+        return None;
+    }
+    let mut info = NtlmInfo::default();
+
+    let name_len = u16_at(12)? as usize;
+    let name_offset = u32_at(16)? as usize;
+    if let Some(name) = message.get(name_offset..name_offset + name_len) {
+        info.target_name = utf16(name);
+    }
+    let flags = u32_at(20)?;
+    let info_len = u16_at(40)? as usize;
+    let info_offset = u32_at(44)? as usize;
+    // The version sits between the fixed header and the payload, so only
+    // servers that leave room for it (and say so) send one.
+    if flags & NEGOTIATE_VERSION != 0 && info_offset >= 56 {
+        if let Some(version) = message.get(48..52) {
+            let build = u16::from_le_bytes([version[2], version[3]]);
+            info.version = Some(format!("{}.{}.{build}", version[0], version[1]));
+        }
+    }
+
+    let mut pairs = message
+        .get(info_offset..info_offset + info_len)
+        .unwrap_or_default();
+    while pairs.len() >= 4 {
+        let id = u16::from_le_bytes([pairs[0], pairs[1]]);
+        let len = u16::from_le_bytes([pairs[2], pairs[3]]) as usize;
+        let Some(value) = pairs.get(4..4 + len) else {
+            break;
+        };
+        match id {
+            0 => break,
+            1 => info.computer = utf16(value),
+            2 => info.domain = utf16(value),
+            3 => info.dns_computer = utf16(value),
+            4 => info.dns_domain = utf16(value),
+            5 => info.dns_tree = utf16(value),
+            7 => info.timestamp = filetime(value),
+            _ => {}
+        }
+        pairs = &pairs[4 + len..];
+    }
+    Some(info)
+}
+
+fn utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// FILETIME counts 100 ns intervals since 1601.
+fn filetime(bytes: &[u8]) -> Option<String> {
+    let ticks = u64::from_le_bytes(bytes.try_into().ok()?);
+    let secs = (ticks / 10_000_000) as i64 - FILETIME_UNIX_OFFSET;
+    let nanos = (ticks % 10_000_000) as u32 * 100;
+    DateTime::from_timestamp(secs, nanos)
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn av(id: u16, value: &[u8]) -> Vec<u8> {
+        let mut pair = id.to_le_bytes().to_vec();
+        pair.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        pair.extend_from_slice(value);
+        pair
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn challenge(flags: u32) -> Vec<u8> {
+        let target = utf16le("CORP");
+        let mut pairs = av(2, &utf16le("CORP"));
+        pairs.extend(av(1, &utf16le("WS01")));
+        pairs.extend(av(4, &utf16le("corp.local")));
+        pairs.extend(av(3, &utf16le("ws01.corp.local")));
+        // 2023-01-01T00:00:00Z.
+        pairs.extend(av(7, &133_170_048_000_000_000u64.to_le_bytes()));
+        pairs.extend(av(0, &[]));
+
+        let mut message = SIGNATURE.to_vec();
+        message.extend_from_slice(&2u32.to_le_bytes());
+        message.extend_from_slice(&(target.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(target.len() as u16).to_le_bytes());
+        message.extend_from_slice(&56u32.to_le_bytes());
+        message.extend_from_slice(&flags.to_le_bytes());
+        message.extend_from_slice(&[0x11; 8]);
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&(pairs.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(pairs.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(56 + target.len() as u32).to_le_bytes());
+        message.extend_from_slice(&[10, 0, 0x61, 0x4a, 0, 0, 0, 15]);
+        message.extend(target);
+        message.extend(pairs);
+        message
+    }
+
+    #[test]
+    fn reads_av_pairs_version_and_timestamp() {
+        let mut blob = vec![0xa1, 0x81, 0xc0];
+        blob.extend(challenge(0xa289_8205));
+
+        let info = parse_ntlm_challenge(&blob).unwrap();
+        assert_eq!(info.target_name, "CORP");
+        assert_eq!(info.domain, "CORP");
+        assert_eq!(info.computer, "WS01");
+        assert_eq!(info.dns_domain, "corp.local");
+        assert_eq!(info.dns_computer, "ws01.corp.local");
+        assert_eq!(info.version.as_deref(), Some("10.0.19041"));
+        assert_eq!(info.timestamp.as_deref(), Some("2023-01-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn skips_version_unless_negotiated() {
+        let info = parse_ntlm_challenge(&challenge(0x0289_8205 & !NEGOTIATE_VERSION)).unwrap();
+        assert_eq!(info.version, None);
+        assert_eq!(info.computer, "WS01");
+    }
+
+    #[test]
+    fn rejects_other_message_types() {
+        assert!(parse_ntlm_challenge(negotiate_blob()).is_none());
+        assert!(parse_ntlm_challenge(b"NTLMSSP\0\x02\0").is_none());
+    }
+}